@@ -29,6 +29,7 @@ pub async fn aria2_add_uri(
     }
 
     set_proxy_from_sys(&mut options.extra_options);
+    start_unpaused(&mut options.extra_options);
 
     Ok(aria2_client.add_uri(url, Some(options), None, None).await?)
 }
@@ -51,12 +52,17 @@ pub async fn aria2_add_torrent(
         r#continue: Some(true),
         ..TaskOptions::default()
     };
+    // respawned jobs find their earlier pieces on disk, verify them instead of downloading again
+    options
+        .extra_options
+        .insert("check-integrity".to_string(), Value::String("true".into()));
 
     if !select_file.is_empty() {
         options
             .extra_options
             .insert("select-file".to_string(), Value::String(select_file));
     }
+    start_unpaused(&mut options.extra_options);
 
     aria2_client
         .add_torrent(torrent, None, Some(options), None, None)
@@ -67,6 +73,33 @@ pub async fn aria2_add_torrent(
         })
 }
 
+/// Change which files of a torrent download are fetched, `select_file` holds
+/// 0-based indices. aria2 keeps the pieces it already has.
+pub async fn aria2_select_files(
+    aria2_client: &Client,
+    gid: &str,
+    select_file: impl IntoIterator<Item = usize>,
+) -> Result<(), Aria2Error> {
+    let select_file: String = select_file
+        .into_iter()
+        .map(|idx| (idx + 1).to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let mut options = TaskOptions::default();
+    options
+        .extra_options
+        .insert("select-file".to_string(), Value::String(select_file));
+
+    Ok(aria2_client.change_option(gid, options).await?)
+}
+
+/// The daemon runs with `--pause=true` so restored sessions wait for the
+/// download manager, downloads added on purpose start right away.
+fn start_unpaused(options: &mut Map<String, Value>) {
+    options.insert("pause".to_string(), Value::String("false".into()));
+}
+
 pub async fn aria2_get_all_list(aria2_client: &Client) -> Result<Vec<Status>, Aria2Error> {
     let mut active = aria2_client.tell_active().await?;
     let mut waiting = aria2_client.tell_waiting(0, 100).await?;
@@ -13,16 +13,23 @@ use fit_launcher_torrent::{functions::TorrentSession, modify_config};
 use lru_cache_adaptor::FileInfo;
 use specta::specta;
 
-use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
+use fit_launcher_config::client::{
+    connectivity,
+    dns::CUSTOM_DNS_CLIENT,
+    scheduler::{self, RetryBudget},
+    site,
+};
 use tauri::{Url, async_runtime::spawn_blocking};
-use tokio::{io::AsyncWriteExt as _, sync::Semaphore};
-use tracing::{debug, error, info, trace, warn};
+use tokio::io::AsyncWriteExt as _;
+use tracing::{debug, error, info, trace};
 
 use crate::{
     CacheManager, error::CacheError, image_path, initialize_used_cache_size, store::Command,
 };
 
-static PER_HOST_SEMAPHORE: LazyLock<SkipMap<String, Semaphore>> = LazyLock::new(SkipMap::new);
+/// Attempts of an image download, retries included
+const DOWNLOAD_ATTEMPTS: u32 = 5;
+
 static URL_DOWNLOAD_CACHING: LazyLock<SkipMap<String, Arc<AtomicBool>>> =
     LazyLock::new(SkipMap::new);
 
@@ -75,8 +82,25 @@ pub async fn cached_download_image(
     manager: tauri::State<'_, Arc<CacheManager>>,
     image_url: String,
 ) -> Result<String, CacheError> {
+    let image = load_image(Some(manager.inner()), &image_url).await?;
+    Ok(encode_data_uri(&image.mime, image.bytes).await)
+}
+
+/// An image and its MIME type
+pub struct CachedImage {
+    pub mime: String,
+    pub bytes: Arc<Vec<u8>>,
+}
+
+/// Read `image_url` from the cache, downloading and caching it on a miss.
+///
+/// Without a `manager` the image is only downloaded.
+pub async fn load_image(
+    manager: Option<&Arc<CacheManager>>,
+    image_url: &str,
+) -> Result<CachedImage, CacheError> {
     let entry = URL_DOWNLOAD_CACHING
-        .get_or_insert_with(image_url.clone(), || Arc::new(AtomicBool::new(false)));
+        .get_or_insert_with(image_url.to_string(), || Arc::new(AtomicBool::new(false)));
     let caching = entry.value().clone();
 
     // Note: this is best-effort to reduce repeated download
@@ -86,80 +110,84 @@ pub async fn cached_download_image(
     caching.store(true, Ordering::Release);
 
     // Check cache first
-    if let Ok(data_uri) = data_uri_from_cache(&manager, &image_url).await {
+    if let Some(manager) = manager
+        && let Ok(image) = image_from_cache(manager, image_url).await
+    {
         trace!("cache hit: {image_url}");
         caching.store(false, Ordering::Release);
-        return Ok(data_uri);
+        return Ok(image);
+    }
+
+    // offline, or the site is down for its own images, only what is cached can be shown
+    if connectivity::is_offline()
+        || (site::is_site_url(image_url) && connectivity::is_site_unavailable())
+    {
+        caching.store(false, Ordering::Release);
+        return Err(CacheError::CacheMissing);
     }
 
     let client_guard = CUSTOM_DNS_CLIENT.read().await;
     let client = &*client_guard;
 
-    let try_times = 5;
-
-    let req = client.get(&image_url).build().inspect_err(|_e| {
+    let req = client.get(image_url).build().inspect_err(|_e| {
         error!("failed to construct request: {image_url:?}");
+        caching.store(false, Ordering::Release);
     })?;
 
-    let host = req.url().host_str().unwrap_or_default();
-    let entry = PER_HOST_SEMAPHORE.get_or_insert_with(host.into(), || Semaphore::const_new(6));
-    let download_semaphore = entry.value();
-    for try_ in 0..try_times {
-        // follow browser behaviour https://stackoverflow.com/a/30064610/13121439
-        let _sem = download_semaphore.acquire().await;
-
-        // clone Request is safe, it's unrelated to underlying connection
-        match client.execute(req.try_clone().unwrap()).await {
-            Ok(resp) => {
-                if !resp.status().is_success() {
-                    let e = resp.error_for_status().unwrap_err();
-                    error!("http error {image_url}: {e}");
-                    return Err(e.into());
-                }
-
-                let mime = resp
-                    .headers()
-                    .get("content-type")
-                    .and_then(|h| h.to_str().ok().map(str::to_string))
-                    .or_else(|| {
-                        let url = Url::parse(&image_url).ok()?;
-                        let filename = url.path_segments()?.next_back()?;
-                        let mime = mime_guess::from_path(filename).first()?;
-                        Some(format!("{}/{}", mime.type_(), mime.subtype()))
-                    })
-                    .unwrap_or_else(|| "image/png".into());
-
-                let bytes: Arc<Vec<u8>> = Arc::new(resp.bytes().await?.iter().as_slice().into());
-                let file_size = bytes.len() as u64;
-
-                // Return immediately, cache asynchronously
-                let data_uri = encode_data_uri(&mime, bytes.clone()).await;
-
-                let manager = manager.inner().clone();
-                let image_url_clone = image_url.clone();
-                let mime_clone = mime.clone();
-
-                tauri::async_runtime::spawn(async move {
-                    cache_image_async(manager, image_url_clone, mime_clone, bytes, file_size).await;
-                    caching.store(false, Ordering::Release);
-                });
-
-                return Ok(data_uri);
-            }
-            Err(e) if try_ == try_times - 1 => {
-                error!("failed to download {image_url}: {e}");
-                caching.store(false, Ordering::Release);
-                return Err(e.into());
-            }
-            Err(e) => {
-                warn!("retry {image_url}: {e}");
-                let delay = (500 * 2_u64.pow(try_)).min(4000);
-                tokio::time::sleep(Duration::from_millis(delay)).await;
-            }
+    // per host limits and retries, see `scheduler`
+    let resp = match scheduler::send(client, req, &mut RetryBudget::new(DOWNLOAD_ATTEMPTS)).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            error!("failed to download {image_url}: {e}");
+            caching.store(false, Ordering::Release);
+            return Err(e.into());
         }
+    };
+
+    if !resp.status().is_success() {
+        let e = resp.error_for_status().unwrap_err();
+        error!("http error {image_url}: {e}");
+        caching.store(false, Ordering::Release);
+        return Err(e.into());
     }
 
-    unreachable!()
+    let mime = resp
+        .headers()
+        .get("content-type")
+        .and_then(|h| h.to_str().ok().map(str::to_string))
+        .or_else(|| {
+            let url = Url::parse(image_url).ok()?;
+            let filename = url.path_segments()?.next_back()?;
+            let mime = mime_guess::from_path(filename).first()?;
+            Some(format!("{}/{}", mime.type_(), mime.subtype()))
+        })
+        .unwrap_or_else(|| "image/png".into());
+
+    let bytes: Arc<Vec<u8>> = match resp.bytes().await {
+        Ok(bytes) => Arc::new(bytes.to_vec()),
+        Err(e) => {
+            caching.store(false, Ordering::Release);
+            return Err(e.into());
+        }
+    };
+    let file_size = bytes.len() as u64;
+
+    let Some(manager) = manager.cloned() else {
+        caching.store(false, Ordering::Release);
+        return Ok(CachedImage { mime, bytes });
+    };
+
+    // Return immediately, cache asynchronously
+    let image_url = image_url.to_string();
+    let mime_clone = mime.clone();
+    let bytes_clone = bytes.clone();
+
+    tauri::async_runtime::spawn(async move {
+        cache_image_async(manager, image_url, mime_clone, bytes_clone, file_size).await;
+        caching.store(false, Ordering::Release);
+    });
+
+    Ok(CachedImage { mime, bytes })
 }
 
 async fn cache_image_async(
@@ -305,10 +333,10 @@ async fn claim_space(manager: &CacheManager, exceed: isize) {
     }
 }
 
-async fn data_uri_from_cache(
+async fn image_from_cache(
     manager: &CacheManager,
     url: impl Into<String>,
-) -> Result<String, CacheError> {
+) -> Result<CachedImage, CacheError> {
     let (tx, rx) = kanal::bounded(0);
     manager
         .command_tx
@@ -321,9 +349,11 @@ async fn data_uri_from_cache(
                 .first()
                 .ok_or(CacheError::MimeGuess)?;
             let image_raw = tokio::fs::read(path).await?;
-            let mime = format!("{}/{}", mime.type_(), mime.subtype());
 
-            Ok(encode_data_uri(mime, Arc::new(image_raw)).await)
+            Ok(CachedImage {
+                mime: format!("{}/{}", mime.type_(), mime.subtype()),
+                bytes: Arc::new(image_raw),
+            })
         }
         _ => Err(CacheError::CacheMissing),
     }
@@ -1,3 +1,4 @@
+use fit_launcher_config::client::scheduler::RequestError;
 use lru_cache_adaptor::LRUError;
 use specta::Type;
 
@@ -43,6 +44,12 @@ impl From<reqwest::Error> for CacheError {
     }
 }
 
+impl From<RequestError> for CacheError {
+    fn from(value: RequestError) -> Self {
+        Self::Reqwest(value.to_string())
+    }
+}
+
 impl From<LRUError> for CacheError {
     fn from(value: LRUError) -> Self {
         Self::LRU(value.to_string())
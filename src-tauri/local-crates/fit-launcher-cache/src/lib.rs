@@ -1,11 +1,13 @@
 mod commands;
 pub mod error;
 mod manager;
+mod protocol;
 mod store;
 
-pub(crate) use store::image_path;
+pub use store::image_path;
 
 pub use manager::CacheManager;
+pub use protocol::{CACHE_SCHEME, handle_cache_request};
 pub use store::{initialize_used_cache_size, spawn_cache_manager};
 
 pub use commands::*;
@@ -0,0 +1,253 @@
+//! `fitcache://` protocol, lets the webview load cached images directly.
+//!
+//! The path is the percent-encoded image URL, which is what
+//! `convertFileSrc(url, "fitcache")` builds on every platform. Images come
+//! from the LRU store and are downloaded on a miss, so unlike
+//! [`cached_download_image`](crate::cached_download_image) nothing is
+//! base64-encoded or sent over IPC.
+
+use std::{ops::Range, sync::Arc};
+
+use tauri::{
+    Manager, Runtime, UriSchemeContext, UriSchemeResponder,
+    http::{
+        Method, Request, Response, StatusCode,
+        header::{
+            ACCEPT_RANGES, ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_LENGTH,
+            CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_NONE_MATCH, RANGE,
+        },
+    },
+};
+use tracing::warn;
+
+use crate::{
+    CacheManager,
+    commands::{CachedImage, load_image},
+    store::url_hash,
+};
+
+pub const CACHE_SCHEME: &str = "fitcache";
+
+/// Images at a given URL practically never change
+const MAX_AGE: &str = "public, max-age=604800";
+
+/// Handler for [`tauri::Builder::register_asynchronous_uri_scheme_protocol`]
+pub fn handle_cache_request<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let app = ctx.app_handle().clone();
+    tauri::async_runtime::spawn(async move {
+        // the manager is set up in the background, early requests skip the cache
+        let manager = app
+            .try_state::<Arc<CacheManager>>()
+            .map(|state| state.inner().clone());
+        responder.respond(serve(manager.as_ref(), &request).await);
+    });
+}
+
+async fn serve(
+    manager: Option<&Arc<CacheManager>>,
+    request: &Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return empty(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    let Some(image_url) = source_url(request.uri().path()) else {
+        return empty(StatusCode::BAD_REQUEST);
+    };
+
+    match load_image(manager, &image_url).await {
+        Ok(image) => respond(request, &url_hash(&image_url), &image),
+        Err(e) => {
+            warn!("fitcache: failed to load {image_url}: {e}");
+            empty(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+fn empty(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .body(Vec::new())
+        .expect("static response")
+}
+
+/// The image URL a request path stands for, only http(s) is fetched
+fn source_url(path: &str) -> Option<String> {
+    let url = percent_decode(path.trim_start_matches('/'))?;
+    (url.starts_with("https://") || url.starts_with("http://")).then_some(url)
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    /// No usable `Range`, multi-range requests included, get the whole body
+    Full,
+    Partial(Range<u64>),
+    Unsatisfiable,
+}
+
+fn parse_range(header: &str, len: u64) -> ByteRange {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    let Some((start, end)) = spec.split_once('-').filter(|_| !spec.contains(',')) else {
+        return ByteRange::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = if start.is_empty() {
+        // `bytes=-500` is the last 500 bytes
+        let Ok(suffix) = end.parse::<u64>() else {
+            return ByteRange::Full;
+        };
+        len.saturating_sub(suffix)..len
+    } else {
+        let Ok(start) = start.parse::<u64>() else {
+            return ByteRange::Full;
+        };
+        let end = if end.is_empty() {
+            len
+        } else {
+            match end.parse::<u64>() {
+                // the last byte is inclusive
+                Ok(last) => last.saturating_add(1).min(len),
+                Err(_) => return ByteRange::Full,
+            }
+        };
+        start..end
+    };
+
+    if range.is_empty() {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(range)
+    }
+}
+
+fn respond(request: &Request<Vec<u8>>, hash: &str, image: &CachedImage) -> Response<Vec<u8>> {
+    let len = image.bytes.len() as u64;
+    let etag = format!("\"{hash}-{len:x}\"");
+    let headers = request.headers();
+
+    let builder = Response::builder()
+        .header(ETAG, &etag)
+        .header(CACHE_CONTROL, MAX_AGE)
+        .header(ACCEPT_RANGES, "bytes")
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+
+    if headers
+        .get(IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| etag_matches(v, &etag))
+    {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Vec::new())
+            .expect("valid headers");
+    }
+
+    let range = headers
+        .get(RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(ByteRange::Full, |v| parse_range(v, len));
+    let (builder, body) = match range {
+        ByteRange::Full => (builder.status(StatusCode::OK), 0..len),
+        ByteRange::Partial(range) => (
+            builder.status(StatusCode::PARTIAL_CONTENT).header(
+                CONTENT_RANGE,
+                format!("bytes {}-{}/{len}", range.start, range.end - 1),
+            ),
+            range,
+        ),
+        ByteRange::Unsatisfiable => {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, format!("bytes */{len}"))
+                .body(Vec::new())
+                .expect("valid headers");
+        }
+    };
+
+    // HEAD still announces the length of what GET would send
+    let length = body.end - body.start;
+    let body = if request.method() == Method::HEAD {
+        Vec::new()
+    } else {
+        image.bytes[body.start as usize..body.end as usize].to_vec()
+    };
+    builder
+        .header(CONTENT_TYPE, &image.mime)
+        .header(CONTENT_LENGTH, length)
+        .body(body)
+        .expect("valid headers")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_decode_to_image_urls() {
+        assert_eq!(
+            source_url("/https%3A%2F%2Fi.example.com%2Fa%20b.jpg").as_deref(),
+            Some("https://i.example.com/a b.jpg")
+        );
+        assert_eq!(source_url("/file%3A%2F%2F%2Fetc%2Fpasswd"), None);
+        assert_eq!(source_url("/https%3A%2F%2Fbroken%2"), None);
+    }
+
+    #[test]
+    fn ranges_follow_rfc_9110() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(0..100));
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            ByteRange::Partial(900..1000)
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            ByteRange::Partial(900..1000)
+        );
+        assert_eq!(
+            parse_range("bytes=500-5000", 1000),
+            ByteRange::Partial(500..1000)
+        );
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,5-9", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Full);
+    }
+
+    #[test]
+    fn etags_match_weak_and_wildcard() {
+        let etag = "\"abc-10\"";
+        assert!(etag_matches("\"abc-10\"", etag));
+        assert!(etag_matches("\"x\", W/\"abc-10\"", etag));
+        assert!(etag_matches("*", etag));
+        assert!(!etag_matches("\"abc-11\"", etag));
+    }
+}
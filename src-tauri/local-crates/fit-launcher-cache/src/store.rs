@@ -37,7 +37,8 @@ pub enum Command {
     ClearCache,
 }
 
-pub fn image_path(url: impl AsRef<str>) -> PathBuf {
+/// Stable hex digest of `url`, names its cache file
+pub fn url_hash(url: impl AsRef<str>) -> String {
     let seed = ahash::RandomState::with_seeds(
         51539607551,
         824633720831,
@@ -45,7 +46,11 @@ pub fn image_path(url: impl AsRef<str>) -> PathBuf {
         108086391056891903,
     )
     .hash_one(url.as_ref());
-    let digest = format!("{seed:016x}");
+    format!("{seed:016x}")
+}
+
+pub fn image_path(url: impl AsRef<str>) -> PathBuf {
+    let digest = url_hash(url);
     let (a, b, c) = (&digest[0..2], &digest[2..4], &digest[4..6]);
 
     cache_image_dir().join(a).join(b).join(c).join(digest)
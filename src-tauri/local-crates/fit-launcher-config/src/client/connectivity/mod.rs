@@ -0,0 +1,170 @@
+//! Online / offline state shared by every network user.
+//!
+//! The app is offline when the user asked for it, or when the network itself
+//! is down. Offline, reads are served from local data only and writes that
+//! need the network wait in an outbox.
+//!
+//! The site going down is tracked on its own: scraping and everything else
+//! served by the site is suspended, downloads and other hosts are not.
+
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::Client;
+use serde::Serialize;
+use specta::Type;
+use tokio::sync::watch;
+use tracing::info;
+
+use crate::settings::commands::get_gamehub_settings;
+
+/// Answered by a host that is not the site, to tell a site outage from a network loss
+const NETWORK_PROBE_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+const NETWORK_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+pub struct Connectivity {
+    /// Offline mode chosen in the settings
+    pub forced_offline: bool,
+    /// Outcome of the last request to the site
+    pub reachable: bool,
+    /// Outcome of the last network probe, see [`probe_network`]
+    pub network: bool,
+    /// Unix timestamp of the last time the site answered
+    pub last_online: Option<i64>,
+}
+
+impl Connectivity {
+    pub fn is_offline(&self) -> bool {
+        self.forced_offline || !self.network
+    }
+
+    /// Offline, or the site alone is down
+    pub fn is_site_unavailable(&self) -> bool {
+        self.forced_offline || !self.reachable
+    }
+}
+
+static STATE: LazyLock<watch::Sender<Connectivity>> = LazyLock::new(|| {
+    watch::Sender::new(Connectivity {
+        forced_offline: get_gamehub_settings().offline_mode,
+        // optimistic until a request says otherwise
+        reachable: true,
+        network: true,
+        last_online: None,
+    })
+});
+
+pub fn connectivity() -> Connectivity {
+    *STATE.borrow()
+}
+
+pub fn is_offline() -> bool {
+    STATE.borrow().is_offline()
+}
+
+pub fn is_site_unavailable() -> bool {
+    STATE.borrow().is_site_unavailable()
+}
+
+/// Changes of the state, the current one is marked as seen
+pub fn subscribe() -> watch::Receiver<Connectivity> {
+    STATE.subscribe()
+}
+
+pub fn set_forced_offline(forced: bool) {
+    STATE.send_if_modified(|state| {
+        let changed = state.forced_offline != forced;
+        state.forced_offline = forced;
+        changed
+    });
+}
+
+/// Fed by every request to the site, see [`crate::client::site::get_with_failover`].
+///
+/// Returns whether the state changed.
+pub fn set_reachable(reachable: bool) -> bool {
+    STATE.send_if_modified(|state| {
+        let changed = state.reachable != reachable || (reachable && !state.network);
+        if state.reachable != reachable {
+            info!("Site reachable: {reachable}");
+        }
+        state.reachable = reachable;
+        if reachable {
+            // the site answered, so the network works
+            state.network = true;
+            state.last_online = Some(now_timestamp());
+        }
+        changed
+    })
+}
+
+fn set_network(network: bool) {
+    STATE.send_if_modified(|state| {
+        let changed = state.network != network;
+        if changed {
+            info!("Network available: {network}");
+        }
+        state.network = network;
+        changed
+    });
+}
+
+/// Checks whether a host other than the site answers, any answer will do
+pub async fn probe_network(client: &Client) {
+    let result = client
+        .get(NETWORK_PROBE_URL)
+        .timeout(NETWORK_PROBE_TIMEOUT)
+        .send()
+        .await;
+    set_network(result.is_ok());
+}
+
+/// Resolves at once when online
+pub async fn wait_until_online() {
+    let mut rx = STATE.subscribe();
+    // only fails when the sender is dropped, it is a static
+    let _ = rx.wait_for(|state| !state.is_offline()).await;
+}
+
+/// Resolves at once when the site is reachable
+pub async fn wait_until_site_available() {
+    let mut rx = STATE.subscribe();
+    let _ = rx.wait_for(|state| !state.is_site_unavailable()).await;
+}
+
+fn now_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(forced_offline: bool, reachable: bool, network: bool) -> Connectivity {
+        Connectivity {
+            forced_offline,
+            reachable,
+            network,
+            last_online: None,
+        }
+    }
+
+    #[test]
+    fn site_outage_is_not_offline() {
+        let site_down = state(false, false, true);
+        assert!(!site_down.is_offline());
+        assert!(site_down.is_site_unavailable());
+
+        let no_network = state(false, false, false);
+        assert!(no_network.is_offline());
+        assert!(no_network.is_site_unavailable());
+
+        let forced = state(true, true, true);
+        assert!(forced.is_offline());
+        assert!(forced.is_site_unavailable());
+    }
+}
@@ -1,9 +1,34 @@
+//! Cookie jar of the shared HTTP client.
+//!
+//! Cookies are scoped by domain and path and dropped once expired, like a
+//! browser does. A `Domain` that is a public suffix is refused and `Secure`
+//! cookies only go over https. The jar is the client's [`CookieStore`], so
+//! `Set-Cookie` answers update it too, and it is saved to `cookies.json` on
+//! every change, session cookies aside. Changes from a response are saved by
+//! a writer thread, not on the request path.
+//! Cookies imported without a domain are sent to every origin of the site,
+//! see [`site::is_site_url`].
+
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock};
+use std::time::Duration;
 
+use chrono::DateTime;
 use directories::BaseDirs;
+use reqwest::Url;
+use reqwest::cookie::CookieStore;
+use reqwest::header::HeaderValue;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use thiserror::Error;
+use tracing::warn;
+
+use crate::client::site;
+
+/// Cookies set by the DDoS-Guard challenge
+pub const DDOS_GUARD_PREFIX: &str = "__ddg";
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -13,17 +38,21 @@ pub enum Error {
     DeSer(#[from] serde_json::Error),
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Type)]
 pub struct Cookie {
     pub name: String,
     pub value: String,
     pub domain: Option<String>,
     pub path: Option<String>,
     pub expires: Option<String>, // Use RFC 1123/ISO 8601 date string
+    /// Seconds from the moment the cookie is imported, wins over `expires`
     pub max_age: Option<i64>,
+    /// Only sent over https
+    #[serde(default)]
+    pub secure: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Type)]
 pub struct Cookies(pub Vec<Cookie>);
 
 impl Cookies {
@@ -47,14 +76,6 @@ impl Cookies {
             .join("cookies")
     }
 
-    pub fn to_header(&self) -> String {
-        self.0
-            .iter()
-            .map(|Cookie { name, value, .. }| format!("{name}={value}"))
-            .collect::<Vec<String>>()
-            .join(";")
-    }
-
     pub fn save(&self) -> Result<(), Error> {
         let path = Self::default_path();
         if let Some(parent) = path.parent() {
@@ -64,3 +85,579 @@ impl Cookies {
         Ok(())
     }
 }
+
+/// A cookie as the jar keeps it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StoredCookie {
+    name: String,
+    value: String,
+    /// Lowercase without the leading dot, `None` for any origin of the site
+    domain: Option<String>,
+    /// Only sent to `domain` itself, not to its subdomains
+    host_only: bool,
+    path: String,
+    /// Unix timestamp, `None` when it never expires
+    expires_at: Option<i64>,
+    /// Set by a server without an expiry, dropped on restart
+    session: bool,
+    /// Only sent over https
+    secure: bool,
+}
+
+impl StoredCookie {
+    fn from_cookie(cookie: Cookie, now: i64) -> Self {
+        let expires_at = cookie
+            .max_age
+            .map(|max_age| now.saturating_add(max_age))
+            .or_else(|| cookie.expires.as_deref().and_then(parse_date));
+        // like browsers, a leading dot marks a cookie of the subdomains too
+        let host_only = cookie
+            .domain
+            .as_deref()
+            .is_some_and(|domain| !domain.starts_with('.'));
+        StoredCookie {
+            name: cookie.name,
+            value: cookie.value,
+            domain: cookie
+                .domain
+                .map(|domain| domain.trim_start_matches('.').to_ascii_lowercase())
+                .filter(|domain| !domain.is_empty()),
+            host_only,
+            path: cookie
+                .path
+                .filter(|path| path.starts_with('/'))
+                .unwrap_or_else(|| "/".into()),
+            expires_at,
+            // imported cookies are kept as given
+            session: false,
+            secure: cookie.secure,
+        }
+    }
+
+    fn to_cookie(&self) -> Cookie {
+        Cookie {
+            name: self.name.clone(),
+            value: self.value.clone(),
+            domain: self.domain.as_ref().map(|domain| match self.host_only {
+                true => domain.clone(),
+                false => format!(".{domain}"),
+            }),
+            path: Some(self.path.clone()),
+            expires: self
+                .expires_at
+                .and_then(|at| DateTime::from_timestamp(at, 0))
+                .map(|at| at.to_rfc2822()),
+            max_age: None,
+            secure: self.secure,
+        }
+    }
+
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let domain_matches = match &self.domain {
+            None => site::is_site_url(url.as_str()),
+            Some(domain) if self.host_only => host == *domain,
+            Some(domain) => domain_matches(&host, domain),
+        };
+        domain_matches
+            && path_matches(url.path(), &self.path)
+            && (!self.secure || url.scheme() == "https")
+    }
+
+    /// Whether `other` replaces this cookie when stored
+    fn same_slot(&self, other: &StoredCookie) -> bool {
+        self.name == other.name
+            && self.path == other.path
+            && (self.domain == other.domain
+                // an imported cookie gives way to the one the site set
+                || self.domain.is_none()
+                    && other
+                        .domain
+                        .as_ref()
+                        .is_some_and(|domain| site::is_site_url(&format!("https://{domain}/"))))
+    }
+}
+
+fn now_timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// RFC 2822 dates, and the `Wed, 21-Oct-2015 07:28:00 GMT` variant
+fn parse_date(date: &str) -> Option<i64> {
+    let date = date.trim();
+    DateTime::parse_from_rfc2822(date)
+        .or_else(|_| DateTime::parse_from_rfc2822(&date.replace('-', " ")))
+        .or_else(|_| DateTime::parse_from_rfc3339(date))
+        .ok()
+        .map(|date| date.timestamp())
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.ends_with('.'))
+}
+
+/// A `Domain` that would cover unrelated sites, e.g. `com` or `co.uk`
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.') || psl::suffix_str(domain) == Some(domain)
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || request_path
+            .strip_prefix(cookie_path)
+            .is_some_and(|rest| cookie_path.ends_with('/') || rest.starts_with('/'))
+}
+
+/// The directory of `url`, used when `Set-Cookie` has no `Path`
+fn default_path(url: &Url) -> String {
+    match url.path().rfind('/') {
+        Some(0) | None => "/".into(),
+        Some(end) => url.path()[..end].into(),
+    }
+}
+
+/// Parses a `Set-Cookie` header sent by `url`, `None` when it is invalid,
+/// its domain does not cover `url` or it is `Secure` and `url` is not https
+fn parse_set_cookie(header: &str, url: &Url, now: i64) -> Option<StoredCookie> {
+    let host = url.host_str()?.to_ascii_lowercase();
+    // `None` for an IP address
+    let is_address = url.domain().is_none();
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut cookie = StoredCookie {
+        name: name.into(),
+        value: value.trim().trim_matches('"').into(),
+        domain: Some(host.clone()),
+        host_only: true,
+        path: default_path(url),
+        expires_at: None,
+        session: false,
+        secure: false,
+    };
+    let mut max_age = None;
+    for attribute in parts {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "domain" => {
+                let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                if domain.is_empty() {
+                    continue;
+                }
+                if !domain_matches(&host, &domain) {
+                    return None;
+                }
+                if is_address || is_public_suffix(&domain) {
+                    // only names the host itself, the cookie stays host-only
+                    if domain != host {
+                        return None;
+                    }
+                    continue;
+                }
+                cookie.domain = Some(domain);
+                cookie.host_only = false;
+            }
+            "path" if value.starts_with('/') => cookie.path = value.into(),
+            "expires" => cookie.expires_at = parse_date(value).or(cookie.expires_at),
+            "max-age" => max_age = value.parse::<i64>().ok(),
+            "secure" => cookie.secure = true,
+            _ => {}
+        }
+    }
+    if cookie.secure && url.scheme() != "https" {
+        return None;
+    }
+    if let Some(max_age) = max_age {
+        cookie.expires_at = Some(now.saturating_add(max_age.max(0)));
+    }
+    cookie.session = cookie.expires_at.is_none();
+    Some(cookie)
+}
+
+/// Cookies of the shared client, see the module docs
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: RwLock<Vec<StoredCookie>>,
+    /// Bumped on every change, under the `cookies` lock
+    version: AtomicU64,
+    saved: Arc<SavedVersion>,
+    writer: OnceLock<Sender<(u64, Cookies)>>,
+}
+
+/// Version of the jar last written to `cookies.json`, a save never goes
+/// back to an older one
+#[derive(Debug, Default)]
+struct SavedVersion(Mutex<u64>);
+
+impl SavedVersion {
+    fn write(&self, version: u64, cookies: &Cookies) -> Result<(), Error> {
+        let mut saved = self.0.lock().unwrap();
+        if *saved >= version {
+            return Ok(());
+        }
+        cookies.save()?;
+        *saved = version;
+        Ok(())
+    }
+}
+
+impl CookieJar {
+    fn load() -> Self {
+        let jar = CookieJar::default();
+        match Cookies::load_cookies() {
+            Ok(Cookies(cookies)) => {
+                let now = now_timestamp();
+                let mut stored = jar.cookies.write().unwrap();
+                for cookie in cookies {
+                    insert(&mut stored, StoredCookie::from_cookie(cookie, now), now);
+                }
+            }
+            Err(Error::IO(e)) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("failed to read cookies: {e}"),
+        }
+        jar
+    }
+
+    /// Replace every cookie, e.g. with an imported file
+    pub fn replace(&self, cookies: Cookies) -> Result<(), Error> {
+        let now = now_timestamp();
+        let (version, saved) = {
+            let mut stored = self.cookies.write().unwrap();
+            stored.clear();
+            for cookie in cookies.0 {
+                insert(&mut stored, StoredCookie::from_cookie(cookie, now), now);
+            }
+            self.changed(&stored, now)
+        };
+        self.saved.write(version, &saved)
+    }
+
+    /// Add or update `cookies`, keeping the others
+    pub fn store(&self, cookies: Vec<Cookie>) -> Result<(), Error> {
+        let now = now_timestamp();
+        let (version, saved) = {
+            let mut stored = self.cookies.write().unwrap();
+            for cookie in cookies {
+                insert(&mut stored, StoredCookie::from_cookie(cookie, now), now);
+            }
+            self.changed(&stored, now)
+        };
+        self.saved.write(version, &saved)
+    }
+
+    /// New version of the jar and the cookies to save for it, called with
+    /// `stored` still locked so versions follow the order of the changes
+    fn changed(&self, stored: &[StoredCookie], now: i64) -> (u64, Cookies) {
+        let version = self.version.fetch_add(1, Ordering::Relaxed) + 1;
+        (version, persistent(stored, now))
+    }
+
+    /// Hand the save to the writer thread, started on first use
+    fn save_in_background(&self, version: u64, cookies: Cookies) {
+        let writer = self.writer.get_or_init(|| {
+            let (tx, rx) = mpsc::channel::<(u64, Cookies)>();
+            let saved = self.saved.clone();
+            std::thread::spawn(move || {
+                while let Ok(mut latest) = rx.recv() {
+                    // only the newest of a burst of changes is written
+                    while let Ok(newer) = rx.try_recv() {
+                        latest = newer;
+                    }
+                    let (version, cookies) = latest;
+                    if let Err(e) = saved.write(version, &cookies) {
+                        warn!("failed to save cookies: {e}");
+                    }
+                }
+            });
+            tx
+        });
+        if writer.send((version, cookies)).is_err() {
+            warn!("cookie writer stopped, cookies not saved");
+        }
+    }
+
+    /// Every cookie that has not expired
+    pub fn all(&self) -> Cookies {
+        let now = now_timestamp();
+        Cookies(
+            self.cookies
+                .read()
+                .unwrap()
+                .iter()
+                .filter(|cookie| !cookie.is_expired(now))
+                .map(StoredCookie::to_cookie)
+                .collect(),
+        )
+    }
+
+    /// Time left until the first of the DDoS-Guard cookies still valid
+    /// expires, `None` without any or when they last the session
+    pub fn ddos_guard_expires_in(&self) -> Option<Duration> {
+        let now = now_timestamp();
+        self.cookies
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|cookie| cookie.name.starts_with(DDOS_GUARD_PREFIX))
+            .filter_map(|cookie| cookie.expires_at)
+            .filter(|at| *at > now)
+            .min()
+            .map(|at| Duration::from_secs((at - now) as u64))
+    }
+
+    /// The `Cookie` header for `url`, longest paths first
+    fn header_for(&self, url: &Url, now: i64) -> Option<String> {
+        let stored = self.cookies.read().unwrap();
+        let mut cookies: Vec<&StoredCookie> = stored
+            .iter()
+            .filter(|cookie| !cookie.is_expired(now) && cookie.matches(url))
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        Some(
+            cookies
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+}
+
+/// The cookies worth keeping across restarts
+fn persistent(cookies: &[StoredCookie], now: i64) -> Cookies {
+    Cookies(
+        cookies
+            .iter()
+            .filter(|cookie| !cookie.session && !cookie.is_expired(now))
+            .map(StoredCookie::to_cookie)
+            .collect(),
+    )
+}
+
+/// Stores `cookie` in place of the one it replaces. An expired cookie only
+/// removes, that is how servers delete one. `true` when `cookies` changed.
+fn insert(cookies: &mut Vec<StoredCookie>, cookie: StoredCookie, now: i64) -> bool {
+    if cookies.contains(&cookie) {
+        return false;
+    }
+    let before = cookies.len();
+    cookies.retain(|existing| !existing.same_slot(&cookie) && !existing.is_expired(now));
+    if cookie.is_expired(now) {
+        return cookies.len() != before;
+    }
+    cookies.push(cookie);
+    true
+}
+
+impl CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let now = now_timestamp();
+        let mut changed = false;
+        let save = {
+            let mut stored = self.cookies.write().unwrap();
+            for header in cookie_headers.filter_map(|header| header.to_str().ok()) {
+                if let Some(cookie) = parse_set_cookie(header, url, now) {
+                    changed |= insert(&mut stored, cookie, now);
+                }
+            }
+            changed.then(|| self.changed(&stored, now))
+        };
+        if let Some((version, cookies)) = save {
+            self.save_in_background(version, cookies);
+        }
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self.header_for(url, now_timestamp())?;
+        HeaderValue::from_str(&header)
+            .inspect_err(|e| warn!("invalid cookie header for {url}: {e}"))
+            .ok()
+    }
+}
+
+/// The jar of [`CUSTOM_DNS_CLIENT`](crate::client::dns::CUSTOM_DNS_CLIENT),
+/// loaded from `cookies.json`
+pub static COOKIE_JAR: LazyLock<Arc<CookieJar>> = LazyLock::new(|| Arc::new(CookieJar::load()));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn jar_with(headers: &[&str], from: &str, now: i64) -> CookieJar {
+        let jar = CookieJar::default();
+        let mut stored = jar.cookies.write().unwrap();
+        for header in headers {
+            let cookie = parse_set_cookie(header, &url(from), now).unwrap();
+            insert(&mut stored, cookie, now);
+        }
+        drop(stored);
+        jar
+    }
+
+    #[test]
+    fn cookies_are_scoped_by_domain_and_path() {
+        let jar = jar_with(
+            &[
+                "host=1",
+                "wide=2; Domain=.example.com; Path=/",
+                "deep=3; Path=/games",
+            ],
+            "https://www.example.com/page",
+            0,
+        );
+        assert_eq!(
+            jar.header_for(&url("https://www.example.com/games/a"), 0)
+                .as_deref(),
+            Some("deep=3; host=1; wide=2")
+        );
+        assert_eq!(
+            jar.header_for(&url("https://cdn.example.com/gamesx"), 0)
+                .as_deref(),
+            Some("wide=2")
+        );
+        assert_eq!(jar.header_for(&url("https://example.org/"), 0), None);
+        assert!(
+            parse_set_cookie("a=1; Domain=other.com", &url("https://example.com/"), 0).is_none()
+        );
+    }
+
+    #[test]
+    fn expired_cookies_are_not_sent() {
+        let jar = jar_with(
+            &[
+                "__ddg1_=a; Max-Age=60",
+                "__ddg2_=b; Expires=Wed, 21-Oct-2015 07:28:00 GMT",
+                "session=c",
+            ],
+            "https://example.com/",
+            1_700_000_000,
+        );
+        assert_eq!(jar.cookies.read().unwrap().len(), 2);
+        assert_eq!(
+            jar.header_for(&url("https://example.com/"), 1_700_000_059)
+                .as_deref(),
+            Some("__ddg1_=a; session=c")
+        );
+        assert_eq!(
+            jar.header_for(&url("https://example.com/"), 1_700_000_060)
+                .as_deref(),
+            Some("session=c")
+        );
+
+        // a server deletes a cookie by expiring it
+        let mut stored = jar.cookies.write().unwrap();
+        let delete = parse_set_cookie(
+            "session=; Max-Age=0",
+            &url("https://example.com/"),
+            1_700_000_000,
+        );
+        assert!(insert(&mut stored, delete.unwrap(), 1_700_000_000));
+        assert_eq!(stored.len(), 1);
+    }
+
+    #[test]
+    fn saved_cookies_round_trip() {
+        let cookie = parse_set_cookie(
+            "__ddg2_=b; Domain=example.com; Max-Age=3600",
+            &url("https://example.com/"),
+            1_700_000_000,
+        )
+        .unwrap();
+        let saved = cookie.to_cookie();
+        assert_eq!(saved.domain.as_deref(), Some(".example.com"));
+        assert_eq!(StoredCookie::from_cookie(saved, 0), cookie);
+    }
+
+    #[test]
+    fn session_cookies_are_not_saved() {
+        let jar = jar_with(
+            &["kept=1; Max-Age=3600", "session=2"],
+            "https://example.com/",
+            0,
+        );
+        let imported = Cookie {
+            name: "imported".into(),
+            value: "3".into(),
+            domain: None,
+            path: None,
+            expires: None,
+            max_age: None,
+            secure: false,
+        };
+        let mut stored = jar.cookies.write().unwrap();
+        insert(&mut stored, StoredCookie::from_cookie(imported, 0), 0);
+
+        let saved = persistent(&stored, 0);
+        let names: Vec<_> = saved.0.iter().map(|cookie| cookie.name.as_str()).collect();
+        assert_eq!(names, ["kept", "imported"]);
+        assert_eq!(stored.len(), 3);
+    }
+
+    #[test]
+    fn public_suffix_domains_are_refused() {
+        let from = url("https://www.example.co.uk/");
+        for header in ["a=1; Domain=uk", "a=1; Domain=co.uk", "a=1; Domain=.co.uk"] {
+            assert!(parse_set_cookie(header, &from, 0).is_none(), "{header}");
+        }
+        let cookie = parse_set_cookie("a=1; Domain=example.co.uk", &from, 0).unwrap();
+        assert_eq!(cookie.domain.as_deref(), Some("example.co.uk"));
+        assert!(!cookie.host_only);
+
+        // a single label host may only name itself
+        let cookie =
+            parse_set_cookie("a=1; Domain=localhost", &url("http://localhost/"), 0).unwrap();
+        assert!(cookie.host_only);
+        let cookie = parse_set_cookie("a=1; Domain=1.1", &url("http://10.0.1.1/"), 0);
+        assert!(cookie.is_none());
+    }
+
+    #[test]
+    fn secure_cookies_need_https() {
+        assert!(parse_set_cookie("a=1; Secure", &url("http://example.com/"), 0).is_none());
+
+        let jar = jar_with(
+            &["a=1; Secure; Max-Age=60", "b=2; Max-Age=60"],
+            "https://example.com/",
+            0,
+        );
+        assert_eq!(
+            jar.header_for(&url("https://example.com/"), 0).as_deref(),
+            Some("a=1; b=2")
+        );
+        assert_eq!(
+            jar.header_for(&url("http://example.com/"), 0).as_deref(),
+            Some("b=2")
+        );
+
+        let saved = persistent(&jar.cookies.read().unwrap(), 0);
+        assert_eq!(saved.0.len(), 2);
+        assert!(
+            saved
+                .0
+                .iter()
+                .all(|cookie| cookie.secure == (cookie.name == "a"))
+        );
+    }
+}
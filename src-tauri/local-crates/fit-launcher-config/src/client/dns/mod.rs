@@ -2,19 +2,15 @@ use directories::BaseDirs;
 
 use reqwest::Client;
 use reqwest::ClientBuilder;
-use reqwest::header::COOKIE;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use std::fs;
 use std::io::Write;
 use std::sync::Arc;
 use std::sync::LazyLock;
-use tauri::http::HeaderMap;
-use tauri::http::HeaderValue;
 use tokio::sync::RwLock;
 use tracing::error;
 use tracing::info;
-use tracing::warn;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Type)]
 pub struct FitLauncherDnsConfig {
@@ -101,19 +97,8 @@ pub fn build_dns_client() -> Client {
         .gzip(true)
         .brotli(true)
         .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36")
-        .pool_max_idle_per_host(0);
-
-    match Cookies::load_cookies() {
-        Ok(cookies) => {
-            client_builder = client_builder.default_headers(HeaderMap::from_iter([(
-                COOKIE,
-                HeaderValue::from_str(&cookies.to_header()).expect("invalid cookies value"),
-            )]))
-        }
-        Err(e) => {
-            warn!("failed to read cookies: {e}");
-        }
-    }
+        .pool_max_idle_per_host(0)
+        .cookie_provider(COOKIE_JAR.clone());
 
     // Conditionally set the custom DNS resolver only if sys_conf is disabled
     if !dns_config.system_conf {
@@ -133,4 +118,4 @@ pub static CUSTOM_DNS_CLIENT: LazyLock<RwLock<Client>> =
 mod resolver;
 pub use resolver::HickoryResolverWithProtocol;
 
-use crate::client::cookies::Cookies;
+use crate::client::cookies::COOKIE_JAR;
@@ -1,2 +1,5 @@
+pub mod connectivity;
 pub mod cookies;
 pub mod dns;
+pub mod scheduler;
+pub mod site;
@@ -0,0 +1,717 @@
+//! Request scheduling shared by every user of [`CUSTOM_DNS_CLIENT`].
+//!
+//! Requests to a host wait for a concurrency slot and respect a minimum
+//! interval between starts. Connection errors, timeouts, 429 and 5xx are
+//! retried with jittered exponential backoff, or after the `Retry-After` the
+//! host asked for, within the [`RetryBudget`] of the caller. A host that keeps
+//! failing has its circuit opened for a while and requests to it fail fast,
+//! then a single trial request decides whether it closes again.
+//! Every host keeps counters that [`metrics`] exposes for diagnostics.
+//!
+//! [`CUSTOM_DNS_CLIENT`]: crate::client::dns::CUSTOM_DNS_CLIENT
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, Request, Response, StatusCode};
+use serde::Serialize;
+use specta::Type;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+use crate::client::site;
+
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(8);
+/// A longer `Retry-After` is not waited for, the response is returned
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Consecutive failures that open the circuit of a host
+const FAILURE_THRESHOLD: u32 = 5;
+const OPEN_FOR: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostPolicy {
+    pub max_concurrent: usize,
+    /// Between the starts of two requests
+    pub min_interval: Duration,
+}
+
+const DEFAULT_POLICY: HostPolicy = HostPolicy {
+    max_concurrent: 6,
+    min_interval: Duration::ZERO,
+};
+
+/// The site sits behind DDoS-Guard
+const SITE_POLICY: HostPolicy = HostPolicy {
+    max_concurrent: 4,
+    min_interval: Duration::from_millis(250),
+};
+
+/// Hosts known to rate limit, matched on the host and its subdomains
+const HOST_POLICIES: &[(&str, HostPolicy)] = &[(
+    "fuckingfast.co",
+    HostPolicy {
+        max_concurrent: 2,
+        min_interval: Duration::from_millis(500),
+    },
+)];
+
+fn policy_for(host: &str) -> HostPolicy {
+    if let Some((_, policy)) = HOST_POLICIES
+        .iter()
+        .find(|(known, _)| host == *known || host.ends_with(&format!(".{known}")))
+    {
+        return *policy;
+    }
+    if site::is_site_url(&format!("https://{host}/")) {
+        SITE_POLICY
+    } else {
+        DEFAULT_POLICY
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("{host} keeps failing, requests paused for {}s", retry_in.as_secs().max(1))]
+    CircuitOpen { host: String, retry_in: Duration },
+}
+
+impl RequestError {
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, RequestError::Http(e) if e.is_timeout())
+    }
+
+    /// The host could not be reached, an open circuit included
+    pub fn is_connect(&self) -> bool {
+        match self {
+            RequestError::Http(e) => e.is_connect(),
+            RequestError::CircuitOpen { .. } => true,
+        }
+    }
+}
+
+/// Attempts left for one logical request, shared by the scheduler and the
+/// caller's own retries
+#[derive(Debug)]
+pub struct RetryBudget {
+    attempt: u32,
+    max_attempts: u32,
+}
+
+impl RetryBudget {
+    /// `max_attempts` counts the first one, `1` never retries
+    pub fn new(max_attempts: u32) -> Self {
+        RetryBudget {
+            attempt: 1,
+            max_attempts: max_attempts.max(1),
+        }
+    }
+
+    /// Delay before the next attempt, `None` once the budget is spent.
+    ///
+    /// `hint` is what the host asked for and replaces the backoff.
+    fn next_delay(&mut self, hint: Option<Duration>) -> Option<Duration> {
+        if self.attempt >= self.max_attempts {
+            return None;
+        }
+        let backoff = BASE_DELAY
+            .saturating_mul(2u32.saturating_pow(self.attempt - 1))
+            .min(MAX_DELAY);
+        self.attempt += 1;
+        Some(hint.unwrap_or_else(|| {
+            // equal jitter, never less than half the backoff
+            let half = backoff / 2;
+            half + half.mul_f64(rand::rng().random::<f64>())
+        }))
+    }
+
+    /// Wait before retrying a request whose response was unusable, e.g. a
+    /// body that did not decode. `false` when the budget is spent.
+    pub async fn backoff(&mut self, host: &str) -> bool {
+        let Some(delay) = self.next_delay(None) else {
+            return false;
+        };
+        host_state(host)
+            .metrics
+            .retries
+            .fetch_add(1, Ordering::Relaxed);
+        tokio::time::sleep(delay).await;
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CircuitState {
+    Closed,
+    /// Requests fail fast until the cooldown is over
+    Open,
+    /// Cooldown over, one trial request decides
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// The half-open trial was let through and has not settled yet
+    trial_in_flight: bool,
+}
+
+impl Circuit {
+    fn state(&self, now: Instant) -> CircuitState {
+        match self.open_until {
+            Some(until) if now < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+            None => CircuitState::Closed,
+        }
+    }
+
+    /// `Err` with the time left while open, or while the half-open trial is
+    /// in flight. `Ok(true)` lets the caller through as the trial.
+    fn admit(&mut self, now: Instant) -> Result<bool, Duration> {
+        match self.open_until {
+            Some(until) if now < until => Err(until - now),
+            Some(_) if self.trial_in_flight => Err(Duration::ZERO),
+            Some(_) => {
+                self.trial_in_flight = true;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn succeeded(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+        self.trial_in_flight = false;
+    }
+
+    /// `true` when this failure opened the circuit
+    fn failed(&mut self, now: Instant) -> bool {
+        self.trial_in_flight = false;
+        self.consecutive_failures += 1;
+        let was_open = self.open_until.is_some_and(|until| now < until);
+        if self.consecutive_failures >= FAILURE_THRESHOLD && !was_open {
+            // a failed trial in half-open state opens it again
+            self.open_until = Some(now + OPEN_FOR);
+            return true;
+        }
+        false
+    }
+}
+
+#[derive(Debug, Default)]
+struct Metrics {
+    requests: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    retries: AtomicU64,
+    throttled: AtomicU64,
+    rejected: AtomicU64,
+    in_flight: AtomicU32,
+    total_latency_ms: AtomicU64,
+}
+
+struct HostState {
+    policy: HostPolicy,
+    slots: Semaphore,
+    /// Earliest start of the next request
+    next_start: Mutex<Instant>,
+    circuit: Mutex<Circuit>,
+    metrics: Metrics,
+}
+
+impl HostState {
+    fn new(policy: HostPolicy) -> Self {
+        HostState {
+            policy,
+            slots: Semaphore::new(policy.max_concurrent),
+            next_start: Mutex::new(Instant::now()),
+            circuit: Mutex::new(Circuit::default()),
+            metrics: Metrics::default(),
+        }
+    }
+
+    async fn wait_turn(&self) {
+        let wait = {
+            let mut next_start = self.next_start.lock().unwrap();
+            let now = Instant::now();
+            let start = (*next_start).max(now);
+            *next_start = start + self.policy.min_interval;
+            start - now
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// No request starts before `delay` from now
+    fn defer(&self, delay: Duration) {
+        let mut next_start = self.next_start.lock().unwrap();
+        *next_start = (*next_start).max(Instant::now() + delay);
+    }
+
+    fn succeeded(&self) {
+        self.circuit.lock().unwrap().succeeded();
+        self.metrics.succeeded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn failed(&self, host: &str) {
+        if self.circuit.lock().unwrap().failed(Instant::now()) {
+            warn!("{host} failed {FAILURE_THRESHOLD} times in a row, pausing requests");
+        }
+    }
+}
+
+/// Gives the half-open trial back however the attempt ended, a 429 or a
+/// dropped future included, so the next request can be the trial
+struct Trial<'a>(&'a HostState);
+
+impl Drop for Trial<'_> {
+    fn drop(&mut self) {
+        self.0.circuit.lock().unwrap().trial_in_flight = false;
+    }
+}
+
+static HOSTS: LazyLock<Mutex<HashMap<String, Arc<HostState>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn host_state(host: &str) -> Arc<HostState> {
+    HOSTS
+        .lock()
+        .unwrap()
+        .entry(host.to_string())
+        .or_insert_with(|| Arc::new(HostState::new(policy_for(host))))
+        .clone()
+}
+
+fn retry_after(resp: &Response) -> Option<Duration> {
+    parse_retry_after(resp.headers().get(RETRY_AFTER)?.to_str().ok()?)
+}
+
+/// Only the delay-seconds form, an HTTP date falls back to the backoff
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504)
+}
+
+/// Pause `host` after the caller saw it throttling, e.g. a rate limit page
+/// served with 200
+pub fn throttle(host: &str, delay: Duration) {
+    let state = host_state(host);
+    state.metrics.throttled.fetch_add(1, Ordering::Relaxed);
+    state.defer(delay);
+}
+
+/// Send `request` with `client`, retrying within `budget`.
+///
+/// A retryable status that is still there when the budget is spent is
+/// returned as a response, like any other status. The concurrency slot is
+/// held until the response headers arrived.
+pub async fn send(
+    client: &Client,
+    request: Request,
+    budget: &mut RetryBudget,
+) -> Result<Response, RequestError> {
+    let host = request
+        .url()
+        .host_str()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let state = host_state(&host);
+    let mut request = Some(request);
+
+    loop {
+        let admitted = state.circuit.lock().unwrap().admit(Instant::now());
+        let trial = match admitted {
+            Ok(is_trial) => is_trial.then(|| Trial(&state)),
+            Err(retry_in) => {
+                state.metrics.rejected.fetch_add(1, Ordering::Relaxed);
+                return Err(RequestError::CircuitOpen { host, retry_in });
+            }
+        };
+
+        // a streamed body can only be sent once
+        let Some(pending) = request.take() else {
+            unreachable!("the last attempt always returns");
+        };
+        let (attempt, last) = match pending.try_clone() {
+            Some(attempt) => {
+                request = Some(pending);
+                (attempt, false)
+            }
+            None => (pending, true),
+        };
+
+        let result = {
+            let _slot = state.slots.acquire().await;
+            state.wait_turn().await;
+            state.metrics.requests.fetch_add(1, Ordering::Relaxed);
+            state.metrics.in_flight.fetch_add(1, Ordering::Relaxed);
+            let started = Instant::now();
+            let result = client.execute(attempt).await;
+            state.metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+            state
+                .metrics
+                .total_latency_ms
+                .fetch_add(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+            result
+        };
+
+        let (wait, error) = match result {
+            Ok(resp) if is_retryable_status(resp.status()) => {
+                let status = resp.status();
+                let hint = retry_after(&resp);
+                if status == StatusCode::TOO_MANY_REQUESTS || hint.is_some() {
+                    state.metrics.throttled.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    state.failed(&host);
+                }
+                let delay = match hint {
+                    Some(hint) if hint > MAX_RETRY_AFTER => None,
+                    _ if last => None,
+                    hint => budget.next_delay(hint),
+                };
+                let Some(delay) = delay else {
+                    state.metrics.failed.fetch_add(1, Ordering::Relaxed);
+                    return Ok(resp);
+                };
+                if hint.is_some() {
+                    state.defer(delay);
+                }
+                info!("{host} answered {status}, retrying in {delay:?}");
+                (delay, None)
+            }
+            Ok(resp) => {
+                state.succeeded();
+                return Ok(resp);
+            }
+            Err(e) => {
+                let transient = e.is_connect() || e.is_timeout();
+                if transient {
+                    state.failed(&host);
+                }
+                match budget.next_delay(None).filter(|_| transient && !last) {
+                    Some(delay) => (delay, Some(e)),
+                    None => {
+                        state.metrics.failed.fetch_add(1, Ordering::Relaxed);
+                        return Err(e.into());
+                    }
+                }
+            }
+        };
+
+        if let Some(e) = error {
+            warn!("{host}: {e}, retrying in {wait:?}");
+        }
+        drop(trial);
+        state.metrics.retries.fetch_add(1, Ordering::Relaxed);
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct HostMetrics {
+    pub host: String,
+    pub max_concurrent: u32,
+    pub min_interval_ms: u64,
+    /// Attempts sent, retries included
+    pub requests: u64,
+    pub succeeded: u64,
+    /// Requests that gave up, with an error or a retryable status
+    pub failed: u64,
+    pub retries: u64,
+    /// 429, `Retry-After` or a rate limit page
+    pub throttled: u64,
+    /// Failed fast on an open circuit
+    pub rejected: u64,
+    pub in_flight: u32,
+    pub average_latency_ms: u64,
+    pub circuit: CircuitState,
+    pub consecutive_failures: u32,
+}
+
+/// Counters of every host requested since the start, by host
+pub fn metrics() -> Vec<HostMetrics> {
+    let now = Instant::now();
+    let mut metrics: Vec<HostMetrics> = HOSTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(host, state)| {
+            let m = &state.metrics;
+            let requests = m.requests.load(Ordering::Relaxed);
+            let circuit = state.circuit.lock().unwrap();
+            HostMetrics {
+                host: host.clone(),
+                max_concurrent: state.policy.max_concurrent as u32,
+                min_interval_ms: state.policy.min_interval.as_millis() as u64,
+                requests,
+                succeeded: m.succeeded.load(Ordering::Relaxed),
+                failed: m.failed.load(Ordering::Relaxed),
+                retries: m.retries.load(Ordering::Relaxed),
+                throttled: m.throttled.load(Ordering::Relaxed),
+                rejected: m.rejected.load(Ordering::Relaxed),
+                in_flight: m.in_flight.load(Ordering::Relaxed),
+                average_latency_ms: m.total_latency_ms.load(Ordering::Relaxed) / requests.max(1),
+                circuit: circuit.state(now),
+                consecutive_failures: circuit.consecutive_failures,
+            }
+        })
+        .collect();
+    metrics.sort_by(|a, b| a.host.cmp(&b.host));
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Requests seen by a [`serve`] listener
+    #[derive(Default)]
+    struct Hits {
+        count: AtomicU32,
+        in_flight: AtomicU32,
+        max_in_flight: AtomicU32,
+    }
+
+    /// Answers the `n`th request with `respond(n)`, a status line and
+    /// headers, after `delay`
+    async fn serve(delay: Duration, respond: fn(u32) -> &'static str) -> (SocketAddr, Arc<Hits>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let hits = Arc::new(Hits::default());
+        let counters = hits.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let hits = counters.clone();
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !head.ends_with(b"\r\n\r\n") {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => head.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let n = hits.count.fetch_add(1, Ordering::SeqCst);
+                    let in_flight = hits.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    hits.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                    tokio::time::sleep(delay).await;
+                    // before answering, the client only frees its slot after
+                    hits.in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let response = format!(
+                        "{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        respond(n)
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        (addr, hits)
+    }
+
+    /// A client sending `host` to `addr`, with its own scheduler state so
+    /// the tests do not share a circuit
+    fn client_for(host: &str, addr: SocketAddr, policy: HostPolicy) -> (Client, String) {
+        HOSTS
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), Arc::new(HostState::new(policy)));
+        let client = Client::builder().resolve(host, addr).build().unwrap();
+        (client, format!("http://{host}:{}/", addr.port()))
+    }
+
+    async fn get(client: &Client, url: &str, attempts: u32) -> Result<Response, RequestError> {
+        let request = client.get(url).build().unwrap();
+        send(client, request, &mut RetryBudget::new(attempts)).await
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_waited_for() {
+        let (addr, hits) = serve(Duration::ZERO, |n| match n {
+            0 => "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 1",
+            _ => "HTTP/1.1 200 OK",
+        })
+        .await;
+        let (client, url) = client_for("retry-after.test", addr, DEFAULT_POLICY);
+
+        let started = Instant::now();
+        let resp = get(&client, &url, 3).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(hits.count.load(Ordering::SeqCst), 2);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        let state = host_state("retry-after.test");
+        assert_eq!(state.metrics.throttled.load(Ordering::Relaxed), 1);
+        // a rate limit is not a failure of the host
+        assert_eq!(state.circuit.lock().unwrap().consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn long_retry_after_is_returned_at_once() {
+        let (addr, hits) = serve(
+            Duration::ZERO,
+            |_| "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 3600",
+        )
+        .await;
+        let (client, url) = client_for("long-retry-after.test", addr, DEFAULT_POLICY);
+
+        let resp = get(&client, &url, 3).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(hits.count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn spent_budget_returns_the_last_response() {
+        let (addr, hits) = serve(Duration::ZERO, |_| "HTTP/1.1 503 Service Unavailable").await;
+        let (client, url) = client_for("spent-budget.test", addr, DEFAULT_POLICY);
+
+        let resp = get(&client, &url, 2).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(hits.count.load(Ordering::SeqCst), 2);
+
+        let state = host_state("spent-budget.test");
+        assert_eq!(state.metrics.retries.load(Ordering::Relaxed), 1);
+        assert_eq!(state.metrics.failed.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn open_circuit_fails_fast() {
+        let (addr, hits) = serve(Duration::ZERO, |_| "HTTP/1.1 500 Internal Server Error").await;
+        let (client, url) = client_for("open-circuit.test", addr, DEFAULT_POLICY);
+
+        for _ in 0..FAILURE_THRESHOLD {
+            let resp = get(&client, &url, 1).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        let err = get(&client, &url, 3).await.unwrap_err();
+        assert!(matches!(err, RequestError::CircuitOpen { .. }), "{err}");
+        assert!(err.is_connect());
+        assert_eq!(hits.count.load(Ordering::SeqCst), FAILURE_THRESHOLD);
+    }
+
+    #[tokio::test]
+    async fn half_open_lets_one_trial_through() {
+        let (addr, hits) = serve(Duration::from_millis(200), |_| "HTTP/1.1 200 OK").await;
+        let (client, url) = client_for("half-open.test", addr, DEFAULT_POLICY);
+        {
+            let state = host_state("half-open.test");
+            let mut circuit = state.circuit.lock().unwrap();
+            circuit.consecutive_failures = FAILURE_THRESHOLD;
+            circuit.open_until = Some(Instant::now());
+        }
+
+        let results = send_together(&client, &url, 3).await;
+        let passed = results.iter().filter(|result| result.is_ok()).count();
+        assert_eq!(passed, 1);
+        assert_eq!(hits.count.load(Ordering::SeqCst), 1);
+
+        // the trial succeeded, the circuit is closed again
+        assert!(get(&client, &url, 1).await.is_ok());
+        assert_eq!(hits.count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrency_is_capped_per_host() {
+        let (addr, hits) = serve(Duration::from_millis(100), |_| "HTTP/1.1 200 OK").await;
+        let policy = HostPolicy {
+            max_concurrent: 2,
+            min_interval: Duration::ZERO,
+        };
+        let (client, url) = client_for("concurrency.test", addr, policy);
+
+        let results = send_together(&client, &url, 6).await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(hits.count.load(Ordering::SeqCst), 6);
+        assert_eq!(hits.max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    /// `count` requests to `url` at the same time
+    async fn send_together(
+        client: &Client,
+        url: &str,
+        count: usize,
+    ) -> Vec<Result<Response, RequestError>> {
+        let tasks: Vec<_> = (0..count)
+            .map(|_| {
+                let (client, url) = (client.clone(), url.to_string());
+                tokio::spawn(async move { get(&client, &url, 1).await })
+            })
+            .collect();
+        let mut results = Vec::new();
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+        results
+    }
+
+    #[test]
+    fn backoff_grows_until_the_budget_is_spent() {
+        let mut budget = RetryBudget::new(4);
+        let delays: Vec<Duration> = std::iter::from_fn(|| budget.next_delay(None)).collect();
+        assert_eq!(delays.len(), 3);
+        for (delay, backoff) in delays.iter().zip([500, 1000, 2000]) {
+            let backoff = Duration::from_millis(backoff);
+            assert!(*delay >= backoff / 2 && *delay <= backoff, "{delay:?}");
+        }
+
+        let mut budget = RetryBudget::new(2);
+        assert_eq!(
+            budget.next_delay(Some(Duration::from_secs(3))),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(budget.next_delay(None), None);
+    }
+
+    #[test]
+    fn circuit_opens_after_repeated_failures() {
+        let start = Instant::now();
+        let mut circuit = Circuit::default();
+        for _ in 1..FAILURE_THRESHOLD {
+            assert!(!circuit.failed(start));
+        }
+        assert!(circuit.failed(start));
+        assert_eq!(circuit.state(start), CircuitState::Open);
+        assert_eq!(circuit.admit(start), Err(OPEN_FOR));
+
+        let later = start + OPEN_FOR;
+        assert_eq!(circuit.state(later), CircuitState::HalfOpen);
+        assert_eq!(circuit.admit(later), Ok(true));
+        // one trial at a time
+        assert_eq!(circuit.admit(later), Err(Duration::ZERO));
+        // the trial failed
+        assert!(circuit.failed(later));
+        assert_eq!(circuit.state(later), CircuitState::Open);
+
+        circuit.succeeded();
+        assert_eq!(circuit.state(later), CircuitState::Closed);
+    }
+
+    #[test]
+    fn policies_follow_the_host() {
+        assert_eq!(policy_for("fuckingfast.co").max_concurrent, 2);
+        assert_eq!(policy_for("cdn.fuckingfast.co").max_concurrent, 2);
+        assert_eq!(policy_for("notfuckingfast.co"), DEFAULT_POLICY);
+        assert_eq!(parse_retry_after(" 12 "), Some(Duration::from_secs(12)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! Registry of the origins the FitGirl site is reachable at.
+//!
+//! Everything stored or hashed uses [`CANONICAL_ORIGIN`], whatever domain it
+//! was scraped from, so `hash_url` keys stay the same when the site moves.
+//! Requests go to the configured base URL first and fail over to the mirrors
+//! on connection, DNS, timeout or 5xx errors. Each origin is sent through
+//! the [`scheduler`](crate::client::scheduler), which retries it a little
+//! before the next one is tried.
+
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+use reqwest::{Client, Response};
+use tauri::async_runtime::spawn;
+use tracing::{info, warn};
+
+use crate::client::connectivity;
+use crate::client::scheduler::{self, RequestError, RetryBudget};
+use crate::settings::commands::get_gamehub_settings;
+use crate::settings::creation::SiteSettings;
+
+pub const CANONICAL_ORIGIN: &str = "https://fitgirl-repacks.site";
+
+/// Per origin, a dead mirror must not eat the whole budget of the caller
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Attempts per origin before failing over
+const ATTEMPTS_PER_ORIGIN: u32 = 2;
+
+struct Registry {
+    /// Base URL first, then the mirrors
+    origins: Vec<String>,
+    /// Index of the origin that answered last
+    active: usize,
+}
+
+impl Registry {
+    fn new(settings: &SiteSettings) -> Self {
+        let mut origins: Vec<String> = Vec::new();
+        for origin in std::iter::once(&settings.base_url).chain(&settings.mirrors) {
+            match clean_origin(origin) {
+                Some(origin) if !origins.contains(&origin) => origins.push(origin),
+                Some(_) => {}
+                None => warn!("Ignoring invalid site origin {origin:?}"),
+            }
+        }
+        if origins.is_empty() {
+            origins.push(CANONICAL_ORIGIN.to_string());
+        }
+        Registry { origins, active: 0 }
+    }
+
+    fn is_site_host(&self, host: &str) -> bool {
+        let host = bare_host(host);
+        host == bare_host(host_of(CANONICAL_ORIGIN))
+            || self
+                .origins
+                .iter()
+                .any(|origin| bare_host(host_of(origin)) == host)
+    }
+
+    fn is_site_url(&self, url: &str) -> bool {
+        split_origin(url)
+            .is_some_and(|(origin, _)| self.is_site_host(&host_of(origin).to_ascii_lowercase()))
+    }
+
+    fn normalize_href(&self, href: &str) -> String {
+        let href = href.trim();
+        if href.starts_with('/') && !href.starts_with("//") {
+            return format!("{CANONICAL_ORIGIN}{href}");
+        }
+        match split_origin(href) {
+            Some((_, path)) if self.is_site_url(href) => format!("{CANONICAL_ORIGIN}{path}"),
+            _ => href.to_string(),
+        }
+    }
+
+    fn endpoint_urls(&self, url: &str) -> Vec<String> {
+        let Some((_, path)) = split_origin(url).filter(|_| self.is_site_url(url)) else {
+            return vec![url.to_string()];
+        };
+        let count = self.origins.len();
+        (0..count)
+            .map(|i| &self.origins[(self.active + i) % count])
+            .map(|origin| format!("{origin}{path}"))
+            .collect()
+    }
+}
+
+static REGISTRY: LazyLock<RwLock<Registry>> =
+    LazyLock::new(|| RwLock::new(Registry::new(&get_gamehub_settings().site)));
+
+/// Called when the gamehub settings change
+pub fn reload_site_endpoints(settings: &SiteSettings) {
+    *REGISTRY.write().unwrap() = Registry::new(settings);
+}
+
+/// The origin requests currently go to
+pub fn active_origin() -> String {
+    let registry = REGISTRY.read().unwrap();
+    registry.origins[registry.active].clone()
+}
+
+/// `https://host[:port]` without a trailing slash, `None` if it is not an http(s) URL
+fn clean_origin(origin: &str) -> Option<String> {
+    let origin = origin.trim().trim_end_matches('/').to_ascii_lowercase();
+    let (scheme, host) = origin.split_once("://")?;
+    (matches!(scheme, "http" | "https") && !host.is_empty() && !host.contains('/'))
+        .then_some(origin)
+}
+
+/// Splits `https://host/path?q` into `("https://host", "/path?q")`
+fn split_origin(url: &str) -> Option<(&str, &str)> {
+    let scheme_end = url.find("://")? + 3;
+    let path_start = url[scheme_end..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |i| scheme_end + i);
+    Some((&url[..path_start], &url[path_start..]))
+}
+
+fn host_of(origin: &str) -> &str {
+    origin.split_once("://").map_or(origin, |(_, host)| host)
+}
+
+fn bare_host(host: &str) -> &str {
+    host.strip_prefix("www.").unwrap_or(host)
+}
+
+/// Whether `url` points at the site, on any known origin
+pub fn is_site_url(url: &str) -> bool {
+    REGISTRY.read().unwrap().is_site_url(url)
+}
+
+/// Rewrites a site URL, or a path relative to the site, onto [`CANONICAL_ORIGIN`].
+///
+/// Other URLs, the paste site included, are returned untouched.
+pub fn normalize_href(href: &str) -> String {
+    REGISTRY.read().unwrap().normalize_href(href)
+}
+
+/// The URLs to try for `url`, on the active origin first
+pub fn endpoint_urls(url: &str) -> Vec<String> {
+    REGISTRY.read().unwrap().endpoint_urls(url)
+}
+
+fn mark_active(url: &str) {
+    let Some((origin, _)) = split_origin(url) else {
+        return;
+    };
+    let mut registry = REGISTRY.write().unwrap();
+    if let Some(index) = registry.origins.iter().position(|o| o == origin)
+        && index != registry.active
+    {
+        info!("Site is now reached through {origin}");
+        registry.active = index;
+    }
+}
+
+/// GET `url`, trying every origin of the site in turn when it is a site URL.
+///
+/// The last error or 5xx response is returned when no origin works.
+pub async fn get_with_failover(client: &Client, url: &str) -> Result<Response, RequestError> {
+    let is_site = is_site_url(url);
+    let candidates = endpoint_urls(url);
+    let last = candidates.len() - 1;
+    for (i, candidate) in candidates.iter().enumerate() {
+        let request = client.get(candidate).timeout(REQUEST_TIMEOUT).build()?;
+        let mut budget = RetryBudget::new(ATTEMPTS_PER_ORIGIN);
+        match scheduler::send(client, request, &mut budget).await {
+            Ok(resp) if resp.status().is_server_error() && i < last => {
+                warn!("{candidate} answered {}, trying next mirror", resp.status());
+            }
+            Err(e) if (e.is_connect() || e.is_timeout()) && i < last => {
+                warn!("{candidate} unreachable, trying next mirror: {e}");
+            }
+            Ok(resp) => {
+                if is_site && !resp.status().is_server_error() {
+                    mark_active(candidate);
+                    connectivity::set_reachable(true);
+                }
+                return Ok(resp);
+            }
+            Err(e) => {
+                if is_site
+                    && (e.is_connect() || e.is_timeout())
+                    && connectivity::set_reachable(false)
+                {
+                    // the site alone may be down, downloads can go on if so
+                    let client = client.clone();
+                    spawn(async move { connectivity::probe_network(&client).await });
+                }
+                return Err(e);
+            }
+        }
+    }
+    unreachable!("endpoint_urls returns at least one URL")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A registry of its own, the global one is shared by every test thread
+    fn with_mirror() -> Registry {
+        Registry::new(&SiteSettings {
+            base_url: "https://FitGirl-Repacks.site/".into(),
+            mirrors: vec!["https://fitgirl-mirror.example".into(), "not a url".into()],
+        })
+    }
+
+    #[test]
+    fn hrefs_normalize_onto_the_canonical_origin() {
+        let registry = with_mirror();
+        for href in [
+            "https://fitgirl-repacks.site/game/",
+            "http://www.fitgirl-repacks.site/game/",
+            "https://fitgirl-mirror.example/game/",
+            "/game/",
+        ] {
+            assert_eq!(
+                registry.normalize_href(href),
+                "https://fitgirl-repacks.site/game/"
+            );
+        }
+        assert_eq!(
+            registry.normalize_href("https://paste.fitgirl-repacks.site/?abc"),
+            "https://paste.fitgirl-repacks.site/?abc"
+        );
+    }
+
+    #[test]
+    fn endpoints_cover_every_origin() {
+        let registry = with_mirror();
+        assert_eq!(
+            registry.endpoint_urls("https://fitgirl-repacks.site/page/2"),
+            [
+                "https://fitgirl-repacks.site/page/2",
+                "https://fitgirl-mirror.example/page/2"
+            ]
+        );
+        assert_eq!(
+            registry.endpoint_urls("https://i.imageban.ru/a.jpg"),
+            ["https://i.imageban.ru/a.jpg"]
+        );
+    }
+}
@@ -7,9 +7,12 @@ use tracing::error;
 use tracing::info;
 
 use crate::SettingsConfigurationError;
+use crate::client::connectivity::{self, Connectivity};
 use crate::client::cookies;
 use crate::client::cookies::Cookies;
 use crate::client::dns::FitLauncherDnsConfig;
+use crate::client::scheduler::{self, HostMetrics};
+use crate::client::site;
 
 use super::creation::GamehubSettings;
 use super::creation::InstallationSettings;
@@ -68,6 +71,28 @@ pub fn get_gamehub_settings() -> GamehubSettings {
     serde_json::from_str::<GamehubSettings>(&file_content).unwrap_or_default()
 }
 
+#[tauri::command]
+#[specta]
+pub fn get_connectivity() -> Connectivity {
+    connectivity::connectivity()
+}
+
+/// Switch the offline mode and save it in the gamehub settings
+#[tauri::command]
+#[specta]
+pub fn set_offline_mode(enabled: bool) -> Result<(), SettingsConfigurationError> {
+    let mut settings = get_gamehub_settings();
+    settings.offline_mode = enabled;
+    change_gamehub_settings(settings)
+}
+
+/// Per host counters of the request scheduler, for diagnostics
+#[tauri::command]
+#[specta]
+pub fn get_request_metrics() -> Vec<HostMetrics> {
+    scheduler::metrics()
+}
+
 #[tauri::command]
 #[specta]
 pub fn get_dns_settings() -> FitLauncherDnsConfig {
@@ -188,6 +213,8 @@ pub fn change_gamehub_settings(
 
     fs::write(installation_file_path, settings_json_string)
         .map_err(SettingsConfigurationError::from)?;
+    site::reload_site_endpoints(&settings.site);
+    connectivity::set_forced_offline(settings.offline_mode);
     Ok(())
 }
 
@@ -251,10 +278,13 @@ pub fn reset_gamehub_settings() -> Result<(), SettingsConfigurationError> {
         .join("gamehub")
         .join("gamehub.json");
 
-    let settings_json_string = serde_json::to_string_pretty(&GamehubSettings::default())
-        .map_err(SettingsConfigurationError::from)?;
+    let settings = GamehubSettings::default();
+    let settings_json_string =
+        serde_json::to_string_pretty(&settings).map_err(SettingsConfigurationError::from)?;
 
     fs::write(gamehub_file_path, settings_json_string).map_err(SettingsConfigurationError::from)?;
+    site::reload_site_endpoints(&settings.site);
+    connectivity::set_forced_offline(settings.offline_mode);
     Ok(())
 }
 
@@ -362,9 +392,7 @@ pub async fn clear_all_cache(app: tauri::AppHandle) -> Result<(), SettingsConfig
 /// ]
 /// ```
 ///
-/// Extra fields are allowed.
-///
-/// Also, to make effect, an restart is required.
+/// Extra fields are allowed. A cookie without a `domain` is sent to the site.
 #[tauri::command]
 #[specta]
 pub async fn import_cookies_file(json_path: String) -> Result<(), String> {
@@ -381,14 +409,11 @@ pub async fn import_cookies_file(json_path: String) -> Result<(), String> {
 #[tauri::command]
 #[specta]
 pub async fn import_cookies(cookies: Cookies) -> Result<(), String> {
-    tokio::fs::create_dir_all(cookies::Cookies::default_dir())
-        .await
-        .map_err(|e| e.to_string())?;
-    let json = serde_json::to_string_pretty(&cookies).map_err(|e| e.to_string())?;
-    tokio::fs::write(cookies::Cookies::default_path(), &json)
+    // the jar saves them, and the client uses them from the next request
+    spawn_blocking(move || cookies::COOKIE_JAR.replace(cookies))
         .await
-        .map_err(|e| e.to_string())?;
-    Ok(())
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -31,6 +31,13 @@ pub struct GamehubSettings {
     pub auto_get_colors_popular_games: bool,
     pub close_to_tray: bool,
     pub game_page_allow_comments: bool,
+    #[serde(default)]
+    pub catalog_crawler: CatalogCrawlerSettings,
+    #[serde(default)]
+    pub site: SiteSettings,
+    /// Serve local data only and hold network writes, see [`crate::client::connectivity`]
+    #[serde(default)]
+    pub offline_mode: bool,
 }
 
 impl Default for GamehubSettings {
@@ -40,6 +47,50 @@ impl Default for GamehubSettings {
             auto_get_colors_popular_games: false,
             close_to_tray: true,
             game_page_allow_comments: false,
+            catalog_crawler: CatalogCrawlerSettings::default(),
+            site: SiteSettings::default(),
+            offline_mode: false,
+        }
+    }
+}
+
+/// Background scraping of the games only known from the sitemaps
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct CatalogCrawlerSettings {
+    pub enabled: bool,
+    /// Game pages fetched per minute at most
+    pub pages_per_minute: u32,
+    pub pause_while_downloading: bool,
+    pub pause_on_metered: bool,
+}
+
+impl Default for CatalogCrawlerSettings {
+    fn default() -> Self {
+        CatalogCrawlerSettings {
+            enabled: true,
+            pages_per_minute: 6,
+            pause_while_downloading: true,
+            pause_on_metered: true,
+        }
+    }
+}
+
+/// Where the FitGirl site is reached, the base URL first and then the mirrors
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct SiteSettings {
+    /// Origin tried first, like `https://fitgirl-repacks.site`
+    pub base_url: String,
+    /// Origins tried in order when the base URL cannot be reached
+    pub mirrors: Vec<String>,
+}
+
+impl Default for SiteSettings {
+    fn default() -> Self {
+        SiteSettings {
+            base_url: crate::client::site::CANONICAL_ORIGIN.to_string(),
+            mirrors: Vec::new(),
         }
     }
 }
@@ -5,12 +5,20 @@ use crate::{
     functions::{get_all_download_links, parse_size_to_bytes},
     structs::{DirectLink, FUCKINGFAST_DDL_REGEX},
 };
-use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
+use fit_launcher_config::client::{
+    dns::CUSTOM_DNS_CLIENT,
+    scheduler::{self, RetryBudget},
+};
 use futures::{StreamExt, stream::FuturesUnordered};
 use reqwest::{Method, Request};
 use specta::specta;
 use tracing::{error, info, warn};
 
+/// Attempts of a download page, retries included
+const PAGE_ATTEMPTS: u32 = 5;
+/// How long the host is left alone after a rate limit page
+const RATE_LIMIT_PAUSE: Duration = Duration::from_secs(5);
+
 #[tauri::command]
 #[specta]
 pub async fn extract_fuckingfast_ddl(fuckingfast_links: Vec<String>) -> Vec<DirectLink> {
@@ -22,31 +30,34 @@ pub async fn extract_fuckingfast_ddl(fuckingfast_links: Vec<String>) -> Vec<Dire
                 Method::GET,
                 link.parse().map_err(|_| anyhow::anyhow!("invalid URL"))?,
             );
-            let mut i = 0;
-            loop {
-                i += 1;
+            let host = request.url().host_str().unwrap_or_default().to_string();
+            let filename = link.split_once('#').unwrap_or_default().1.to_string();
+            let client = CUSTOM_DNS_CLIENT.read().await.clone();
 
-                let sleep = Duration::from_millis((500 * 2_u64.pow(i)).min(4000));
-                match CUSTOM_DNS_CLIENT
-                    .read()
-                    .await
-                    .execute(request.try_clone().unwrap())
-                    .await
-                {
-                    Err(e) if i < 5 => {
-                        error!("retry: {e}, sleep...");
-                        tokio::time::sleep(sleep).await;
-                    }
+            // per host limits and retries, see `scheduler`
+            let mut budget = RetryBudget::new(PAGE_ATTEMPTS);
+            loop {
+                let resp =
+                    scheduler::send(&client, request.try_clone().unwrap(), &mut budget).await?;
+                let text = match resp.text().await {
+                    Ok(text) => text,
                     Err(e) => {
-                        break Result::<_, anyhow::Error>::Err(e.into());
-                    }
-                    Ok(resp) => {
-                        if let Ok(text) = resp.text().await {
-                            let filename = link.split_once('#').unwrap_or_default().1.to_string();
-                            break Ok((text, filename));
+                        warn!("retry {link}: {e}");
+                        if budget.backoff(&host).await {
+                            continue;
                         }
+                        break Err(e.into());
+                    }
+                };
+                // served with 200, so the scheduler cannot tell by itself
+                if text.contains("rate limit") {
+                    warn!("rate limited on {link}");
+                    scheduler::throttle(&host, RATE_LIMIT_PAUSE);
+                    if budget.backoff(&host).await {
+                        continue;
                     }
                 }
+                break Result::<_, anyhow::Error>::Ok((text, filename));
             }
         };
         futures.push(fut);
@@ -1,13 +1,10 @@
 use anyhow::Result;
-use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
+use fit_launcher_config::client::{dns::CUSTOM_DNS_CLIENT, site};
 use fit_launcher_scraping::errors::ScrapingError;
 
 pub(crate) async fn get_all_download_links(url: String) -> Result<Vec<String>, Box<ScrapingError>> {
-    let response = CUSTOM_DNS_CLIENT
-        .read()
-        .await
-        .get(&url)
-        .send()
+    let client = CUSTOM_DNS_CLIENT.read().await.clone();
+    let response = site::get_with_failover(&client, &url)
         .await
         .map_err(|e| {
             eprintln!("Failed to get response from URL: {}", &url);
@@ -1,32 +1,48 @@
 use std::{sync::Arc, time::Duration};
 
 use anyhow::Result;
-use aria2_ws::{Client, response::Status};
+use aria2_ws::{
+    Client,
+    response::{Peer, Status},
+};
+use async_trait::async_trait;
 use fit_launcher_aria2::{
-    aria2::{aria2_add_torrent, aria2_add_uri},
+    aria2::{aria2_add_torrent, aria2_add_uri, aria2_select_files},
     error::Aria2Error,
 };
 use fit_launcher_torrent::{FitLauncherConfigAria2, functions::TorrentSession};
-use sha2::Digest;
 use tokio::sync::Mutex;
-use tracing::{error, info, warn};
-
-use crate::manager::DownloadManager;
+use tracing::{info, warn};
 
 /// Timeout for aria2 operations (health check uses a shorter timeout)
 const OPERATION_TIMEOUT: Duration = Duration::from_secs(15);
 const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
 
+/// Hands out a fresh aria2 client once the current connection went stale
+#[async_trait]
+pub trait Aria2Connector: Send + Sync {
+    async fn connect(&self) -> Result<Client>;
+}
+
+#[async_trait]
+impl Aria2Connector for TorrentSession {
+    async fn connect(&self) -> Result<Client> {
+        // Reinitialize the aria2 client (restarting the daemon if needed)
+        self.init_client().await?;
+        self.aria2_client().await
+    }
+}
+
 #[derive(Clone)]
 pub struct Aria2WsClient {
     pub client: Arc<Mutex<Client>>,
-    /// Reference to TorrentSession for reconnection capability
-    session: Arc<TorrentSession>,
+    /// Where a new client comes from when reconnecting, the TorrentSession in the app
+    connector: Arc<dyn Aria2Connector>,
 }
 
 impl Aria2WsClient {
-    pub fn new(client: Arc<Mutex<Client>>, session: Arc<TorrentSession>) -> Self {
-        Self { client, session }
+    pub fn new(client: Arc<Mutex<Client>>, connector: Arc<dyn Aria2Connector>) -> Self {
+        Self { client, connector }
     }
 
     /// Check if the connection is healthy by doing a quick version call
@@ -53,16 +69,9 @@ impl Aria2WsClient {
 
         info!("Aria2 connection is stale, attempting reconnection...");
 
-        // Reinitialize the aria2 client through TorrentSession
-        self.session
-            .init_client()
-            .await
-            .map_err(|e| Aria2Error::InitializationFailed(e.to_string()))?;
-
-        // Get the new client and update our reference
         let new_client = self
-            .session
-            .aria2_client()
+            .connector
+            .connect()
             .await
             .map_err(|e| Aria2Error::InitializationFailed(e.to_string()))?;
 
@@ -119,6 +128,18 @@ impl Aria2WsClient {
         }
     }
 
+    pub async fn select_files(&self, gid: &str, selected: Vec<usize>) -> Result<(), Aria2Error> {
+        let guard = self.client.lock().await;
+        match tokio::time::timeout(OPERATION_TIMEOUT, aria2_select_files(&guard, gid, selected))
+            .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(Aria2Error::Timeout(
+                "select_files operation timed out".into(),
+            )),
+        }
+    }
+
     pub async fn pause(&self, gid: &str) -> Result<(), Aria2Error> {
         let guard = self.client.lock().await;
 
@@ -155,6 +176,25 @@ impl Aria2WsClient {
         }
     }
 
+    pub async fn status(&self, gid: &str) -> Result<Status, Aria2Error> {
+        let guard = self.client.lock().await;
+
+        match tokio::time::timeout(OPERATION_TIMEOUT, guard.tell_status(gid)).await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(Aria2Error::Timeout("tell_status timed out".into())),
+        }
+    }
+
+    /// Peers of a BitTorrent download
+    pub async fn peers(&self, gid: &str) -> Result<Vec<Peer>, Aria2Error> {
+        let guard = self.client.lock().await;
+
+        match tokio::time::timeout(OPERATION_TIMEOUT, guard.get_peers(gid)).await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(Aria2Error::Timeout("get_peers timed out".into())),
+        }
+    }
+
     /// Fetch all (active + waiting + stopped)
     pub async fn list_all(&self) -> Result<Vec<Status>, Aria2Error> {
         let client = self.client.lock().await;
@@ -184,51 +224,4 @@ impl Aria2WsClient {
 
         Ok(list)
     }
-
-    /// Spawn a background polling task that feeds DownloadManager
-    pub fn spawn_status_listener(self, manager: Arc<DownloadManager>) {
-        tokio::spawn(async move {
-            let mut last_hash = String::new();
-            let mut consecutive_errors = 0u32;
-
-            loop {
-                let statuses_result = self.list_all().await;
-
-                match statuses_result {
-                    Ok(statuses) => {
-                        consecutive_errors = 0;
-                        if let Ok(serialized) = serde_json::to_string(&statuses) {
-                            let hash = format!("{:x}", sha2::Sha256::digest(serialized.as_bytes()));
-                            if hash != last_hash {
-                                last_hash = hash.clone();
-
-                                manager.on_aria2_update(statuses).await;
-                            }
-                        }
-                    }
-                    Err(err) => {
-                        consecutive_errors += 1;
-                        error!(
-                            "Aria2 status poll error (consecutive: {}): {:?}",
-                            consecutive_errors, err
-                        );
-
-                        // If we have multiple consecutive errors, try to reconnect
-                        if consecutive_errors >= 3 {
-                            warn!(
-                                "Multiple consecutive aria2 poll errors, attempting reconnection..."
-                            );
-                            if let Err(e) = self.ensure_connected().await {
-                                error!("Failed to reconnect aria2: {:?}", e);
-                            } else {
-                                consecutive_errors = 0;
-                            }
-                        }
-                    }
-                }
-
-                tokio::time::sleep(Duration::from_millis(500)).await;
-            }
-        });
-    }
 }
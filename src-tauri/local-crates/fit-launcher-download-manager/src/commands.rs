@@ -1,15 +1,27 @@
-use crate::{manager::DownloadManager, types::*};
+use crate::{
+    details::JobDetails,
+    error::DownloadManagerError,
+    manager::DownloadManager,
+    outbox::{JobSubmission, OUTBOX_UPDATED_EVENT, OutboxEntry, OutboxRequest},
+    pipeline::extract_job_archives,
+    preflight::{PreflightReport, PreflightVerdict},
+    stats::{DownloadStats, JobRecord, StatsExport},
+    supervisor::{Aria2Supervisor, DaemonHealth},
+    types::*,
+};
+use chrono::NaiveDate;
+use fit_launcher_config::client::connectivity;
 use fit_launcher_ddl::DirectLink;
 use fit_launcher_scraping::structs::Game;
 #[cfg(windows)]
 use fit_launcher_ui_automation::controller_manager::ControllerManager;
 use fit_launcher_ui_automation::{
-    InstallationError, api::InstallationManager, errors::ExtractError, extract_archive,
+    InstallationError, api::InstallationManager, errors::ExtractError,
 };
 use specta::specta;
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc};
 use tauri::State;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 #[tauri::command]
@@ -25,12 +37,103 @@ pub async fn dm_add_ddl_job(
     files: Vec<DirectLink>,
     target: String,
     game: Game,
+    ignore_space_check: bool,
+) -> Result<JobSubmission, String> {
+    submit_or_queue(
+        dm.inner(),
+        OutboxRequest::AddDdlJob {
+            files,
+            target,
+            game,
+            ignore_space_check,
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+#[specta]
+pub async fn dm_add_torrent_job(
+    dm: State<'_, Arc<DownloadManager>>,
+    magnet: String,
+    files_list: Vec<usize>,
+    target: String,
+    game: Game,
+    ignore_space_check: bool,
+) -> Result<JobSubmission, String> {
+    submit_or_queue(
+        dm.inner(),
+        OutboxRequest::AddTorrentJob {
+            magnet,
+            files_list,
+            target,
+            game,
+            ignore_space_check,
+        },
+    )
+    .await
+}
+
+/// Submit the request, or park it in the outbox while offline
+pub async fn submit_or_queue(
+    dm: &Arc<DownloadManager>,
+    request: OutboxRequest,
+) -> Result<JobSubmission, String> {
+    if !connectivity::is_offline() {
+        return submit_request(dm, request)
+            .await
+            .map(JobSubmission::Started);
+    }
+
+    info!("Offline, queueing the download until the network is back");
+    let id = dm.outbox().push(request).map_err(|e| format!("{e:#}"))?;
+    dm.emit(OUTBOX_UPDATED_EVENT, dm.outbox().entries());
+    Ok(JobSubmission::Queued(id))
+}
+
+pub(crate) async fn submit_request(
+    dm: &Arc<DownloadManager>,
+    request: OutboxRequest,
 ) -> Result<JobId, String> {
-    let path = std::path::PathBuf::from(target);
-    let job_id = dm
-        .add_ddl_job(files, path, game)
-        .await
-        .map_err(|e| e.to_string())?;
+    let job_id = match request {
+        OutboxRequest::AddDdlJob {
+            files,
+            target,
+            game,
+            ignore_space_check,
+        } => {
+            let path = PathBuf::from(target);
+
+            if !ignore_space_check {
+                enforce_preflight(&dm.preflight_ddl(&files, &path, &game))?;
+            }
+
+            dm.add_ddl_job(files, path, game)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        OutboxRequest::AddTorrentJob {
+            magnet,
+            files_list,
+            target,
+            game,
+            ignore_space_check,
+        } => {
+            let path = PathBuf::from(target);
+
+            if !ignore_space_check {
+                let report = dm
+                    .preflight_torrent(&magnet, &files_list, &path, &game)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                enforce_preflight(&report)?;
+            }
+
+            dm.add_torrent_job(magnet, files_list, path, game)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+    };
 
     // Only register download for early UAC if auto-install is enabled
     #[cfg(windows)]
@@ -47,34 +150,62 @@ pub async fn dm_add_ddl_job(
     Ok(job_id)
 }
 
+/// Downloads waiting for the app to be online again
 #[tauri::command]
 #[specta]
-pub async fn dm_add_torrent_job(
+pub async fn dm_outbox(dm: State<'_, Arc<DownloadManager>>) -> Result<Vec<OutboxEntry>, String> {
+    Ok(dm.outbox().entries())
+}
+
+#[tauri::command]
+#[specta]
+pub async fn dm_discard_outbox_entry(
+    dm: State<'_, Arc<DownloadManager>>,
+    id: String,
+) -> Result<bool, String> {
+    let found = dm.outbox().discard(&id).map_err(|e| format!("{e:#}"))?;
+    dm.emit(OUTBOX_UPDATED_EVENT, dm.outbox().entries());
+    Ok(found)
+}
+
+/// Check free space for a DDL job without adding it
+#[tauri::command]
+#[specta]
+pub async fn dm_preflight_ddl(
+    dm: State<'_, Arc<DownloadManager>>,
+    files: Vec<DirectLink>,
+    target: String,
+    game: Game,
+) -> Result<PreflightReport, String> {
+    Ok(dm.preflight_ddl(&files, &PathBuf::from(target), &game))
+}
+
+/// Check free space for a torrent job without adding it
+#[tauri::command]
+#[specta]
+pub async fn dm_preflight_torrent(
     dm: State<'_, Arc<DownloadManager>>,
     magnet: String,
     files_list: Vec<usize>,
     target: String,
     game: Game,
-) -> Result<JobId, String> {
-    let path = std::path::PathBuf::from(target);
-    let job_id = dm
-        .add_torrent_job(magnet, files_list, path, game)
+) -> Result<PreflightReport, String> {
+    dm.preflight_torrent(&magnet, &files_list, &PathBuf::from(target), &game)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
 
-    // Only register download for early UAC if auto-install is enabled
-    #[cfg(windows)]
-    {
-        let settings = fit_launcher_config::commands::get_installation_settings();
-        if settings.auto_install
-            && let Ok(uuid) = Uuid::parse_str(&job_id)
-            && let Err(e) = ControllerManager::global().register_download(uuid)
-        {
-            error!("Failed to register download with controller: {}", e);
+fn enforce_preflight(report: &PreflightReport) -> Result<(), String> {
+    match report.verdict {
+        PreflightVerdict::Insufficient => {
+            Err(DownloadManagerError::InsufficientSpace(report.summary()).to_string())
         }
+        PreflightVerdict::Warn => {
+            warn!("Low disk space for new job: {}", report.summary());
+            Ok(())
+        }
+        PreflightVerdict::Ok => Ok(()),
     }
-
-    Ok(job_id)
 }
 
 #[tauri::command]
@@ -89,6 +220,65 @@ pub async fn dm_resume(dm: State<'_, Arc<DownloadManager>>, job_id: String) -> R
     dm.resume(&job_id).await.map_err(|e| e.to_string())
 }
 
+/// Download more files of a torrent job, e.g. an optional file skipped at first
+#[tauri::command]
+#[specta]
+pub async fn dm_add_torrent_files(
+    dm: State<'_, Arc<DownloadManager>>,
+    job_id: String,
+    file_indices: Vec<usize>,
+) -> Result<(), String> {
+    dm.add_torrent_files(&job_id, file_indices)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta]
+pub async fn dm_job_details(
+    dm: State<'_, Arc<DownloadManager>>,
+    job_id: String,
+) -> Result<JobDetails, String> {
+    dm.job_details(&job_id)
+        .await
+        .ok_or_else(|| DownloadManagerError::JobNotFound(job_id).to_string())
+}
+
+/// Transfer totals between `from` and `to`, both included, fastest providers first
+#[tauri::command]
+#[specta]
+pub async fn dm_download_stats(
+    dm: State<'_, Arc<DownloadManager>>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<DownloadStats, String> {
+    Ok(dm.download_stats(from, to))
+}
+
+#[tauri::command]
+#[specta]
+pub async fn dm_download_history(
+    dm: State<'_, Arc<DownloadManager>>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<JobRecord>, String> {
+    Ok(dm.download_history(from, to))
+}
+
+#[tauri::command]
+#[specta]
+pub async fn dm_export_download_stats(
+    dm: State<'_, Arc<DownloadManager>>,
+    kind: StatsExport,
+    from: NaiveDate,
+    to: NaiveDate,
+    path: String,
+) -> Result<(), String> {
+    dm.export_download_stats(kind, from, to, &PathBuf::from(path))
+        .await
+        .map_err(|e| format!("{e:#}"))
+}
+
 #[tauri::command]
 #[specta]
 pub async fn dm_remove(dm: State<'_, Arc<DownloadManager>>, job_id: String) -> Result<(), String> {
@@ -107,6 +297,16 @@ pub async fn dm_load_from_disk(dm: State<'_, Arc<DownloadManager>>) -> Result<()
     dm.load_from_disk().await.map_err(|e| e.to_string())
 }
 
+/// Retry the post-download pipeline of a job from the stage that failed
+#[tauri::command]
+#[specta]
+pub async fn dm_retry_pipeline(
+    dm: State<'_, Arc<DownloadManager>>,
+    job_id: String,
+) -> Result<(), String> {
+    dm.retry_pipeline(&job_id).await.map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 #[specta]
 pub async fn dm_run_automate_setup_install(
@@ -160,51 +360,31 @@ pub async fn dm_extract_and_install(
     app_handle: tauri::AppHandle,
     job: Job,
     auto_clean: bool,
+    password: Option<String>,
 ) -> Result<Uuid, ExtractError> {
-    let list = job.job_path.read_dir()?;
-    let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
-
-    for entry in list.flatten() {
-        if entry
-            .metadata()
-            .map_err(|e| InstallationError::IOError(e.to_string()))?
-            .is_dir()
-        {
-            continue;
-        }
-
-        let name = entry.file_name().to_string_lossy().to_string();
-        if !name.ends_with(".rar") {
-            continue;
-        }
-
-        let group = name.split_once(".part").map(|(g, _)| g).unwrap_or(&name);
-        groups.entry(group.into()).or_default().push(entry.path());
-    }
+    // Parse the download ID if valid UUID
+    let download_id = Uuid::parse_str(&job.id).ok();
+    let id = manager
+        .create_job(job.game, job.job_path.clone(), download_id)
+        .await;
+    let cancel = manager.get_cancellation_token(id).await.unwrap_or_default();
 
-    for paths in groups.values_mut() {
-        // pick a random path is fine,`extract_archive` computes
-        // first archive name internally
-        if let Some(first) = paths.first() {
-            info!("Extracting {first:?} in-place...");
-            extract_archive(first)?;
+    let archives = match extract_job_archives(&app_handle, &job.job_path, password, cancel).await {
+        Ok(archives) => archives,
+        Err(e) => {
+            manager.fail_job(id, &e).await;
+            return Err(e);
         }
-    }
+    };
 
     if auto_clean {
         let mut set = tokio::task::JoinSet::new();
-        for rar in groups.into_values().flatten() {
-            set.spawn(tokio::fs::remove_file(rar));
+        for archive in archives {
+            set.spawn(tokio::fs::remove_file(archive));
         }
         set.join_all().await;
     }
 
-    // Parse the download ID if valid UUID
-    let download_id = Uuid::parse_str(&job.id).ok();
-    let id = manager
-        .create_job(job.game, job.job_path.clone(), download_id)
-        .await;
-
     // Use spawn_blocking because the installer uses Windows-specific blocking I/O
     // that contains raw pointers (HANDLE) which aren't Send
     let manager_clone = manager.inner().clone();
@@ -217,3 +397,31 @@ pub async fn dm_extract_and_install(
 
     Ok(id)
 }
+
+/// Cancel a running extraction (and the installation that would follow it)
+/// for the given download job.
+#[tauri::command]
+#[specta]
+pub async fn dm_cancel_extraction(
+    manager: tauri::State<'_, InstallationManager>,
+    job_id: JobId,
+) -> Result<bool, String> {
+    let download_id = Uuid::parse_str(&job_id).map_err(|e| e.to_string())?;
+    Ok(manager.cancel_by_download(download_id).await)
+}
+
+#[tauri::command]
+#[specta]
+pub async fn dm_daemon_health(
+    supervisor: State<'_, Arc<Aria2Supervisor>>,
+) -> Result<DaemonHealth, String> {
+    Ok(supervisor.health().await)
+}
+
+/// Bring aria2 back by hand, e.g. after the supervisor gave up on a crash loop
+#[tauri::command]
+#[specta]
+pub async fn dm_restart_daemon(supervisor: State<'_, Arc<Aria2Supervisor>>) -> Result<(), String> {
+    supervisor.request_restart();
+    Ok(())
+}
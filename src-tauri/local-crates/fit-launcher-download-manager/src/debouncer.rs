@@ -4,33 +4,43 @@ use tokio::task::JoinHandle;
 use tokio::time::{Duration, sleep};
 use tracing::error;
 
-use crate::persistence::save_jobs_atomic;
+use crate::persistence::JobStore;
 use crate::types::{Job, JobId};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
 
 pub struct SaveDebouncer {
     delay: Duration,
     pending: Mutex<Option<JoinHandle<()>>>,
     jobs: Arc<tokio::sync::RwLock<HashMap<JobId, Job>>>,
-    path: PathBuf,
+    store: Arc<JobStore>,
+    /// Jobs changed (or removed) since the last save
+    dirty: std::sync::Mutex<HashSet<JobId>>,
+    /// Keeps saves in request order
+    saving: Mutex<()>,
 }
 
 impl SaveDebouncer {
     pub fn new(
         jobs: Arc<tokio::sync::RwLock<HashMap<JobId, Job>>>,
-        path: PathBuf,
+        store: Arc<JobStore>,
         delay: Duration,
     ) -> Arc<Self> {
         Arc::new(Self {
             delay,
             pending: Mutex::new(None),
             jobs,
-            path,
+            store,
+            dirty: std::sync::Mutex::new(HashSet::new()),
+            saving: Mutex::new(()),
         })
     }
 
-    pub async fn request_save(self: &Arc<Self>) {
+    pub async fn request_save(self: &Arc<Self>, job_id: &str) {
+        self.dirty
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(job_id.to_string());
+
         if let Some(handle) = self.pending.lock().await.take() {
             handle.abort();
         }
@@ -40,13 +50,37 @@ impl SaveDebouncer {
         let handle = tokio::spawn(async move {
             sleep(this.delay).await;
 
-            let snapshot = this.jobs.read().await.clone();
-
-            if let Err(e) = save_jobs_atomic(this.path.clone(), &snapshot).await {
-                error!("FATAL: failed to save jobs: {:?}", e);
-            }
+            // once started, a save is not cancelled by newer requests
+            tokio::spawn(async move { this.flush().await });
         });
 
         *self.pending.lock().await = Some(handle);
     }
+
+    /// Append the dirty jobs to the journal
+    async fn flush(&self) {
+        let _saving = self.saving.lock().await;
+
+        let dirty = std::mem::take(&mut *self.dirty.lock().unwrap_or_else(|e| e.into_inner()));
+        if dirty.is_empty() {
+            return;
+        }
+
+        let changes: Vec<(JobId, Option<Job>)> = {
+            let jobs = self.jobs.read().await;
+            dirty
+                .iter()
+                .map(|id| (id.clone(), jobs.get(id).cloned()))
+                .collect()
+        };
+
+        if let Err(e) = self.store.save_changes(changes).await {
+            error!("FATAL: failed to save jobs: {:?}", e);
+            // retried with the next save
+            self.dirty
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .extend(dirty);
+        }
+    }
 }
@@ -0,0 +1,308 @@
+//! Swarm view of transfers: peers, trackers, pieces and per-file progress.
+//!
+//! [`FileStatus`] only carries totals and speeds. The details here cost a few
+//! more RPCs, so they are fetched on demand through `dm_job_details` and pushed
+//! for active torrent jobs at a slower pace than job updates. The same pass
+//! flags jobs that stopped moving with nobody to download from.
+
+use std::{collections::HashMap, sync::Arc};
+
+use aria2_ws::response::{File, Peer, Status};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use specta::Type;
+use tokio::time::{Duration, MissedTickBehavior, interval};
+use tracing::{info, warn};
+
+use crate::{manager::DownloadManager, types::*};
+
+pub const JOB_DETAILS_EVENT: &str = "download::job_details";
+
+/// How often details of active torrent jobs are pushed
+const DETAILS_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct PeerDetails {
+    pub ip: String,
+    pub port: u16,
+    pub seeder: bool,
+    pub download_speed: u64,
+    pub upload_speed: u64,
+    /// Share of the pieces the peer has, from 0.0 to 1.0
+    pub progress: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct FileProgress {
+    /// 0-based, like `TorrentJob::file_indices`
+    pub index: usize,
+    pub path: String,
+    pub length: u64,
+    pub completed_length: u64,
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Type)]
+pub struct TransferDetails {
+    pub gid: Gid,
+    /// Seeders we are connected to
+    pub num_seeders: u64,
+    /// Peers we are connected to, seeders included
+    pub connections: u64,
+    /// We have every selected piece and only upload
+    pub seeder: bool,
+    /// Announce URLs, one tier after the other
+    pub trackers: Vec<String>,
+    pub num_pieces: u64,
+    pub piece_length: u64,
+    /// Hex bitfield of the pieces we have, highest bit first.
+    /// `None` until the download started or when the engine does not expose it
+    pub bitfield: Option<String>,
+    pub pieces_done: u64,
+    pub peers: Vec<PeerDetails>,
+    pub files: Vec<FileProgress>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct JobDetails {
+    pub job_id: JobId,
+    pub transfers: Vec<TransferDetails>,
+    pub stalled_since: Option<DateTime<Utc>>,
+}
+
+impl JobDetails {
+    fn has_peers(&self) -> bool {
+        self.transfers
+            .iter()
+            .any(|t| t.connections > 0 || !t.peers.is_empty())
+    }
+}
+
+/// Number of set bits in an aria2 hex bitfield
+pub fn count_pieces(bitfield: &str) -> u64 {
+    bitfield
+        .chars()
+        .filter_map(|c| c.to_digit(16))
+        .map(|nibble| u64::from(nibble.count_ones()))
+        .sum()
+}
+
+pub(crate) fn file_progress(files: &[File]) -> Vec<FileProgress> {
+    files
+        .iter()
+        .map(|file| FileProgress {
+            index: (file.index as usize).saturating_sub(1),
+            path: file.path.clone(),
+            length: file.length,
+            completed_length: file.completed_length,
+            selected: file.selected,
+        })
+        .collect()
+}
+
+impl From<&FileStatus> for TransferDetails {
+    fn from(fs: &FileStatus) -> Self {
+        Self {
+            gid: fs.gid.clone().unwrap_or_default(),
+            files: file_progress(&fs.files),
+            ..Default::default()
+        }
+    }
+}
+
+impl TransferDetails {
+    pub(crate) fn from_aria2(status: &Status, peers: &[Peer]) -> Self {
+        let pieces = status.num_pieces.max(1) as f64;
+
+        Self {
+            gid: status.gid.clone(),
+            num_seeders: status.num_seeders.unwrap_or_default(),
+            connections: status.connections,
+            seeder: status.seeder.unwrap_or_default(),
+            trackers: status
+                .bittorrent
+                .as_ref()
+                .map(|bt| bt.announce_list.iter().flatten().cloned().collect())
+                .unwrap_or_default(),
+            num_pieces: status.num_pieces,
+            piece_length: status.piece_length,
+            bitfield: status.bitfield.clone(),
+            pieces_done: status.bitfield.as_deref().map(count_pieces).unwrap_or(0),
+            peers: peers
+                .iter()
+                .map(|peer| PeerDetails {
+                    ip: peer.ip.clone(),
+                    port: peer.port,
+                    seeder: peer.seeder,
+                    download_speed: peer.download_speed,
+                    upload_speed: peer.upload_speed,
+                    progress: (count_pieces(&peer.bitfield) as f64 / pieces).min(1.0),
+                })
+                .collect(),
+            files: file_progress(&status.files),
+        }
+    }
+}
+
+/// Last time each watched job made progress or had peers
+#[derive(Default)]
+struct StallTracker {
+    /// job -> (completed length, last time it moved or had peers)
+    last_moved: HashMap<JobId, (u64, DateTime<Utc>)>,
+}
+
+impl StallTracker {
+    /// Record a sample of `job_id` taken at `now`, returning since when it is
+    /// stalled once it went `stall_after` without progress and without peers.
+    fn observe(
+        &mut self,
+        job_id: &str,
+        completed: u64,
+        has_peers: bool,
+        now: DateTime<Utc>,
+        stall_after: Option<Duration>,
+    ) -> Option<DateTime<Utc>> {
+        let moved = self
+            .last_moved
+            .entry(job_id.to_string())
+            .or_insert((completed, now));
+        if completed > moved.0 || has_peers {
+            *moved = (completed, now);
+        }
+
+        stall_after
+            .and_then(|after| chrono::Duration::from_std(after).ok())
+            .filter(|after| now - moved.1 >= *after)
+            .map(|_| moved.1)
+    }
+
+    fn forget(&mut self, job_id: &str) {
+        self.last_moved.remove(job_id);
+    }
+
+    fn retain(&mut self, jobs: &[Job]) {
+        self.last_moved
+            .retain(|id, _| jobs.iter().any(|job| &job.id == id));
+    }
+}
+
+/// Push details of active torrent jobs and flag the ones that stalled.
+///
+/// A job stalls once it went `stall_after` without progress and without a
+/// single peer, `None` turns the flagging off.
+pub fn spawn_details_monitor(manager: Arc<DownloadManager>, stall_after: Option<Duration>) {
+    tokio::spawn(async move {
+        info!("Details monitor: started, stall after {stall_after:?}");
+        let mut tracker = StallTracker::default();
+        let mut tick = interval(DETAILS_INTERVAL);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            tick.tick().await;
+            let now = Utc::now();
+
+            let jobs = manager.all_jobs().await;
+            for job in &jobs {
+                let watched =
+                    job.source == DownloadSource::Torrent && job.state == DownloadState::Active;
+                if !watched {
+                    tracker.forget(&job.id);
+                    if job.stalled_since.is_some() {
+                        manager.set_stalled(&job.id, None).await;
+                    }
+                    continue;
+                }
+
+                let Some(mut details) = manager.job_details(&job.id).await else {
+                    continue;
+                };
+
+                let completed = job.status.as_ref().map_or(0, |s| s.completed_length);
+                let stalled =
+                    tracker.observe(&job.id, completed, details.has_peers(), now, stall_after);
+                match (stalled, job.stalled_since) {
+                    (Some(since), None) => {
+                        warn!("Job {} stalled: no progress and no peers", job.id);
+                        manager.set_stalled(&job.id, Some(since)).await;
+                        details.stalled_since = Some(since);
+                    }
+                    (None, Some(_)) => {
+                        manager.set_stalled(&job.id, None).await;
+                        details.stalled_since = None;
+                    }
+                    _ => {}
+                }
+
+                manager.emit(JOB_DETAILS_EVENT, details);
+            }
+
+            tracker.retain(&jobs);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+    fn minutes(m: i64) -> chrono::Duration {
+        chrono::Duration::minutes(m)
+    }
+
+    #[test]
+    fn flags_job_once_stall_timeout_passes() {
+        let mut tracker = StallTracker::default();
+        let start = Utc::now();
+
+        assert_eq!(
+            tracker.observe("job", 100, false, start, Some(TIMEOUT)),
+            None
+        );
+        assert_eq!(
+            tracker.observe("job", 100, false, start + minutes(9), Some(TIMEOUT)),
+            None
+        );
+        assert_eq!(
+            tracker.observe("job", 100, false, start + minutes(10), Some(TIMEOUT)),
+            Some(start)
+        );
+
+        // progress clears the flag and restarts the countdown
+        assert_eq!(
+            tracker.observe("job", 200, false, start + minutes(11), Some(TIMEOUT)),
+            None
+        );
+        assert_eq!(
+            tracker.observe("job", 200, false, start + minutes(21), Some(TIMEOUT)),
+            Some(start + minutes(11))
+        );
+    }
+
+    #[test]
+    fn peers_or_no_timeout_keep_job_unflagged() {
+        let mut tracker = StallTracker::default();
+        let start = Utc::now();
+
+        tracker.observe("seeded", 0, true, start, Some(TIMEOUT));
+        assert_eq!(
+            tracker.observe("seeded", 0, true, start + minutes(30), Some(TIMEOUT)),
+            None
+        );
+
+        tracker.observe("unwatched", 0, false, start, None);
+        assert_eq!(
+            tracker.observe("unwatched", 0, false, start + minutes(30), None),
+            None
+        );
+
+        // a job that left and came back starts over
+        tracker.observe("paused", 0, false, start, Some(TIMEOUT));
+        tracker.forget("paused");
+        assert_eq!(
+            tracker.observe("paused", 0, false, start + minutes(30), Some(TIMEOUT)),
+            None
+        );
+    }
+}
@@ -1,62 +1,223 @@
-use crate::{manager::DownloadManager, types::FileStatus};
-use aria2_ws::Client;
-use sha2::{Digest, Sha256};
-use std::sync::Arc;
-use tokio::time::{Duration, sleep};
-use tracing::{error, info};
-
-fn normalize(statuses: &[aria2_ws::response::Status]) -> Vec<FileStatus> {
-    statuses
-        .iter()
-        .map(|s| FileStatus {
-            gid: Some(s.gid.clone()),
-            status: s.status.clone().into(),
-            total_length: s.total_length,
+use crate::{manager::DownloadManager, types::DownloadState};
+use aria2_ws::{Client, Notification, response::Status};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    sync::Arc,
+};
+use tokio::{
+    sync::broadcast::error::RecvError,
+    time::{Duration, MissedTickBehavior, interval, sleep, timeout},
+};
+use tracing::{debug, info, warn};
+
+/// How often progress of the downloads aria2 reports as active is refreshed
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+/// Full reconciliation, catches anything a missed notification left behind
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+/// Downloads fetched by `tellWaiting`/`tellStopped` during a sweep
+const SWEEP_PAGE: i32 = 1000;
+
+/// Fields compared to decide whether a status is worth forwarding to the manager
+#[derive(PartialEq)]
+struct Snapshot {
+    state: DownloadState,
+    completed_length: u64,
+    total_length: u64,
+    download_speed: u64,
+    upload_speed: u64,
+}
+
+impl From<&Status> for Snapshot {
+    fn from(s: &Status) -> Self {
+        Self {
+            state: s.status.clone().into(),
             completed_length: s.completed_length,
+            total_length: s.total_length,
             download_speed: s.download_speed,
             upload_speed: s.upload_speed,
-            files: s.files.clone(),
-            info_hash: s.info_hash.clone(),
-        })
-        .collect()
+        }
+    }
+}
+
+struct Dispatcher {
+    manager: Arc<DownloadManager>,
+    client: Arc<tokio::sync::Mutex<Client>>,
+    /// GIDs aria2 is currently downloading, the only ones polled for progress
+    active: HashSet<String>,
+    last_seen: HashMap<String, Snapshot>,
 }
 
-/// Polls aria2 every 500ms and feeds (yum yum yum) updates to DownloadManager
+impl Dispatcher {
+    async fn tell_status(&self, gid: &str) -> Option<Status> {
+        let locked = self.client.lock().await;
+        match timeout(RPC_TIMEOUT, locked.tell_status(gid)).await {
+            Ok(Ok(status)) => Some(status),
+            Ok(Err(e)) => {
+                // aria2 forgets stopped downloads once its result list is purged
+                debug!("tellStatus failed for gid {gid}: {e}");
+                None
+            }
+            Err(_) => {
+                warn!("tellStatus timed out for gid {gid}");
+                None
+            }
+        }
+    }
+
+    /// Fetch the status of `gids` and forward the ones that changed.
+    async fn refresh(&mut self, gids: &[String]) {
+        let mut statuses = Vec::with_capacity(gids.len());
+        for gid in gids {
+            match self.tell_status(gid).await {
+                Some(status) => statuses.push(status),
+                None => {
+                    self.active.remove(gid);
+                }
+            }
+        }
+        self.forward(statuses).await;
+    }
+
+    async fn forward(&mut self, statuses: Vec<Status>) {
+        let changed: Vec<Status> = statuses
+            .into_iter()
+            .filter(|status| {
+                if DownloadState::from(status.status.clone()) == DownloadState::Active {
+                    self.active.insert(status.gid.clone());
+                } else {
+                    self.active.remove(&status.gid);
+                }
+
+                let snapshot = Snapshot::from(status);
+                if self.last_seen.get(&status.gid) == Some(&snapshot) {
+                    return false;
+                }
+                self.last_seen.insert(status.gid.clone(), snapshot);
+                true
+            })
+            .collect();
+
+        if !changed.is_empty() {
+            self.manager.on_aria2_update(changed).await;
+        }
+    }
+
+    /// Re-read every active download plus the jobs the manager still waits on.
+    ///
+    /// Lists the active, waiting and stopped downloads in one call each, only
+    /// GIDs missing from those lists are asked for one by one.
+    async fn sweep(&mut self) {
+        let (active, waiting, stopped) = {
+            let locked = self.client.lock().await;
+            let active = sweep_rpc("tellActive", locked.tell_active()).await;
+            let waiting = sweep_rpc("tellWaiting", locked.tell_waiting(0, SWEEP_PAGE)).await;
+            let stopped = sweep_rpc("tellStopped", locked.tell_stopped(0, SWEEP_PAGE)).await;
+            (active, waiting, stopped)
+        };
+        let Some(active) = active else {
+            return;
+        };
+
+        let active_gids: HashSet<String> = active.iter().map(|s| s.gid.clone()).collect();
+        let unsettled: HashSet<String> = self.manager.unsettled_gids().await.into_iter().collect();
+
+        let mut listed: HashMap<String, Status> = waiting
+            .into_iter()
+            .chain(stopped)
+            .flatten()
+            .map(|s| (s.gid.clone(), s))
+            .collect();
+        let mut statuses = active;
+        let mut missing = Vec::new();
+        for gid in unsettled.iter().filter(|gid| !active_gids.contains(*gid)) {
+            match listed.remove(gid) {
+                Some(status) => statuses.push(status),
+                None => missing.push(gid.clone()),
+            }
+        }
+
+        self.active = active_gids;
+        self.forward(statuses).await;
+        // past the listed page, or already purged by aria2
+        self.refresh(&missing).await;
+
+        self.last_seen
+            .retain(|gid, _| self.active.contains(gid) || unsettled.contains(gid));
+    }
+}
+
+async fn sweep_rpc<T, E: Display>(
+    method: &str,
+    call: impl Future<Output = Result<T, E>>,
+) -> Option<T> {
+    match timeout(RPC_TIMEOUT, call).await {
+        Ok(Ok(result)) => Some(result),
+        Ok(Err(e)) => {
+            warn!("Sweep: {method} failed: {e}");
+            None
+        }
+        Err(_) => {
+            warn!("Sweep: {method} timed out");
+            None
+        }
+    }
+}
+
+/// Drives the DownloadManager from aria2 notifications.
+///
+/// Notifications (`onDownloadStart`, `onDownloadComplete`, ...) trigger a
+/// `tellStatus` for that GID only. Active downloads are polled for progress,
+/// and a slow sweep reconciles whatever a dropped notification missed.
 pub fn spawn_dispatcher(manager: Arc<DownloadManager>, client: Arc<tokio::sync::Mutex<Client>>) {
     tokio::spawn(async move {
-        info!("Dispatcher: starting aria2 poll loop");
-        let mut last_hash = String::new();
+        info!("Dispatcher: subscribing to aria2 notifications");
+        let mut notifications = client.lock().await.subscribe_notifications();
+        let mut dispatcher = Dispatcher {
+            manager,
+            client,
+            active: HashSet::new(),
+            last_seen: HashMap::new(),
+        };
+
+        let mut progress = interval(PROGRESS_INTERVAL);
+        progress.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        // the first tick fires right away, which doubles as the initial reconciliation
+        let mut sweep = interval(SWEEP_INTERVAL);
+        sweep.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         loop {
-            let statuses = {
-                let locked = client.lock().await;
-                match locked.tell_active().await {
-                    Ok(mut active) => {
-                        if let Ok(mut waiting) = locked.tell_waiting(0, 100).await {
-                            active.append(&mut waiting);
-                        }
-                        if let Ok(mut stopped) = locked.tell_stopped(0, 100).await {
-                            active.append(&mut stopped);
-                        }
-                        active
+            tokio::select! {
+                notification = notifications.recv() => match notification {
+                    Ok(Notification::Aria2 { gid, event }) => {
+                        debug!("aria2 {event:?} for gid {gid}");
+                        dispatcher.refresh(&[gid]).await;
                     }
-                    Err(err) => {
-                        error!("Error fetching aria2 tasks: {:?}", err);
-                        Vec::new()
+                    Ok(Notification::WebSocketConnected) => {
+                        info!("Dispatcher: aria2 connected, reconciling");
+                        dispatcher.sweep().await;
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Dispatcher: {skipped} aria2 notifications dropped, reconciling");
+                        dispatcher.sweep().await;
+                    }
+                    Err(RecvError::Closed) => {
+                        // the client was replaced after a reconnection
+                        sleep(Duration::from_secs(1)).await;
+                        notifications = dispatcher.client.lock().await.subscribe_notifications();
+                        dispatcher.sweep().await;
+                    }
+                },
+                _ = progress.tick() => {
+                    if !dispatcher.active.is_empty() {
+                        let gids: Vec<String> = dispatcher.active.iter().cloned().collect();
+                        dispatcher.refresh(&gids).await;
                     }
                 }
-            };
-            let normalized = normalize(&statuses);
-
-            if let Ok(serialized) = serde_json::to_string(&normalized) {
-                let hash = format!("{:x}", Sha256::digest(serialized.as_bytes()));
-                if hash != last_hash {
-                    last_hash = hash;
-                    manager.on_aria2_update(statuses).await;
-                }
+                _ = sweep.tick() => dispatcher.sweep().await,
             }
-
-            sleep(Duration::from_millis(500)).await;
         }
     });
 }
@@ -0,0 +1,407 @@
+//! Backends that actually move bytes for the [`DownloadManager`].
+//!
+//! Each engine hands out its own transfer ids (GIDs). The manager only keeps
+//! those ids around and asks [`DownloadEngine::owns`] which engine to talk to,
+//! so jobs started on one engine stay reachable after the user switches.
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use fit_launcher_torrent::{
+    FitLauncherConfigAria2, LibrqbitSession,
+    model::{TransferState, TransferStats},
+};
+use tokio::{
+    sync::Mutex,
+    time::{Duration, MissedTickBehavior, interval},
+};
+use tracing::{debug, info};
+
+use crate::{
+    aria2::Aria2WsClient,
+    details::{FileProgress, TransferDetails},
+    error::DownloadManagerError,
+    manager::DownloadManager,
+    types::{DownloadState, FileStatus, Gid},
+};
+
+/// Prefix of the GIDs handed out by [`LibrqbitEngine`], followed by the info hash
+pub const LIBRQBIT_GID_PREFIX: &str = "rqbit-";
+
+/// How often librqbit transfers are polled, it has no notification channel
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[async_trait]
+pub trait DownloadEngine: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Whether `gid` was handed out by this engine
+    fn owns(&self, gid: &str) -> bool;
+
+    async fn add_uri(
+        &self,
+        urls: Vec<String>,
+        dir: Option<String>,
+        filename: Option<String>,
+    ) -> Result<Gid, DownloadManagerError>;
+
+    /// `selected` holds 0-based file indices, empty means every file
+    async fn add_torrent(
+        &self,
+        torrent: Vec<u8>,
+        dir: Option<String>,
+        selected: Vec<usize>,
+    ) -> Result<Gid, DownloadManagerError>;
+
+    async fn pause(&self, gid: &str) -> Result<(), DownloadManagerError>;
+
+    async fn resume(&self, gid: &str) -> Result<(), DownloadManagerError>;
+
+    async fn remove(&self, gid: &str) -> Result<(), DownloadManagerError>;
+
+    async fn status(&self, gid: &str) -> Result<FileStatus, DownloadManagerError>;
+
+    /// Change the files of a running torrent transfer, `selected` as in `add_torrent`
+    async fn select_files(
+        &self,
+        gid: &str,
+        selected: Vec<usize>,
+    ) -> Result<(), DownloadManagerError>;
+
+    /// Every transfer the engine currently knows about, used to reattach jobs
+    async fn list(&self) -> Result<Vec<FileStatus>, DownloadManagerError> {
+        Ok(Vec::new())
+    }
+
+    /// Peers, trackers and pieces of a transfer, as much as the engine exposes
+    async fn details(&self, gid: &str) -> Result<TransferDetails, DownloadManagerError> {
+        Ok(TransferDetails::from(&self.status(gid).await?))
+    }
+}
+
+/// Transfers through the external aria2c daemon.
+pub struct Aria2Engine {
+    client: Mutex<Aria2WsClient>,
+    cfg: FitLauncherConfigAria2,
+}
+
+impl Aria2Engine {
+    pub fn new(client: Aria2WsClient, cfg: FitLauncherConfigAria2) -> Self {
+        Self {
+            client: Mutex::new(client),
+            cfg,
+        }
+    }
+}
+
+fn rpc_error(e: impl std::fmt::Display) -> DownloadManagerError {
+    DownloadManagerError::Aria2RpcError(e.to_string())
+}
+
+#[async_trait]
+impl DownloadEngine for Aria2Engine {
+    fn name(&self) -> &'static str {
+        "aria2"
+    }
+
+    fn owns(&self, gid: &str) -> bool {
+        // aria2 GIDs are 64-bit ids printed as 16 hex characters
+        gid.len() == 16 && gid.bytes().all(|b| b.is_ascii_hexdigit())
+    }
+
+    async fn add_uri(
+        &self,
+        urls: Vec<String>,
+        dir: Option<String>,
+        filename: Option<String>,
+    ) -> Result<Gid, DownloadManagerError> {
+        self.client
+            .lock()
+            .await
+            .add_uri(urls, dir, filename, self.cfg.clone())
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn add_torrent(
+        &self,
+        torrent: Vec<u8>,
+        dir: Option<String>,
+        selected: Vec<usize>,
+    ) -> Result<Gid, DownloadManagerError> {
+        self.client
+            .lock()
+            .await
+            .add_torrent(torrent, dir, selected)
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn pause(&self, gid: &str) -> Result<(), DownloadManagerError> {
+        self.client.lock().await.pause(gid).await.map_err(rpc_error)
+    }
+
+    async fn resume(&self, gid: &str) -> Result<(), DownloadManagerError> {
+        self.client
+            .lock()
+            .await
+            .resume(gid)
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn remove(&self, gid: &str) -> Result<(), DownloadManagerError> {
+        self.client
+            .lock()
+            .await
+            .remove(gid)
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn status(&self, gid: &str) -> Result<FileStatus, DownloadManagerError> {
+        let status = self
+            .client
+            .lock()
+            .await
+            .status(gid)
+            .await
+            .map_err(rpc_error)?;
+        Ok(FileStatus::from(&status))
+    }
+
+    async fn select_files(
+        &self,
+        gid: &str,
+        selected: Vec<usize>,
+    ) -> Result<(), DownloadManagerError> {
+        self.client
+            .lock()
+            .await
+            .select_files(gid, selected)
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn list(&self) -> Result<Vec<FileStatus>, DownloadManagerError> {
+        let statuses = self
+            .client
+            .lock()
+            .await
+            .list_all()
+            .await
+            .map_err(rpc_error)?;
+        Ok(statuses.iter().map(FileStatus::from).collect())
+    }
+
+    async fn details(&self, gid: &str) -> Result<TransferDetails, DownloadManagerError> {
+        let client = self.client.lock().await;
+        let status = client.status(gid).await.map_err(rpc_error)?;
+        let peers = if status.bittorrent.is_some() {
+            // aria2 refuses getPeers once a download stopped
+            client.peers(gid).await.unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        Ok(TransferDetails::from_aria2(&status, &peers))
+    }
+}
+
+/// Transfers torrents inside the embedded librqbit session, no aria2c needed.
+///
+/// Direct links are not supported, librqbit only speaks BitTorrent.
+pub struct LibrqbitEngine {
+    session: LibrqbitSession,
+}
+
+impl LibrqbitEngine {
+    pub fn new(session: LibrqbitSession) -> Self {
+        Self { session }
+    }
+
+    fn info_hash(gid: &str) -> Result<&str, DownloadManagerError> {
+        gid.strip_prefix(LIBRQBIT_GID_PREFIX).ok_or_else(|| {
+            DownloadManagerError::InvalidArgument(format!("not a librqbit gid: {gid}"))
+        })
+    }
+}
+
+fn librqbit_error(e: impl std::fmt::Display) -> DownloadManagerError {
+    DownloadManagerError::EngineError(format!("librqbit: {e}"))
+}
+
+impl From<TransferStats> for FileStatus {
+    fn from(stats: TransferStats) -> Self {
+        let status = match stats.state {
+            TransferState::Initializing => DownloadState::Waiting,
+            TransferState::Live => DownloadState::Active,
+            TransferState::Paused => DownloadState::Paused,
+            TransferState::Finished => DownloadState::Complete,
+            TransferState::Error => DownloadState::Error,
+        };
+
+        FileStatus {
+            gid: Some(format!("{LIBRQBIT_GID_PREFIX}{}", stats.info_hash)),
+            status,
+            total_length: stats.total_bytes,
+            completed_length: stats.progress_bytes,
+            download_speed: stats.download_speed,
+            upload_speed: stats.upload_speed,
+            files: Vec::new(),
+            info_hash: Some(stats.info_hash),
+        }
+    }
+}
+
+#[async_trait]
+impl DownloadEngine for LibrqbitEngine {
+    fn name(&self) -> &'static str {
+        "librqbit"
+    }
+
+    fn owns(&self, gid: &str) -> bool {
+        gid.starts_with(LIBRQBIT_GID_PREFIX)
+    }
+
+    async fn add_uri(
+        &self,
+        _urls: Vec<String>,
+        _dir: Option<String>,
+        _filename: Option<String>,
+    ) -> Result<Gid, DownloadManagerError> {
+        Err(DownloadManagerError::DdlInitError(
+            "librqbit cannot download direct links".into(),
+        ))
+    }
+
+    async fn add_torrent(
+        &self,
+        torrent: Vec<u8>,
+        dir: Option<String>,
+        selected: Vec<usize>,
+    ) -> Result<Gid, DownloadManagerError> {
+        let dir = dir.map(PathBuf::from).ok_or_else(|| {
+            DownloadManagerError::InvalidArgument("librqbit needs a download directory".into())
+        })?;
+        let info_hash = self
+            .session
+            .add_download(torrent, dir, selected)
+            .await
+            .map_err(librqbit_error)?;
+        Ok(format!("{LIBRQBIT_GID_PREFIX}{info_hash}"))
+    }
+
+    async fn pause(&self, gid: &str) -> Result<(), DownloadManagerError> {
+        self.session
+            .pause_download(Self::info_hash(gid)?)
+            .await
+            .map_err(librqbit_error)
+    }
+
+    async fn resume(&self, gid: &str) -> Result<(), DownloadManagerError> {
+        self.session
+            .resume_download(Self::info_hash(gid)?)
+            .await
+            .map_err(librqbit_error)
+    }
+
+    async fn remove(&self, gid: &str) -> Result<(), DownloadManagerError> {
+        self.session
+            .remove_download(Self::info_hash(gid)?)
+            .await
+            .map_err(librqbit_error)
+    }
+
+    async fn status(&self, gid: &str) -> Result<FileStatus, DownloadManagerError> {
+        self.session
+            .download_stats(Self::info_hash(gid)?)
+            .map(FileStatus::from)
+            .map_err(librqbit_error)
+    }
+
+    async fn details(&self, gid: &str) -> Result<TransferDetails, DownloadManagerError> {
+        let stats = self
+            .session
+            .download_stats(Self::info_hash(gid)?)
+            .map_err(librqbit_error)?;
+
+        Ok(TransferDetails {
+            gid: gid.to_string(),
+            connections: stats.peers,
+            seeder: stats.state == TransferState::Finished,
+            // librqbit only reports bytes per file, the manager adds names from the job
+            files: stats
+                .file_progress
+                .iter()
+                .enumerate()
+                .map(|(index, &completed_length)| FileProgress {
+                    index,
+                    path: String::new(),
+                    length: 0,
+                    completed_length,
+                    selected: true,
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    async fn select_files(
+        &self,
+        gid: &str,
+        selected: Vec<usize>,
+    ) -> Result<(), DownloadManagerError> {
+        self.session
+            .select_download_files(Self::info_hash(gid)?, selected)
+            .await
+            .map_err(librqbit_error)
+    }
+}
+
+/// Drives the DownloadManager for engines without notifications.
+///
+/// Every unsettled GID owned by `engine` is polled and forwarded when it changed.
+pub fn spawn_status_poller(manager: Arc<DownloadManager>, engine: Arc<dyn DownloadEngine>) {
+    tokio::spawn(async move {
+        info!("Poller: following {} transfers", engine.name());
+        let mut last_seen: HashMap<Gid, (DownloadState, u64, u64, u64)> = HashMap::new();
+        let mut tick = interval(POLL_INTERVAL);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            tick.tick().await;
+
+            let gids: Vec<Gid> = manager
+                .unsettled_gids()
+                .await
+                .into_iter()
+                .filter(|gid| engine.owns(gid))
+                .collect();
+
+            for gid in &gids {
+                let status = match engine.status(gid).await {
+                    Ok(status) => status,
+                    Err(e) => {
+                        // transfers do not survive a restart until the job is resumed
+                        debug!("{} status failed for gid {gid}: {e}", engine.name());
+                        continue;
+                    }
+                };
+
+                let key = (
+                    status.status.clone(),
+                    status.completed_length,
+                    status.download_speed,
+                    status.upload_speed,
+                );
+                if last_seen.get(gid) == Some(&key) {
+                    continue;
+                }
+                last_seen.insert(gid.clone(), key);
+                manager.apply_file_status(gid, status).await;
+            }
+
+            last_seen.retain(|gid, _| gids.contains(gid));
+        }
+    });
+}
@@ -13,6 +13,9 @@ pub enum DownloadManagerError {
     #[error("Aria2 RPC error: {0}")]
     Aria2RpcError(String),
 
+    #[error("Download engine error: {0}")]
+    EngineError(String),
+
     #[error("Persistence error: {0}")]
     PersistenceError(String),
 
@@ -27,4 +30,7 @@ pub enum DownloadManagerError {
 
     #[error("I/O error: {0}")]
     IoError(String),
+
+    #[error("Not enough disk space: {0}")]
+    InsufficientSpace(String),
 }
@@ -1,9 +1,16 @@
 pub mod aria2;
 pub mod debouncer;
+pub mod details;
 pub mod dispatch;
+pub mod engine;
 pub mod error;
 pub mod manager;
+pub mod outbox;
 pub mod persistence;
+pub mod pipeline;
+pub mod preflight;
+pub mod stats;
+pub mod supervisor;
 pub mod types;
 
 pub mod commands;
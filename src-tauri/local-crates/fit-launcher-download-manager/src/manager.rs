@@ -1,18 +1,23 @@
-use crate::aria2::Aria2WsClient;
 use crate::debouncer::SaveDebouncer;
+use crate::details::JobDetails;
+use crate::engine::DownloadEngine;
 use crate::error::DownloadManagerError;
-use crate::persistence::{load_jobs, save_jobs_atomic};
+use crate::outbox::Outbox;
+use crate::persistence::JobStore;
+use crate::preflight::{self, PreflightReport};
+use crate::stats::{DownloadStats, JobRecord, StatsExport, StatsStore};
 use crate::types::*;
 use anyhow::{Context, Result};
 use aria2_ws::response::{File, Status};
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use fit_launcher_ddl::DirectLink;
 use fit_launcher_scraping::structs::Game;
+use fit_launcher_torrent::LibrqbitSession;
 use fit_launcher_torrent::model::FileInfo;
-use fit_launcher_torrent::{FitLauncherConfigAria2, LibrqbitSession};
+use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tauri::{Emitter, State};
@@ -32,7 +37,10 @@ const EMIT_THROTTLE_MS: u64 = 250;
 ///
 /// If any contributor intends on adding something, please, make sure that you add an Index if needed.
 pub struct DownloadManager {
-    pub aria: Arc<tokio::sync::Mutex<Aria2WsClient>>,
+    /// Engine for direct links, `None` when aria2c could not be started
+    ddl_engine: Option<Arc<dyn DownloadEngine>>,
+    /// Engine for torrents, picked by `FitLauncherConfigV2::engine`
+    torrent_engine: Arc<dyn DownloadEngine>,
 
     /// Lock order: read job data under jobs lock then immediatly release lock before RPC
     jobs: Arc<RwLock<HashMap<JobId, Job>>>,
@@ -44,52 +52,118 @@ pub struct DownloadManager {
     infohash_index: RwLock<HashMap<String, JobId>>,
     /// Track last emission time per job to throttle events
     last_emit: RwLock<HashMap<JobId, Instant>>,
-    persist_path: PathBuf,
-    tauri_handle: tauri::AppHandle,
-    pub aria_cfg: FitLauncherConfigAria2,
+    store: Arc<JobStore>,
+    /// Download history, kept after jobs are removed
+    stats: Arc<StatsStore>,
+    /// Downloads asked for while offline
+    outbox: Outbox,
+    /// `None` for a headless manager, see [`DownloadManager::headless`]
+    tauri_handle: Option<tauri::AppHandle>,
     /// Librqbit session owned here to regenerate metadata when needed
     torrent_session: Arc<LibrqbitSession>,
+    /// Jobs whose post-download pipeline is currently running
+    pub(crate) running_pipelines: tokio::sync::Mutex<HashSet<JobId>>,
 }
 
 //todo: make it DRY
 
 impl DownloadManager {
     pub fn new(
-        aria: Arc<tokio::sync::Mutex<Aria2WsClient>>,
+        ddl_engine: Option<Arc<dyn DownloadEngine>>,
+        torrent_engine: Arc<dyn DownloadEngine>,
         handle: tauri::AppHandle,
-        aria_cfg: FitLauncherConfigAria2,
         librqbit_state: State<'_, LibrqbitSession>,
     ) -> Arc<Self> {
-        let persist_path = directories::BaseDirs::new()
+        let store_path = directories::BaseDirs::new()
             .expect("Could not determine base directories")
             .config_dir()
             .join("com.fitlauncher.carrotrub")
             .join("manager.json");
 
+        Self::build(
+            ddl_engine,
+            torrent_engine,
+            Some(handle),
+            store_path,
+            librqbit_state.inner().clone(),
+        )
+    }
+
+    /// A manager that is not attached to a Tauri app and persists to `store_path`.
+    ///
+    /// Events are dropped and finished jobs don't enter the post-download pipeline.
+    pub fn headless(
+        ddl_engine: Option<Arc<dyn DownloadEngine>>,
+        torrent_engine: Arc<dyn DownloadEngine>,
+        store_path: PathBuf,
+        torrent_session: LibrqbitSession,
+    ) -> Arc<Self> {
+        Self::build(
+            ddl_engine,
+            torrent_engine,
+            None,
+            store_path,
+            torrent_session,
+        )
+    }
+
+    fn build(
+        ddl_engine: Option<Arc<dyn DownloadEngine>>,
+        torrent_engine: Arc<dyn DownloadEngine>,
+        tauri_handle: Option<tauri::AppHandle>,
+        store_path: PathBuf,
+        torrent_session: LibrqbitSession,
+    ) -> Arc<Self> {
+        let stats = StatsStore::open(store_path.with_file_name("download_stats.json"));
+        let outbox = Outbox::open(store_path.with_file_name("download_outbox.json"));
+        let store = JobStore::new(store_path);
+
         let jobs = Arc::new(RwLock::new(HashMap::new()));
 
         let save = SaveDebouncer::new(
             jobs.clone(),
-            persist_path.clone(),
+            store.clone(),
             Duration::from_millis(SAVE_DEBOUNCE_MS),
         );
-        let librqbit_sess = Arc::new(librqbit_state.clone().inner().to_owned());
         Arc::new(Self {
-            aria,
+            ddl_engine,
+            torrent_engine,
             jobs,
             save,
             gid_index: RwLock::new(HashMap::new()),
             infohash_index: RwLock::new(HashMap::new()),
             last_emit: RwLock::new(HashMap::new()),
-            persist_path,
-            tauri_handle: handle,
-            aria_cfg,
-            torrent_session: librqbit_sess,
+            store,
+            stats,
+            outbox,
+            tauri_handle,
+            torrent_session: Arc::new(torrent_session),
+            running_pipelines: tokio::sync::Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// The engine that handed out `gid`, if it is still running
+    fn engine_for(&self, gid: &str) -> Option<&Arc<dyn DownloadEngine>> {
+        std::iter::once(&self.torrent_engine)
+            .chain(self.ddl_engine.as_ref())
+            .find(|engine| engine.owns(gid))
+    }
+
+    fn require_ddl_engine(&self) -> Result<&Arc<dyn DownloadEngine>, DownloadManagerError> {
+        self.ddl_engine.as_ref().ok_or_else(|| {
+            DownloadManagerError::DdlInitError(
+                "direct links need aria2, which is not running".into(),
+            )
         })
     }
 
-    pub async fn request_save_debounced(&self) {
-        self.save.request_save().await;
+    pub fn outbox(&self) -> &Outbox {
+        &self.outbox
+    }
+
+    /// Schedule a save of `job_id`, or of its removal when it is gone by then
+    pub async fn request_save_debounced(&self, job_id: &str) {
+        self.save.request_save(job_id).await;
     }
 
     /// Emit job_updated event only if enough time has passed since last emission
@@ -113,13 +187,19 @@ impl DownloadManager {
         };
 
         if should_emit {
-            let _ = self.tauri_handle.emit("download::job_updated", job.clone());
+            self.emit("download::job_updated", job.clone());
+        }
+    }
+
+    pub(crate) fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Some(handle) = &self.tauri_handle {
+            let _ = handle.emit(event, payload);
         }
     }
 
     /// Load persisted jobs and rebuild indicess. (guys lock order: jobs -> gid_index -> infohash_index)
     pub async fn load_from_disk(self: &Arc<Self>) -> Result<()> {
-        let map = load_jobs(&self.persist_path).await.context("load jobs")?;
+        let map = self.store.load().await.context("load jobs")?;
 
         {
             let mut jobs_lock = self.jobs.write().await;
@@ -132,7 +212,11 @@ impl DownloadManager {
 
             for (id, mut job) in map.into_iter() {
                 job.state = DownloadState::Paused;
-                job.status = None;
+                // keep the last known progress, nothing transfers until reattached
+                if let Some(status) = job.status.as_mut() {
+                    status.pause();
+                }
+                job.stalled_since = None;
                 job.metadata.updated_at = Utc::now();
 
                 for g in job.gids.iter() {
@@ -147,10 +231,24 @@ impl DownloadManager {
         }
 
         info!("Loaded {} jobs from disk", self.jobs.read().await.len());
+        self.resume_pipelines().await;
 
         {
             let dm = Arc::clone(self);
             tokio::spawn(async move {
+                // aria2 restores its session on start, pick those transfers up first
+                let mut engines = vec![dm.torrent_engine.clone()];
+                engines.extend(
+                    dm.ddl_engine
+                        .clone()
+                        .filter(|ddl| !Arc::ptr_eq(ddl, &dm.torrent_engine)),
+                );
+                for engine in engines {
+                    if let Err(e) = dm.reattach(&engine).await {
+                        debug!("Could not reattach jobs to {}: {:?}", engine.name(), e);
+                    }
+                }
+
                 let jobs_snapshot: Vec<(JobId, Vec<Gid>)> = {
                     let jobs = dm.jobs.read().await;
                     jobs.iter()
@@ -160,15 +258,17 @@ impl DownloadManager {
 
                 for (_id, gids) in jobs_snapshot {
                     for gid in gids {
-                        let aria_guard = dm.aria.lock().await;
-                        if let Err(e) = aria_guard.pause(&gid).await {
-                            // GIDs from previous sessions may not exist in aria2 anymore - this is normal
+                        let Some(engine) = dm.engine_for(&gid) else {
+                            continue;
+                        };
+                        if let Err(e) = engine.pause(&gid).await {
+                            // GIDs from previous sessions may not exist in the engine anymore - this is normal
                             debug!(
                                 "Could not pause gid {} during load (likely stale): {:?}",
                                 gid, e
                             );
                         }
-                        // small yield to avoid hammering aria2 in busy startup scenarios
+                        // small yield to avoid hammering the engine in busy startup scenarios
                         tokio::task::yield_now().await;
                     }
                 }
@@ -181,12 +281,34 @@ impl DownloadManager {
     /// Immediate save (no debounce) safe to call from anywhere.
     pub async fn save_now(&self) -> Result<()> {
         let snapshot: HashMap<_, _> = self.jobs.read().await.clone();
-        save_jobs_atomic(self.persist_path.clone(), &snapshot)
-            .await
-            .context("save jobs")?;
+        self.store.save(snapshot).await.context("save jobs")?;
+        self.stats.flush().await;
         Ok(())
     }
 
+    /// Totals of the jobs and days between `from` and `to`, both included
+    pub fn download_stats(&self, from: NaiveDate, to: NaiveDate) -> DownloadStats {
+        self.stats.summary(from, to)
+    }
+
+    /// Jobs started between `from` and `to`, removed ones included
+    pub fn download_history(&self, from: NaiveDate, to: NaiveDate) -> Vec<JobRecord> {
+        self.stats.history(from, to)
+    }
+
+    pub async fn export_download_stats(
+        &self,
+        kind: StatsExport,
+        from: NaiveDate,
+        to: NaiveDate,
+        path: &Path,
+    ) -> Result<()> {
+        self.stats
+            .export_csv(kind, from, to, path)
+            .await
+            .with_context(|| format!("export download stats to {}", path.display()))
+    }
+
     pub async fn add_ddl_job(
         self: &Arc<Self>,
         files: Vec<DirectLink>,
@@ -203,8 +325,8 @@ impl DownloadManager {
 
         let mut job = Job::new_ddl(files.clone(), target.clone(), game, dir.clone());
 
-        // prepare add_uri calls with cfg (do RPC calls outside of job locks plz)
-        let cfg = self.aria_cfg.clone();
+        // do RPC calls outside of job locks plz
+        let engine = self.require_ddl_engine()?;
 
         let dir_str = Some(dir.to_string_lossy().to_string());
 
@@ -212,9 +334,8 @@ impl DownloadManager {
         for f in files.iter() {
             let filename = f.filename.clone();
             let url = f.url.clone();
-            let aria_guard = self.aria.lock().await;
-            let gid = aria_guard
-                .add_uri(vec![url], dir_str.clone(), Some(filename), cfg.clone())
+            let gid = engine
+                .add_uri(vec![url], dir_str.clone(), Some(filename))
                 .await?;
             gids.push(gid);
         }
@@ -238,7 +359,7 @@ impl DownloadManager {
 
         self.emit_job_updated_throttled(&job, true).await;
 
-        self.request_save_debounced().await;
+        self.request_save_debounced(&job.id).await;
         Ok(job.id)
     }
 
@@ -249,9 +370,65 @@ impl DownloadManager {
         target: PathBuf,
         game: Game,
     ) -> Result<JobId> {
+        let (bytes, info_hash, files) = self.fetch_torrent_files(&magnet).await?;
+
+        let selected_files: Vec<FileInfo> = files_list
+            .iter()
+            .filter_map(|&i| files.get(i).cloned())
+            .collect();
+
+        // Use UUID as folder name to avoid unicode/ASCII issues with installer controller
+        // This ensures consistency with DDL downloads and avoids any character encoding problems
+        let job_id = Uuid::new_v4();
+        let folder_name = job_id.to_string();
+        let job_path = target.join(&folder_name);
+
+        let mut job = Job::new_torrent(
+            bytes.clone(),
+            files_list.clone(),
+            info_hash.clone(),
+            magnet.clone(),
+            target.clone(),
+            job_path,
+            selected_files,
+            game,
+        );
+
+        let dir = Some(target.to_string_lossy().to_string());
+
+        let gid = self
+            .torrent_engine
+            .add_torrent(bytes.clone(), dir.clone(), files_list.clone())
+            .await?;
+
+        job.id = job_id.to_string();
+        job.gids = vec![gid.clone()];
+        job.state = DownloadState::Active;
+        job.metadata.updated_at = Utc::now();
+
+        {
+            let mut jobs = self.jobs.write().await;
+            let mut gid_idx = self.gid_index.write().await;
+            gid_idx.insert(gid.clone(), job.id.clone());
+            if let Some(t) = &job.torrent {
+                let mut idx_hash = self.infohash_index.write().await;
+                idx_hash.insert(t.info_hash.clone(), job.id.clone());
+            }
+            jobs.insert(job.id.clone(), job.clone());
+        }
+
+        self.emit_job_updated_throttled(&job, true).await;
+        self.request_save_debounced(&job.id).await;
+        Ok(job.id)
+    }
+
+    /// Fetch (cached) torrent metadata and list its files.
+    ///
+    /// Returns the raw torrent bytes, the info hash and the file list.
+    async fn fetch_torrent_files(&self, magnet: &str) -> Result<(Vec<u8>, String, Vec<FileInfo>)> {
         let meta = self
             .torrent_session
-            .get_metadata_only(magnet.clone())
+            .get_metadata_only(magnet.to_string())
             .await
             .context("librqbit: get metadata")?;
 
@@ -289,56 +466,164 @@ impl DownloadManager {
             });
         }
 
-        let selected_files: Vec<FileInfo> = files_list
-            .iter()
-            .filter_map(|&i| files.get(i).cloned())
-            .collect();
-
-        // Use UUID as folder name to avoid unicode/ASCII issues with installer controller
-        // This ensures consistency with DDL downloads and avoids any character encoding problems
-        let job_id = Uuid::new_v4();
-        let folder_name = job_id.to_string();
-        let job_path = target.join(&folder_name);
-
-        let mut job = Job::new_torrent(
-            bytes.clone(),
-            files_list.clone(),
-            info_hash.clone(),
-            magnet.clone(),
-            target.clone(),
-            job_path,
-            selected_files,
-            game,
-        );
+        Ok((bytes, info_hash, files))
+    }
 
-        let dir = Some(target.to_string_lossy().to_string());
+    /// Every file of a torrent job's torrent, `file_index` being 0-based
+    async fn torrent_file_list(&self, torrent: &TorrentJob) -> Result<Vec<FileInfo>> {
+        if torrent.torrent_bytes.is_empty() {
+            return Ok(self.fetch_torrent_files(&torrent.magnet).await?.2);
+        }
 
-        let _cfg = self.aria_cfg.clone();
-        let aria_guard = self.aria.lock().await;
+        let files = fit_launcher_torrent::list_torrent_files_local(torrent.torrent_bytes.clone())
+            .await
+            .map_err(DownloadManagerError::TorrentInitError)?;
+        Ok(files
+            .into_iter()
+            .map(|file| FileInfo {
+                file_index: file.file_index - 1,
+                ..file
+            })
+            .collect())
+    }
 
-        let gid = aria_guard
-            .add_torrent(bytes.clone(), dir.clone(), files_list.clone())
-            .await?;
+    /// Add files to a torrent job, e.g. an optional soundtrack picked later on.
+    ///
+    /// A transfer the engine still runs just starts fetching the new files.
+    /// Otherwise the torrent is added again and the pieces already on disk are
+    /// picked up instead of downloaded again. A job that already went through
+    /// its post-download pipeline does not run it again, the new files simply
+    /// land next to the others.
+    pub async fn add_torrent_files(
+        self: &Arc<Self>,
+        job_id: &str,
+        file_indices: Vec<usize>,
+    ) -> Result<()> {
+        let job = self
+            .get_job(job_id)
+            .await
+            .ok_or_else(|| DownloadManagerError::JobNotFound(job_id.to_string()))?;
+        let torrent = job.torrent.as_ref().ok_or_else(|| {
+            DownloadManagerError::InvalidArgument(format!("job {job_id} is not a torrent"))
+        })?;
+        if self.running_pipelines.lock().await.contains(job_id) {
+            return Err(DownloadManagerError::InvalidArgument(format!(
+                "job {job_id} is being installed"
+            ))
+            .into());
+        }
+        // an empty selection already means every file
+        if torrent.file_indices.is_empty() {
+            return Ok(());
+        }
 
-        job.id = job_id.to_string();
-        job.gids = vec![gid.clone()];
-        job.state = DownloadState::Active;
-        job.metadata.updated_at = Utc::now();
+        let all_files = self.torrent_file_list(torrent).await?;
+        let mut selected = torrent.file_indices.clone();
+        for index in file_indices {
+            if index >= all_files.len() {
+                return Err(DownloadManagerError::InvalidArgument(format!(
+                    "file {index} is not part of the torrent"
+                ))
+                .into());
+            }
+            if !selected.contains(&index) {
+                selected.push(index);
+            }
+        }
+        if selected.len() == torrent.file_indices.len() {
+            return Ok(());
+        }
+        selected.sort_unstable();
 
+        let mut switched = false;
+        if let Some(gid) = job.gids.first()
+            && let Some(engine) = self.engine_for(gid)
         {
+            match engine.select_files(gid, selected.clone()).await {
+                Ok(()) => switched = true,
+                Err(e) => debug!("Could not change files of gid {gid}, respawning: {e}"),
+            }
+        }
+
+        let snapshot = {
             let mut jobs = self.jobs.write().await;
-            let mut gid_idx = self.gid_index.write().await;
-            gid_idx.insert(gid.clone(), job.id.clone());
-            if let Some(t) = &job.torrent {
-                let mut idx_hash = self.infohash_index.write().await;
-                idx_hash.insert(t.info_hash.clone(), job.id.clone());
+            let Some(j) = jobs.get_mut(job_id) else {
+                return Ok(());
+            };
+            if let Some(t) = j.torrent.as_mut() {
+                t.torrent_files = selected
+                    .iter()
+                    .filter_map(|&i| all_files.get(i).cloned())
+                    .collect();
+                t.file_indices = selected;
             }
-            jobs.insert(job.id.clone(), job.clone());
+            if switched {
+                if j.state == DownloadState::Complete {
+                    j.state = DownloadState::Active;
+                }
+                // the total length changed, wait for the next report
+                j.status = None;
+            }
+            j.metadata.updated_at = Utc::now();
+            j.clone()
+        };
+        info!(
+            "Job {job_id} now downloads files {:?}",
+            snapshot.torrent.as_ref().map(|t| &t.file_indices)
+        );
+
+        if switched {
+            self.emit_job_updated_throttled(&snapshot, true).await;
+            self.request_save_debounced(job_id).await;
+            return Ok(());
         }
 
-        self.emit_job_updated_throttled(&job, true).await;
-        self.request_save_debounced().await;
-        Ok(job.id)
+        for gid in &snapshot.gids {
+            if let Some(engine) = self.engine_for(gid) {
+                let _ = engine.remove(gid).await;
+            }
+        }
+        self.resume_torrent(&snapshot).await
+    }
+
+    /// Check free space for a DDL job before adding it.
+    pub fn preflight_ddl(
+        &self,
+        files: &[DirectLink],
+        target: &Path,
+        game: &Game,
+    ) -> PreflightReport {
+        let download_bytes = files.iter().map(|f| f.size).sum();
+        Self::preflight(download_bytes, target, game)
+    }
+
+    /// Check free space for a torrent job before adding it.
+    pub async fn preflight_torrent(
+        &self,
+        magnet: &str,
+        files_list: &[usize],
+        target: &Path,
+        game: &Game,
+    ) -> Result<PreflightReport> {
+        let (_, _, files) = self.fetch_torrent_files(magnet).await?;
+        let download_bytes = files_list
+            .iter()
+            .filter_map(|&i| files.get(i))
+            .map(|f| f.length)
+            .sum();
+
+        Ok(Self::preflight(download_bytes, target, game))
+    }
+
+    fn preflight(download_bytes: u64, target: &Path, game: &Game) -> PreflightReport {
+        // downloads go to <target>/<uuid>, the installer to <target>/<slug>
+        preflight::plan(
+            download_bytes,
+            preflight::original_size_bytes(&game.details),
+            target,
+            target,
+            &preflight::system_volumes(),
+        )
     }
 
     pub async fn pause(&self, job_id: &str) -> Result<()> {
@@ -352,7 +637,7 @@ impl DownloadManager {
                 job.metadata.updated_at = Utc::now();
                 let snap = job.clone();
 
-                self.request_save_debounced().await;
+                self.request_save_debounced(job_id).await;
                 (Some(g), Some(snap))
             } else {
                 (None, None)
@@ -365,9 +650,11 @@ impl DownloadManager {
 
         if let Some(gids) = gids {
             for gid in gids {
-                let aria_guard = self.aria.lock().await;
-                if let Err(e) = aria_guard.pause(&gid).await {
-                    error!("aria pause failed for gid {}: {:?}", gid, e);
+                let Some(engine) = self.engine_for(&gid) else {
+                    continue;
+                };
+                if let Err(e) = engine.pause(&gid).await {
+                    error!("{} pause failed for gid {}: {:?}", engine.name(), gid, e);
                 }
             }
         }
@@ -399,8 +686,11 @@ impl DownloadManager {
         let mut need_respawn = false;
 
         for gid in job.gids.clone() {
-            let aria_guard = self.aria.lock().await;
-            match aria_guard.resume(&gid).await {
+            let Some(engine) = self.engine_for(&gid) else {
+                need_respawn = true;
+                continue;
+            };
+            match engine.resume(&gid).await {
                 Ok(_) => any_resumed = true,
                 Err(e) => {
                     error!("Failed to resume gid {}: {:?}", gid, e);
@@ -424,7 +714,7 @@ impl DownloadManager {
             if let Some(job) = job_snapshot {
                 self.emit_job_updated_throttled(&job, true).await;
             }
-            self.request_save_debounced().await;
+            self.request_save_debounced(job_id).await;
             return Ok(());
         }
 
@@ -439,7 +729,7 @@ impl DownloadManager {
     async fn resume_ddl(&self, job: &Job) -> Result<()> {
         if let Some(ddl) = &job.ddl {
             println!("Resume: {:#?}", job);
-            let cfg = self.aria_cfg.clone();
+            let engine = self.require_ddl_engine()?;
             let dir = Some(
                 job.metadata
                     .target_path
@@ -450,14 +740,8 @@ impl DownloadManager {
             let mut new_gids = Vec::with_capacity(ddl.files.len());
 
             for f in ddl.files.iter() {
-                let aria_guard = self.aria.lock().await;
-                match aria_guard
-                    .add_uri(
-                        vec![f.url.clone()],
-                        dir.clone(),
-                        Some(f.filename.clone()),
-                        cfg.clone(),
-                    )
+                match engine
+                    .add_uri(vec![f.url.clone()], dir.clone(), Some(f.filename.clone()))
                     .await
                 {
                     Ok(gid) => new_gids.push(gid),
@@ -472,6 +756,8 @@ impl DownloadManager {
                         j.gids.extend(new_gids.iter().cloned());
                         j.gids.sort();
                         j.gids.dedup();
+                        // drop progress reported under the previous gids
+                        j.status = None;
 
                         let mut gid_idx = self.gid_index.write().await;
                         for g in j.gids.iter() {
@@ -489,14 +775,18 @@ impl DownloadManager {
                 if let Some(job) = job_snapshot {
                     self.emit_job_updated_throttled(&job, true).await;
                 }
-                self.request_save_debounced().await;
+                self.request_save_debounced(&job.id).await;
             }
         }
         Ok(())
     }
 
     async fn resume_torrent(&self, job: &Job) -> Result<()> {
-        let torrent_bytes = job.torrent.as_ref().map(|t| t.torrent_bytes.clone());
+        let torrent_bytes = job
+            .torrent
+            .as_ref()
+            .map(|t| t.torrent_bytes.clone())
+            .filter(|b| !b.is_empty());
 
         let magnet = job.torrent.as_ref().map(|t| t.magnet.clone());
         let files_list = job
@@ -532,16 +822,24 @@ impl DownloadManager {
             }
         };
 
-        let aria_guard = self.aria.lock().await;
-        match aria_guard.add_torrent(torrent_bytes, dir, files_list).await {
+        match self
+            .torrent_engine
+            .add_torrent(torrent_bytes, dir, files_list)
+            .await
+        {
             Ok(new_gid) => {
                 let job_snapshot = {
                     let mut jobs = self.jobs.write().await;
                     if let Some(j) = jobs.get_mut(&job.id) {
+                        let mut gid_idx = self.gid_index.write().await;
+                        for old in j.gids.drain(..) {
+                            gid_idx.remove(&old);
+                        }
                         j.gids = vec![new_gid.clone()];
+                        // drop progress reported under the previous gid
+                        j.status = None;
                         j.state = DownloadState::Active;
                         j.metadata.updated_at = Utc::now();
-                        let mut gid_idx = self.gid_index.write().await;
                         gid_idx.insert(new_gid, j.id.clone());
                         if let Some(t) = &j.torrent {
                             let mut ih = self.infohash_index.write().await;
@@ -556,7 +854,7 @@ impl DownloadManager {
                 if let Some(job) = job_snapshot {
                     self.emit_job_updated_throttled(&job, true).await;
                 }
-                self.request_save_debounced().await;
+                self.request_save_debounced(&job.id).await;
             }
             Err(e) => {
                 error!("Failed to respawn torrent for job {}: {:?}", job.id, e);
@@ -597,9 +895,7 @@ impl DownloadManager {
                             .shutdown_if_idle();
                 }
 
-                let _ = self
-                    .tauri_handle
-                    .emit("download::job_removed", job_id.to_string());
+                self.emit("download::job_removed", job_id.to_string());
                 Some(gids)
             } else {
                 None
@@ -607,21 +903,37 @@ impl DownloadManager {
         };
 
         if let Some(gids) = gids_opt {
+            if self.stats.removed(job_id) {
+                self.stats.flush().await;
+            }
+
             for gid in gids {
-                let aria_guard = self.aria.lock().await;
-                if let Err(e) = aria_guard.remove(&gid).await {
-                    debug!("Failed to remove gid {} from aria2: {:?}", gid, e);
+                let Some(engine) = self.engine_for(&gid) else {
+                    continue;
+                };
+                if let Err(e) = engine.remove(&gid).await {
+                    debug!(
+                        "Failed to remove gid {} from {}: {:?}",
+                        gid,
+                        engine.name(),
+                        e
+                    );
                 }
             }
-            self.request_save_debounced().await;
+            self.request_save_debounced(job_id).await;
         }
 
         Ok(())
     }
 
-    pub async fn apply_status_raw(&self, gid: &str, raw: Value) -> Result<()> {
+    pub async fn apply_status_raw(self: &Arc<Self>, gid: &str, raw: Value) -> Result<()> {
         let fs = Self::file_status_from_raw(&raw);
+        self.apply_file_status(gid, fs).await;
+        Ok(())
+    }
 
+    /// Fold one transfer's status into its job's `AggregatedStatus`
+    pub async fn apply_file_status(self: &Arc<Self>, gid: &str, fs: FileStatus) {
         let job_id_opt = {
             let idx = self.gid_index.read().await;
             idx.get(gid).cloned()
@@ -653,6 +965,10 @@ impl DownloadManager {
                         .per_file
                         .values()
                         .all(|s| s.status == DownloadState::Complete);
+                    let any_error = agg
+                        .per_file
+                        .values()
+                        .any(|s| s.status == DownloadState::Error);
                     let any_active = agg
                         .per_file
                         .values()
@@ -663,6 +979,9 @@ impl DownloadManager {
 
                     agg.state = if all_complete {
                         DownloadState::Complete
+                    } else if any_error {
+                        // aria2 does not retry a failed part, the job cannot finish on its own
+                        DownloadState::Error
                     } else if any_active {
                         DownloadState::Active
                     } else if any_paused {
@@ -712,6 +1031,12 @@ impl DownloadManager {
             }
 
             if let Some(js) = job_snapshot {
+                let engine = self.engine_for(gid).map_or("unknown", |e| e.name());
+                if self.stats.observe(&js, engine) {
+                    let stats = self.stats.clone();
+                    tokio::spawn(async move { stats.flush().await });
+                }
+
                 // Force emit on state changes, throttle progress updates
                 self.emit_job_updated_throttled(&js, state_changed).await;
 
@@ -722,23 +1047,23 @@ impl DownloadManager {
                         .map(|s| s.completed_length >= s.total_length)
                         .unwrap_or(false)
                 {
-                    let _ = self
-                        .tauri_handle
-                        .emit("download::job_completed", js.clone());
+                    self.emit("download::job_completed", js.clone());
+                }
+
+                if js.state == DownloadState::Complete && js.pipeline.is_none() {
+                    self.spawn_pipeline(js.id.clone());
                 }
             }
 
-            self.request_save_debounced().await;
+            self.request_save_debounced(&job_id).await;
         } else {
             // This can happen for stale GIDs from completed/removed downloads
             debug!("Received update for unknown gid: {}", gid);
         }
-
-        Ok(())
     }
 
     /// Convert an aria2 Status update vector into calls to apply_status_raw
-    pub async fn on_aria2_update(&self, statuses: Vec<Status>) {
+    pub async fn on_aria2_update(self: &Arc<Self>, statuses: Vec<Status>) {
         for status in statuses.into_iter() {
             let mut m = serde_json::Map::new();
             m.insert(
@@ -851,6 +1176,247 @@ impl DownloadManager {
         }
     }
 
+    /// Point unfinished jobs at the transfers `engine` currently knows about.
+    ///
+    /// A restarted daemon hands out new GIDs for the downloads it restores
+    /// from its session file, so torrents are matched by info hash and direct
+    /// links by URI. Returns the jobs that could not be matched, they are
+    /// respawned the next time they are resumed.
+    pub async fn reattach(&self, engine: &Arc<dyn DownloadEngine>) -> Result<Vec<JobId>> {
+        let transfers: Vec<FileStatus> = engine
+            .list()
+            .await?
+            .into_iter()
+            .filter(|t| t.status != DownloadState::Removed)
+            .collect();
+
+        let candidates: Vec<Job> = {
+            let jobs = self.jobs.read().await;
+            jobs.values()
+                .filter(|job| {
+                    job.pipeline.is_none()
+                        && !job.gids.is_empty()
+                        && job.gids.iter().all(|gid| engine.owns(gid))
+                        && !matches!(
+                            job.state,
+                            DownloadState::Complete
+                                | DownloadState::Installing
+                                | DownloadState::Removed
+                        )
+                })
+                .cloned()
+                .collect()
+        };
+
+        let mut claimed: HashSet<Gid> = HashSet::new();
+        let mut unattached = Vec::new();
+
+        for job in candidates {
+            let matched = Self::match_transfers(&job, &transfers, &claimed);
+            let Some(gids) = matched else {
+                unattached.push(job.id);
+                continue;
+            };
+            claimed.extend(gids.iter().cloned());
+            if gids != job.gids {
+                info!(
+                    "Reattached job {} to {} gids {:?}",
+                    job.id,
+                    engine.name(),
+                    gids
+                );
+                self.replace_gids(&job.id, gids).await;
+            }
+        }
+
+        let orphans = transfers
+            .iter()
+            .filter_map(|t| t.gid.as_ref())
+            .filter(|gid| !claimed.contains(*gid))
+            .count();
+        if orphans > 0 {
+            debug!(
+                "{orphans} {} transfers do not belong to any job",
+                engine.name()
+            );
+        }
+
+        Ok(unattached)
+    }
+
+    /// The transfers standing in for every file of `job`, `None` unless all were found
+    fn match_transfers(
+        job: &Job,
+        transfers: &[FileStatus],
+        claimed: &HashSet<Gid>,
+    ) -> Option<Vec<Gid>> {
+        let free = |t: &&FileStatus| t.gid.as_ref().is_some_and(|gid| !claimed.contains(gid));
+
+        match job.source {
+            DownloadSource::Torrent => {
+                let info_hash = &job.torrent.as_ref()?.info_hash;
+                transfers
+                    .iter()
+                    .filter(free)
+                    .find(|t| {
+                        t.info_hash
+                            .as_ref()
+                            .is_some_and(|h| h.eq_ignore_ascii_case(info_hash))
+                    })
+                    .and_then(|t| t.gid.clone())
+                    .map(|gid| vec![gid])
+            }
+            DownloadSource::Ddl => {
+                let mut gids = Vec::new();
+                for file in &job.ddl.as_ref()?.files {
+                    let serves_file = |t: &&FileStatus| {
+                        t.files
+                            .iter()
+                            .any(|f| f.uris.iter().any(|u| u.uri == file.url))
+                    };
+                    // prefer the gid the job already had, it survives a reconnect
+                    let transfer = transfers
+                        .iter()
+                        .filter(free)
+                        .filter(serves_file)
+                        .filter(|t| t.gid.as_ref().is_some_and(|g| !gids.contains(g)))
+                        .max_by_key(|t| t.gid.as_ref().is_some_and(|g| job.gids.contains(g)))?;
+                    gids.push(transfer.gid.clone()?);
+                }
+                // in file order, like `job.gids`
+                Some(gids)
+            }
+        }
+    }
+
+    /// Swap a job's GIDs, dropping progress reported under the old ones
+    async fn replace_gids(&self, job_id: &str, gids: Vec<Gid>) {
+        let snapshot = {
+            let mut jobs = self.jobs.write().await;
+            let Some(job) = jobs.get_mut(job_id) else {
+                return;
+            };
+            let mut gid_idx = self.gid_index.write().await;
+            for old in job.gids.drain(..) {
+                gid_idx.remove(&old);
+            }
+            for gid in &gids {
+                gid_idx.insert(gid.clone(), job.id.clone());
+            }
+            job.gids = gids;
+            job.status = None;
+            job.metadata.updated_at = Utc::now();
+            job.clone()
+        };
+
+        self.emit_job_updated_throttled(&snapshot, true).await;
+        self.request_save_debounced(job_id).await;
+    }
+
+    /// GIDs of jobs still expecting progress, i.e. not finished, failed or removed
+    pub async fn unsettled_gids(&self) -> Vec<Gid> {
+        let jobs = self.jobs.read().await;
+        jobs.values()
+            .filter(|job| {
+                !matches!(
+                    job.state,
+                    DownloadState::Complete | DownloadState::Installing | DownloadState::Removed
+                )
+            })
+            .flat_map(|job| {
+                job.gids.iter().filter(|gid| {
+                    job.status
+                        .as_ref()
+                        .and_then(|s| s.per_file.get(*gid))
+                        .is_none_or(|fs| {
+                            !matches!(fs.status, DownloadState::Complete | DownloadState::Removed)
+                        })
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub async fn get_job(&self, job_id: &str) -> Option<Job> {
+        self.jobs.read().await.get(job_id).cloned()
+    }
+
+    /// Peers, trackers, pieces and per-file progress of every transfer of a job
+    pub async fn job_details(&self, job_id: &str) -> Option<JobDetails> {
+        let job = self.get_job(job_id).await?;
+
+        let mut transfers = Vec::with_capacity(job.gids.len());
+        for gid in &job.gids {
+            let Some(engine) = self.engine_for(gid) else {
+                continue;
+            };
+            match engine.details(gid).await {
+                Ok(mut details) => {
+                    if let Some(torrent) = &job.torrent {
+                        for file in details.files.iter_mut().filter(|f| f.path.is_empty()) {
+                            file.selected = torrent.file_indices.is_empty()
+                                || torrent.file_indices.contains(&file.index);
+                            if let Some(info) = torrent
+                                .torrent_files
+                                .iter()
+                                .find(|i| i.file_index == file.index)
+                            {
+                                file.path = info.file_name.to_string_lossy().into_owned();
+                                file.length = info.length;
+                            }
+                        }
+                    }
+                    transfers.push(details);
+                }
+                Err(e) => debug!("No details for gid {gid} from {}: {e}", engine.name()),
+            }
+        }
+
+        Some(JobDetails {
+            job_id: job.id,
+            transfers,
+            stalled_since: job.stalled_since,
+        })
+    }
+
+    /// Flag a job as stalled since `since`, or clear the flag with `None`
+    pub(crate) async fn set_stalled(&self, job_id: &str, since: Option<chrono::DateTime<Utc>>) {
+        let snapshot = {
+            let mut jobs = self.jobs.write().await;
+            let Some(job) = jobs.get_mut(job_id) else {
+                return;
+            };
+            if job.stalled_since == since {
+                return;
+            }
+            job.stalled_since = since;
+            job.clone()
+        };
+
+        self.emit_job_updated_throttled(&snapshot, true).await;
+        self.request_save_debounced(job_id).await;
+    }
+
+    /// Store a job's pipeline state, persist it and notify the frontend
+    pub(crate) async fn set_pipeline(&self, job_id: &str, pipeline: PipelineState) {
+        let snapshot = {
+            let mut jobs = self.jobs.write().await;
+            let Some(job) = jobs.get_mut(job_id) else {
+                return;
+            };
+            job.pipeline = Some(pipeline);
+            job.metadata.updated_at = Utc::now();
+            job.clone()
+        };
+
+        self.emit_job_updated_throttled(&snapshot, true).await;
+        self.request_save_debounced(job_id).await;
+    }
+
+    pub(crate) fn app_handle(&self) -> Option<&tauri::AppHandle> {
+        self.tauri_handle.as_ref()
+    }
+
     /// Return a snapshot of all jobs
     pub async fn all_jobs(&self) -> Vec<Job> {
         self.jobs.read().await.values().cloned().collect()
@@ -0,0 +1,253 @@
+//! Downloads asked for while offline.
+//!
+//! They wait in `download_outbox.json` next to `manager.json` and are
+//! submitted like any new job once the app is back online. A request
+//! that still fails stays in the outbox with its error until the next
+//! reconnection or until the user discards it.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use fit_launcher_config::client::connectivity;
+use fit_launcher_ddl::DirectLink;
+use fit_launcher_scraping::structs::Game;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    commands::submit_request, manager::DownloadManager, persistence::write_atomic, types::JobId,
+};
+
+/// Emitted with every entry whenever the outbox changes
+pub const OUTBOX_UPDATED_EVENT: &str = "download::outbox_updated";
+
+/// What became of a download request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(tag = "kind", content = "id", rename_all = "camelCase")]
+pub enum JobSubmission {
+    /// Submitted right away, with the id of the new job
+    Started(JobId),
+    /// Offline, waiting in the outbox under the id of its entry
+    Queued(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum OutboxRequest {
+    AddDdlJob {
+        files: Vec<DirectLink>,
+        target: String,
+        game: Game,
+        ignore_space_check: bool,
+    },
+    AddTorrentJob {
+        magnet: String,
+        files_list: Vec<usize>,
+        target: String,
+        game: Game,
+        ignore_space_check: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct OutboxEntry {
+    pub id: String,
+    pub queued_at: DateTime<Utc>,
+    pub request: OutboxRequest,
+    /// Why the last replay failed
+    pub last_error: Option<String>,
+}
+
+pub struct Outbox {
+    path: PathBuf,
+    entries: Mutex<Vec<OutboxEntry>>,
+}
+
+impl Outbox {
+    pub fn open(path: PathBuf) -> Self {
+        let entries = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                warn!(
+                    "Ignoring unreadable download outbox {}: {e}",
+                    path.display()
+                );
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Oldest first
+    pub fn entries(&self) -> Vec<OutboxEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    pub(crate) fn push(&self, request: OutboxRequest) -> Result<String> {
+        let id = Uuid::new_v4().to_string();
+        self.update(|entries| {
+            entries.push(OutboxEntry {
+                id: id.clone(),
+                queued_at: Utc::now(),
+                request,
+                last_error: None,
+            })
+        })?;
+        Ok(id)
+    }
+
+    /// `false` when there is no such entry
+    pub fn discard(&self, id: &str) -> Result<bool> {
+        let mut found = false;
+        self.update(|entries| {
+            let before = entries.len();
+            entries.retain(|e| e.id != id);
+            found = entries.len() != before;
+        })?;
+        Ok(found)
+    }
+
+    fn settle(&self, id: &str, result: Result<(), String>) -> Result<()> {
+        self.update(|entries| match result {
+            Ok(()) => entries.retain(|e| e.id != id),
+            Err(e) => {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                    entry.last_error = Some(e);
+                }
+            }
+        })
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Vec<OutboxEntry>)) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        f(&mut entries);
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(&self.path, &serde_json::to_vec_pretty(&*entries)?)?;
+        Ok(())
+    }
+}
+
+/// Replay the outbox whenever the app comes back online
+pub fn spawn_outbox_replay(manager: Arc<DownloadManager>) {
+    tokio::spawn(async move {
+        let mut rx = connectivity::subscribe();
+        loop {
+            let offline = rx.borrow_and_update().is_offline();
+            if !offline {
+                replay(&manager).await;
+            }
+            if rx.changed().await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+async fn replay(manager: &Arc<DownloadManager>) {
+    let entries = manager.outbox().entries();
+    if entries.is_empty() {
+        return;
+    }
+    info!("Replaying {} queued downloads", entries.len());
+
+    for entry in entries {
+        if connectivity::is_offline() {
+            break;
+        }
+        let result = submit_request(manager, entry.request)
+            .await
+            .map(|job_id| info!("Queued download {} started as {job_id}", entry.id));
+        if let Err(e) = &result {
+            warn!("Queued download {} failed: {e}", entry.id);
+        }
+        if let Err(e) = manager.outbox().settle(&entry.id, result) {
+            error!("Failed to save the download outbox: {e:#}");
+        }
+    }
+    manager.emit(OUTBOX_UPDATED_EVENT, manager.outbox().entries());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_outbox() -> (Outbox, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("fit-outbox-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("download_outbox.json");
+        (Outbox::open(path.clone()), path)
+    }
+
+    fn request(magnet: &str) -> OutboxRequest {
+        OutboxRequest::AddTorrentJob {
+            magnet: magnet.into(),
+            files_list: vec![0, 2],
+            target: "/downloads".into(),
+            game: Game::default(),
+            ignore_space_check: false,
+        }
+    }
+
+    fn magnets(outbox: &Outbox) -> Vec<String> {
+        outbox
+            .entries()
+            .into_iter()
+            .map(|e| match e.request {
+                OutboxRequest::AddTorrentJob { magnet, .. } => magnet,
+                OutboxRequest::AddDdlJob { target, .. } => target,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn entries_survive_reopening_in_order() {
+        let (outbox, path) = temp_outbox();
+        let first = outbox.push(request("magnet:one")).unwrap();
+        outbox.push(request("magnet:two")).unwrap();
+        outbox.settle(&first, Err("site down".into())).unwrap();
+
+        let reopened = Outbox::open(path);
+        assert_eq!(magnets(&reopened), vec!["magnet:one", "magnet:two"]);
+        let entries = reopened.entries();
+        assert_eq!(entries[0].id, first);
+        assert_eq!(entries[0].last_error.as_deref(), Some("site down"));
+        assert!(entries[1].last_error.is_none());
+    }
+
+    #[test]
+    fn settled_and_discarded_entries_are_gone_for_good() {
+        let (outbox, path) = temp_outbox();
+        let started = outbox.push(request("magnet:one")).unwrap();
+        let dropped = outbox.push(request("magnet:two")).unwrap();
+        outbox.push(request("magnet:three")).unwrap();
+
+        outbox.settle(&started, Ok(())).unwrap();
+        assert!(outbox.discard(&dropped).unwrap());
+        assert!(!outbox.discard(&dropped).unwrap());
+
+        assert_eq!(magnets(&Outbox::open(path)), vec!["magnet:three"]);
+    }
+
+    #[test]
+    fn unreadable_outbox_starts_empty() {
+        let (_, path) = temp_outbox();
+        fs::write(&path, b"{ not json").unwrap();
+
+        let outbox = Outbox::open(path.clone());
+        assert!(outbox.entries().is_empty());
+        outbox.push(request("magnet:one")).unwrap();
+        assert_eq!(magnets(&Outbox::open(path)), vec!["magnet:one"]);
+    }
+}
@@ -1,42 +1,521 @@
+//! Job persistence: a `manager.json` snapshot plus an append-only journal.
+//!
+//! Every save only appends the jobs that changed since the previous one to
+//! `manager.journal`, the snapshot is rewritten when the journal is compacted.
+//! Debounced saves name the mutated jobs, only [`JobStore::save`] compares
+//! every job against what was last written.
+//! Torrent payloads are written once to `manager_torrents/<info_hash>.torrent`
+//! instead of being inlined in the JSON.
+
 use crate::types::{Job, JobId};
 use anyhow::Result;
-use serde_json;
-use std::collections::HashMap;
-use std::fs::OpenOptions;
-use std::io::Write;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
 
-pub async fn save_jobs_atomic(path: PathBuf, jobs: &HashMap<JobId, Job>) -> Result<()> {
-    let tmp = path.with_extension("tmp");
-    // Serialize on blocking thread to avoid tokio blocking
-    let bytes = tokio::task::spawn_blocking({
-        let jobs = jobs.clone();
-        move || serde_json::to_vec_pretty(&jobs)
-    })
-    .await??;
-
-    tokio::task::spawn_blocking(move || -> Result<(), anyhow::Error> {
+/// Journal entries after which the snapshot is rewritten and the journal dropped
+const COMPACT_AFTER: usize = 1000;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalEntry {
+    Upsert { job: Box<Job> },
+    Remove { id: JobId },
+}
+
+#[derive(Default)]
+struct StoreState {
+    /// Compact JSON of every job as last written, used to find changed jobs
+    persisted: HashMap<JobId, String>,
+    journal_entries: usize,
+    /// Info hashes whose payload is known to be on disk
+    blobs: HashSet<String>,
+}
+
+pub struct JobStore {
+    snapshot_path: PathBuf,
+    journal_path: PathBuf,
+    torrents_dir: PathBuf,
+    state: Mutex<StoreState>,
+}
+
+impl JobStore {
+    /// Store rooted at `snapshot_path` (`manager.json`), other files live next to it.
+    pub fn new(snapshot_path: PathBuf) -> Arc<Self> {
+        let dir = snapshot_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        Arc::new(Self {
+            journal_path: snapshot_path.with_extension("journal"),
+            torrents_dir: dir.join("manager_torrents"),
+            snapshot_path,
+            state: Mutex::new(StoreState::default()),
+        })
+    }
+
+    pub async fn load(self: &Arc<Self>) -> Result<HashMap<JobId, Job>> {
+        let this = Arc::clone(self);
+        tokio::task::spawn_blocking(move || this.load_blocking()).await?
+    }
+
+    pub async fn save(self: &Arc<Self>, jobs: HashMap<JobId, Job>) -> Result<()> {
+        let this = Arc::clone(self);
+        tokio::task::spawn_blocking(move || this.save_blocking(&jobs)).await?
+    }
+
+    /// Append `changes` only, `None` for a removed job.
+    pub async fn save_changes(self: &Arc<Self>, changes: Vec<(JobId, Option<Job>)>) -> Result<()> {
+        let this = Arc::clone(self);
+        tokio::task::spawn_blocking(move || this.save_changes_blocking(&changes)).await?
+    }
+
+    /// Read the snapshot (inline torrent bytes from older versions included),
+    /// replay the journal, then compact so the next start is a plain read.
+    fn load_blocking(&self) -> Result<HashMap<JobId, Job>> {
+        let mut jobs: HashMap<JobId, Job> = if self.snapshot_path.exists() {
+            serde_json::from_slice(&fs::read(&self.snapshot_path)?)?
+        } else {
+            HashMap::new()
+        };
+
+        let replayed = self.replay_journal(&mut jobs)?;
+
+        for job in jobs.values_mut() {
+            if let Some(t) = job.torrent.as_mut()
+                && t.torrent_bytes.is_empty()
+                && let Some(path) = self.blob_path(&t.info_hash)
+            {
+                match fs::read(&path) {
+                    Ok(bytes) => t.torrent_bytes = bytes,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => warn!("Failed to read {}: {e}", path.display()),
+                }
+            }
+        }
+
+        if replayed > 0 {
+            info!("Replayed {replayed} job journal entries");
+        }
+        self.compact(&jobs)?;
+        Ok(jobs)
+    }
+
+    fn replay_journal(&self, jobs: &mut HashMap<JobId, Job>) -> Result<usize> {
+        let file = match fs::File::open(&self.journal_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut replayed = 0;
+        for (n, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // a torn last line is expected after a crash mid-append
+            match serde_json::from_str::<JournalEntry>(&line) {
+                Ok(JournalEntry::Upsert { job }) => {
+                    jobs.insert(job.id.clone(), *job);
+                }
+                Ok(JournalEntry::Remove { id }) => {
+                    jobs.remove(&id);
+                }
+                Err(e) => {
+                    warn!("Skipping unreadable job journal line {}: {e}", n + 1);
+                    continue;
+                }
+            }
+            replayed += 1;
+        }
+
+        Ok(replayed)
+    }
+
+    /// Append the jobs that changed (or were removed) since the last save.
+    fn save_blocking(&self, jobs: &HashMap<JobId, Job>) -> Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.append_changes(jobs, &mut state)?;
+        self.compact_if_needed(&mut state)
+    }
+
+    fn save_changes_blocking(&self, changes: &[(JobId, Option<Job>)]) -> Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.append(
+            changes.iter().map(|(id, job)| (id, job.as_ref())),
+            &mut state,
+        )?;
+        self.compact_if_needed(&mut state)
+    }
+
+    fn compact_if_needed(&self, state: &mut StoreState) -> Result<()> {
+        if state.journal_entries >= COMPACT_AFTER {
+            self.write_snapshot(state)?;
+        }
+        Ok(())
+    }
+
+    fn compact(&self, jobs: &HashMap<JobId, Job>) -> Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.append_changes(jobs, &mut state)?;
+        self.write_snapshot(&mut state)
+    }
+
+    fn append_changes(&self, jobs: &HashMap<JobId, Job>, state: &mut StoreState) -> Result<()> {
+        let removed: Vec<JobId> = state
+            .persisted
+            .keys()
+            .filter(|id| !jobs.contains_key(*id))
+            .cloned()
+            .collect();
+
+        let changes = jobs
+            .iter()
+            .map(|(id, job)| (id, Some(job)))
+            .chain(removed.iter().map(|id| (id, None)));
+        self.append(changes, state)
+    }
+
+    /// Journal each job that differs from what was last written, `None` removes it.
+    fn append<'a>(
+        &self,
+        changes: impl IntoIterator<Item = (&'a JobId, Option<&'a Job>)>,
+        state: &mut StoreState,
+    ) -> Result<()> {
+        let mut lines = Vec::new();
+
+        for (id, job) in changes {
+            match job {
+                Some(job) => {
+                    let stored = self.stored_form(job, state)?;
+                    let json = serde_json::to_string(&stored)?;
+                    if state.persisted.get(id) == Some(&json) {
+                        continue;
+                    }
+
+                    serde_json::to_writer(
+                        &mut lines,
+                        &JournalEntry::Upsert {
+                            job: Box::new(stored),
+                        },
+                    )?;
+                    state.persisted.insert(id.clone(), json);
+                }
+                None => {
+                    if state.persisted.remove(id).is_none() {
+                        continue;
+                    }
+                    serde_json::to_writer(&mut lines, &JournalEntry::Remove { id: id.clone() })?;
+                }
+            }
+            lines.push(b'\n');
+            state.journal_entries += 1;
+        }
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.journal_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
         let mut f = OpenOptions::new()
             .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&tmp)?;
-        f.write_all(&bytes)?;
-        f.sync_all()?;
-        std::fs::rename(&tmp, path)?;
+            .append(true)
+            .open(&self.journal_path)?;
+        f.write_all(&lines)?;
+        f.sync_data()?;
         Ok(())
-    })
-    .await??;
+    }
+
+    /// Rewrite the snapshot from what the journal holds, then drop the journal.
+    ///
+    /// The journal ends with the snapshot's exact content, so a crash between
+    /// both steps replays to the same state.
+    fn write_snapshot(&self, state: &mut StoreState) -> Result<()> {
+        let mut jobs = HashMap::with_capacity(state.persisted.len());
+        for (id, json) in &state.persisted {
+            jobs.insert(id.clone(), serde_json::from_str::<Job>(json)?);
+        }
+
+        if let Some(parent) = self.snapshot_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_atomic(&self.snapshot_path, &serde_json::to_vec_pretty(&jobs)?)?;
+        match fs::remove_file(&self.journal_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        state.journal_entries = 0;
+
+        self.prune_blobs(&jobs, state);
+        Ok(())
+    }
+
+    /// Job as persisted, with the torrent payload moved to its own file
+    fn stored_form(&self, job: &Job, state: &mut StoreState) -> Result<Job> {
+        let mut stored = job.clone();
+
+        if let Some(t) = stored.torrent.as_mut()
+            && !t.torrent_bytes.is_empty()
+            && let Some(path) = self.blob_path(&t.info_hash)
+        {
+            let hash = t.info_hash.to_ascii_lowercase();
+            if !state.blobs.contains(&hash) {
+                if !path.exists() {
+                    fs::create_dir_all(&self.torrents_dir)?;
+                    write_atomic(&path, &t.torrent_bytes)?;
+                }
+                state.blobs.insert(hash);
+            }
+            t.torrent_bytes = Vec::new();
+        }
+
+        Ok(stored)
+    }
+
+    fn prune_blobs(&self, jobs: &HashMap<JobId, Job>, state: &mut StoreState) {
+        let referenced: HashSet<String> = jobs
+            .values()
+            .filter_map(|j| j.torrent.as_ref())
+            .map(|t| t.info_hash.to_ascii_lowercase())
+            .collect();
 
-    Ok(())
+        let Ok(entries) = fs::read_dir(&self.torrents_dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(hash) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if path.extension().is_some_and(|e| e == "torrent") && !referenced.contains(hash) {
+                if let Err(e) = fs::remove_file(&path) {
+                    warn!("Failed to remove {}: {e}", path.display());
+                }
+                state.blobs.remove(hash);
+            }
+        }
+    }
+
+    /// `None` for hashes that can't be used as a file name, those stay inline
+    fn blob_path(&self, info_hash: &str) -> Option<PathBuf> {
+        if info_hash.is_empty() || !info_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(
+            self.torrents_dir
+                .join(format!("{}.torrent", info_hash.to_ascii_lowercase())),
+        )
+    }
+}
+
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut f = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&tmp)?;
+    f.write_all(bytes)?;
+    f.sync_all()?;
+    fs::rename(&tmp, path)
 }
 
-pub async fn load_jobs(path: impl AsRef<Path>) -> Result<HashMap<JobId, Job>> {
-    let path = path.as_ref();
-    if !path.exists() {
-        return Ok(HashMap::new());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AggregatedStatus, DownloadState, FileStatus};
+    use fit_launcher_scraping::structs::Game;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn temp_store() -> (Arc<JobStore>, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("fit-jobstore-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        (JobStore::new(dir.join("manager.json")), dir)
+    }
+
+    fn torrent_job() -> Job {
+        Job::new_torrent(
+            vec![1, 2, 3, 4],
+            vec![0],
+            HASH.into(),
+            "magnet:?xt=urn:btih:x".into(),
+            PathBuf::from("/downloads"),
+            PathBuf::from("/downloads/game"),
+            vec![],
+            Game::default(),
+        )
+    }
+
+    #[test]
+    fn legacy_snapshot_moves_torrent_out() {
+        let (store, dir) = temp_store();
+        let job = torrent_job();
+        let legacy = HashMap::from([(job.id.clone(), job.clone())]);
+        fs::write(
+            &store.snapshot_path,
+            serde_json::to_vec_pretty(&legacy).unwrap(),
+        )
+        .unwrap();
+
+        let loaded = store.load_blocking().unwrap();
+        assert_eq!(
+            loaded[&job.id].torrent.as_ref().unwrap().torrent_bytes,
+            vec![1, 2, 3, 4]
+        );
+
+        let blob = dir.join("manager_torrents").join(format!("{HASH}.torrent"));
+        assert_eq!(fs::read(blob).unwrap(), vec![1, 2, 3, 4]);
+        let snapshot: HashMap<JobId, Job> =
+            serde_json::from_slice(&fs::read(&store.snapshot_path).unwrap()).unwrap();
+        assert!(
+            snapshot[&job.id]
+                .torrent
+                .as_ref()
+                .unwrap()
+                .torrent_bytes
+                .is_empty()
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn journal_replays_only_changes() {
+        let (store, dir) = temp_store();
+        let mut job = torrent_job();
+        let other = torrent_job();
+        let mut jobs = HashMap::from([
+            (job.id.clone(), job.clone()),
+            (other.id.clone(), other.clone()),
+        ]);
+
+        store.save_blocking(&jobs).unwrap();
+        job.metadata.game_title = "renamed".into();
+        jobs.insert(job.id.clone(), job.clone());
+        jobs.remove(&other.id);
+        store.save_blocking(&jobs).unwrap();
+        // nothing changed, nothing appended
+        store.save_blocking(&jobs).unwrap();
+
+        let journal = fs::read_to_string(&store.journal_path).unwrap();
+        assert_eq!(journal.lines().count(), 4);
+
+        let reloaded = JobStore::new(store.snapshot_path.clone())
+            .load_blocking()
+            .unwrap();
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded[&job.id].metadata.game_title, "renamed");
+        assert_eq!(
+            reloaded[&job.id].torrent.as_ref().unwrap().torrent_bytes,
+            vec![1, 2, 3, 4]
+        );
+        assert!(!store.journal_path.exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn progress_survives_reload() {
+        let (store, dir) = temp_store();
+        let mut job = torrent_job();
+        job.status = Some(AggregatedStatus {
+            total_length: 1000,
+            completed_length: 400,
+            download_speed: 50,
+            per_file: HashMap::from([(
+                "gid".to_string(),
+                FileStatus {
+                    status: DownloadState::Active,
+                    total_length: 1000,
+                    completed_length: 400,
+                    download_speed: 50,
+                    ..Default::default()
+                },
+            )]),
+            state: DownloadState::Active,
+            progress_percentage: 40.0,
+            ..Default::default()
+        });
+        store
+            .save_blocking(&HashMap::from([(job.id.clone(), job.clone())]))
+            .unwrap();
+
+        let reloaded = JobStore::new(store.snapshot_path.clone())
+            .load_blocking()
+            .unwrap();
+        let mut status = reloaded[&job.id].status.clone().unwrap();
+        assert_eq!(status.completed_length, 400);
+        assert_eq!(status.progress_percentage, 40.0);
+
+        status.pause();
+        assert_eq!(status.state, DownloadState::Paused);
+        assert_eq!(status.download_speed, 0);
+        assert_eq!(status.per_file["gid"].status, DownloadState::Paused);
+        assert_eq!(status.per_file["gid"].completed_length, 400);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn targeted_saves_append_only_named_jobs() {
+        let (store, dir) = temp_store();
+        let mut job = torrent_job();
+        let mut other = torrent_job();
+        store
+            .save_blocking(&HashMap::from([
+                (job.id.clone(), job.clone()),
+                (other.id.clone(), other.clone()),
+            ]))
+            .unwrap();
+
+        job.metadata.game_title = "renamed".into();
+        // changed too, but not named, so not written
+        other.metadata.game_title = "unsaved".into();
+        store
+            .save_changes_blocking(&[(job.id.clone(), Some(job.clone()))])
+            .unwrap();
+        // unchanged since the last save, nothing appended
+        store
+            .save_changes_blocking(&[(job.id.clone(), Some(job.clone()))])
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&store.journal_path)
+                .unwrap()
+                .lines()
+                .count(),
+            3
+        );
+
+        store
+            .save_changes_blocking(&[(other.id.clone(), None), ("unknown".into(), None)])
+            .unwrap();
+        let journal = fs::read_to_string(&store.journal_path).unwrap();
+        assert_eq!(journal.lines().count(), 4);
+        assert!(
+            journal
+                .lines()
+                .last()
+                .unwrap()
+                .contains("\"op\":\"remove\"")
+        );
+
+        let reloaded = JobStore::new(store.snapshot_path.clone())
+            .load_blocking()
+            .unwrap();
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded[&job.id].metadata.game_title, "renamed");
+
+        fs::remove_dir_all(dir).unwrap();
     }
-    let bytes = tokio::fs::read(path).await?;
-    let map = serde_json::from_slice(&bytes)?;
-    Ok(map)
 }
@@ -0,0 +1,521 @@
+//! Post-download pipeline: verify → extract → install → clean → register.
+//!
+//! The pipeline state is stored on the [`Job`] itself, so a pipeline cut short
+//! by closing the app resumes on the next start. Failed stages are retried with
+//! exponential backoff before the pipeline gives up and waits for a manual retry.
+
+use crate::{error::DownloadManagerError, manager::DownloadManager, types::*};
+use anyhow::Result;
+use chrono::Utc;
+use fit_launcher_config::{
+    commands::get_installation_settings, settings::creation::InstallationSettings,
+};
+use fit_launcher_library::structs::{DownloadedGame, ExecutableInfo, InstallationInfo};
+use fit_launcher_ui_automation::{
+    ArchiveFormat,
+    api::{InstallationManager, types::clean_parts},
+    archive_group,
+    errors::ExtractError,
+    extract_archive_async,
+};
+use serde::Serialize;
+use specta::Type;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+pub const PIPELINE_STAGE_EVENT: &str = "pipeline::stage";
+
+/// Attempts per stage before the pipeline is marked failed
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE: Duration = Duration::from_secs(15);
+const RETRY_MAX: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineStageEvent {
+    pub job_id: JobId,
+    pub game_title: String,
+    pub stage: PipelineStage,
+    pub status: StageStatus,
+    pub attempt: u32,
+    pub error: Option<String>,
+    /// Folder the game was downloaded to, also what was excluded from Defender
+    pub target_path: PathBuf,
+}
+
+enum StageOutcome {
+    Succeeded,
+    Skipped,
+}
+
+/// Delay before the `attempt`-th retry (1-based): 15s, 30s, 60s, ... capped at 10 minutes
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(RETRY_MAX)
+}
+
+/// Archives are only unpacked and setups only run when the game gets installed automatically
+fn installs_automatically(settings: &InstallationSettings) -> bool {
+    settings.auto_install && cfg!(windows)
+}
+
+/// Whether `stage` has nothing to do for this job under the current settings
+fn skips_stage(
+    stage: PipelineStage,
+    source: &DownloadSource,
+    settings: &InstallationSettings,
+    state: &PipelineState,
+) -> bool {
+    match stage {
+        PipelineStage::Verify => false,
+        PipelineStage::Extract => {
+            *source != DownloadSource::Ddl || !installs_automatically(settings)
+        }
+        PipelineStage::Install => !installs_automatically(settings),
+        PipelineStage::Clean => state.install_skipped || !settings.auto_clean,
+        PipelineStage::Register => state.install_skipped,
+        PipelineStage::Done => true,
+    }
+}
+
+/// A finished job only leaves the download list when the user wants things cleaned up
+fn removes_finished_job(settings: &InstallationSettings) -> bool {
+    settings.auto_clean
+}
+
+impl DownloadManager {
+    /// Run the pipeline of a completed job in the background, no-op if it is already running.
+    pub fn spawn_pipeline(self: &Arc<Self>, job_id: JobId) {
+        if self.app_handle().is_none() {
+            debug!("Headless manager, not running the pipeline of {job_id}");
+            return;
+        }
+        let this = Arc::clone(self);
+        tokio::spawn(async move {
+            if !this.running_pipelines.lock().await.insert(job_id.clone()) {
+                return;
+            }
+            this.run_pipeline(&job_id).await;
+            this.running_pipelines.lock().await.remove(&job_id);
+        });
+    }
+
+    /// Resume every pipeline left unfinished by a previous session
+    pub async fn resume_pipelines(self: &Arc<Self>) {
+        for job in self.all_jobs().await {
+            if job
+                .pipeline
+                .as_ref()
+                .is_some_and(|p| p.stage != PipelineStage::Done && !p.failed)
+            {
+                info!("Resuming pipeline of {}", job.metadata.game_title);
+                self.spawn_pipeline(job.id);
+            }
+        }
+    }
+
+    /// Give a failed pipeline a fresh set of attempts, starting at the stage that failed.
+    pub async fn retry_pipeline(self: &Arc<Self>, job_id: &str) -> Result<()> {
+        let job = self
+            .get_job(job_id)
+            .await
+            .ok_or_else(|| DownloadManagerError::JobNotFound(job_id.to_string()))?;
+
+        let mut state = job.pipeline.unwrap_or_default();
+        state.failed = false;
+        state.attempts = 0;
+        state.next_retry_at = None;
+        self.set_pipeline(job_id, state).await;
+
+        self.spawn_pipeline(job_id.to_string());
+        Ok(())
+    }
+
+    async fn run_pipeline(self: &Arc<Self>, job_id: &str) {
+        loop {
+            let Some(job) = self.get_job(job_id).await else {
+                return;
+            };
+            let mut state = job.pipeline.clone().unwrap_or_default();
+            if state.failed {
+                return;
+            }
+            if state.stage == PipelineStage::Done {
+                // the job has nothing left to do, drop it like `dm_clean_job` does
+                if removes_finished_job(&get_installation_settings())
+                    && let Err(e) = self.remove(job_id).await
+                {
+                    error!("Failed to remove finished job {job_id}: {e}");
+                }
+                return;
+            }
+
+            if let Some(at) = state.next_retry_at
+                && let Ok(wait) = (at - Utc::now()).to_std()
+            {
+                tokio::time::sleep(wait).await;
+            }
+
+            let stage = state.stage;
+            self.emit_stage(&job, stage, StageStatus::Running, &state);
+            if job.pipeline.is_none() {
+                self.set_pipeline(job_id, state.clone()).await;
+            }
+
+            match self.run_stage(&job, &mut state).await {
+                Ok(outcome) => {
+                    let status = match outcome {
+                        StageOutcome::Succeeded => StageStatus::Succeeded,
+                        StageOutcome::Skipped => StageStatus::Skipped,
+                    };
+                    info!("{} pipeline: {stage:?} {status:?}", job.metadata.game_title);
+                    state.stage = stage.next();
+                    state.attempts = 0;
+                    state.last_error = None;
+                    state.next_retry_at = None;
+                    self.emit_stage(&job, stage, status, &state);
+                }
+                Err(e) => {
+                    state.attempts += 1;
+                    state.last_error = Some(e.clone());
+                    // a cancelled stage is failed right away
+                    let status = if state.failed || state.attempts >= MAX_ATTEMPTS {
+                        state.failed = true;
+                        StageStatus::Failed
+                    } else {
+                        state.next_retry_at =
+                            chrono::Duration::from_std(retry_delay(state.attempts))
+                                .ok()
+                                .map(|d| Utc::now() + d);
+                        StageStatus::Retrying
+                    };
+                    warn!(
+                        "{} pipeline: {stage:?} failed (attempt {}): {e}",
+                        job.metadata.game_title, state.attempts
+                    );
+                    self.emit_stage(&job, stage, status, &state);
+                }
+            }
+
+            self.set_pipeline(job_id, state).await;
+        }
+    }
+
+    async fn run_stage(
+        &self,
+        job: &Job,
+        state: &mut PipelineState,
+    ) -> Result<StageOutcome, String> {
+        let settings = get_installation_settings();
+        let app = self
+            .app_handle()
+            .ok_or_else(|| "the pipeline needs the app".to_string())?;
+
+        if skips_stage(state.stage, &job.source, &settings, state) {
+            if state.stage == PipelineStage::Install {
+                state.install_skipped = true;
+            }
+            return Ok(StageOutcome::Skipped);
+        }
+
+        match state.stage {
+            PipelineStage::Verify => verify(job).map(|_| StageOutcome::Succeeded),
+            PipelineStage::Extract => {
+                let installs = app.state::<InstallationManager>();
+                let (_, cancel) = install_job(&installs, job).await;
+                let result = extract_job_archives(app, &job.job_path, None, cancel.clone()).await;
+                if cancel.is_cancelled() {
+                    state.failed = true;
+                    return Err("Extraction was cancelled".into());
+                }
+                let archives = result.map_err(|e| e.to_string())?;
+                if settings.auto_clean {
+                    for archive in archives {
+                        if let Err(e) = tokio::fs::remove_file(&archive).await {
+                            warn!("Failed to remove {}: {e}", archive.display());
+                        }
+                    }
+                }
+                Ok(StageOutcome::Succeeded)
+            }
+            PipelineStage::Install => {
+                let installs = app.state::<InstallationManager>().inner().clone();
+                let (id, cancel) = install_job(&installs, job).await;
+
+                // the installer holds Windows handles that aren't Send
+                let handle = app.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    tokio::runtime::Handle::current()
+                        .block_on(async move { installs.run_job(id, handle).await })
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+                if cancel.is_cancelled() {
+                    state.failed = true;
+                }
+                let outcome = result.map_err(|e| e.to_string())?;
+
+                state.install_path = outcome.install_path;
+                state.executable = outcome.executable;
+                Ok(StageOutcome::Succeeded)
+            }
+            PipelineStage::Clean => clean_parts(&job.job_path)
+                .await
+                .map(|_| StageOutcome::Succeeded)
+                .map_err(|e| e.to_string()),
+            PipelineStage::Register => register(job, state).await.map(|_| StageOutcome::Succeeded),
+            PipelineStage::Done => Ok(StageOutcome::Skipped),
+        }
+    }
+
+    fn emit_stage(
+        &self,
+        job: &Job,
+        stage: PipelineStage,
+        status: StageStatus,
+        state: &PipelineState,
+    ) {
+        self.emit(
+            PIPELINE_STAGE_EVENT,
+            PipelineStageEvent {
+                job_id: job.id.clone(),
+                game_title: job.metadata.game_title.clone(),
+                stage,
+                status,
+                attempt: state.attempts,
+                error: state.last_error.clone(),
+                target_path: job.metadata.target_path.clone(),
+            },
+        );
+    }
+}
+
+/// The installation job of `job`, created before Extract so that
+/// `dm_cancel_extraction` stops the extraction as well as the installer.
+/// A cancelled one is replaced, the pipeline was retried since.
+async fn install_job(installs: &InstallationManager, job: &Job) -> (Uuid, CancellationToken) {
+    let download_id = Uuid::parse_str(&job.id).ok();
+    for existing in installs.list_jobs().await {
+        if download_id.is_none() || existing.download_id != download_id {
+            continue;
+        }
+        if !existing.cancel_emitter.is_cancelled() {
+            return (existing.id, existing.cancel_emitter.clone());
+        }
+        installs.remove(existing.id).await;
+    }
+
+    let id = installs
+        .create_job(job.game.clone(), job.job_path.clone(), download_id)
+        .await;
+    let cancel = installs
+        .get_cancellation_token(id)
+        .await
+        .unwrap_or_default();
+    (id, cancel)
+}
+
+/// The download folder must exist and hold every expected file
+fn verify(job: &Job) -> Result<(), String> {
+    if !job.job_path.is_dir() {
+        return Err(format!(
+            "Download folder {} is missing",
+            job.job_path.display()
+        ));
+    }
+
+    if let Some(status) = &job.status
+        && status.completed_length < status.total_length
+    {
+        return Err(format!(
+            "Download incomplete: {} of {} bytes",
+            status.completed_length, status.total_length
+        ));
+    }
+
+    if let Some(ddl) = &job.ddl {
+        for file in &ddl.files {
+            let path = job.job_path.join(&file.filename);
+            let len = std::fs::metadata(&path)
+                .map(|m| m.len())
+                .map_err(|_| format!("Missing file {}", file.filename))?;
+            if file.size > 0 && len < file.size {
+                return Err(format!(
+                    "{} is truncated ({len} of {} bytes)",
+                    file.filename, file.size
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Add the installed game to the library, keeping an existing entry as is
+async fn register(job: &Job, state: &PipelineState) -> Result<(), String> {
+    let existing = fit_launcher_library::get_downloaded_games().await;
+    if existing.iter().any(|g| g.href == job.game.href) {
+        return Ok(());
+    }
+
+    let mut game = DownloadedGame::from(job.game.clone());
+    if let Some(install_path) = &state.install_path {
+        game.installation_info = InstallationInfo {
+            output_folder: install_path.clone(),
+            download_folder: job.job_path.to_string_lossy().to_string(),
+            file_list: vec![],
+        };
+
+        let executable = state
+            .executable
+            .clone()
+            .or_else(|| fit_launcher_library::find_game_executable(install_path.clone()));
+        if let Some(info) = executable
+            .as_deref()
+            .and_then(|exe| ExecutableInfo::discover(exe, Path::new(install_path)))
+        {
+            game.executable_info = info;
+        }
+    }
+
+    fit_launcher_library::add_downloaded_game(game).await
+}
+
+/// Extract every archive set in `job_path` in place, returning the archive files.
+pub(crate) async fn extract_job_archives(
+    app: &AppHandle,
+    job_path: &Path,
+    password: Option<String>,
+    cancel: CancellationToken,
+) -> Result<Vec<PathBuf>, ExtractError> {
+    let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+    for entry in job_path.read_dir()?.flatten() {
+        if entry.metadata()?.is_dir() {
+            continue;
+        }
+
+        if ArchiveFormat::from_path(&entry.path()).is_none() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        groups
+            .entry(archive_group(&name).into())
+            .or_default()
+            .push(entry.path());
+    }
+
+    for paths in groups.values() {
+        // pick a random path is fine,`extract_archive` computes
+        // first archive name internally
+        if let Some(first) = paths.first() {
+            info!("Extracting {first:?} in-place...");
+            extract_archive_async(
+                Some(app.clone()),
+                first.clone(),
+                password.clone(),
+                cancel.clone(),
+            )
+            .await?;
+        }
+    }
+
+    Ok(groups.into_values().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_backs_off_and_caps() {
+        assert_eq!(retry_delay(1), Duration::from_secs(15));
+        assert_eq!(retry_delay(2), Duration::from_secs(30));
+        assert_eq!(retry_delay(3), Duration::from_secs(60));
+        assert_eq!(retry_delay(40), RETRY_MAX);
+    }
+
+    fn settings(auto_install: bool, auto_clean: bool) -> InstallationSettings {
+        InstallationSettings {
+            auto_install,
+            auto_clean,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn settings_decide_skipped_stages() {
+        let state = PipelineState::default();
+        let ddl = DownloadSource::Ddl;
+        let all = settings(true, true);
+
+        assert!(!skips_stage(PipelineStage::Verify, &ddl, &all, &state));
+        assert_eq!(
+            skips_stage(PipelineStage::Extract, &ddl, &all, &state),
+            !cfg!(windows)
+        );
+        assert!(skips_stage(
+            PipelineStage::Extract,
+            &DownloadSource::Torrent,
+            &all,
+            &state
+        ));
+
+        // nothing gets installed, so there is nothing to unpack either
+        let manual = settings(false, true);
+        assert!(skips_stage(PipelineStage::Extract, &ddl, &manual, &state));
+        assert!(skips_stage(PipelineStage::Install, &ddl, &manual, &state));
+
+        assert!(skips_stage(
+            PipelineStage::Clean,
+            &ddl,
+            &settings(true, false),
+            &state
+        ));
+
+        let skipped = PipelineState {
+            install_skipped: true,
+            ..Default::default()
+        };
+        assert!(skips_stage(PipelineStage::Clean, &ddl, &all, &skipped));
+        assert!(skips_stage(PipelineStage::Register, &ddl, &all, &skipped));
+        assert!(!skips_stage(PipelineStage::Register, &ddl, &all, &state));
+    }
+
+    #[test]
+    fn finished_jobs_stay_listed_without_auto_clean() {
+        assert!(removes_finished_job(&settings(true, true)));
+        assert!(!removes_finished_job(&settings(true, false)));
+    }
+
+    #[test]
+    fn stages_run_in_order() {
+        let mut stage = PipelineStage::default();
+        let mut order = vec![stage];
+        while stage != PipelineStage::Done {
+            stage = stage.next();
+            order.push(stage);
+        }
+        assert_eq!(
+            order,
+            vec![
+                PipelineStage::Verify,
+                PipelineStage::Extract,
+                PipelineStage::Install,
+                PipelineStage::Clean,
+                PipelineStage::Register,
+                PipelineStage::Done,
+            ]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Disk-space preflight run before a download job is added.
+//!
+//! A repack needs room for the downloaded archives *and* for the installed
+//! game (archives are only cleaned after installation), so the planner sums
+//! both requirements when download and install end up on the same volume.
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use sysinfo::Disks;
+
+/// Below this much free space left after the job, the verdict becomes `Warn`.
+const WARN_MARGIN_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+
+static SIZE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(\d+(?:[.,]\d+)?)\s*(KB|MB|GB|TB)").expect("invalid size regex")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum PreflightVerdict {
+    Ok,
+    Warn,
+    Insufficient,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeCheck {
+    pub mount_point: String,
+    pub available_bytes: u64,
+    pub required_bytes: u64,
+    pub verdict: PreflightVerdict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    pub verdict: PreflightVerdict,
+    pub download_bytes: u64,
+    /// Estimated from "Original Size", or twice the download when unknown
+    pub install_bytes: u64,
+    pub install_estimated_from_original_size: bool,
+    pub volumes: Vec<VolumeCheck>,
+}
+
+impl PreflightReport {
+    pub fn summary(&self) -> String {
+        self.volumes
+            .iter()
+            .filter(|v| v.verdict != PreflightVerdict::Ok)
+            .map(|v| {
+                format!(
+                    "{}: {} required, {} available",
+                    v.mount_point,
+                    format_bytes(v.required_bytes),
+                    format_bytes(v.available_bytes)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Free space of a mounted volume
+#[derive(Debug, Clone)]
+pub struct VolumeInfo {
+    pub mount_point: PathBuf,
+    pub available_bytes: u64,
+}
+
+/// Parse the "Original Size: 41.7 GB" line of a repack's details.
+pub fn original_size_bytes(details: &str) -> Option<u64> {
+    let line = details
+        .lines()
+        .find(|l| l.trim_start().starts_with("Original Size:"))?;
+    let caps = SIZE_RE.captures(line)?;
+
+    let number: f64 = caps[1].replace(',', ".").parse().ok()?;
+    let multiplier = match caps[2].to_ascii_uppercase().as_str() {
+        "KB" => 1024.0,
+        "MB" => 1024.0 * 1024.0,
+        "GB" => 1024.0 * 1024.0 * 1024.0,
+        "TB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+
+    Some((number * multiplier) as u64)
+}
+
+/// Query the mounted volumes of this machine.
+pub fn system_volumes() -> Vec<VolumeInfo> {
+    Disks::new_with_refreshed_list()
+        .iter()
+        .map(|d| VolumeInfo {
+            mount_point: d.mount_point().to_path_buf(),
+            available_bytes: d.available_space(),
+        })
+        .collect()
+}
+
+/// Build the preflight report for a job.
+///
+/// `download_dir` and `install_dir` do not need to exist yet,
+/// the closest existing ancestor is used to resolve their volume.
+pub fn plan(
+    download_bytes: u64,
+    original_size: Option<u64>,
+    download_dir: &Path,
+    install_dir: &Path,
+    volumes: &[VolumeInfo],
+) -> PreflightReport {
+    let install_bytes = original_size.unwrap_or(download_bytes.saturating_mul(2));
+
+    let download_vol = volume_for(download_dir, volumes);
+    let install_vol = volume_for(install_dir, volumes);
+
+    let mut requirements: Vec<(Option<&VolumeInfo>, u64)> = Vec::new();
+    let same_volume = match (download_vol, install_vol) {
+        (Some(a), Some(b)) => a.mount_point == b.mount_point,
+        (None, None) => true,
+        _ => false,
+    };
+
+    if same_volume {
+        requirements.push((download_vol, download_bytes + install_bytes));
+    } else {
+        requirements.push((download_vol, download_bytes));
+        requirements.push((install_vol, install_bytes));
+    }
+
+    let volumes: Vec<VolumeCheck> = requirements
+        .into_iter()
+        .map(|(vol, required)| match vol {
+            Some(vol) => VolumeCheck {
+                mount_point: vol.mount_point.to_string_lossy().to_string(),
+                available_bytes: vol.available_bytes,
+                required_bytes: required,
+                verdict: verdict_for(vol.available_bytes, required),
+            },
+            // unknown volume (e.g. network share), don't block the user
+            None => VolumeCheck {
+                mount_point: String::new(),
+                available_bytes: 0,
+                required_bytes: required,
+                verdict: PreflightVerdict::Warn,
+            },
+        })
+        .collect();
+
+    let verdict = volumes
+        .iter()
+        .map(|v| v.verdict)
+        .max_by_key(|v| match v {
+            PreflightVerdict::Ok => 0,
+            PreflightVerdict::Warn => 1,
+            PreflightVerdict::Insufficient => 2,
+        })
+        .unwrap_or(PreflightVerdict::Ok);
+
+    PreflightReport {
+        verdict,
+        download_bytes,
+        install_bytes,
+        install_estimated_from_original_size: original_size.is_some(),
+        volumes,
+    }
+}
+
+fn verdict_for(available: u64, required: u64) -> PreflightVerdict {
+    if available < required {
+        PreflightVerdict::Insufficient
+    } else if available - required < WARN_MARGIN_BYTES {
+        PreflightVerdict::Warn
+    } else {
+        PreflightVerdict::Ok
+    }
+}
+
+/// Longest mount point containing `path` (or its closest existing ancestor)
+fn volume_for<'a>(path: &Path, volumes: &'a [VolumeInfo]) -> Option<&'a VolumeInfo> {
+    let resolved = path
+        .ancestors()
+        .find_map(|p| p.canonicalize().ok())
+        .unwrap_or_else(|| path.to_path_buf());
+
+    volumes
+        .iter()
+        .filter(|v| resolved.starts_with(&v.mount_point))
+        .max_by_key(|v| v.mount_point.as_os_str().len())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn parse_original_size() {
+        let details = "Genres/Tags: Action\nOriginal Size: 41.7 GB\nRepack Size: from 20.1 GB";
+        assert_eq!(
+            original_size_bytes(details),
+            Some((41.7 * GB as f64) as u64)
+        );
+        assert_eq!(
+            original_size_bytes("Original Size: 900 MB"),
+            Some(900 * 1024 * 1024)
+        );
+        assert_eq!(original_size_bytes("Repack Size: 3 GB"), None);
+    }
+
+    #[test]
+    fn verdicts_on_shared_volume() {
+        let volumes = [VolumeInfo {
+            mount_point: PathBuf::from("/"),
+            available_bytes: 100 * GB,
+        }];
+        let dir = Path::new("/games/downloads");
+
+        let ok = plan(20 * GB, Some(40 * GB), dir, dir, &volumes);
+        assert_eq!(ok.verdict, PreflightVerdict::Ok);
+        assert_eq!(ok.volumes.len(), 1);
+        assert_eq!(ok.volumes[0].required_bytes, 60 * GB);
+
+        let warn = plan(30 * GB, Some(67 * GB), dir, dir, &volumes);
+        assert_eq!(warn.verdict, PreflightVerdict::Warn);
+
+        // no original size: install estimated as twice the download
+        let insufficient = plan(40 * GB, None, dir, dir, &volumes);
+        assert_eq!(insufficient.install_bytes, 80 * GB);
+        assert_eq!(insufficient.verdict, PreflightVerdict::Insufficient);
+    }
+}
@@ -0,0 +1,598 @@
+//! Download history and bandwidth counters that outlive the jobs.
+//!
+//! Every status the manager applies is folded into a [`JobRecord`] for its job
+//! and into the counters of the current day. Records are kept after the job is
+//! removed, which is what makes comparing DDL hosts possible. Everything lives
+//! in `download_stats.json` next to `manager.json`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tracing::{error, warn};
+use url::Url;
+
+use crate::{persistence::write_atomic, types::*};
+
+/// Longest time between two flushes while jobs are moving
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+/// Longer gaps between two statuses (sleep, app closed) are not transfer time
+const MAX_GAP: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum JobOutcome {
+    InProgress,
+    Completed,
+    Failed,
+    /// Removed before it completed
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct JobRecord {
+    pub job_id: JobId,
+    pub game_title: String,
+    pub source: DownloadSource,
+    /// Host of the direct links, `None` for torrents
+    pub host: Option<String>,
+    /// Engine that moved the bytes
+    pub engine: String,
+    pub total_bytes: u64,
+    /// Bytes transferred while we watched, data already on disk is not counted
+    pub downloaded_bytes: u64,
+    /// Estimated from the upload speed
+    pub uploaded_bytes: u64,
+    /// Time spent active, pauses excluded
+    pub active_secs: f64,
+    /// Bytes per second over `active_secs`
+    pub average_speed: u64,
+    pub peak_speed: u64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub outcome: JobOutcome,
+}
+
+impl JobRecord {
+    fn new(job: &Job, engine: &str) -> Self {
+        Self {
+            job_id: job.id.clone(),
+            game_title: job.metadata.game_title.clone(),
+            source: job.source.clone(),
+            host: job
+                .ddl
+                .as_ref()
+                .and_then(|ddl| ddl.files.first())
+                .and_then(|file| url_host(&file.url)),
+            engine: engine.to_string(),
+            total_bytes: 0,
+            downloaded_bytes: 0,
+            uploaded_bytes: 0,
+            active_secs: 0.0,
+            average_speed: 0,
+            peak_speed: 0,
+            started_at: Utc::now(),
+            finished_at: None,
+            outcome: JobOutcome::InProgress,
+        }
+    }
+
+    /// The DDL host, or the engine for torrents
+    pub fn provider(&self) -> &str {
+        self.host.as_deref().unwrap_or(&self.engine)
+    }
+
+    fn started_on(&self) -> NaiveDate {
+        self.started_at.with_timezone(&Local).date_naive()
+    }
+}
+
+/// Transfer counters of one local day
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct DayCounters {
+    pub date: NaiveDate,
+    pub downloaded_bytes: u64,
+    pub uploaded_bytes: u64,
+    pub jobs_completed: u32,
+    pub jobs_failed: u32,
+}
+
+impl DayCounters {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            downloaded_bytes: 0,
+            uploaded_bytes: 0,
+            jobs_completed: 0,
+            jobs_failed: 0,
+        }
+    }
+}
+
+/// Jobs of one DDL host or torrent engine
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ProviderStats {
+    pub provider: String,
+    pub source: DownloadSource,
+    pub jobs: u32,
+    pub downloaded_bytes: u64,
+    pub active_secs: f64,
+    pub average_speed: u64,
+    pub peak_speed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct DownloadStats {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub downloaded_bytes: u64,
+    pub uploaded_bytes: u64,
+    pub jobs_completed: u32,
+    pub jobs_failed: u32,
+    /// Days with any traffic, oldest first
+    pub days: Vec<DayCounters>,
+    /// Fastest first
+    pub providers: Vec<ProviderStats>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum StatsExport {
+    /// One row per job
+    Jobs,
+    /// One row per day
+    Days,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StatsFile {
+    #[serde(default)]
+    jobs: HashMap<JobId, JobRecord>,
+    #[serde(default)]
+    days: BTreeMap<NaiveDate, DayCounters>,
+}
+
+/// Last status seen of a job, not persisted
+struct LastSeen {
+    at: Instant,
+    /// Most bytes seen on disk, a re-verification climbs back up to it
+    completed: u64,
+    gids: Vec<Gid>,
+    upload_speed: u64,
+    active: bool,
+}
+
+struct StatsState {
+    file: StatsFile,
+    last_seen: HashMap<JobId, LastSeen>,
+    dirty: bool,
+    last_flush: Instant,
+}
+
+pub struct StatsStore {
+    path: PathBuf,
+    state: Mutex<StatsState>,
+    /// Keeps flushes from overtaking each other
+    write: Mutex<()>,
+}
+
+/// Host part of `url`, without credentials or port
+pub(crate) fn url_host(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()?
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .map(str::to_ascii_lowercase)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn in_range(date: NaiveDate, from: NaiveDate, to: NaiveDate) -> bool {
+    from <= date && date <= to
+}
+
+impl StatsStore {
+    /// Open the store at `path`, an unreadable file starts a fresh history.
+    pub fn open(path: PathBuf) -> Arc<Self> {
+        let file = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable download stats {}: {e}", path.display());
+                StatsFile::default()
+            }),
+            Err(_) => StatsFile::default(),
+        };
+
+        Arc::new(Self {
+            path,
+            state: Mutex::new(StatsState {
+                file,
+                last_seen: HashMap::new(),
+                dirty: false,
+                last_flush: Instant::now(),
+            }),
+            write: Mutex::new(()),
+        })
+    }
+
+    /// Fold the latest status of `job` in, `true` when it is time to flush
+    pub(crate) fn observe(&self, job: &Job, engine: &str) -> bool {
+        let Some(status) = &job.status else {
+            return false;
+        };
+        let now = Instant::now();
+        let today = Local::now().date_naive();
+
+        let mut state = self.state.lock().unwrap();
+        let StatsState {
+            file,
+            last_seen,
+            dirty,
+            last_flush,
+        } = &mut *state;
+
+        let new = !file.jobs.contains_key(&job.id);
+        let record = file
+            .jobs
+            .entry(job.id.clone())
+            .or_insert_with(|| JobRecord::new(job, engine));
+        let day = file
+            .days
+            .entry(today)
+            .or_insert_with(|| DayCounters::new(today));
+
+        record.engine = engine.to_string();
+        record.total_bytes = status.total_length;
+        record.peak_speed = record.peak_speed.max(status.download_speed);
+
+        // a job seen for the first time counts from zero, one resumed after a
+        // restart or respawned under new gids from the first status we get,
+        // what it already had on disk is not downloaded again
+        let prev = last_seen.get(&job.id);
+        let baseline = match prev {
+            Some(prev) if prev.gids == job.gids => Some(prev.completed),
+            Some(_) => None,
+            None => new.then_some(0),
+        };
+        if let Some(baseline) = baseline {
+            // bytes re-verified after a drop to zero are only counted past the old mark
+            let downloaded = status.completed_length.saturating_sub(baseline);
+            record.downloaded_bytes += downloaded;
+            day.downloaded_bytes += downloaded;
+        }
+        let high_water = prev
+            .map_or(0, |prev| prev.completed)
+            .max(status.completed_length);
+
+        if let Some(prev) = last_seen.get(&job.id) {
+            let gap = now.duration_since(prev.at);
+            if prev.active && gap <= MAX_GAP {
+                let uploaded = (prev.upload_speed as f64 * gap.as_secs_f64()) as u64;
+                record.uploaded_bytes += uploaded;
+                day.uploaded_bytes += uploaded;
+                record.active_secs += gap.as_secs_f64();
+            }
+        }
+        if record.active_secs > 0.0 {
+            record.average_speed = (record.downloaded_bytes as f64 / record.active_secs) as u64;
+        }
+
+        let outcome = match job.state {
+            DownloadState::Complete => JobOutcome::Completed,
+            DownloadState::Error => JobOutcome::Failed,
+            _ => JobOutcome::InProgress,
+        };
+        // a completed job that gets more files keeps its completion, counted once
+        let settled = outcome != record.outcome && record.outcome != JobOutcome::Completed;
+        if settled {
+            match outcome {
+                JobOutcome::Completed => day.jobs_completed += 1,
+                JobOutcome::Failed => day.jobs_failed += 1,
+                _ => {}
+            }
+            record.finished_at = (outcome != JobOutcome::InProgress).then(Utc::now);
+            record.outcome = outcome;
+        }
+
+        last_seen.insert(
+            job.id.clone(),
+            LastSeen {
+                at: now,
+                completed: high_water,
+                gids: job.gids.clone(),
+                upload_speed: status.upload_speed,
+                active: job.state == DownloadState::Active,
+            },
+        );
+        *dirty = true;
+        settled || last_flush.elapsed() >= FLUSH_INTERVAL
+    }
+
+    /// Close the record of a removed job, `true` when it changed
+    pub(crate) fn removed(&self, job_id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.last_seen.remove(job_id);
+
+        let Some(record) = state.file.jobs.get_mut(job_id) else {
+            return false;
+        };
+        if record.outcome != JobOutcome::InProgress {
+            return false;
+        }
+        record.outcome = JobOutcome::Removed;
+        record.finished_at = Some(Utc::now());
+        state.dirty = true;
+        true
+    }
+
+    pub async fn flush(self: &Arc<Self>) {
+        let this = Arc::clone(self);
+        match tokio::task::spawn_blocking(move || this.flush_blocking()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("Failed to save download stats: {e:#}"),
+            Err(e) => error!("Download stats flush panicked: {e}"),
+        }
+    }
+
+    fn flush_blocking(&self) -> Result<()> {
+        let _write = self.write.lock().unwrap();
+        let bytes = {
+            let mut state = self.state.lock().unwrap();
+            if !state.dirty {
+                return Ok(());
+            }
+            state.dirty = false;
+            state.last_flush = Instant::now();
+            serde_json::to_vec_pretty(&state.file)?
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(&self.path, &bytes)?;
+        Ok(())
+    }
+
+    /// Jobs started between `from` and `to`, both included, oldest first
+    pub fn history(&self, from: NaiveDate, to: NaiveDate) -> Vec<JobRecord> {
+        let state = self.state.lock().unwrap();
+        let mut records: Vec<JobRecord> = state
+            .file
+            .jobs
+            .values()
+            .filter(|r| in_range(r.started_on(), from, to))
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.started_at);
+        records
+    }
+
+    pub fn days(&self, from: NaiveDate, to: NaiveDate) -> Vec<DayCounters> {
+        let state = self.state.lock().unwrap();
+        state
+            .file
+            .days
+            .range(from..=to)
+            .map(|(_, day)| day.clone())
+            .collect()
+    }
+
+    /// Totals between `from` and `to`, both included
+    pub fn summary(&self, from: NaiveDate, to: NaiveDate) -> DownloadStats {
+        let days = self.days(from, to);
+
+        let mut providers: HashMap<String, ProviderStats> = HashMap::new();
+        for record in self.history(from, to) {
+            let p = providers
+                .entry(record.provider().to_string())
+                .or_insert_with(|| ProviderStats {
+                    provider: record.provider().to_string(),
+                    source: record.source.clone(),
+                    jobs: 0,
+                    downloaded_bytes: 0,
+                    active_secs: 0.0,
+                    average_speed: 0,
+                    peak_speed: 0,
+                });
+            p.jobs += 1;
+            p.downloaded_bytes += record.downloaded_bytes;
+            p.active_secs += record.active_secs;
+            p.peak_speed = p.peak_speed.max(record.peak_speed);
+        }
+        let mut providers: Vec<ProviderStats> = providers
+            .into_values()
+            .map(|mut p| {
+                if p.active_secs > 0.0 {
+                    p.average_speed = (p.downloaded_bytes as f64 / p.active_secs) as u64;
+                }
+                p
+            })
+            .collect();
+        providers.sort_by_key(|p| std::cmp::Reverse(p.average_speed));
+
+        DownloadStats {
+            from,
+            to,
+            downloaded_bytes: days.iter().map(|d| d.downloaded_bytes).sum(),
+            uploaded_bytes: days.iter().map(|d| d.uploaded_bytes).sum(),
+            jobs_completed: days.iter().map(|d| d.jobs_completed).sum(),
+            jobs_failed: days.iter().map(|d| d.jobs_failed).sum(),
+            days,
+            providers,
+        }
+    }
+
+    pub fn to_csv(&self, kind: StatsExport, from: NaiveDate, to: NaiveDate) -> String {
+        let mut csv = String::new();
+        match kind {
+            StatsExport::Jobs => {
+                csv.push_str(
+                    "job_id,game_title,source,provider,engine,total_bytes,downloaded_bytes,\
+                     uploaded_bytes,active_secs,average_speed,peak_speed,started_at,finished_at,outcome\n",
+                );
+                for r in self.history(from, to) {
+                    let _ = writeln!(
+                        csv,
+                        "{},{},{:?},{},{},{},{},{},{:.0},{},{},{},{},{:?}",
+                        r.job_id,
+                        csv_field(&r.game_title),
+                        r.source,
+                        csv_field(r.provider()),
+                        r.engine,
+                        r.total_bytes,
+                        r.downloaded_bytes,
+                        r.uploaded_bytes,
+                        r.active_secs,
+                        r.average_speed,
+                        r.peak_speed,
+                        r.started_at.to_rfc3339(),
+                        r.finished_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+                        r.outcome,
+                    );
+                }
+            }
+            StatsExport::Days => {
+                csv.push_str("date,downloaded_bytes,uploaded_bytes,jobs_completed,jobs_failed\n");
+                for d in self.days(from, to) {
+                    let _ = writeln!(
+                        csv,
+                        "{},{},{},{},{}",
+                        d.date,
+                        d.downloaded_bytes,
+                        d.uploaded_bytes,
+                        d.jobs_completed,
+                        d.jobs_failed
+                    );
+                }
+            }
+        }
+        csv
+    }
+
+    pub async fn export_csv(
+        &self,
+        kind: StatsExport,
+        from: NaiveDate,
+        to: NaiveDate,
+        path: &Path,
+    ) -> Result<()> {
+        tokio::fs::write(path, self.to_csv(kind, from, to)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fit_launcher_scraping::structs::Game;
+
+    use super::*;
+
+    fn store(name: &str) -> Arc<StatsStore> {
+        let path = std::env::temp_dir().join(format!("{name}-{}.json", std::process::id()));
+        _ = fs::remove_file(&path);
+        StatsStore::open(path)
+    }
+
+    fn job() -> Job {
+        let mut job = Job::new_ddl(vec![], PathBuf::new(), Game::default(), PathBuf::new());
+        job.gids = vec!["a".into()];
+        job
+    }
+
+    fn observe(store: &StatsStore, job: &mut Job, state: DownloadState, completed: u64) {
+        job.state = state.clone();
+        job.status = Some(AggregatedStatus {
+            total_length: 1000,
+            completed_length: completed,
+            state,
+            ..Default::default()
+        });
+        store.observe(job, "aria2");
+    }
+
+    fn downloaded(store: &StatsStore, job: &Job) -> u64 {
+        store.state.lock().unwrap().file.jobs[&job.id].downloaded_bytes
+    }
+
+    #[test]
+    fn reverified_bytes_are_not_downloaded() {
+        let store = store("fit-stats-reverify");
+        let mut job = job();
+
+        observe(&store, &mut job, DownloadState::Active, 0);
+        observe(&store, &mut job, DownloadState::Active, 400);
+        assert_eq!(downloaded(&store, &job), 400);
+
+        // check-integrity starts over from zero and climbs back
+        observe(&store, &mut job, DownloadState::Active, 0);
+        observe(&store, &mut job, DownloadState::Active, 300);
+        observe(&store, &mut job, DownloadState::Active, 600);
+        assert_eq!(downloaded(&store, &job), 600);
+
+        // respawned under a new gid, with what was on disk already
+        job.gids = vec!["b".into()];
+        observe(&store, &mut job, DownloadState::Active, 650);
+        observe(&store, &mut job, DownloadState::Active, 700);
+        assert_eq!(downloaded(&store, &job), 650);
+
+        let state = store.state.lock().unwrap();
+        let day = state.file.days.values().next().unwrap();
+        assert_eq!(day.downloaded_bytes, 650);
+    }
+
+    #[test]
+    fn completion_is_counted_once_per_job() {
+        let store = store("fit-stats-complete");
+        let mut job = job();
+
+        observe(&store, &mut job, DownloadState::Active, 0);
+        observe(&store, &mut job, DownloadState::Complete, 1000);
+        // more files added to the finished torrent, then done again
+        observe(&store, &mut job, DownloadState::Active, 1000);
+        observe(&store, &mut job, DownloadState::Complete, 1000);
+
+        let state = store.state.lock().unwrap();
+        let day = state.file.days.values().next().unwrap();
+        assert_eq!(day.jobs_completed, 1);
+        assert_eq!(state.file.jobs[&job.id].outcome, JobOutcome::Completed);
+    }
+
+    #[test]
+    fn hosts_drop_credentials_and_ports() {
+        assert_eq!(
+            url_host("https://user:pw@Files.Example.com:8443/a/b.rar?x=1").as_deref(),
+            Some("files.example.com")
+        );
+        assert_eq!(
+            url_host("https://fuckingfast.co/abc#game.part1.rar").as_deref(),
+            Some("fuckingfast.co")
+        );
+        assert_eq!(
+            url_host("http://[::1]:6800/jsonrpc").as_deref(),
+            Some("[::1]")
+        );
+        assert_eq!(url_host("not a url"), None);
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("Elden Ring"), "Elden Ring");
+        assert_eq!(
+            csv_field("Ring, the \"Elden\""),
+            "\"Ring, the \"\"Elden\"\"\""
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Keeps the aria2c daemon alive.
+//!
+//! A daemon we spawned reports its own death through
+//! [`subscribe_daemon_exits`], one started by someone else is noticed by
+//! failing health checks. Either way aria2 is brought back with exponential
+//! backoff, restores its session file, and the jobs are reattached to the GIDs
+//! it hands out for the restored downloads. A daemon that keeps dying is given
+//! up on until the user asks for another try.
+
+use crate::{aria2::Aria2Connector, engine::DownloadEngine, manager::DownloadManager, types::*};
+use aria2_ws::Client;
+use fit_launcher_torrent::functions::{DaemonExit, subscribe_daemon_exits};
+use serde::Serialize;
+use specta::Type;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{Mutex, Notify, RwLock, broadcast},
+    time::{MissedTickBehavior, interval, sleep, timeout},
+};
+use tracing::{error, info, warn};
+
+pub const DAEMON_HEALTH_EVENT: &str = "aria2::daemon_health";
+
+const HEALTH_INTERVAL: Duration = Duration::from_secs(15);
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Failed health checks in a row before aria2 counts as gone
+const MAX_MISSED_CHECKS: u32 = 3;
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+/// A daemon dying sooner than this after a restart keeps the failure streak going
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+/// Restarts in a row before giving up
+const MAX_ATTEMPTS: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum DaemonHealth {
+    Running,
+    Restarting {
+        attempt: u32,
+        retry_in_secs: u64,
+        reason: String,
+    },
+    /// Crash loop, nothing is retried until [`Aria2Supervisor::request_restart`]
+    Failed {
+        reason: String,
+    },
+}
+
+pub struct Aria2Supervisor {
+    manager: Arc<DownloadManager>,
+    /// The aria2 engine, its GIDs are the ones reattached
+    engine: Arc<dyn DownloadEngine>,
+    /// Shared with the engine and the dispatcher, swapped on restart
+    client: Arc<Mutex<Client>>,
+    connector: Arc<dyn Aria2Connector>,
+    health: RwLock<DaemonHealth>,
+    restart: Notify,
+}
+
+fn backoff(attempt: u32) -> Duration {
+    // the first restart is immediate, retries double from BACKOFF_BASE
+    match attempt {
+        0 | 1 => Duration::ZERO,
+        n => BACKOFF_BASE
+            .saturating_mul(1 << (n - 2).min(16))
+            .min(BACKOFF_MAX),
+    }
+}
+
+fn exit_reason(exit: &DaemonExit) -> String {
+    match exit.code {
+        Some(code) => format!("aria2c exited with code {code}"),
+        None => "aria2c was terminated".to_string(),
+    }
+}
+
+impl Aria2Supervisor {
+    pub fn new(
+        manager: Arc<DownloadManager>,
+        engine: Arc<dyn DownloadEngine>,
+        client: Arc<Mutex<Client>>,
+        connector: Arc<dyn Aria2Connector>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            manager,
+            engine,
+            client,
+            connector,
+            health: RwLock::new(DaemonHealth::Running),
+            restart: Notify::new(),
+        })
+    }
+
+    pub async fn health(&self) -> DaemonHealth {
+        self.health.read().await.clone()
+    }
+
+    /// Reconnect to aria2 now, spawning it again if it is gone.
+    ///
+    /// This is also the way out of [`DaemonHealth::Failed`].
+    pub fn request_restart(&self) {
+        self.restart.notify_one();
+    }
+
+    async fn set_health(&self, health: DaemonHealth) {
+        *self.health.write().await = health.clone();
+        self.manager.emit(DAEMON_HEALTH_EVENT, health);
+    }
+
+    async fn is_alive(&self) -> bool {
+        let client = self.client.lock().await;
+        matches!(
+            timeout(HEALTH_TIMEOUT, client.get_version()).await,
+            Ok(Ok(_))
+        )
+    }
+
+    /// Jobs aria2 was downloading, resumed once it is back
+    async fn active_jobs(&self) -> Vec<JobId> {
+        self.manager
+            .all_jobs()
+            .await
+            .into_iter()
+            .filter(|job| {
+                job.state == DownloadState::Active
+                    && job.gids.iter().any(|gid| self.engine.owns(gid))
+            })
+            .map(|job| job.id)
+            .collect()
+    }
+
+    /// Bring aria2 back, `false` once `MAX_ATTEMPTS` is exhausted.
+    ///
+    /// `streak` counts restarts since the daemon last stayed up for `STABLE_AFTER`.
+    async fn recover(
+        &self,
+        mut reason: String,
+        streak: &mut u32,
+        exits: &mut broadcast::Receiver<DaemonExit>,
+    ) -> bool {
+        let resume = self.active_jobs().await;
+
+        let client = loop {
+            *streak += 1;
+            if *streak > MAX_ATTEMPTS {
+                error!("Supervisor: giving up on aria2 after {MAX_ATTEMPTS} restarts: {reason}");
+                self.set_health(DaemonHealth::Failed { reason }).await;
+                return false;
+            }
+
+            let delay = backoff(*streak);
+            warn!(
+                "Supervisor: restarting aria2 (attempt {}) in {:?}: {reason}",
+                streak, delay
+            );
+            self.set_health(DaemonHealth::Restarting {
+                attempt: *streak,
+                retry_in_secs: delay.as_secs(),
+                reason: reason.clone(),
+            })
+            .await;
+            sleep(delay).await;
+
+            match self.connector.connect().await {
+                Ok(client) => break client,
+                Err(e) => reason = format!("{e:#}"),
+            }
+        };
+
+        *self.client.lock().await = client;
+        // daemons that died while we were retrying are already dealt with
+        while let Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) = exits.try_recv() {}
+
+        match self.manager.reattach(&self.engine).await {
+            Ok(unattached) if !unattached.is_empty() => {
+                info!(
+                    "Supervisor: {} jobs lost their aria2 downloads and will be respawned",
+                    unattached.len()
+                );
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Supervisor: reattaching jobs failed: {e:#}"),
+        }
+
+        for job_id in resume {
+            if let Err(e) = self.manager.resume(&job_id).await {
+                warn!("Supervisor: could not resume job {job_id}: {e:#}");
+            }
+        }
+
+        info!("Supervisor: aria2 is back");
+        self.set_health(DaemonHealth::Running).await;
+        true
+    }
+
+    async fn run(self: Arc<Self>) {
+        let mut exits = subscribe_daemon_exits();
+        let mut checks = interval(HEALTH_INTERVAL);
+        checks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        checks.reset();
+
+        let mut missed = 0;
+        let mut streak = 0;
+        let mut restarted_at: Option<Instant> = None;
+
+        loop {
+            let reason = tokio::select! {
+                exit = exits.recv() => match exit {
+                    Ok(exit) => exit_reason(&exit),
+                    Err(broadcast::error::RecvError::Lagged(_)) => "aria2c exited".to_string(),
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = checks.tick() => {
+                    if self.is_alive().await {
+                        missed = 0;
+                        continue;
+                    }
+                    missed += 1;
+                    if missed < MAX_MISSED_CHECKS {
+                        continue;
+                    }
+                    "aria2 stopped responding".to_string()
+                }
+                _ = self.restart.notified() => {
+                    streak = 0;
+                    "restart requested".to_string()
+                }
+            };
+
+            missed = 0;
+            if restarted_at.is_none_or(|at| at.elapsed() >= STABLE_AFTER) {
+                streak = 0;
+            }
+
+            let mut recovered = self.recover(reason, &mut streak, &mut exits).await;
+            while !recovered {
+                // crash loop, wait for the user before trying again
+                self.restart.notified().await;
+                streak = 0;
+                recovered = self
+                    .recover("restart requested".to_string(), &mut streak, &mut exits)
+                    .await;
+            }
+            restarted_at = Some(Instant::now());
+            checks.reset();
+        }
+    }
+}
+
+/// Watch the aria2 daemon behind `client` and restart it when it goes away.
+pub fn spawn_supervisor(supervisor: Arc<Aria2Supervisor>) {
+    tokio::spawn(supervisor.run());
+}
@@ -1,4 +1,4 @@
-use aria2_ws::response::{File, TaskStatus};
+use aria2_ws::response::{File, Status, TaskStatus};
 use chrono::{DateTime, Utc};
 use fit_launcher_ddl::DirectLink;
 use fit_launcher_scraping::structs::Game;
@@ -77,6 +77,21 @@ pub struct FileStatus {
     pub info_hash: Option<String>,
 }
 
+impl From<&Status> for FileStatus {
+    fn from(status: &Status) -> Self {
+        Self {
+            gid: Some(status.gid.clone()),
+            status: status.status.clone().into(),
+            total_length: status.total_length,
+            completed_length: status.completed_length,
+            download_speed: status.download_speed,
+            upload_speed: status.upload_speed,
+            files: status.files.clone(),
+            info_hash: status.info_hash.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default, Type)]
 pub struct AggregatedStatus {
     pub total_length: u64,
@@ -88,6 +103,24 @@ pub struct AggregatedStatus {
     pub progress_percentage: f64,
 }
 
+impl AggregatedStatus {
+    /// Keep the progress but report nothing as transferring, e.g. after a restart
+    pub fn pause(&mut self) {
+        for file in self.per_file.values_mut() {
+            if matches!(file.status, DownloadState::Active | DownloadState::Waiting) {
+                file.status = DownloadState::Paused;
+            }
+            file.download_speed = 0;
+            file.upload_speed = 0;
+        }
+        self.download_speed = 0;
+        self.upload_speed = 0;
+        if matches!(self.state, DownloadState::Active | DownloadState::Waiting) {
+            self.state = DownloadState::Paused;
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct TorrentJob {
     pub torrent_bytes: Vec<u8>,
@@ -111,6 +144,58 @@ pub struct DdlJob {
     pub files: Vec<DirectLink>,
 }
 
+/// Post-download stages, run in this order once a job completes
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Type)]
+pub enum PipelineStage {
+    #[default]
+    Verify,
+    Extract,
+    Install,
+    Clean,
+    Register,
+    Done,
+}
+
+impl PipelineStage {
+    pub fn next(self) -> Self {
+        match self {
+            PipelineStage::Verify => PipelineStage::Extract,
+            PipelineStage::Extract => PipelineStage::Install,
+            PipelineStage::Install => PipelineStage::Clean,
+            PipelineStage::Clean => PipelineStage::Register,
+            PipelineStage::Register | PipelineStage::Done => PipelineStage::Done,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Type)]
+pub enum StageStatus {
+    Running,
+    Succeeded,
+    Skipped,
+    /// Failed, `next_retry_at` tells when it runs again
+    Retrying,
+    /// Out of attempts, needs `dm_retry_pipeline`
+    Failed,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct PipelineState {
+    /// Stage to run next, `Done` once everything finished
+    pub stage: PipelineStage,
+    /// Failed attempts of the current stage
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub next_retry_at: Option<DateTime<Utc>>,
+    /// Set when the current stage ran out of attempts
+    pub failed: bool,
+    /// Install stage was skipped, nothing left to clean or register
+    pub install_skipped: bool,
+    pub install_path: Option<String>,
+    pub executable: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct Job {
     pub id: JobId,
@@ -128,6 +213,12 @@ pub struct Job {
 
     pub state: DownloadState,
     pub status: Option<AggregatedStatus>,
+    /// Post-download pipeline, `None` until the download completes
+    #[serde(default)]
+    pub pipeline: Option<PipelineState>,
+    /// Set while the job makes no progress and has no peers, see `details::spawn_details_monitor`
+    #[serde(default)]
+    pub stalled_since: Option<DateTime<Utc>>,
 }
 
 impl Job {
@@ -157,6 +248,8 @@ impl Job {
 
             state: DownloadState::Waiting,
             status: None,
+            pipeline: None,
+            stalled_since: None,
         }
     }
 
@@ -197,6 +290,8 @@ impl Job {
 
             state: DownloadState::Waiting,
             status: None,
+            pipeline: None,
+            stalled_since: None,
         }
     }
 }
@@ -0,0 +1,404 @@
+//! In-process stand-in for the aria2c websocket JSON-RPC interface.
+//!
+//! Implements the subset `DownloadManager` talks to and lets tests script
+//! progress, completion, failures and dropped connections.
+
+#![allow(dead_code)]
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::JoinHandle,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+#[derive(Debug, Clone)]
+pub struct Download {
+    pub gid: String,
+    /// aria2 status string: active, waiting, paused, error, complete or removed
+    pub status: &'static str,
+    pub total_length: u64,
+    pub completed_length: u64,
+    pub download_speed: u64,
+    pub dir: String,
+    pub uris: Vec<String>,
+    pub info_hash: Option<String>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    downloads: BTreeMap<String, Download>,
+    /// method -> error message returned by its next call
+    failures: HashMap<String, String>,
+    calls: Vec<String>,
+    peers: Vec<mpsc::UnboundedSender<Message>>,
+    connections: Vec<JoinHandle<()>>,
+}
+
+impl State {
+    fn notify(&mut self, event: &str, gid: &str) {
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": format!("aria2.{event}"),
+            "params": [{ "gid": gid }],
+        });
+        self.peers
+            .retain(|peer| peer.send(Message::text(message.to_string())).is_ok());
+    }
+
+    fn get_mut(&mut self, gid: &str) -> Result<&mut Download, String> {
+        self.downloads
+            .get_mut(gid)
+            .ok_or_else(|| format!("GID {gid} is not found"))
+    }
+
+    fn add(&mut self, uris: Vec<String>, dir: String, info_hash: Option<String>) -> String {
+        // random like aria2, so a restarted mock never reuses a GID
+        let gid = format!("{:016x}", uuid::Uuid::new_v4().as_u64_pair().0);
+        self.downloads.insert(
+            gid.clone(),
+            Download {
+                gid: gid.clone(),
+                status: "active",
+                total_length: 1_000_000,
+                completed_length: 0,
+                download_speed: 0,
+                dir,
+                uris,
+                info_hash,
+                error_message: None,
+            },
+        );
+        self.notify("onDownloadStart", &gid);
+        gid
+    }
+
+    fn handle(&mut self, method: &str, params: &[Value]) -> Result<Value, String> {
+        self.calls.push(method.to_string());
+        if let Some(message) = self.failures.remove(method) {
+            return Err(message);
+        }
+
+        let gid_param = || {
+            params
+                .first()
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| "missing gid".to_string())
+        };
+        // the options object, wherever the optional arguments before it left it
+        let dir_param = || {
+            params
+                .iter()
+                .find_map(|param| param.get("dir"))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        match method {
+            "aria2.getVersion" => Ok(json!({ "version": "1.37.0", "enabledFeatures": [] })),
+            "aria2.addUri" => {
+                let uris = params
+                    .first()
+                    .and_then(Value::as_array)
+                    .map(|uris| {
+                        uris.iter()
+                            .filter_map(Value::as_str)
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(json!(self.add(uris, dir_param(), None)))
+            }
+            "aria2.addTorrent" => {
+                let torrent = params.first().and_then(Value::as_str).unwrap_or_default();
+                let info_hash = format!("{:040x}", torrent.len());
+                Ok(json!(self.add(Vec::new(), dir_param(), Some(info_hash))))
+            }
+            "aria2.pause" | "aria2.forcePause" => {
+                let gid = gid_param()?;
+                let download = self.get_mut(&gid)?;
+                if !matches!(download.status, "active" | "waiting") {
+                    return Err(format!("GID {gid} cannot be paused now"));
+                }
+                download.status = "paused";
+                download.download_speed = 0;
+                self.notify("onDownloadPause", &gid);
+                Ok(json!(gid))
+            }
+            "aria2.unpause" => {
+                let gid = gid_param()?;
+                let download = self.get_mut(&gid)?;
+                if download.status != "paused" {
+                    return Err(format!("GID {gid} cannot be unpaused now"));
+                }
+                download.status = "active";
+                self.notify("onDownloadStart", &gid);
+                Ok(json!(gid))
+            }
+            "aria2.remove" | "aria2.forceRemove" => {
+                let gid = gid_param()?;
+                let download = self.get_mut(&gid)?;
+                if matches!(download.status, "complete" | "error" | "removed") {
+                    return Err(format!("Active Download not found for GID#{gid}"));
+                }
+                download.status = "removed";
+                download.download_speed = 0;
+                self.notify("onDownloadStop", &gid);
+                Ok(json!(gid))
+            }
+            "aria2.tellStatus" => {
+                let gid = gid_param()?;
+                Ok(status_json(self.get_mut(&gid)?))
+            }
+            "aria2.getPeers" => {
+                let gid = gid_param()?;
+                self.get_mut(&gid)?;
+                Ok(json!([]))
+            }
+            "aria2.tellActive" => Ok(self.list(&["active"])),
+            "aria2.tellWaiting" => Ok(self.list(&["waiting", "paused"])),
+            "aria2.tellStopped" => Ok(self.list(&["complete", "error", "removed"])),
+            other => Err(format!("No such method: {other}")),
+        }
+    }
+
+    fn list(&self, statuses: &[&str]) -> Value {
+        Value::Array(
+            self.downloads
+                .values()
+                .filter(|d| statuses.contains(&d.status))
+                .map(status_json)
+                .collect(),
+        )
+    }
+}
+
+/// Shape of an aria2 `tellStatus` result, numbers are sent as strings
+fn status_json(d: &Download) -> Value {
+    let uri = d.uris.first().cloned().unwrap_or_default();
+    let filename = uri.rsplit('/').next().unwrap_or("file");
+    let mut status = json!({
+        "gid": d.gid,
+        "status": d.status,
+        "totalLength": d.total_length.to_string(),
+        "completedLength": d.completed_length.to_string(),
+        "uploadLength": "0",
+        "downloadSpeed": d.download_speed.to_string(),
+        "uploadSpeed": "0",
+        "connections": "1",
+        "numPieces": "1",
+        "pieceLength": "1048576",
+        "dir": d.dir,
+        "files": [{
+            "index": "1",
+            "path": format!("{}/{filename}", d.dir),
+            "length": d.total_length.to_string(),
+            "completedLength": d.completed_length.to_string(),
+            "selected": "true",
+            "uris": d.uris.iter().map(|uri| json!({ "uri": uri, "status": "used" })).collect::<Vec<_>>(),
+        }],
+    });
+    if let Some(info_hash) = &d.info_hash {
+        status["infoHash"] = json!(info_hash);
+    }
+    if let Some(message) = &d.error_message {
+        status["errorCode"] = json!("1");
+        status["errorMessage"] = json!(message);
+    }
+    status
+}
+
+fn reply(state: &Mutex<State>, text: &str) -> Option<Value> {
+    let request: Value = serde_json::from_str(text).ok()?;
+    let id = request.get("id").cloned()?;
+    let method = request.get("method")?.as_str()?;
+    let mut params = request
+        .get("params")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    if params
+        .first()
+        .and_then(Value::as_str)
+        .is_some_and(|p| p.starts_with("token:"))
+    {
+        params.remove(0);
+    }
+
+    let outcome = state.lock().unwrap().handle(method, &params);
+    Some(match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(message) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": 1, "message": message },
+        }),
+    })
+}
+
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let Ok(ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut sink, mut source) = ws.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    state.lock().unwrap().peers.push(tx.clone());
+
+    let writer = async move {
+        while let Some(message) = rx.recv().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    };
+    let reader = async move {
+        while let Some(Ok(message)) = source.next().await {
+            if let Message::Text(text) = message
+                && let Some(response) = reply(&state, text.as_str())
+            {
+                let _ = tx.send(Message::text(response.to_string()));
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = writer => {}
+        _ = reader => {}
+    }
+}
+
+pub struct MockAria2 {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    accept: JoinHandle<()>,
+}
+
+impl MockAria2 {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let accept = tokio::spawn({
+            let state = state.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let connection = tokio::spawn(serve(stream, state.clone()));
+                    state.lock().unwrap().connections.push(connection);
+                }
+            }
+        });
+
+        Self {
+            addr,
+            state,
+            accept,
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}/jsonrpc", self.addr)
+    }
+
+    pub async fn client(&self) -> aria2_ws::Client {
+        aria2_ws::Client::connect(&self.url(), None).await.unwrap()
+    }
+
+    /// Make the next call to `method` (e.g. `aria2.addUri`) fail with `message`
+    pub fn fail_next(&self, method: &str, message: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .failures
+            .insert(method.to_string(), message.to_string());
+    }
+
+    /// How many times `method` was called
+    pub fn calls(&self, method: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        state.calls.iter().filter(|call| *call == method).count()
+    }
+
+    pub fn download(&self, gid: &str) -> Option<Download> {
+        self.state.lock().unwrap().downloads.get(gid).cloned()
+    }
+
+    pub fn downloads(&self) -> Vec<Download> {
+        self.state
+            .lock()
+            .unwrap()
+            .downloads
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Report progress, aria2 sends no notification for this
+    pub fn progress(&self, gid: &str, completed_length: u64, download_speed: u64) {
+        let mut state = self.state.lock().unwrap();
+        let download = state.get_mut(gid).unwrap();
+        download.completed_length = completed_length;
+        download.download_speed = download_speed;
+    }
+
+    pub fn complete(&self, gid: &str) {
+        let mut state = self.state.lock().unwrap();
+        let download = state.get_mut(gid).unwrap();
+        download.status = "complete";
+        download.completed_length = download.total_length;
+        download.download_speed = 0;
+        state.notify("onDownloadComplete", gid);
+    }
+
+    pub fn fail(&self, gid: &str, message: &str) {
+        let mut state = self.state.lock().unwrap();
+        let download = state.get_mut(gid).unwrap();
+        download.status = "error";
+        download.download_speed = 0;
+        download.error_message = Some(message.to_string());
+        state.notify("onDownloadError", gid);
+    }
+
+    /// Change the status without a notification, like one lost on the wire
+    pub fn set_status_silently(&self, gid: &str, status: &'static str) {
+        let mut state = self.state.lock().unwrap();
+        let download = state.get_mut(gid).unwrap();
+        download.status = status;
+        download.download_speed = 0;
+        if status == "complete" {
+            download.completed_length = download.total_length;
+        }
+    }
+
+    /// Drop a download entirely, like aria2 purging its result list
+    pub fn forget(&self, gid: &str) {
+        self.state.lock().unwrap().downloads.remove(gid);
+    }
+
+    /// Stop listening and drop every open connection, like a crashed daemon
+    pub fn shutdown(&self) {
+        self.accept.abort();
+        let mut state = self.state.lock().unwrap();
+        state.peers.clear();
+        for connection in state.connections.drain(..) {
+            connection.abort();
+        }
+    }
+}
+
+impl Drop for MockAria2 {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -0,0 +1,727 @@
+mod common;
+
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use aria2_ws::Client;
+use async_trait::async_trait;
+use common::MockAria2;
+use fit_launcher_config::client::connectivity;
+use fit_launcher_ddl::DirectLink;
+use fit_launcher_download_manager::{
+    aria2::{Aria2Connector, Aria2WsClient},
+    details::count_pieces,
+    dispatch::spawn_dispatcher,
+    engine::{Aria2Engine, DownloadEngine},
+    manager::DownloadManager,
+    outbox::{JobSubmission, OutboxRequest, spawn_outbox_replay},
+    stats::{JobOutcome, StatsExport},
+    submit_or_queue,
+    supervisor::{Aria2Supervisor, DaemonHealth, spawn_supervisor},
+    types::{DownloadState, FileStatus, Job},
+};
+use fit_launcher_scraping::structs::Game;
+use fit_launcher_torrent::{FitLauncherConfigAria2, LibrqbitSession};
+use tokio::sync::Mutex;
+
+/// Reconnects to whichever mock `url` points at
+struct MockConnector {
+    url: std::sync::Mutex<String>,
+    connects: AtomicUsize,
+}
+
+impl MockConnector {
+    fn new(url: String) -> Arc<Self> {
+        Arc::new(Self {
+            url: std::sync::Mutex::new(url),
+            connects: AtomicUsize::new(0),
+        })
+    }
+}
+
+#[async_trait]
+impl Aria2Connector for MockConnector {
+    async fn connect(&self) -> anyhow::Result<Client> {
+        self.connects.fetch_add(1, Ordering::SeqCst);
+        let url = self.url.lock().unwrap().clone();
+        Ok(Client::connect(&url, None).await?)
+    }
+}
+
+struct Harness {
+    manager: Arc<DownloadManager>,
+    engine: Arc<dyn DownloadEngine>,
+    client: Arc<Mutex<Client>>,
+    connector: Arc<MockConnector>,
+    store: PathBuf,
+}
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fit-dm-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+async fn harness(mock: &MockAria2, store: PathBuf) -> Harness {
+    let client = Arc::new(Mutex::new(mock.client().await));
+    let connector = MockConnector::new(mock.url());
+    let engine: Arc<dyn DownloadEngine> = Arc::new(Aria2Engine::new(
+        Aria2WsClient::new(client.clone(), connector.clone()),
+        FitLauncherConfigAria2::default(),
+    ));
+    let manager = DownloadManager::headless(
+        Some(engine.clone()),
+        engine.clone(),
+        store.join("manager.json"),
+        LibrqbitSession::detached(),
+    );
+
+    Harness {
+        manager,
+        engine,
+        client,
+        connector,
+        store,
+    }
+}
+
+fn links(count: usize) -> Vec<DirectLink> {
+    (0..count)
+        .map(|i| DirectLink {
+            url: format!("https://example.invalid/game.part{i}.rar"),
+            filename: format!("game.part{i}.rar"),
+            size: 1_000_000,
+        })
+        .collect()
+}
+
+async fn job(manager: &DownloadManager, job_id: &str) -> Job {
+    manager.get_job(job_id).await.expect("job is tracked")
+}
+
+/// Poll the job until `check` holds, failing the test after a few seconds
+async fn wait_for(
+    manager: &DownloadManager,
+    job_id: &str,
+    what: &str,
+    check: impl Fn(&Job) -> bool,
+) {
+    for _ in 0..100 {
+        if check(&job(manager, job_id).await) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("timed out waiting for {what}");
+}
+
+#[tokio::test]
+async fn ddl_job_add_pause_resume_remove() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+
+    let target = h.store.join("downloads");
+    let job_id = h
+        .manager
+        .add_ddl_job(links(2), target.clone(), Game::default())
+        .await
+        .unwrap();
+
+    let added = job(&h.manager, &job_id).await;
+    assert_eq!(added.state, DownloadState::Active);
+    assert_eq!(added.gids.len(), 2);
+    for gid in &added.gids {
+        let download = mock.download(gid).expect("aria2 knows the gid");
+        assert_eq!(download.status, "active");
+        assert_eq!(PathBuf::from(download.dir), target.join(&job_id));
+    }
+
+    h.manager.pause(&job_id).await.unwrap();
+    assert_eq!(job(&h.manager, &job_id).await.state, DownloadState::Paused);
+    assert!(
+        added
+            .gids
+            .iter()
+            .all(|gid| mock.download(gid).unwrap().status == "paused")
+    );
+
+    h.manager.resume(&job_id).await.unwrap();
+    let resumed = job(&h.manager, &job_id).await;
+    assert_eq!(resumed.state, DownloadState::Active);
+    assert_eq!(resumed.gids, added.gids, "unpaused in place, not respawned");
+    assert!(
+        added
+            .gids
+            .iter()
+            .all(|gid| mock.download(gid).unwrap().status == "active")
+    );
+
+    h.manager.remove(&job_id).await.unwrap();
+    assert!(h.manager.get_job(&job_id).await.is_none());
+    assert!(
+        added
+            .gids
+            .iter()
+            .all(|gid| mock.download(gid).unwrap().status == "removed")
+    );
+}
+
+#[tokio::test]
+async fn failed_add_leaves_no_job() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+
+    mock.fail_next("aria2.addUri", "No URI to download");
+    let result = h
+        .manager
+        .add_ddl_job(links(1), h.store.join("downloads"), Game::default())
+        .await;
+
+    assert!(result.is_err());
+    assert!(h.manager.all_jobs().await.is_empty());
+}
+
+#[tokio::test]
+async fn notifications_drive_progress_and_completion() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+    spawn_dispatcher(h.manager.clone(), h.client.clone());
+
+    let target = h.store.join("downloads");
+    let done_id = h
+        .manager
+        .add_ddl_job(links(1), target, Game::default())
+        .await
+        .unwrap();
+    let done_gid = job(&h.manager, &done_id).await.gids[0].clone();
+
+    mock.progress(&done_gid, 250_000, 4096);
+    wait_for(&h.manager, &done_id, "progress", |job| {
+        job.status
+            .as_ref()
+            .is_some_and(|s| s.completed_length == 250_000 && s.download_speed == 4096)
+    })
+    .await;
+
+    mock.complete(&done_gid);
+    wait_for(&h.manager, &done_id, "completion", |job| {
+        job.state == DownloadState::Complete
+    })
+    .await;
+    let status = job(&h.manager, &done_id).await.status.unwrap();
+    assert_eq!(status.completed_length, status.total_length);
+    assert_eq!(status.progress_percentage, 100.0);
+}
+
+#[tokio::test]
+async fn dispatcher_follows_notifications_between_sweeps() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+
+    let job_id = h
+        .manager
+        .add_ddl_job(links(2), h.store.join("downloads"), Game::default())
+        .await
+        .unwrap();
+    let gids = job(&h.manager, &job_id).await.gids;
+
+    spawn_dispatcher(h.manager.clone(), h.client.clone());
+    // the initial reconciliation
+    for _ in 0..100 {
+        if mock.calls("aria2.tellStopped") > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    let sweeps = mock.calls("aria2.tellActive");
+    assert_eq!(sweeps, 1);
+
+    mock.complete(&gids[0]);
+    mock.fail(&gids[1], "Resource not found");
+    wait_for(&h.manager, &job_id, "notified file states", |job| {
+        job.status.as_ref().is_some_and(|s| {
+            s.per_file.get(&gids[0]).map(|f| &f.status) == Some(&DownloadState::Complete)
+                && s.per_file.get(&gids[1]).map(|f| &f.status) == Some(&DownloadState::Error)
+        })
+    })
+    .await;
+
+    assert_eq!(
+        mock.calls("aria2.tellActive"),
+        sweeps,
+        "notifications alone drove the updates"
+    );
+}
+
+#[tokio::test]
+async fn one_failed_part_marks_the_whole_job_error() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+    spawn_dispatcher(h.manager.clone(), h.client.clone());
+
+    let job_id = h
+        .manager
+        .add_ddl_job(links(2), h.store.join("downloads"), Game::default())
+        .await
+        .unwrap();
+    let gids = job(&h.manager, &job_id).await.gids;
+
+    mock.progress(&gids[0], 250_000, 4096);
+    wait_for(&h.manager, &job_id, "active part", |job| {
+        job.status.as_ref().is_some_and(|s| {
+            s.per_file.get(&gids[0]).map(|f| &f.status) == Some(&DownloadState::Active)
+        })
+    })
+    .await;
+
+    // the other part still downloads, the job used to stay Active
+    mock.fail(&gids[1], "Resource not found");
+    wait_for(&h.manager, &job_id, "error", |job| {
+        job.state == DownloadState::Error
+    })
+    .await;
+    assert_eq!(
+        job(&h.manager, &job_id).await.status.unwrap().state,
+        DownloadState::Error
+    );
+}
+
+#[tokio::test]
+async fn reconnect_sweep_catches_missed_notifications() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+    spawn_dispatcher(h.manager.clone(), h.client.clone());
+
+    let target = h.store.join("downloads");
+    let mut jobs = Vec::new();
+    for _ in 0..3 {
+        let job_id = h
+            .manager
+            .add_ddl_job(links(1), target.clone(), Game::default())
+            .await
+            .unwrap();
+        h.manager.pause(&job_id).await.unwrap();
+        wait_for(&h.manager, &job_id, "pause", |job| {
+            job.state == DownloadState::Paused
+        })
+        .await;
+        jobs.push(job_id);
+    }
+
+    // let the dispatcher handle the pause notifications, nothing is polled afterwards
+    let mut status_calls = mock.calls("aria2.tellStatus");
+    loop {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let calls = mock.calls("aria2.tellStatus");
+        if calls == status_calls {
+            break;
+        }
+        status_calls = calls;
+    }
+
+    // the downloads finish while the connection is down, nothing is notified
+    for job_id in &jobs {
+        let gid = job(&h.manager, job_id).await.gids[0].clone();
+        mock.set_status_silently(&gid, "complete");
+    }
+
+    // replacing the client closes the old notification stream
+    *h.client.lock().await = mock.client().await;
+
+    for job_id in &jobs {
+        wait_for(&h.manager, job_id, "completion from the sweep", |job| {
+            job.status.as_ref().is_some_and(|s| {
+                s.per_file
+                    .values()
+                    .all(|f| f.status == DownloadState::Complete)
+            })
+        })
+        .await;
+    }
+    assert_eq!(
+        mock.calls("aria2.tellStatus"),
+        status_calls,
+        "the sweep lists downloads instead of asking for each gid"
+    );
+}
+
+#[tokio::test]
+async fn restart_recovers_jobs_and_respawns_stale_gids() {
+    let store = temp_dir();
+    let target = store.join("downloads");
+
+    let (job_id, old_gids) = {
+        let mock = MockAria2::start().await;
+        let h = harness(&mock, store.clone()).await;
+        let job_id = h
+            .manager
+            .add_ddl_job(links(2), target.clone(), Game::default())
+            .await
+            .unwrap();
+        h.manager.save_now().await.unwrap();
+        let gids = job(&h.manager, &job_id).await.gids;
+        (job_id, gids)
+    };
+
+    // a fresh aria2 that never heard of the previous session's gids
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, store).await;
+    h.manager.load_from_disk().await.unwrap();
+
+    let restored = job(&h.manager, &job_id).await;
+    assert_eq!(restored.state, DownloadState::Paused);
+    assert_eq!(restored.gids, old_gids);
+    assert!(restored.status.is_none());
+
+    h.manager.resume(&job_id).await.unwrap();
+
+    let resumed = job(&h.manager, &job_id).await;
+    assert_eq!(resumed.state, DownloadState::Active);
+    let downloads = mock.downloads();
+    assert_eq!(downloads.len(), 2, "every file is added again");
+    for download in &downloads {
+        assert!(resumed.gids.contains(&download.gid));
+        assert_eq!(PathBuf::from(&download.dir), target.join(&job_id));
+    }
+}
+
+#[tokio::test]
+async fn updates_for_stale_gids_are_ignored() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+
+    let job_id = h
+        .manager
+        .add_ddl_job(links(1), h.store.join("downloads"), Game::default())
+        .await
+        .unwrap();
+    let gid = job(&h.manager, &job_id).await.gids[0].clone();
+    h.manager.remove(&job_id).await.unwrap();
+
+    let late = FileStatus {
+        gid: Some(gid.clone()),
+        status: DownloadState::Complete,
+        total_length: 1_000_000,
+        completed_length: 1_000_000,
+        ..Default::default()
+    };
+    h.manager.apply_file_status(&gid, late.clone()).await;
+    h.manager.apply_file_status("00000000deadbeef", late).await;
+
+    assert!(h.manager.all_jobs().await.is_empty());
+}
+
+#[tokio::test]
+async fn stale_connection_reconnects_before_adding() {
+    let first = MockAria2::start().await;
+    let second = MockAria2::start().await;
+    let connector = MockConnector::new(second.url());
+    let aria2 = Aria2WsClient::new(
+        Arc::new(Mutex::new(first.client().await)),
+        connector.clone(),
+    );
+
+    let url = vec!["https://example.invalid/a.rar".to_string()];
+    let cfg = FitLauncherConfigAria2::default();
+    let gid = aria2
+        .add_uri(url.clone(), None, None, cfg.clone())
+        .await
+        .unwrap();
+    assert!(first.download(&gid).is_some());
+    assert_eq!(connector.connects.load(Ordering::SeqCst), 0);
+    assert_eq!(first.calls("aria2.getVersion"), 1, "health checked first");
+
+    // the daemon died, the next call has to reconnect through the connector
+    first.shutdown();
+    let gid = aria2.add_uri(url, None, None, cfg).await.unwrap();
+
+    assert_eq!(connector.connects.load(Ordering::SeqCst), 1);
+    assert!(second.download(&gid).is_some());
+    assert_eq!(aria2.status(&gid).await.unwrap().gid, gid);
+}
+
+/// Stand-in for aria2 restoring `links` from its session file, paused like `--pause=true`
+async fn restore_session(mock: &MockAria2, links: &[DirectLink], dir: &std::path::Path) {
+    let client = mock.client().await;
+    for link in links {
+        let gid = client
+            .add_uri(
+                vec![link.url.clone()],
+                Some(aria2_ws::TaskOptions {
+                    dir: Some(dir.to_string_lossy().to_string()),
+                    ..Default::default()
+                }),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        client.pause(&gid).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn restart_reattaches_jobs_to_restored_downloads() {
+    let store = temp_dir();
+    let target = store.join("downloads");
+    let files = links(2);
+
+    let (job_id, old_gids) = {
+        let mock = MockAria2::start().await;
+        let h = harness(&mock, store.clone()).await;
+        let job_id = h
+            .manager
+            .add_ddl_job(files.clone(), target.clone(), Game::default())
+            .await
+            .unwrap();
+        h.manager.save_now().await.unwrap();
+        let gids = job(&h.manager, &job_id).await.gids;
+        (job_id, gids)
+    };
+
+    let mock = MockAria2::start().await;
+    restore_session(&mock, &files, &target.join(&job_id)).await;
+    let h = harness(&mock, store).await;
+    h.manager.load_from_disk().await.unwrap();
+    // let the startup reattach and pause pass finish before resuming
+    for _ in 0..100 {
+        if mock.calls("aria2.pause") >= 4 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    let unattached = h.manager.reattach(&h.engine).await.unwrap();
+    assert!(unattached.is_empty());
+
+    let mut restored: Vec<String> = mock.downloads().into_iter().map(|d| d.gid).collect();
+    restored.sort();
+    let reattached = job(&h.manager, &job_id).await;
+    let mut gids = reattached.gids.clone();
+    gids.sort();
+    assert_eq!(gids, restored);
+    assert!(reattached.gids.iter().all(|gid| !old_gids.contains(gid)));
+
+    h.manager.resume(&job_id).await.unwrap();
+    assert_eq!(mock.downloads().len(), 2, "resumed in place, not respawned");
+    assert!(mock.downloads().iter().all(|d| d.status == "active"));
+}
+
+#[tokio::test]
+async fn reattach_keeps_progress_of_multi_file_jobs() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+    spawn_dispatcher(h.manager.clone(), h.client.clone());
+
+    // random gids, four parts are next to never in sorted order
+    let job_id = h
+        .manager
+        .add_ddl_job(links(4), h.store.join("downloads"), Game::default())
+        .await
+        .unwrap();
+    let gids = job(&h.manager, &job_id).await.gids;
+
+    mock.progress(&gids[0], 250_000, 4096);
+    wait_for(&h.manager, &job_id, "progress", |job| {
+        job.status
+            .as_ref()
+            .is_some_and(|s| s.completed_length == 250_000)
+    })
+    .await;
+
+    let unattached = h.manager.reattach(&h.engine).await.unwrap();
+    assert!(unattached.is_empty());
+
+    let reattached = job(&h.manager, &job_id).await;
+    assert_eq!(reattached.gids, gids);
+    assert!(reattached.status.is_some(), "status kept, gids unchanged");
+}
+
+#[tokio::test]
+async fn supervisor_restarts_aria2_and_resumes_active_jobs() {
+    let first = MockAria2::start().await;
+    let h = harness(&first, temp_dir()).await;
+    let supervisor = Aria2Supervisor::new(
+        h.manager.clone(),
+        h.engine.clone(),
+        h.client.clone(),
+        h.connector.clone(),
+    );
+    spawn_supervisor(supervisor.clone());
+
+    let files = links(1);
+    let target = h.store.join("downloads");
+    let job_id = h
+        .manager
+        .add_ddl_job(files.clone(), target.clone(), Game::default())
+        .await
+        .unwrap();
+
+    // aria2 crashes and comes back with its session restored on another port
+    first.shutdown();
+    let second = MockAria2::start().await;
+    restore_session(&second, &files, &target.join(&job_id)).await;
+    *h.connector.url.lock().unwrap() = second.url();
+    supervisor.request_restart();
+
+    let restored = second.downloads()[0].gid.clone();
+    wait_for(&h.manager, &job_id, "reattached gid", |job| {
+        job.gids == [restored.clone()]
+    })
+    .await;
+    wait_for(&h.manager, &job_id, "resume", |job| {
+        job.state == DownloadState::Active
+    })
+    .await;
+    assert_eq!(supervisor.health().await, DaemonHealth::Running);
+    assert_eq!(second.download(&restored).unwrap().status, "active");
+    assert_eq!(second.downloads().len(), 1);
+}
+
+#[tokio::test]
+async fn job_details_report_per_file_progress() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+
+    let job_id = h
+        .manager
+        .add_ddl_job(links(2), h.store.join("downloads"), Game::default())
+        .await
+        .unwrap();
+    let gids = job(&h.manager, &job_id).await.gids;
+    mock.progress(&gids[0], 400_000, 0);
+
+    let details = h.manager.job_details(&job_id).await.unwrap();
+    assert_eq!(details.transfers.len(), 2);
+    assert!(details.stalled_since.is_none());
+
+    let first = details.transfers.iter().find(|t| t.gid == gids[0]).unwrap();
+    assert_eq!(first.files.len(), 1);
+    assert_eq!(first.files[0].index, 0);
+    assert_eq!(first.files[0].completed_length, 400_000);
+    assert!(first.peers.is_empty(), "direct links have no swarm");
+
+    assert!(h.manager.job_details("missing").await.is_none());
+}
+
+#[tokio::test]
+async fn history_outlives_removed_jobs() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+    let target = h.store.join("downloads");
+
+    let done_id = h
+        .manager
+        .add_ddl_job(links(1), target.clone(), Game::default())
+        .await
+        .unwrap();
+    let removed_id = h
+        .manager
+        .add_ddl_job(links(1), target, Game::default())
+        .await
+        .unwrap();
+    let done_gid = job(&h.manager, &done_id).await.gids[0].clone();
+    let removed_gid = job(&h.manager, &removed_id).await.gids[0].clone();
+
+    let status = |state, completed_length, download_speed| FileStatus {
+        status: state,
+        total_length: 1_000_000,
+        completed_length,
+        download_speed,
+        ..Default::default()
+    };
+    h.manager
+        .apply_file_status(&done_gid, status(DownloadState::Active, 400_000, 8192))
+        .await;
+    h.manager
+        .apply_file_status(&done_gid, status(DownloadState::Complete, 1_000_000, 0))
+        .await;
+    h.manager
+        .apply_file_status(&removed_gid, status(DownloadState::Active, 250_000, 2048))
+        .await;
+    h.manager.remove(&removed_id).await.unwrap();
+
+    let today = chrono::Local::now().date_naive();
+    let history = h.manager.download_history(today, today);
+    assert_eq!(history.len(), 2);
+    let done = history.iter().find(|r| r.job_id == done_id).unwrap();
+    assert_eq!(done.outcome, JobOutcome::Completed);
+    assert_eq!(done.downloaded_bytes, 1_000_000);
+    assert_eq!(done.peak_speed, 8192);
+    assert_eq!(done.host.as_deref(), Some("example.invalid"));
+    let removed = history.iter().find(|r| r.job_id == removed_id).unwrap();
+    assert_eq!(removed.outcome, JobOutcome::Removed);
+    assert_eq!(removed.downloaded_bytes, 250_000);
+
+    let stats = h.manager.download_stats(today, today);
+    assert_eq!(stats.downloaded_bytes, 1_250_000);
+    assert_eq!(stats.jobs_completed, 1);
+    assert_eq!(stats.providers.len(), 1);
+    assert_eq!(stats.providers[0].provider, "example.invalid");
+    assert_eq!(stats.providers[0].jobs, 2);
+
+    let csv = h.store.join("jobs.csv");
+    h.manager
+        .export_download_stats(StatsExport::Jobs, today, today, &csv)
+        .await
+        .unwrap();
+    let csv = std::fs::read_to_string(csv).unwrap();
+    assert_eq!(csv.lines().count(), 3);
+    assert!(csv.starts_with("job_id,"));
+
+    // the history is on disk for the next start
+    let restarted = harness(&mock, h.store.clone()).await;
+    assert_eq!(restarted.manager.download_history(today, today).len(), 2);
+}
+
+#[test]
+fn bitfields_count_set_pieces() {
+    assert_eq!(count_pieces(""), 0);
+    assert_eq!(count_pieces("f0"), 4);
+    assert_eq!(count_pieces("ff8"), 9);
+}
+
+#[tokio::test]
+async fn offline_requests_are_replayed_once_online() {
+    let mock = MockAria2::start().await;
+    let h = harness(&mock, temp_dir()).await;
+
+    // the only test touching the process-wide connectivity state
+    connectivity::set_forced_offline(true);
+    let request = OutboxRequest::AddDdlJob {
+        files: links(2),
+        target: h.store.join("downloads").to_string_lossy().to_string(),
+        game: Game::default(),
+        ignore_space_check: true,
+    };
+    let submitted = submit_or_queue(&h.manager, request).await.unwrap();
+    let JobSubmission::Queued(entry_id) = submitted else {
+        panic!("offline request was not queued: {submitted:?}");
+    };
+    assert_eq!(h.manager.outbox().entries()[0].id, entry_id);
+    assert!(h.manager.all_jobs().await.is_empty());
+    assert!(mock.downloads().is_empty());
+
+    spawn_outbox_replay(h.manager.clone());
+    connectivity::set_forced_offline(false);
+
+    for _ in 0..100 {
+        if h.manager.outbox().entries().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(
+        h.manager.outbox().entries().is_empty(),
+        "outbox was not replayed"
+    );
+    let jobs = h.manager.all_jobs().await;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].gids.len(), 2);
+}
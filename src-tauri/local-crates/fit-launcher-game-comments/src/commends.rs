@@ -1,10 +1,15 @@
 use crate::core_commands::fetch_comments;
 use crate::types::Comments;
+use fit_launcher_config::client::connectivity;
 use specta::specta;
 use tauri;
 
 #[tauri::command]
 #[specta]
 pub async fn get_game_comments(url: String) -> Result<Comments, String> {
+    // comments are not stored locally
+    if connectivity::is_site_unavailable() {
+        return Err("Comments are unavailable offline".to_string());
+    }
     fetch_comments(&url).await.map_err(|e| e.to_string())
 }
@@ -1,14 +1,17 @@
 use crate::types::Comments;
 use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
+use fit_launcher_config::client::scheduler::{self, RetryBudget};
 use reqwest::header::{ACCEPT, ACCEPT_LANGUAGE};
 
+const COMMENTS_ATTEMPTS: u32 = 3;
+
 pub async fn fetch_comments(game_url: &str) -> Result<Comments, String> {
     let client_guard = CUSTOM_DNS_CLIENT.read().await;
     let client = &*client_guard;
 
     let api_url = "https://web.tolstoycomments.com/api/chatpage/first";
 
-    let response = client
+    let request = client
         .get(api_url)
         .query(&[
             ("siteid", "6289"),
@@ -19,7 +22,10 @@ pub async fn fetch_comments(game_url: &str) -> Result<Comments, String> {
         ])
         .header(ACCEPT, "*/*")
         .header(ACCEPT_LANGUAGE, "en-US,en;q=0.5")
-        .send()
+        .build()
+        .map_err(|e| format!("Invalid comments request: {}", e))?;
+
+    let response = scheduler::send(client, request, &mut RetryBudget::new(COMMENTS_ATTEMPTS))
         .await
         .map_err(|e| format!("Network request failed: {}", e))?;
 
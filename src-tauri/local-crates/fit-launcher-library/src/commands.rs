@@ -13,6 +13,7 @@ use crate::{
         get_collection_list_path, get_downloaded_games_path, get_games_to_download_path,
     },
     legacy::{LegacyDownloadedGame, convert_legacy_downloads},
+    recommendations::library_changed,
     structs::{DownloadedGame, ExecutableInfo, GameCollection},
 };
 
@@ -62,28 +63,33 @@ pub async fn get_games_to_download() -> Vec<Game> {
 #[tauri::command]
 #[specta]
 pub async fn get_downloaded_games() -> Vec<DownloadedGame> {
+    load_downloaded_games().unwrap_or_else(|err| {
+        error!("{err}");
+        vec![]
+    })
+}
+
+/// Read the library, accepting both the current and the legacy format.
+///
+/// Unlike [`get_downloaded_games`] a parse failure is an error, so that callers
+/// writing the library back never replace an unreadable file with `[]`.
+pub(crate) fn load_downloaded_games() -> Result<Vec<DownloadedGame>, String> {
     let path = get_downloaded_games_path();
 
-    let file_content = match fs::read_to_string(&path) {
-        Ok(content) => content,
-        Err(err) => {
-            error!("Error reading the file at {:?}: {:#?}", path, err);
-            return vec![];
-        }
-    };
+    let file_content = fs::read_to_string(&path)
+        .map_err(|err| format!("Error reading the file at {path:?}: {err}"))?;
 
     // Try modern format first
     if let Ok(games) = serde_json::from_str::<Vec<DownloadedGame>>(&file_content) {
-        return games;
+        return Ok(games);
     }
 
     // Fallback to legacy
     if let Ok(legacy_games) = serde_json::from_str::<Vec<LegacyDownloadedGame>>(&file_content) {
-        return convert_legacy_downloads(legacy_games);
+        return Ok(convert_legacy_downloads(legacy_games));
     }
 
-    error!("Failed to parse downloaded games in any known format");
-    vec![]
+    Err("Failed to parse downloaded games in any known format".into())
 }
 
 #[tauri::command]
@@ -148,6 +154,7 @@ pub async fn add_downloaded_game(game: DownloadedGame) -> Result<(), String> {
     std::fs::write(&path, serde_json::to_string_pretty(&games).unwrap())
         .map_err(|e| e.to_string())?;
 
+    library_changed();
     Ok(())
 }
 
@@ -169,8 +176,7 @@ pub async fn get_downloaded_game(game_href: String) -> Result<Option<DownloadedG
 pub async fn remove_downloaded_game(game_title: String) -> Result<(), String> {
     let path = get_downloaded_games_path();
 
-    let file_content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let mut games: Vec<DownloadedGame> = serde_json::from_str(&file_content).unwrap_or_default();
+    let mut games = load_downloaded_games()?;
     let original_len = games.len();
     games.retain(|game| game.title != game_title);
 
@@ -180,6 +186,7 @@ pub async fn remove_downloaded_game(game_title: String) -> Result<(), String> {
 
     fs::write(&path, serde_json::to_string_pretty(&games).unwrap()).map_err(|e| e.to_string())?;
 
+    library_changed();
     Ok(())
 }
 
@@ -215,7 +222,8 @@ pub async fn remove_game_from_collection(
     let path = get_collection_list_path().join(format!("{collection_name}.json"));
 
     let file_content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let mut games: Vec<Game> = serde_json::from_str(&file_content).unwrap_or_default();
+    let mut games: Vec<Game> = serde_json::from_str(&file_content)
+        .map_err(|e| format!("Failed to parse collection '{collection_name}': {e}"))?;
     let original_len = games.len();
     games.retain(|game| game.title != game_title);
 
@@ -225,6 +233,7 @@ pub async fn remove_game_from_collection(
 
     fs::write(&path, serde_json::to_string_pretty(&games).unwrap()).map_err(|e| e.to_string())?;
 
+    library_changed();
     Ok(())
 }
 
@@ -240,6 +249,7 @@ pub async fn remove_collection(collection_name: String) -> Result<(), String> {
     fs::remove_file(&path)
         .map_err(|err| format!("Failed to remove collection '{collection_name}': {err}"))?;
 
+    library_changed();
     Ok(())
 }
 
@@ -268,6 +278,7 @@ pub async fn update_downloaded_game_executable_info(
 
     fs::write(&path, serde_json::to_string_pretty(&games).unwrap()).map_err(|e| e.to_string())?;
 
+    library_changed();
     Ok(())
 }
 
@@ -309,6 +320,7 @@ pub async fn add_game_to_collection(collection_name: String, game: Game) -> Resu
     fs::write(&path, serde_json::to_string_pretty(&games).unwrap())
         .map_err(|e| format!("Failed to write collection file: {e}"))?;
 
+    library_changed();
     Ok(())
 }
 
@@ -341,6 +353,7 @@ pub async fn create_collection(
     fs::write(&path, serde_json::to_string_pretty(&game_list).unwrap())
         .map_err(|e| format!("Failed to write collection file: {e}"))?;
 
+    library_changed();
     Ok(())
 }
 
@@ -0,0 +1,323 @@
+//! Launching downloaded games through their [`LaunchProfile`].
+//!
+//! The profile is turned into a plain [`LaunchCommand`] first, which is then
+//! handed to a [`GameRunner`]. Tests use a stub runner instead of spawning processes.
+
+use std::{
+    fs::{self, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use directories::BaseDirs;
+use fit_launcher_scraping::db::extract_slug;
+use specta::specta;
+use tracing::{error, info};
+
+use crate::{
+    commands::get_downloaded_games,
+    core_commands::get_downloaded_games_path,
+    structs::{LaunchProfile, LaunchRunner},
+};
+
+/// Fully resolved command line for a game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub working_dir: PathBuf,
+}
+
+pub trait GameRunner {
+    /// Start the command with stdout/stderr appended to `log_path`, returns the PID
+    fn launch(&self, command: &LaunchCommand, log_path: &Path) -> io::Result<u32>;
+}
+
+/// `CreateProcess` refusing an executable whose manifest requires elevation
+#[cfg(windows)]
+const ERROR_ELEVATION_REQUIRED: i32 = 740;
+
+/// Runner spawning a real child process.
+///
+/// On Windows an executable requiring elevation is started through the
+/// shell instead, which prompts for it but does not capture its output.
+pub struct ProcessRunner;
+
+impl GameRunner for ProcessRunner {
+    fn launch(&self, command: &LaunchCommand, log_path: &Path) -> io::Result<u32> {
+        if let Some(parent) = log_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let stdout = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)?;
+        let stderr = stdout.try_clone()?;
+
+        let spawned = Command::new(&command.program)
+            .args(&command.args)
+            .envs(command.env.iter().map(|(k, v)| (k, v)))
+            .current_dir(&command.working_dir)
+            .stdin(Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr)
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            #[cfg(windows)]
+            Err(e) if e.raw_os_error() == Some(ERROR_ELEVATION_REQUIRED) => {
+                info!(
+                    "{} requires elevation, launching through the shell",
+                    command.program
+                );
+                return fit_launcher_ui_automation::shell_execute(
+                    Path::new(&command.program),
+                    &command.args,
+                    &command.working_dir,
+                );
+            }
+            Err(e) => return Err(e),
+        };
+
+        let pid = child.id();
+        let program = command.program.clone();
+        // reap the child so it does not linger as a zombie
+        std::thread::spawn(move || match child.wait() {
+            Ok(status) => info!("{program} (pid {pid}) exited with {status}"),
+            Err(e) => error!("Failed to wait on {program} (pid {pid}): {e}"),
+        });
+
+        Ok(pid)
+    }
+}
+
+/// Build the command line for `executable` according to `profile`.
+pub fn build_command(profile: &LaunchProfile, executable: &Path) -> Result<LaunchCommand, String> {
+    let exe = executable.to_string_lossy().to_string();
+    let working_dir = profile
+        .working_dir
+        .as_ref()
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| executable.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let mut env = profile.env.clone();
+    if let Some(prefix) = profile.prefix.as_ref().filter(|p| !p.is_empty()) {
+        env.push(("WINEPREFIX".into(), prefix.clone()));
+    }
+
+    let (program, args) = match &profile.runner {
+        LaunchRunner::Native => (exe, profile.args.clone()),
+        LaunchRunner::Wine { binary } => {
+            let program = binary
+                .clone()
+                .filter(|b| !b.is_empty())
+                .unwrap_or_else(|| "wine".into());
+            let mut args = vec![exe];
+            args.extend(profile.args.iter().cloned());
+            (program, args)
+        }
+        LaunchRunner::Custom { template } => {
+            let mut parts = Vec::new();
+            for token in template.split_whitespace() {
+                match token {
+                    "{args}" => parts.extend(profile.args.iter().cloned()),
+                    _ => parts.push(
+                        token
+                            .replace("{exe}", &exe)
+                            .replace("{prefix}", profile.prefix.as_deref().unwrap_or(""))
+                            .replace("{dir}", &working_dir.to_string_lossy()),
+                    ),
+                }
+            }
+
+            if parts.is_empty() {
+                return Err("Custom launch template is empty".into());
+            }
+            let program = parts.remove(0);
+            (program, parts)
+        }
+    };
+
+    Ok(LaunchCommand {
+        program,
+        args,
+        env,
+        working_dir,
+    })
+}
+
+/// Per-game log file receiving the game's stdout/stderr
+pub fn game_log_path(game_href: &str) -> Option<PathBuf> {
+    let base_dirs = BaseDirs::new()?;
+    let slug = extract_slug(game_href);
+
+    Some(
+        base_dirs
+            .config_dir()
+            .join("com.fitlauncher.carrotrub")
+            .join("logs")
+            .join("games")
+            .join(format!("{slug}.log")),
+    )
+}
+
+/// Launch `executable` through `profile` using `runner`.
+pub fn launch_with(
+    runner: &dyn GameRunner,
+    profile: &LaunchProfile,
+    executable: &Path,
+    log_path: &Path,
+) -> Result<u32, String> {
+    let command = build_command(profile, executable)?;
+    info!(
+        "Launching {} {:?} in {}",
+        command.program,
+        command.args,
+        command.working_dir.display()
+    );
+
+    runner
+        .launch(&command, log_path)
+        .map_err(|e| format!("Failed to launch {}: {e}", command.program))
+}
+
+/// Launch a downloaded game through its launch profile, returns the PID.
+#[tauri::command]
+#[specta]
+pub async fn launch_game(game_href: String) -> Result<u32, String> {
+    let games = get_downloaded_games().await;
+    let game = games
+        .iter()
+        .find(|g| g.href == game_href)
+        .ok_or_else(|| format!("Game '{game_href}' is not in the library"))?;
+
+    let executable = PathBuf::from(&game.executable_info.executable_path);
+    if game.executable_info.executable_path.is_empty() || !executable.exists() {
+        return Err(format!(
+            "Executable not found for '{}', set it from the game page first",
+            game.title
+        ));
+    }
+
+    let log_path = game_log_path(&game.href).ok_or("Failed to determine base directories")?;
+    launch_with(&ProcessRunner, &game.launch_profile, &executable, &log_path)
+}
+
+#[tauri::command]
+#[specta]
+pub async fn update_downloaded_game_launch_profile(
+    game_href: String,
+    launch_profile: LaunchProfile,
+) -> Result<(), String> {
+    let mut games = get_downloaded_games().await;
+    let path = get_downloaded_games_path();
+
+    let Some(target_game) = games.iter_mut().find(|game| game.href == game_href) else {
+        return Err(format!("Game '{game_href}' not found"));
+    };
+
+    target_game.launch_profile = launch_profile;
+
+    fs::write(&path, serde_json::to_string_pretty(&games).unwrap()).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta]
+pub fn get_game_log_path(game_href: String) -> Result<String, String> {
+    game_log_path(&game_href)
+        .map(|p| p.to_string_lossy().to_string())
+        .ok_or_else(|| "Failed to determine base directories".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct StubRunner {
+        launched: RefCell<Vec<(LaunchCommand, PathBuf)>>,
+    }
+
+    impl GameRunner for StubRunner {
+        fn launch(&self, command: &LaunchCommand, log_path: &Path) -> io::Result<u32> {
+            self.launched
+                .borrow_mut()
+                .push((command.clone(), log_path.to_path_buf()));
+            Ok(4242)
+        }
+    }
+
+    #[test]
+    fn wine_profile_exports_prefix() {
+        let profile = LaunchProfile {
+            runner: LaunchRunner::Wine { binary: None },
+            prefix: Some("/home/user/.wine-games".into()),
+            env: vec![("DXVK_HUD".into(), "fps".into())],
+            args: vec!["-windowed".into()],
+            working_dir: None,
+        };
+
+        let cmd = build_command(&profile, Path::new("/games/foo/Foo.exe")).unwrap();
+        assert_eq!(cmd.program, "wine");
+        assert_eq!(cmd.args, vec!["/games/foo/Foo.exe", "-windowed"]);
+        assert_eq!(cmd.working_dir, PathBuf::from("/games/foo"));
+        assert!(
+            cmd.env
+                .contains(&("WINEPREFIX".into(), "/home/user/.wine-games".into()))
+        );
+        assert!(cmd.env.contains(&("DXVK_HUD".into(), "fps".into())));
+    }
+
+    #[test]
+    fn custom_template_expands_placeholders() {
+        let profile = LaunchProfile {
+            runner: LaunchRunner::Custom {
+                template: "protonrun --prefix {prefix} {exe} {args}".into(),
+            },
+            prefix: Some("/pfx".into()),
+            args: vec!["-a".into(), "-b".into()],
+            working_dir: Some("/tmp".into()),
+            ..Default::default()
+        };
+
+        let runner = StubRunner::default();
+        let pid = launch_with(
+            &runner,
+            &profile,
+            Path::new("/games/foo/Foo.exe"),
+            Path::new("/logs/foo.log"),
+        )
+        .unwrap();
+        assert_eq!(pid, 4242);
+
+        let launched = runner.launched.borrow();
+        let (cmd, log) = &launched[0];
+        assert_eq!(cmd.program, "protonrun");
+        assert_eq!(
+            cmd.args,
+            vec!["--prefix", "/pfx", "/games/foo/Foo.exe", "-a", "-b"]
+        );
+        assert_eq!(cmd.working_dir, PathBuf::from("/tmp"));
+        assert_eq!(log, &PathBuf::from("/logs/foo.log"));
+    }
+
+    #[test]
+    fn empty_custom_template_is_rejected() {
+        let profile = LaunchProfile {
+            runner: LaunchRunner::Custom {
+                template: "   ".into(),
+            },
+            ..Default::default()
+        };
+
+        assert!(build_command(&profile, Path::new("Foo.exe")).is_err());
+    }
+}
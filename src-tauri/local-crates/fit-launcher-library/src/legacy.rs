@@ -50,6 +50,7 @@ pub(crate) fn convert_legacy_downloads(
                 download_folder: legacy.torrentDownloadFolder,
                 file_list: legacy.torrentFileList,
             },
+            launch_profile: Default::default(),
         })
         .collect()
 }
@@ -1,5 +1,9 @@
 pub mod commands;
+pub mod launch;
 pub mod legacy;
+pub mod recommendations;
+pub mod steam;
 pub mod structs;
 pub use commands::*;
 pub mod core_commands;
+pub mod uninstall;
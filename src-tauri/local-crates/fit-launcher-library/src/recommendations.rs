@@ -0,0 +1,89 @@
+//! Feeds the library into the discovery recommendations of the scraping crate.
+//!
+//! Library writes call [`library_changed`], and the refresher spawned at
+//! startup re-ranks the feed once a burst of edits has settled.
+
+use std::time::Duration;
+
+use fit_launcher_scraping::recommend::{self, LibraryGame};
+use specta::specta;
+use tauri::AppHandle;
+use tokio::sync::Notify;
+use tracing::error;
+
+use crate::commands::{get_collection_list, get_downloaded_games};
+
+/// Wait after a library edit, an uninstall or an import writes several times
+const REFRESH_DELAY: Duration = Duration::from_secs(2);
+
+static LIBRARY_CHANGED: Notify = Notify::const_new();
+
+/// The downloaded games or the collections changed
+pub fn library_changed() {
+    LIBRARY_CHANGED.notify_one();
+}
+
+/// Refresh the recommendations after every library change
+pub fn spawn_recommendation_refresher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            LIBRARY_CHANGED.notified().await;
+            tokio::time::sleep(REFRESH_DELAY).await;
+            let _ = refresh_recommendations(app.clone()).await;
+        }
+    });
+}
+
+/// Downloaded and collected games, one entry per game
+pub async fn library_games() -> Vec<LibraryGame> {
+    let mut library: Vec<LibraryGame> = get_downloaded_games()
+        .await
+        .into_iter()
+        .map(|game| LibraryGame {
+            installed: !game.executable_info.executable_path.is_empty(),
+            play_time: game.executable_info.executable_play_time,
+            title: game.title,
+            href: game.href,
+            tag: game.tag,
+            details: game.details,
+            downloaded: true,
+            collections: Vec::new(),
+        })
+        .collect();
+
+    for collection in get_collection_list().await {
+        for game in collection.games_list {
+            let known = library.iter_mut().find(|known| {
+                if game.href.is_empty() {
+                    known.title == game.title
+                } else {
+                    known.href == game.href
+                }
+            });
+            match known {
+                Some(known) => known.collections.push(collection.name.clone()),
+                None => library.push(LibraryGame {
+                    title: game.title,
+                    href: game.href,
+                    tag: game.tag,
+                    details: game.details,
+                    collections: vec![collection.name.clone()],
+                    ..Default::default()
+                }),
+            }
+        }
+    }
+
+    library
+}
+
+/// Re-rank the discovery feed, e.g. after the library changed
+#[tauri::command]
+#[specta]
+pub async fn refresh_recommendations(app: AppHandle) -> Result<(), String> {
+    let library = library_games().await;
+    recommend::refresh_recommendations(&app, &library).map_err(|e| {
+        error!("Failed to refresh recommendations: {e}");
+        e.to_string()
+    })
+}
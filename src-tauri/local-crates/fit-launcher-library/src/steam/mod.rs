@@ -0,0 +1,393 @@
+//! Export of downloaded games as Steam "non-Steam game" shortcuts.
+//!
+//! Steam rewrites `shortcuts.vdf` on exit, so it should be closed while exporting.
+
+pub mod vdf;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+use specta::{Type, specta};
+use tracing::{info, warn};
+
+use crate::{
+    commands::get_downloaded_games,
+    launch::build_command,
+    steam::vdf::{VdfMap, VdfValue},
+    structs::DownloadedGame,
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct SteamExportReport {
+    /// `shortcuts.vdf` files that were written
+    pub shortcut_files: Vec<String>,
+    pub added: u32,
+    pub updated: u32,
+    /// Games skipped because no executable is known
+    pub skipped: Vec<String>,
+    pub artwork_copied: u32,
+}
+
+/// Write every downloaded game with a known executable into Steam's `shortcuts.vdf`,
+/// for each Steam user found under `steam_dir` (or the default Steam install).
+#[tauri::command]
+#[specta]
+pub async fn export_steam_shortcuts(
+    steam_dir: Option<String>,
+) -> Result<SteamExportReport, String> {
+    let games = get_downloaded_games().await;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let steam_dir = steam_dir
+            .map(PathBuf::from)
+            .or_else(default_steam_dir)
+            .ok_or("Steam installation not found")?;
+
+        export_to(&steam_dir, &games)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn export_to(steam_dir: &Path, games: &[DownloadedGame]) -> Result<SteamExportReport, String> {
+    let user_dirs = steam_user_dirs(steam_dir);
+    if user_dirs.is_empty() {
+        return Err(format!(
+            "No Steam user found in {}",
+            steam_dir.join("userdata").display()
+        ));
+    }
+
+    let mut report = SteamExportReport::default();
+    let mut shortcuts = Vec::new();
+    for game in games {
+        let exe = &game.executable_info.executable_path;
+        if exe.is_empty() || !Path::new(exe).exists() {
+            report.skipped.push(game.title.clone());
+            continue;
+        }
+
+        match shortcut_for(game) {
+            Ok(s) => shortcuts.push((game, s)),
+            Err(e) => {
+                warn!("Skipping {} for Steam export: {e}", game.title);
+                report.skipped.push(game.title.clone());
+            }
+        }
+    }
+
+    for user_dir in user_dirs {
+        let config_dir = user_dir.join("config");
+        let vdf_path = config_dir.join("shortcuts.vdf");
+
+        let mut root = match fs::read(&vdf_path) {
+            Ok(bytes) => vdf::read(&bytes).map_err(|e| format!("{}: {e}", vdf_path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(format!("{}: {e}", vdf_path.display())),
+        };
+
+        let mut entries = match vdf::get(&root, "shortcuts").and_then(VdfValue::as_map) {
+            Some(map) => map.iter().map(|(_, v)| v.clone()).collect(),
+            None => Vec::new(),
+        };
+
+        for (game, shortcut) in &shortcuts {
+            if merge_shortcut(&mut entries, shortcut) {
+                report.updated += 1;
+            } else {
+                report.added += 1;
+            }
+
+            if copy_grid_art(&config_dir.join("grid"), game, shortcut_app_id(shortcut)) {
+                report.artwork_copied += 1;
+            }
+        }
+
+        // Steam expects consecutive "0".."n" keys
+        let reindexed: VdfMap = entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect();
+        vdf::set(&mut root, "shortcuts", VdfValue::Map(reindexed));
+
+        fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
+        if vdf_path.exists() {
+            let _ = fs::copy(&vdf_path, vdf_path.with_extension("vdf.bak"));
+        }
+        fs::write(&vdf_path, vdf::write(&root)).map_err(|e| e.to_string())?;
+
+        info!(
+            "Exported {} shortcuts to {}",
+            shortcuts.len(),
+            vdf_path.display()
+        );
+        report
+            .shortcut_files
+            .push(vdf_path.to_string_lossy().to_string());
+    }
+
+    Ok(report)
+}
+
+/// Build the shortcut entry for a game, launching through its launch profile.
+fn shortcut_for(game: &DownloadedGame) -> Result<VdfValue, String> {
+    let exe_path = Path::new(&game.executable_info.executable_path);
+    let command = build_command(&game.launch_profile, exe_path)?;
+
+    let exe = quote(&command.program);
+    let mut launch_options: Vec<String> = command
+        .env
+        .iter()
+        .map(|(k, v)| format!("{k}={}", quote(v)))
+        .collect();
+    if !launch_options.is_empty() {
+        launch_options.push("%command%".into());
+    }
+    launch_options.extend(command.args.iter().map(|a| quote(a)));
+
+    let tags: VdfMap = genres(&game.details)
+        .into_iter()
+        .enumerate()
+        .map(|(i, tag)| (i.to_string(), VdfValue::String(tag)))
+        .collect();
+
+    Ok(VdfValue::Map(vec![
+        (
+            "appid".into(),
+            VdfValue::Int32(shortcut_id(&exe, &game.title) as i32),
+        ),
+        ("AppName".into(), VdfValue::String(game.title.clone())),
+        ("Exe".into(), VdfValue::String(exe)),
+        (
+            "StartDir".into(),
+            VdfValue::String(quote(&command.working_dir.to_string_lossy())),
+        ),
+        (
+            "icon".into(),
+            VdfValue::String(game.executable_info.executable_path.clone()),
+        ),
+        ("ShortcutPath".into(), VdfValue::String(String::new())),
+        (
+            "LaunchOptions".into(),
+            VdfValue::String(launch_options.join(" ")),
+        ),
+        ("IsHidden".into(), VdfValue::Int32(0)),
+        ("AllowDesktopConfig".into(), VdfValue::Int32(1)),
+        ("AllowOverlay".into(), VdfValue::Int32(1)),
+        ("OpenVR".into(), VdfValue::Int32(0)),
+        ("Devkit".into(), VdfValue::Int32(0)),
+        ("DevkitGameID".into(), VdfValue::String(String::new())),
+        ("DevkitOverrideAppID".into(), VdfValue::Int32(0)),
+        ("LastPlayTime".into(), VdfValue::Int32(0)),
+        ("FlatpakAppID".into(), VdfValue::String(String::new())),
+        ("tags".into(), VdfValue::Map(tags)),
+    ]))
+}
+
+/// Merge `shortcut` into `entries`, returns `true` if an existing entry was updated
+/// and `false` if it was appended.
+///
+/// Entries match on app id, or on name + exe for shortcuts added by hand.
+/// Fields Steam maintains itself (play time, hidden state...) are preserved.
+fn merge_shortcut(entries: &mut Vec<VdfValue>, shortcut: &VdfValue) -> bool {
+    const STEAM_OWNED: &[&str] = &["LastPlayTime", "IsHidden", "AllowOverlay", "OpenVR"];
+
+    let new = shortcut.as_map().expect("shortcut is a map");
+    let str_of = |m: &VdfMap, k: &str| vdf::get(m, k).and_then(VdfValue::as_str).map(str::to_owned);
+    let id_of = |m: &VdfMap| vdf::get(m, "appid").and_then(VdfValue::as_i32);
+
+    let existing = entries.iter_mut().filter_map(|e| match e {
+        VdfValue::Map(m) => Some(m),
+        _ => None,
+    });
+
+    for map in existing {
+        let same_id = id_of(map).is_some() && id_of(map) == id_of(new);
+        let same_target = str_of(map, "AppName") == str_of(new, "AppName")
+            && str_of(map, "Exe") == str_of(new, "Exe");
+
+        if same_id || same_target {
+            for (key, value) in new {
+                if vdf::get(map, key).is_some() && STEAM_OWNED.contains(&key.as_str()) {
+                    continue;
+                }
+                vdf::set(map, key, value.clone());
+            }
+            return true;
+        }
+    }
+
+    entries.push(shortcut.clone());
+    false
+}
+
+fn shortcut_app_id(shortcut: &VdfValue) -> u32 {
+    shortcut
+        .as_map()
+        .and_then(|m| vdf::get(m, "appid"))
+        .and_then(VdfValue::as_i32)
+        .unwrap_or_default() as u32
+}
+
+/// Id Steam derives for non-Steam shortcuts, also used to name grid images
+pub fn shortcut_id(exe: &str, app_name: &str) -> u32 {
+    crc32fast::hash(format!("{exe}{app_name}").as_bytes()) | 0x8000_0000
+}
+
+/// Copy the cached cover into `grid/<appid>p.<ext>`, unless the user already set one.
+fn copy_grid_art(grid_dir: &Path, game: &DownloadedGame, app_id: u32) -> bool {
+    if game.img.is_empty() {
+        return false;
+    }
+
+    let cached = fit_launcher_cache::image_path(&game.img);
+    if !cached.exists() {
+        return false;
+    }
+
+    let ext = Path::new(game.img.split(['?', '#']).next().unwrap_or_default())
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase)
+        .filter(|e| ["png", "jpg", "jpeg", "webp"].contains(&e.as_str()))
+        .unwrap_or_else(|| "jpg".into());
+
+    let already_set = ["png", "jpg", "jpeg", "webp"]
+        .iter()
+        .any(|e| grid_dir.join(format!("{app_id}p.{e}")).exists());
+    if already_set {
+        return false;
+    }
+
+    let target = grid_dir.join(format!("{app_id}p.{ext}"));
+    match fs::create_dir_all(grid_dir).and_then(|_| fs::copy(&cached, &target)) {
+        Ok(_) => true,
+        Err(e) => {
+            warn!("Failed to copy cover art to {}: {e}", target.display());
+            false
+        }
+    }
+}
+
+/// Tags taken from the "Genres/Tags:" line of the repack details
+fn genres(details: &str) -> Vec<String> {
+    details
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("Genres/Tags:"))
+        .map(|tags| {
+            tags.split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn quote(s: &str) -> String {
+    if s.starts_with('"') {
+        s.to_string()
+    } else {
+        format!("\"{s}\"")
+    }
+}
+
+fn steam_user_dirs(steam_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(steam_dir.join("userdata")) else {
+        return vec![];
+    };
+
+    entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|e| {
+            e.file_name()
+                .to_str()
+                .is_some_and(|n| n != "0" && n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(|e| e.path())
+        .collect()
+}
+
+fn default_steam_dir() -> Option<PathBuf> {
+    let base_dirs = BaseDirs::new()?;
+
+    let candidates: Vec<PathBuf> = if cfg!(windows) {
+        vec![
+            PathBuf::from(r"C:\Program Files (x86)\Steam"),
+            PathBuf::from(r"C:\Program Files\Steam"),
+        ]
+    } else {
+        let home = base_dirs.home_dir();
+        vec![
+            home.join(".steam").join("steam"),
+            home.join(".local").join("share").join("Steam"),
+            home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+        ]
+    };
+
+    candidates.into_iter().find(|p| p.join("userdata").is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{ExecutableInfo, LaunchProfile};
+
+    fn game() -> DownloadedGame {
+        DownloadedGame {
+            title: "Hollow Knight".into(),
+            details: "Genres/Tags: Action, Metroidvania\nOriginal Size: 9 GB".into(),
+            executable_info: ExecutableInfo {
+                executable_path: "/games/hk/hollow_knight.exe".into(),
+                ..Default::default()
+            },
+            launch_profile: LaunchProfile::default(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn builds_shortcut_with_tags() {
+        let shortcut = shortcut_for(&game()).unwrap();
+        let map = shortcut.as_map().unwrap();
+
+        assert_eq!(
+            vdf::get(map, "Exe").and_then(VdfValue::as_str),
+            Some("\"/games/hk/hollow_knight.exe\"")
+        );
+        assert_eq!(
+            vdf::get(map, "StartDir").and_then(VdfValue::as_str),
+            Some("\"/games/hk\"")
+        );
+        let tags = vdf::get(map, "tags").and_then(VdfValue::as_map).unwrap();
+        assert_eq!(tags.len(), 2);
+        assert!(shortcut_app_id(&shortcut) & 0x8000_0000 != 0);
+    }
+
+    #[test]
+    fn merge_keeps_steam_fields() {
+        let shortcut = shortcut_for(&game()).unwrap();
+        let mut existing = shortcut.clone();
+        if let VdfValue::Map(m) = &mut existing {
+            vdf::set(m, "LastPlayTime", VdfValue::Int32(1_700_000_000));
+            vdf::set(m, "LaunchOptions", VdfValue::String("-old".into()));
+        }
+        let mut entries = vec![existing];
+
+        assert!(merge_shortcut(&mut entries, &shortcut));
+        let map = entries[0].as_map().unwrap();
+        assert_eq!(
+            vdf::get(map, "LastPlayTime").and_then(VdfValue::as_i32),
+            Some(1_700_000_000)
+        );
+        assert_eq!(
+            vdf::get(map, "LaunchOptions").and_then(VdfValue::as_str),
+            Some("")
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Reader/writer for Steam's binary VDF (`shortcuts.vdf`).
+//!
+//! Layout: every entry is `<type byte><key>\0<value>`, maps end with `0x08`.
+//! Key order is preserved so that untouched entries are written back byte-for-byte.
+
+use std::fmt;
+
+const TYPE_MAP: u8 = 0x00;
+const TYPE_STRING: u8 = 0x01;
+const TYPE_INT32: u8 = 0x02;
+const TYPE_FLOAT32: u8 = 0x03;
+const TYPE_UINT64: u8 = 0x07;
+const TYPE_MAP_END: u8 = 0x08;
+
+pub type VdfMap = Vec<(String, VdfValue)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VdfValue {
+    Map(VdfMap),
+    String(String),
+    Int32(i32),
+    Float32(f32),
+    UInt64(u64),
+}
+
+impl VdfValue {
+    pub fn as_map(&self) -> Option<&VdfMap> {
+        match self {
+            VdfValue::Map(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            VdfValue::Int32(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum VdfError {
+    UnexpectedEof,
+    UnknownType(u8, usize),
+    InvalidUtf8(usize),
+}
+
+impl fmt::Display for VdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VdfError::UnexpectedEof => write!(f, "Unexpected end of VDF data"),
+            VdfError::UnknownType(t, at) => write!(f, "Unknown VDF type {t:#04x} at {at}"),
+            VdfError::InvalidUtf8(at) => write!(f, "Invalid UTF-8 string at {at}"),
+        }
+    }
+}
+
+impl std::error::Error for VdfError {}
+
+/// Get a value from a map by key, case-insensitively as Steam does
+pub fn get<'a>(map: &'a VdfMap, key: &str) -> Option<&'a VdfValue> {
+    map.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+/// Insert or replace `key` in `map`, keeping the original key spelling and position
+pub fn set(map: &mut VdfMap, key: &str, value: VdfValue) {
+    match map.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+        Some((_, v)) => *v = value,
+        None => map.push((key.to_string(), value)),
+    }
+}
+
+/// Parse a whole binary VDF document (the root map, without its end marker).
+pub fn read(data: &[u8]) -> Result<VdfMap, VdfError> {
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut reader = Reader { data, pos: 0 };
+    reader.read_map()
+}
+
+/// Serialize a root map to binary VDF.
+pub fn write(root: &VdfMap) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_map(&mut out, root);
+    out
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn read_map(&mut self) -> Result<VdfMap, VdfError> {
+        let mut map = Vec::new();
+
+        loop {
+            let ty = self.byte()?;
+            if ty == TYPE_MAP_END {
+                return Ok(map);
+            }
+
+            let type_pos = self.pos - 1;
+            let key = self.cstring()?;
+            let value = match ty {
+                TYPE_MAP => VdfValue::Map(self.read_map()?),
+                TYPE_STRING => VdfValue::String(self.cstring()?),
+                TYPE_INT32 => VdfValue::Int32(i32::from_le_bytes(self.array()?)),
+                TYPE_FLOAT32 => VdfValue::Float32(f32::from_le_bytes(self.array()?)),
+                TYPE_UINT64 => VdfValue::UInt64(u64::from_le_bytes(self.array()?)),
+                other => return Err(VdfError::UnknownType(other, type_pos)),
+            };
+            map.push((key, value));
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, VdfError> {
+        let b = *self.data.get(self.pos).ok_or(VdfError::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], VdfError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + N)
+            .ok_or(VdfError::UnexpectedEof)?;
+        self.pos += N;
+        Ok(bytes.try_into().expect("slice has N bytes"))
+    }
+
+    fn cstring(&mut self) -> Result<String, VdfError> {
+        let start = self.pos;
+        let len = self.data[start..]
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(VdfError::UnexpectedEof)?;
+        self.pos += len + 1;
+
+        String::from_utf8(self.data[start..start + len].to_vec())
+            .map_err(|_| VdfError::InvalidUtf8(start))
+    }
+}
+
+fn write_map(out: &mut Vec<u8>, map: &VdfMap) {
+    for (key, value) in map {
+        let ty = match value {
+            VdfValue::Map(_) => TYPE_MAP,
+            VdfValue::String(_) => TYPE_STRING,
+            VdfValue::Int32(_) => TYPE_INT32,
+            VdfValue::Float32(_) => TYPE_FLOAT32,
+            VdfValue::UInt64(_) => TYPE_UINT64,
+        };
+        out.push(ty);
+        write_cstring(out, key);
+
+        match value {
+            VdfValue::Map(m) => write_map(out, m),
+            VdfValue::String(s) => write_cstring(out, s),
+            VdfValue::Int32(i) => out.extend_from_slice(&i.to_le_bytes()),
+            VdfValue::Float32(f) => out.extend_from_slice(&f.to_le_bytes()),
+            VdfValue::UInt64(u) => out.extend_from_slice(&u.to_le_bytes()),
+        }
+    }
+    out.push(TYPE_MAP_END);
+}
+
+fn write_cstring(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> VdfMap {
+        vec![(
+            "shortcuts".into(),
+            VdfValue::Map(vec![(
+                "0".into(),
+                VdfValue::Map(vec![
+                    ("appid".into(), VdfValue::Int32(-1_234_567_890)),
+                    ("AppName".into(), VdfValue::String("Hollow Knight".into())),
+                    (
+                        "Exe".into(),
+                        VdfValue::String("\"/games/hk/hollow_knight.exe\"".into()),
+                    ),
+                    ("LastPlayTime".into(), VdfValue::Int32(0)),
+                    ("Volume".into(), VdfValue::Float32(0.5)),
+                    ("SteamID".into(), VdfValue::UInt64(76561197960287930)),
+                    (
+                        "tags".into(),
+                        VdfValue::Map(vec![("0".into(), VdfValue::String("Metroidvania".into()))]),
+                    ),
+                ]),
+            )]),
+        )]
+    }
+
+    #[test]
+    fn round_trip() {
+        let root = sample();
+        let bytes = write(&root);
+        assert_eq!(read(&bytes).unwrap(), root);
+        // writing the parsed document again is byte-identical
+        assert_eq!(write(&read(&bytes).unwrap()), bytes);
+    }
+
+    #[test]
+    fn matches_steam_layout() {
+        let root = vec![(
+            "shortcuts".into(),
+            VdfValue::Map(vec![(
+                "0".into(),
+                VdfValue::Map(vec![("appid".into(), VdfValue::Int32(1))]),
+            )]),
+        )];
+
+        let mut expected = b"\x00shortcuts\x00\x000\x00\x02appid\x00".to_vec();
+        expected.extend_from_slice(&1i32.to_le_bytes());
+        expected.extend_from_slice(b"\x08\x08\x08");
+
+        assert_eq!(write(&root), expected);
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let bytes = write(&sample());
+        assert_eq!(
+            read(&bytes[..bytes.len() - 1]),
+            Err(VdfError::UnexpectedEof)
+        );
+        assert!(matches!(
+            read(b"\x05bogus\x00"),
+            Err(VdfError::UnknownType(0x05, 0))
+        ));
+    }
+}
@@ -1,7 +1,11 @@
+use chrono::{DateTime, NaiveDate};
 use fit_launcher_scraping::structs::Game;
 use serde::de::{Deserializer, Error as SerdeError};
 use serde::{Deserialize, Serialize};
 use specta::Type;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io};
 
 #[derive(Default, Clone, Serialize, Deserialize, Debug, Type)]
 #[serde(default)]
@@ -24,6 +28,8 @@ pub struct DownloadedGame {
 
     pub executable_info: ExecutableInfo,
     pub installation_info: InstallationInfo,
+    /// How the game executable is started (mostly relevant on Linux)
+    pub launch_profile: LaunchProfile,
 }
 
 #[derive(Default, Serialize, Deserialize, Debug, Clone, Type)]
@@ -36,6 +42,84 @@ pub struct ExecutableInfo {
     pub executable_disk_size: u64,
 }
 
+impl From<Game> for DownloadedGame {
+    fn from(game: Game) -> Self {
+        DownloadedGame {
+            title: game.title,
+            img: game.img,
+            details: game.details,
+            features: game.features,
+            description: game.description,
+            gameplay_features: game.gameplay_features,
+            included_dlcs: game.included_dlcs,
+            magnetlink: game.magnetlink,
+            href: game.href,
+            tag: game.tag,
+            ..Default::default()
+        }
+    }
+}
+
+impl ExecutableInfo {
+    /// Build the info of an installed executable, `folder` being the install folder.
+    pub fn discover(path_to_exe: &str, folder: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path_to_exe).ok()?;
+
+        let executable_disk_size = dir_size(folder).unwrap_or(0);
+
+        fn system_time_to_naive_date(system_time: SystemTime) -> Option<NaiveDate> {
+            let duration_since_epoch = system_time.duration_since(UNIX_EPOCH).ok()?;
+            DateTime::from_timestamp(duration_since_epoch.as_secs() as i64, 0)
+                .map(|naive_date_time| naive_date_time.date_naive())
+        }
+
+        // Get the installed date (creation time or fallback to modified time)
+        let executable_installed_date = Some(
+            metadata
+                .created()
+                .ok()
+                .and_then(system_time_to_naive_date)
+                .or_else(|| metadata.modified().ok().and_then(system_time_to_naive_date))
+                .unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap())
+                .to_string(),
+        );
+
+        // Get the last opened date (accessed time)
+        let executable_last_opened_date = Some(
+            metadata
+                .accessed()
+                .ok()
+                .and_then(system_time_to_naive_date)
+                .unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap())
+                .to_string(),
+        );
+
+        Some(ExecutableInfo {
+            executable_path: path_to_exe.to_string(),
+            executable_last_opened_date,
+            // can't be determined from metadata
+            executable_play_time: 0,
+            executable_installed_date,
+            executable_disk_size,
+        })
+    }
+}
+
+fn dir_size(path: &Path) -> io::Result<u64> {
+    fn dir_size(mut dir: fs::ReadDir) -> io::Result<u64> {
+        dir.try_fold(0, |acc, file| {
+            let file = file?;
+            let size = match file.metadata()? {
+                data if data.is_dir() => dir_size(fs::read_dir(file.path())?)?,
+                data => data.len(),
+            };
+            Ok(acc + size)
+        })
+    }
+
+    dir_size(fs::read_dir(path)?)
+}
+
 fn deserialize_play_time<'de, D>(deserializer: D) -> Result<u64, D::Error>
 where
     D: Deserializer<'de>,
@@ -57,6 +141,32 @@ pub struct InstallationInfo {
     pub file_list: Vec<String>,
 }
 
+#[derive(Default, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LaunchRunner {
+    /// Execute the executable directly
+    #[default]
+    Native,
+    /// Run through Wine, `binary` defaults to `wine` from `PATH`
+    Wine { binary: Option<String> },
+    /// Custom command line, e.g. `protonrun {exe} {args}`
+    ///
+    /// Placeholders: `{exe}`, `{args}`, `{prefix}`, `{dir}`
+    Custom { template: String },
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Type)]
+#[serde(default)]
+pub struct LaunchProfile {
+    pub runner: LaunchRunner,
+    /// Wine prefix, exported as `WINEPREFIX` when set
+    pub prefix: Option<String>,
+    pub env: Vec<(String, String)>,
+    pub args: Vec<String>,
+    /// Defaults to the executable's folder
+    pub working_dir: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Type)]
 pub struct GameCollection {
     pub name: String,
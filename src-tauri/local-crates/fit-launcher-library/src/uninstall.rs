@@ -0,0 +1,581 @@
+//! Coordinated uninstall of a downloaded game.
+//!
+//! The uninstall is always planned first, the plan is what a dry run returns
+//! and exactly what a real run deletes.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use directories::{BaseDirs, UserDirs};
+use fit_launcher_ui_automation::api::types::INSTALL_MARKER;
+use serde::{Deserialize, Serialize};
+use specta::{Type, specta};
+use tracing::{error, info, warn};
+
+use crate::{
+    commands::{
+        get_collection_list, get_downloaded_games, remove_downloaded_game,
+        remove_game_from_collection,
+    },
+    structs::DownloadedGame,
+};
+
+/// Folder names (lowercase) treated as save folders when `keep_saves` is set
+const SAVE_DIR_NAMES: &[&str] = &[
+    "save",
+    "saves",
+    "savegame",
+    "savegames",
+    "savedgames",
+    "saved",
+    "savedata",
+    "userdata",
+    "profiles",
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct UninstallOptions {
+    /// Only compute what would be deleted
+    pub dry_run: bool,
+    /// Leave save folders found in the install folder untouched
+    pub keep_saves: bool,
+    /// Also delete the download folder (archives, `.bin` parts, setup)
+    pub remove_download_leftovers: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct UninstallPlan {
+    pub game_title: String,
+    /// Files to delete, in deletion order
+    pub files: Vec<String>,
+    /// Directories to delete once emptied, deepest first
+    pub directories: Vec<String>,
+    /// Paths kept because they look like save folders
+    pub kept: Vec<String>,
+    /// Download folder removed as a whole
+    pub download_leftovers: Vec<String>,
+    /// Defender exclusions recorded for the install/download folders
+    pub defender_exclusions: Vec<String>,
+    /// Collections the game will be removed from
+    pub collections: Vec<String>,
+    pub total_bytes: u64,
+    /// Install folder left for the user to delete, it has no file list and
+    /// no [`INSTALL_MARKER`] so it may hold more than this game
+    pub manual: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct UninstallReport {
+    pub plan: UninstallPlan,
+    pub dry_run: bool,
+    /// Paths (or steps) that could not be removed, with the reason
+    pub failures: Vec<String>,
+}
+
+/// Uninstall a downloaded game, or list what would be removed with `dry_run`.
+#[tauri::command]
+#[specta]
+pub async fn uninstall_game(
+    game_href: String,
+    options: UninstallOptions,
+) -> Result<UninstallReport, String> {
+    let games = get_downloaded_games().await;
+    let game = games
+        .into_iter()
+        .find(|g| g.href == game_href)
+        .ok_or_else(|| format!("Game '{game_href}' is not in the library"))?;
+
+    let collections = get_collection_list().await;
+    let dry_run = options.dry_run;
+    let plan = {
+        let game = game.clone();
+        tauri::async_runtime::spawn_blocking(move || plan_uninstall(&game, &collections, &options))
+            .await
+            .map_err(|e| e.to_string())??
+    };
+
+    if dry_run {
+        return Ok(UninstallReport {
+            plan,
+            dry_run: true,
+            failures: vec![],
+        });
+    }
+
+    let mut failures = {
+        let plan = plan.clone();
+        tauri::async_runtime::spawn_blocking(move || execute_plan(&plan))
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    #[cfg(windows)]
+    for path in &plan.defender_exclusions {
+        use fit_launcher_ui_automation::defender::ExclusionAction;
+
+        if let Err(e) =
+            fit_launcher_ui_automation::folder_exclusion(ExclusionAction::Remove(path.clone()))
+                .await
+        {
+            failures.push(format!("Defender exclusion {path}: {e}"));
+        }
+    }
+
+    if let Err(e) = remove_from_library(&game.title, &plan.collections).await {
+        failures.push(format!("Library update: {e}"));
+    }
+
+    info!(
+        "Uninstalled {} ({} files, {} failures)",
+        plan.game_title,
+        plan.files.len(),
+        failures.len()
+    );
+
+    Ok(UninstallReport {
+        plan,
+        dry_run: false,
+        failures,
+    })
+}
+
+fn plan_uninstall(
+    game: &DownloadedGame,
+    collections: &[crate::structs::GameCollection],
+    options: &UninstallOptions,
+) -> Result<UninstallPlan, String> {
+    let info = &game.installation_info;
+    let mut plan = UninstallPlan {
+        game_title: game.title.clone(),
+        ..Default::default()
+    };
+
+    let output = PathBuf::from(&info.output_folder);
+    if !info.output_folder.is_empty() && output.exists() {
+        if is_protected_dir(&output) {
+            return Err(format!(
+                "Refusing to uninstall from protected folder {}",
+                output.display()
+            ));
+        }
+
+        if info.file_list.is_empty() {
+            if !output.join(INSTALL_MARKER).is_file() {
+                warn!(
+                    "{} was not created by an install, leaving it in place",
+                    output.display()
+                );
+                plan.manual = Some(output.to_string_lossy().to_string());
+            } else if !plan_tree(&output, options.keep_saves, &mut plan) {
+                plan.directories.push(output.to_string_lossy().to_string());
+            }
+        } else {
+            plan_file_list(&output, &info.file_list, options.keep_saves, &mut plan);
+        }
+    }
+
+    let download = PathBuf::from(&info.download_folder);
+    if options.remove_download_leftovers
+        && !info.download_folder.is_empty()
+        && download.is_dir()
+        && !is_protected_dir(&download)
+        && !output.starts_with(&download)
+        && !download.starts_with(&output)
+    {
+        plan.total_bytes += dir_size(&download);
+        plan.download_leftovers
+            .push(download.to_string_lossy().to_string());
+    }
+
+    plan.defender_exclusions = recorded_exclusions(&[&output, &download]);
+
+    plan.collections = collections
+        .iter()
+        .filter(|c| c.games_list.iter().any(|g| g.href == game.href))
+        .map(|c| c.name.clone())
+        .collect();
+
+    Ok(plan)
+}
+
+/// Walk `dir`, adding files and emptied directories to the plan.
+///
+/// Returns `true` if anything inside was kept, in which case `dir` itself must stay.
+fn plan_tree(dir: &Path, keep_saves: bool, plan: &mut UninstallPlan) -> bool {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Cannot read {}: {e}", dir.display());
+            return true;
+        }
+    };
+
+    let mut kept_any = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            kept_any = true;
+            continue;
+        };
+
+        if file_type.is_dir() {
+            if keep_saves && is_save_dir(&path) {
+                plan.kept.push(path.to_string_lossy().to_string());
+                kept_any = true;
+                continue;
+            }
+
+            if plan_tree(&path, keep_saves, plan) {
+                kept_any = true;
+            } else {
+                plan.directories.push(path.to_string_lossy().to_string());
+            }
+        } else {
+            plan.total_bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            plan.files.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    kept_any
+}
+
+/// Plan removal of the files recorded at install time, plus their emptied parents.
+fn plan_file_list(output: &Path, file_list: &[String], keep_saves: bool, plan: &mut UninstallPlan) {
+    let mut parents: Vec<PathBuf> = Vec::new();
+
+    for file in file_list {
+        let path = if Path::new(file).is_absolute() {
+            PathBuf::from(file)
+        } else {
+            output.join(file)
+        };
+
+        // never follow a file list outside of the install folder
+        if !path.starts_with(output) || !path.is_file() {
+            continue;
+        }
+
+        if keep_saves && path.ancestors().skip(1).any(is_save_dir) {
+            plan.kept.push(path.to_string_lossy().to_string());
+            continue;
+        }
+
+        plan.total_bytes += fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        plan.files.push(path.to_string_lossy().to_string());
+
+        for parent in path.ancestors().skip(1) {
+            if !parent.starts_with(output) {
+                break;
+            }
+            if !parents.iter().any(|p| p == parent) {
+                parents.push(parent.to_path_buf());
+            }
+        }
+    }
+
+    // deepest first, so that `remove_dir` only ever sees emptied folders
+    parents.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
+    plan.directories = parents
+        .into_iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+}
+
+fn execute_plan(plan: &UninstallPlan) -> Vec<String> {
+    let mut failures = Vec::new();
+
+    for file in &plan.files {
+        if let Err(e) = fs::remove_file(file) {
+            failures.push(format!("{file}: {e}"));
+        }
+    }
+
+    for dir in &plan.directories {
+        // only remove emptied folders, anything left over was not planned
+        if let Err(e) = fs::remove_dir(dir)
+            && Path::new(dir).exists()
+        {
+            warn!("Keeping non-empty folder {dir}: {e}");
+        }
+    }
+
+    for dir in &plan.download_leftovers {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            failures.push(format!("{dir}: {e}"));
+        }
+    }
+
+    failures
+}
+
+/// Drop the game from the library and from the collections listing it.
+///
+/// Goes through the regular library commands, so that a legacy or unreadable
+/// `downloaded_games.json` is never overwritten.
+async fn remove_from_library(game_title: &str, collections: &[String]) -> Result<(), String> {
+    remove_downloaded_game(game_title.to_string()).await?;
+
+    for name in collections {
+        if let Err(e) = remove_game_from_collection(game_title.to_string(), name.clone()).await {
+            error!("Failed to update collection {name}: {e}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn recorded_exclusions(folders: &[&Path]) -> Vec<String> {
+    use fit_launcher_ui_automation::defender::{exclusions_file_path, load_exclusions};
+
+    let Some(path) = exclusions_file_path() else {
+        return vec![];
+    };
+
+    load_exclusions(&path)
+        .entries
+        .into_iter()
+        .map(|e| e.path)
+        .filter(|p| {
+            let p = Path::new(p);
+            folders
+                .iter()
+                .any(|f| !f.as_os_str().is_empty() && p.starts_with(f))
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn recorded_exclusions(_folders: &[&Path]) -> Vec<String> {
+    vec![]
+}
+
+fn is_save_dir(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.to_lowercase())
+        .is_some_and(|n| SAVE_DIR_NAMES.contains(&n.as_str()))
+}
+
+/// Folder names (lowercase) of shared install locations, never uninstalled
+const PROTECTED_DIR_NAMES: &[&str] = &["program files", "program files (x86)"];
+
+/// Drive roots, `Program Files` and the user's home, config, Downloads and
+/// Desktop folders are never uninstalled
+fn is_protected_dir(path: &Path) -> bool {
+    if path.parent().is_none() {
+        return true;
+    }
+
+    if path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| PROTECTED_DIR_NAMES.contains(&n.to_lowercase().as_str()))
+    {
+        return true;
+    }
+
+    let Some(base_dirs) = BaseDirs::new() else {
+        return true;
+    };
+    let user_dirs = UserDirs::new();
+
+    [
+        Some(base_dirs.home_dir()),
+        Some(base_dirs.config_dir()),
+        Some(base_dirs.data_dir()),
+        Some(base_dirs.data_local_dir()),
+        user_dirs.as_ref().and_then(UserDirs::download_dir),
+        user_dirs.as_ref().and_then(UserDirs::desktop_dir),
+    ]
+    .contains(&Some(path))
+}
+
+fn dir_size(path: &Path) -> u64 {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| match e.file_type() {
+                    Ok(t) if t.is_dir() => dir_size(&e.path()),
+                    _ => e.metadata().map(|m| m.len()).unwrap_or(0),
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use fit_launcher_scraping::structs::Game;
+
+    use super::*;
+    use crate::structs::{GameCollection, InstallationInfo};
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+            _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn file(&self, rel: &str, len: usize) -> PathBuf {
+            let path = self.0.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, vec![0u8; len]).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn game(output: &Path, download: &Path, file_list: Vec<String>) -> DownloadedGame {
+        DownloadedGame {
+            title: "Hollow Knight".into(),
+            href: "https://fitgirl-repacks.site/hollow-knight/".into(),
+            installation_info: InstallationInfo {
+                output_folder: output.to_string_lossy().to_string(),
+                download_folder: download.to_string_lossy().to_string(),
+                file_list,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn strings(paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn plans_whole_tree_and_keeps_saves() {
+        let tmp = TempDir::new("fit-uninstall-tree");
+        let output = tmp.0.join("game");
+        let marker = tmp.file(&format!("game/{INSTALL_MARKER}"), 0);
+        let exe = tmp.file("game/hk.exe", 10);
+        let data = tmp.file("game/data/level.pak", 20);
+        let save = tmp.file("game/Saves/slot1.dat", 5);
+
+        let options = UninstallOptions {
+            keep_saves: true,
+            ..Default::default()
+        };
+        let plan =
+            plan_uninstall(&game(&output, &tmp.0.join("dl"), vec![]), &[], &options).unwrap();
+
+        let mut files = plan.files.clone();
+        files.sort();
+        let mut expected = strings(&[marker, exe, data]);
+        expected.sort();
+        assert_eq!(files, expected);
+        assert_eq!(plan.total_bytes, 30);
+        assert_eq!(plan.kept, strings(&[save.parent().unwrap().to_path_buf()]));
+        // the install folder holds a kept save folder, so only `data` goes away
+        assert_eq!(plan.directories, strings(&[output.join("data")]));
+    }
+
+    #[test]
+    fn file_list_stays_inside_install_folder() {
+        let tmp = TempDir::new("fit-uninstall-list");
+        let output = tmp.0.join("game");
+        let exe = tmp.file("game/bin/hk.exe", 10);
+        let outside = tmp.file("other/keep.txt", 10);
+        tmp.file("game/user_added.txt", 10);
+
+        let file_list = vec![
+            "bin/hk.exe".to_string(),
+            outside.to_string_lossy().to_string(),
+            "missing.dll".to_string(),
+        ];
+        let plan = plan_uninstall(
+            &game(&output, &tmp.0.join("dl"), file_list),
+            &[],
+            &UninstallOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(plan.files, strings(&[exe]));
+        assert_eq!(
+            plan.directories,
+            strings(&[output.join("bin"), output.clone()])
+        );
+        assert!(plan.download_leftovers.is_empty());
+    }
+
+    #[test]
+    fn plans_download_leftovers_and_collections() {
+        let tmp = TempDir::new("fit-uninstall-leftovers");
+        let output = tmp.0.join("game");
+        let download = tmp.0.join("dl");
+        tmp.file(&format!("game/{INSTALL_MARKER}"), 0);
+        tmp.file("game/hk.exe", 10);
+        tmp.file("dl/fg-01.bin", 100);
+
+        let target = game(&output, &download, vec![]);
+        let collections = vec![
+            GameCollection {
+                name: "Favourites".into(),
+                games_list: vec![Game {
+                    href: target.href.clone(),
+                    ..Default::default()
+                }],
+            },
+            GameCollection {
+                name: "Later".into(),
+                games_list: vec![],
+            },
+        ];
+        let options = UninstallOptions {
+            remove_download_leftovers: true,
+            ..Default::default()
+        };
+
+        let plan = plan_uninstall(&target, &collections, &options).unwrap();
+        assert_eq!(plan.download_leftovers, strings(&[download]));
+        assert_eq!(plan.total_bytes, 110);
+        assert_eq!(plan.collections, vec!["Favourites".to_string()]);
+    }
+
+    #[test]
+    fn unmarked_folders_are_left_to_the_user() {
+        let tmp = TempDir::new("fit-uninstall-shared");
+        let shared = tmp.0.join("Games");
+        tmp.file("Games/hk/hk.exe", 10);
+        tmp.file("Games/other/other.exe", 10);
+
+        let plan = plan_uninstall(
+            &game(&shared, &tmp.0.join("dl"), vec![]),
+            &[],
+            &UninstallOptions::default(),
+        )
+        .unwrap();
+
+        assert!(plan.files.is_empty());
+        assert!(plan.directories.is_empty());
+        assert_eq!(plan.manual, Some(shared.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn refuses_protected_folders() {
+        let root = if cfg!(windows) { "C:\\" } else { "/" };
+        let target = game(Path::new(root), Path::new(""), vec![]);
+        assert!(plan_uninstall(&target, &[], &UninstallOptions::default()).is_err());
+
+        let tmp = TempDir::new("fit-uninstall-program-files");
+        let program_files = tmp.0.join("Program Files");
+        tmp.file(&format!("Program Files/{INSTALL_MARKER}"), 0);
+        let target = game(&program_files, Path::new(""), vec![]);
+        assert!(plan_uninstall(&target, &[], &UninstallOptions::default()).is_err());
+    }
+}
@@ -1,30 +1,42 @@
 //! DDOS-Guard captcha bypass handling.
 
 use fit_launcher_config::client::{
-    cookies::{Cookie, Cookies},
-    dns::{CUSTOM_DNS_CLIENT, build_dns_client},
+    connectivity,
+    cookies::{COOKIE_JAR, Cookie, DDOS_GUARD_PREFIX},
+    site,
 };
 use std::sync::LazyLock;
-use tauri::{Listener, Url, WindowEvent};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Listener, Url, WindowEvent};
 use time::format_description::well_known::Rfc2822;
 use tokio::sync::Mutex;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::errors::ScrapingError;
-
-async fn update_client_cookies(new_cookies: Vec<Cookie>) {
-    Cookies(new_cookies).save().unwrap();
-    *CUSTOM_DNS_CLIENT.write().await = build_dns_client();
+use crate::scraping::fetch_page;
+
+/// A challenge solved this recently is not shown again to the requests that
+/// waited for it, they retry with the new cookies
+const RECENTLY_SOLVED: Duration = Duration::from_secs(30);
+/// How often the expiry of the DDoS-Guard cookies is looked at
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// The client reads the jar on every request, nothing to rebuild
+fn update_client_cookies(new_cookies: Vec<Cookie>) -> Result<(), ScrapingError> {
+    COOKIE_JAR
+        .store(new_cookies)
+        .map_err(|e| ScrapingError::CookieError(e.to_string()))
 }
 
 pub async fn handle_ddos_guard_captcha(
     app: &tauri::AppHandle,
     url: &str,
 ) -> Result<(), ScrapingError> {
-    static COOKIES_UPDATED: LazyLock<Mutex<bool>> = LazyLock::new(|| Mutex::new(false));
+    // one challenge at a time, whatever the number of guarded requests
+    static LAST_SOLVED: LazyLock<Mutex<Option<Instant>>> = LazyLock::new(|| Mutex::new(None));
 
-    let mut cookies_updated = COOKIES_UPDATED.lock().await;
-    if *cookies_updated {
+    let mut last_solved = LAST_SOLVED.lock().await;
+    if last_solved.is_some_and(|at| at.elapsed() < RECENTLY_SOLVED) {
         return Ok(());
     }
 
@@ -88,7 +100,7 @@ pub async fn handle_ddos_guard_captcha(
     let captured_cookies = all_cookies
         .into_iter()
         .filter(|cookie| {
-            if !cookie.name().contains("__ddg") {
+            if !cookie.name().starts_with(DDOS_GUARD_PREFIX) {
                 return false;
             }
 
@@ -102,21 +114,45 @@ pub async fn handle_ddos_guard_captcha(
             value: c.value().to_string(),
             domain: c.domain().map(|d| d.to_string()),
             path: c.path().map(|p| p.to_string()),
-            expires: Some(
-                c.expires()
-                    .unwrap()
-                    .datetime()
-                    .unwrap()
-                    .format(&Rfc2822)
-                    .unwrap(),
-            ),
+            // session cookies have no expiry
+            expires: c
+                .expires()
+                .and_then(|e| e.datetime())
+                .and_then(|d| d.format(&Rfc2822).ok()),
             max_age: c.max_age().map(|d| d.whole_seconds()),
+            secure: c.secure().unwrap_or(false),
         })
         .collect();
 
     info!("updating cookies...");
-    update_client_cookies(captured_cookies).await;
-    *cookies_updated = true;
+    update_client_cookies(captured_cookies)?;
+    *last_solved = Some(Instant::now());
 
     Ok(())
 }
+
+/// Runs the challenge again once the DDoS-Guard cookies expired, rather than
+/// in the middle of a later request. Fetching the site is enough, a guarded
+/// answer goes through [`handle_ddos_guard_captcha`].
+pub async fn watch_ddos_guard_cookies(app: AppHandle) {
+    loop {
+        let Some(expires_in) = COOKIE_JAR.ddos_guard_expires_in() else {
+            tokio::time::sleep(EXPIRY_CHECK_INTERVAL).await;
+            continue;
+        };
+        if expires_in > EXPIRY_CHECK_INTERVAL {
+            // the cookies may be replaced by then
+            tokio::time::sleep(EXPIRY_CHECK_INTERVAL).await;
+            continue;
+        }
+        tokio::time::sleep(expires_in + Duration::from_secs(1)).await;
+
+        if connectivity::is_site_unavailable() {
+            continue;
+        }
+        info!("DDoS-Guard cookies expired, checking the site");
+        if let Err(e) = fetch_page(&site::active_origin(), &app).await {
+            warn!("DDoS-Guard check failed: {e}");
+        }
+    }
+}
@@ -4,15 +4,17 @@
 
 use futures::{StreamExt as _, stream};
 use specta::specta;
-use std::time::Instant;
-use tauri::AppHandle;
+use std::{sync::Arc, time::Instant};
+use tauri::{AppHandle, State};
 use tracing::info;
 
-use crate::db::{self, SearchIndexEntry};
-use crate::discovery::try_high_res_img;
+use crate::crawler::{CatalogCrawler, CrawlerProgress};
+use crate::db::{self, GameRevision, Recommendation, SearchIndexEntry};
+use crate::discovery::{read_meta_ts, try_high_res_img};
 use crate::errors::ScrapingError;
+use crate::health::{self, ParserHealth};
 use crate::parser::parse_game_from_article;
-use crate::structs::Game;
+use crate::structs::{DataFreshness, Game};
 
 // ============================================================================
 // Game Data Commands
@@ -55,6 +57,14 @@ pub fn get_discovery_games(app: AppHandle) -> Result<Vec<Game>, ScrapingError> {
     db::get_games_by_category(&conn, "discovery")
 }
 
+/// The discovery feed with why each game is in it, best first
+#[tauri::command]
+#[specta]
+pub fn get_recommendations(app: AppHandle) -> Result<Vec<Recommendation>, ScrapingError> {
+    let conn = db::open_connection(&app)?;
+    db::get_recommendations(&conn)
+}
+
 #[tauri::command]
 #[specta]
 pub fn get_singular_game_local(app: AppHandle, url: &str) -> Result<Game, ScrapingError> {
@@ -70,6 +80,36 @@ pub fn get_singular_game_local(app: AppHandle, url: &str) -> Result<Game, Scrapi
     }
 }
 
+/// When the local data was last synced, to show how stale it is offline
+#[tauri::command]
+#[specta]
+pub fn get_data_freshness(
+    app: AppHandle,
+    game_url: Option<String>,
+) -> Result<DataFreshness, ScrapingError> {
+    let conn = db::open_connection(&app)?;
+    let game = match game_url {
+        Some(url) => db::get_game_scraped_at(&conn, db::hash_url(&url))?,
+        None => None,
+    };
+    Ok(DataFreshness {
+        newly_added: db::synced_at(&conn, "newly_added")?,
+        popular: db::synced_at(&conn, "popular")?,
+        recently_updated: db::synced_at(&conn, "recently_updated")?,
+        discovery: read_meta_ts(&conn).map(|ts| ts.timestamp()),
+        sitemaps: db::synced_at(&conn, "sitemaps")?,
+        game,
+    })
+}
+
+/// Past repacks of a game, newest first, with what changed in each one
+#[tauri::command]
+#[specta]
+pub fn get_game_revisions(app: AppHandle, url: String) -> Result<Vec<GameRevision>, ScrapingError> {
+    let conn = db::open_connection(&app)?;
+    db::get_game_revisions(&conn, db::hash_url(&url))
+}
+
 // ============================================================================
 // Scraping Commands
 // ============================================================================
@@ -88,7 +128,7 @@ pub async fn get_singular_game_info(
     app: tauri::AppHandle,
     game_link: String,
 ) -> Result<(), ScrapingError> {
-    use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
+    use fit_launcher_config::client::{connectivity, dns::CUSTOM_DNS_CLIENT, site};
     use scraper::Html;
 
     let start_time = Instant::now();
@@ -97,6 +137,14 @@ pub async fn get_singular_game_info(
 
     let conn = db::open_connection(&app)?;
 
+    // Offline, whatever is stored is served however old it is, and kept
+    if connectivity::is_site_unavailable() {
+        return match db::get_game_scraped_at(&conn, url_hash)? {
+            Some(_) => Ok(()),
+            None => Err(ScrapingError::Offline),
+        };
+    }
+
     // Clean up expired cache entries (24 hours)
     const CACHE_EXPIRY_SECS: i64 = 60 * 60 * 24;
     if let Ok(deleted) = db::cleanup_expired_games(&conn, CACHE_EXPIRY_SECS)
@@ -112,11 +160,8 @@ pub async fn get_singular_game_info(
     }
 
     // Fetch fresh data
-    let response = CUSTOM_DNS_CLIENT
-        .read()
-        .await
-        .get(&url)
-        .send()
+    let client = CUSTOM_DNS_CLIENT.read().await.clone();
+    let response = site::get_with_failover(&client, &url)
         .await
         .map_err(|e| ScrapingError::HttpStatusCodeError(e.to_string()))?;
 
@@ -193,3 +238,37 @@ pub async fn query_search_index(
     .await
     .map_err(|e| ScrapingError::IOError(e.to_string()))?
 }
+
+// ============================================================================
+// Catalog Crawler Commands
+// ============================================================================
+
+#[tauri::command]
+#[specta]
+pub fn get_crawler_progress(crawler: State<'_, Arc<CatalogCrawler>>) -> CrawlerProgress {
+    crawler.progress()
+}
+
+#[tauri::command]
+#[specta]
+pub fn pause_crawler(crawler: State<'_, Arc<CatalogCrawler>>) {
+    crawler.pause();
+}
+
+#[tauri::command]
+#[specta]
+pub fn resume_crawler(crawler: State<'_, Arc<CatalogCrawler>>) {
+    crawler.resume();
+}
+
+// ============================================================================
+// Parser Health Commands
+// ============================================================================
+
+/// Empty field rates of the last scrape runs, to spot a site layout change
+#[tauri::command]
+#[specta]
+pub fn get_parser_health(app: AppHandle) -> Result<Vec<ParserHealth>, ScrapingError> {
+    let conn = db::open_connection(&app)?;
+    health::get_parser_health(&conn)
+}
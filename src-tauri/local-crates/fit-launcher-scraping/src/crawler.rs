@@ -0,0 +1,401 @@
+//! Background crawler for the games only known from the sitemaps.
+//!
+//! Sitemap stubs have a slug, title and href, the crawler fetches their
+//! pages one at a time so details, genres and sizes end up in the database
+//! for offline use. The last crawled `url_hash` is checkpointed in the
+//! `metadata` table, so a restart resumes where the previous run stopped,
+//! and so is the end of the last pass, so a restart does not start the
+//! next pass before `PASS_INTERVAL` is over.
+
+use std::sync::{
+    Arc, Mutex, RwLock,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use fit_launcher_config::client::connectivity;
+use fit_launcher_config::commands::get_gamehub_settings;
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+use crate::db;
+use crate::errors::ScrapingError;
+use crate::health::ParseStats;
+use crate::scraping::scrape_game_page;
+
+pub const CRAWLER_PROGRESS_EVENT: &str = "crawler-progress";
+
+const CURSOR_KEY: &str = "crawler_cursor";
+const PASS_COMPLETED_KEY: &str = "crawler_pass_completed";
+
+/// How often a paused or disabled crawler looks at its conditions again
+const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Wait after a full pass before retrying the stubs that failed
+const PASS_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const MAX_BACKOFF: Duration = Duration::from_secs(30 * 60);
+/// Crawled pages per parser health report
+const HEALTH_BATCH: u32 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum PauseReason {
+    User,
+    DownloadsActive,
+    Metered,
+    /// Offline mode, or the site could not be reached
+    Offline,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CrawlerState {
+    #[default]
+    Disabled,
+    Crawling,
+    Paused {
+        reason: PauseReason,
+    },
+    /// Every stub was visited, waiting for the next pass
+    Idle,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CrawlerProgress {
+    pub state: CrawlerState,
+    pub scraped: u32,
+    pub total: u32,
+    /// Pages that failed during the current run
+    pub failed: u32,
+    pub current: Option<String>,
+}
+
+pub struct CatalogCrawler {
+    app: AppHandle,
+    downloads_active: AtomicBool,
+    user_paused: AtomicBool,
+    wake: Notify,
+    progress: RwLock<CrawlerProgress>,
+    parse_stats: ParseStats,
+}
+
+enum Step {
+    Crawled,
+    /// The page is gone or unparsable, skipped until the next pass
+    Skipped,
+    /// The site is unreachable, the cursor did not move
+    Unreachable,
+    PassCompleted,
+    /// The last pass ended recently, the next one starts after this wait
+    Resting(Duration),
+}
+
+impl CatalogCrawler {
+    pub fn new(app: AppHandle) -> Arc<Self> {
+        Arc::new(Self {
+            app,
+            downloads_active: AtomicBool::new(false),
+            user_paused: AtomicBool::new(false),
+            wake: Notify::new(),
+            progress: RwLock::new(CrawlerProgress::default()),
+            parse_stats: ParseStats::default(),
+        })
+    }
+
+    pub fn progress(&self) -> CrawlerProgress {
+        self.progress.read().unwrap().clone()
+    }
+
+    pub fn pause(&self) {
+        self.user_paused.store(true, Ordering::Relaxed);
+        self.wake.notify_one();
+    }
+
+    pub fn resume(&self) {
+        self.user_paused.store(false, Ordering::Relaxed);
+        self.wake.notify_one();
+    }
+
+    /// Kept up to date by the app, the scraping crate knows nothing about downloads
+    pub fn set_downloads_active(&self, active: bool) {
+        if self.downloads_active.swap(active, Ordering::Relaxed) != active {
+            self.wake.notify_one();
+        }
+    }
+
+    pub async fn run(self: Arc<Self>) {
+        info!("Catalog crawler started");
+        let mut backoff = RECHECK_INTERVAL;
+
+        loop {
+            let settings = get_gamehub_settings().catalog_crawler;
+            if !settings.enabled {
+                self.update(|p| {
+                    p.state = CrawlerState::Disabled;
+                    p.current = None;
+                });
+                self.sleep(RECHECK_INTERVAL).await;
+                continue;
+            }
+
+            let pause = if self.user_paused.load(Ordering::Relaxed) {
+                Some(PauseReason::User)
+            } else if connectivity::is_site_unavailable() {
+                Some(PauseReason::Offline)
+            } else if settings.pause_while_downloading
+                && self.downloads_active.load(Ordering::Relaxed)
+            {
+                Some(PauseReason::DownloadsActive)
+            } else if settings.pause_on_metered && is_metered().await {
+                Some(PauseReason::Metered)
+            } else {
+                None
+            };
+            if let Some(reason) = pause {
+                self.update(|p| {
+                    p.state = CrawlerState::Paused { reason };
+                    p.current = None;
+                });
+                self.sleep(RECHECK_INTERVAL).await;
+                continue;
+            }
+
+            match self.step().await {
+                Ok(Step::Crawled | Step::Skipped) => {
+                    backoff = RECHECK_INTERVAL;
+                    let per_minute = settings.pages_per_minute.max(1);
+                    self.sleep(Duration::from_secs(60) / per_minute).await;
+                }
+                Ok(Step::Unreachable) => {
+                    self.update(|p| {
+                        p.state = CrawlerState::Paused {
+                            reason: PauseReason::Offline,
+                        };
+                        p.current = None;
+                    });
+                    self.sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+                Ok(Step::PassCompleted) => {
+                    info!("Catalog crawler finished a pass");
+                    self.update(|p| {
+                        p.state = CrawlerState::Idle;
+                        p.current = None;
+                    });
+                    self.sleep(PASS_INTERVAL).await;
+                }
+                Ok(Step::Resting(wait)) => {
+                    self.update(|p| {
+                        p.state = CrawlerState::Idle;
+                        p.current = None;
+                    });
+                    self.sleep(wait).await;
+                }
+                Err(e) => {
+                    error!("Catalog crawler error: {e}");
+                    self.sleep(RECHECK_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    async fn step(&self) -> Result<Step, ScrapingError> {
+        let cursor = {
+            let conn = db::open_connection(&self.app)?;
+            let cursor = db::get_metadata(&conn, CURSOR_KEY)?
+                .and_then(|c| c.parse().ok())
+                .unwrap_or(i64::MIN);
+            if cursor == i64::MIN {
+                let completed_at =
+                    db::get_metadata(&conn, PASS_COMPLETED_KEY)?.and_then(|at| at.parse().ok());
+                if let Some(wait) = pass_rest(completed_at, now_timestamp()) {
+                    drop(conn);
+                    self.refresh_counts()?;
+                    return Ok(Step::Resting(wait));
+                }
+            }
+            match db::next_unscraped_stub(&conn, cursor)? {
+                Some(stub) => Some(stub),
+                None => {
+                    db::set_metadata(&conn, CURSOR_KEY, &i64::MIN.to_string())?;
+                    db::set_metadata(&conn, PASS_COMPLETED_KEY, &now_timestamp().to_string())?;
+                    None
+                }
+            }
+        };
+        let Some((url_hash, href)) = cursor else {
+            self.refresh_counts()?;
+            return Ok(Step::PassCompleted);
+        };
+
+        self.update(|p| {
+            p.state = CrawlerState::Crawling;
+            p.current = Some(href.clone());
+        });
+
+        let step = match scrape_game_page(&href, &self.app).await {
+            Ok(game) => {
+                self.parse_stats.record(&game);
+                if self.parse_stats.parsed() >= HEALTH_BATCH {
+                    self.parse_stats.report(&self.app, "crawler");
+                }
+                let conn = db::open_connection(&self.app)?;
+                db::upsert_game(&conn, url_hash, &game)?;
+                if let Err(e) = db::refresh_fts_entry(&conn, url_hash) {
+                    warn!("Catalog crawler could not index {href}: {e}");
+                }
+                Step::Crawled
+            }
+            Err(e) if is_transient(&e) => {
+                warn!("Catalog crawler cannot reach {href}: {e}");
+                return Ok(Step::Unreachable);
+            }
+            Err(e) => {
+                warn!("Catalog crawler skipped {href}: {e}");
+                self.update(|p| p.failed += 1);
+                Step::Skipped
+            }
+        };
+
+        let conn = db::open_connection(&self.app)?;
+        db::set_metadata(&conn, CURSOR_KEY, &url_hash.to_string())?;
+        self.refresh_counts()?;
+        Ok(step)
+    }
+
+    fn refresh_counts(&self) -> Result<(), ScrapingError> {
+        let conn = db::open_connection(&self.app)?;
+        let (scraped, total) = db::get_scraped_counts(&conn)?;
+        self.update(|p| {
+            p.scraped = scraped as u32;
+            p.total = total as u32;
+        });
+        Ok(())
+    }
+
+    fn update(&self, f: impl FnOnce(&mut CrawlerProgress)) {
+        let progress = {
+            let mut progress = self.progress.write().unwrap();
+            f(&mut progress);
+            progress.clone()
+        };
+        let _ = self.app.emit(CRAWLER_PROGRESS_EVENT, progress);
+    }
+
+    /// Sleep that ends early on pause, resume or a change in downloads
+    async fn sleep(&self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.wake.notified() => {}
+        }
+    }
+}
+
+/// Network trouble, a guard that was not passed or a server error, unlike a
+/// page that is gone or does not parse
+fn is_transient(error: &ScrapingError) -> bool {
+    match error {
+        ScrapingError::ReqwestError(_)
+        | ScrapingError::TimeoutError(_)
+        | ScrapingError::Offline => true,
+        // formatted as "<status> - <url>" by `fetch_page`
+        ScrapingError::HttpStatusCodeError(status) => {
+            !(status.starts_with("404") || status.starts_with("410"))
+        }
+        _ => false,
+    }
+}
+
+/// Time left before the next pass when the last one ended at `completed_at`
+fn pass_rest(completed_at: Option<i64>, now: i64) -> Option<Duration> {
+    let elapsed = now.saturating_sub(completed_at?);
+    let left = PASS_INTERVAL.as_secs() as i64 - elapsed.max(0);
+    (left > 0).then(|| Duration::from_secs(left as u64))
+}
+
+fn now_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Last answer of [`query_metered`], it is asked again after [`RECHECK_INTERVAL`]
+static METERED: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+async fn is_metered() -> bool {
+    if let Some((at, metered)) = *METERED.lock().unwrap()
+        && at.elapsed() < RECHECK_INTERVAL
+    {
+        return metered;
+    }
+    let metered = query_metered().await;
+    *METERED.lock().unwrap() = Some((Instant::now(), metered));
+    metered
+}
+
+#[cfg(windows)]
+async fn query_metered() -> bool {
+    use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+
+    tokio::task::spawn_blocking(|| {
+        NetworkInformation::GetInternetConnectionProfile()
+            .and_then(|profile| profile.GetConnectionCost())
+            .and_then(|cost| cost.NetworkCostType())
+            .is_ok_and(|kind| kind == NetworkCostType::Fixed || kind == NetworkCostType::Variable)
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Asks NetworkManager, anything else is assumed unmetered
+#[cfg(target_os = "linux")]
+async fn query_metered() -> bool {
+    let output = tokio::process::Command::new("busctl")
+        .args([
+            "--system",
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .await;
+
+    // prints `u <NMMetered>`, 1 is yes and 3 is guess-yes
+    output
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .and_then(|out| out.split_whitespace().nth(1)?.parse::<u32>().ok())
+        .is_some_and(|metered| matches!(metered, 1 | 3))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+async fn query_metered() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pass_rest_waits_out_the_interval() {
+        let interval = PASS_INTERVAL.as_secs() as i64;
+
+        assert_eq!(pass_rest(None, 1_000), None);
+        assert_eq!(pass_rest(Some(1_000), 1_000), Some(PASS_INTERVAL));
+        assert_eq!(
+            pass_rest(Some(1_000), 1_000 + interval - 60),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(pass_rest(Some(1_000), 1_000 + interval), None);
+        // a clock that went backwards does not make the wait longer
+        assert_eq!(pass_rest(Some(1_000), 0), Some(PASS_INTERVAL));
+    }
+}
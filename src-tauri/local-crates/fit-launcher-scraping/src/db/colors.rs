@@ -0,0 +1,56 @@
+//! Memoized colour palettes of cover images, keyed by image URL.
+
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::errors::ScrapingError;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type, PartialEq, Eq)]
+pub struct ImagePalette {
+    /// Most dominant first
+    pub colors: Vec<Rgb>,
+    /// Black or white, whichever reads better on the dominant colour
+    pub text_color: Rgb,
+}
+
+pub fn get_image_palette(
+    conn: &Connection,
+    image_url: &str,
+) -> Result<Option<ImagePalette>, ScrapingError> {
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT palette FROM image_colors WHERE image_url = ?1",
+            params![image_url],
+            |row| row.get(0),
+        )
+        .optional()?;
+    // an unreadable entry is computed again
+    Ok(json.and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+pub fn set_image_palette(
+    conn: &Connection,
+    image_url: &str,
+    palette: &ImagePalette,
+) -> Result<(), ScrapingError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    conn.execute(
+        "INSERT INTO image_colors (image_url, palette, computed_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(image_url) DO UPDATE SET
+             palette = excluded.palette,
+             computed_at = excluded.computed_at",
+        params![image_url, serde_json::to_string(palette)?, now],
+    )?;
+    Ok(())
+}
@@ -4,6 +4,7 @@ use rusqlite::{Connection, OptionalExtension, params};
 use std::fmt::Display;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use super::revisions::record_revision;
 use crate::errors::ScrapingError;
 use crate::structs::Game;
 
@@ -87,6 +88,8 @@ pub fn upsert_game(conn: &Connection, url_hash: i64, game: &Game) -> Result<(),
     let secondary_json = serialize_secondary_images(&game.secondary_images);
     let slug = extract_slug(&game.href);
 
+    record_revision(conn, url_hash, game, now)?;
+
     conn.execute(
         r#"
         INSERT INTO games (url_hash, href, slug, title, img, details, features, description, gameplay_features, included_dlcs,
@@ -169,22 +172,64 @@ pub fn is_game_cache_valid(
     Ok(stmt.exists(params![url_hash, cutoff])?)
 }
 
+/// Drop expired games that were only scraped for a page or a category.
+///
+/// Games from a sitemap are kept, they are the searchable catalog, and
+/// `is_game_cache_valid` already gets them refreshed when opened.
 pub fn cleanup_expired_games(conn: &Connection, expiry_secs: i64) -> Result<usize, ScrapingError> {
     let cutoff = now_timestamp() - expiry_secs;
     let deleted = conn.execute(
         "DELETE FROM games WHERE is_scraped = 1 AND updated_at < ?1 
+         AND source_sitemap IS NULL
          AND url_hash NOT IN (SELECT url_hash FROM game_categories)",
         params![cutoff],
     )?;
     Ok(deleted)
 }
 
+/// The first unscraped sitemap stub after `after` in `url_hash` order, as `(url_hash, href)`
+pub fn next_unscraped_stub(
+    conn: &Connection,
+    after: i64,
+) -> Result<Option<(i64, String)>, ScrapingError> {
+    Ok(conn
+        .query_row(
+            "SELECT url_hash, href FROM games WHERE is_scraped = 0 AND url_hash > ?1
+             ORDER BY url_hash LIMIT 1",
+            params![after],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?)
+}
+
+/// When a game page was last scraped, `None` for unknown games and sitemap stubs
+pub fn get_game_scraped_at(conn: &Connection, url_hash: i64) -> Result<Option<i64>, ScrapingError> {
+    Ok(conn
+        .query_row(
+            "SELECT updated_at FROM games WHERE url_hash = ?1 AND is_scraped = 1",
+            params![url_hash],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// `(scraped, total)` games in the catalog
+pub fn get_scraped_counts(conn: &Connection) -> Result<(usize, usize), ScrapingError> {
+    let (scraped, total): (i64, i64) = conn.query_row(
+        "SELECT COALESCE(SUM(is_scraped), 0), COUNT(*) FROM games",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok((scraped as usize, total as usize))
+}
+
 /// Clears scraped game data but preserves sitemap stubs.
 /// Resets is_scraped to 0 and clears detail fields, keeping slug/title/href for search.
 pub fn clear_game_cache(conn: &Connection) -> Result<(), ScrapingError> {
     conn.execute_batch(
         "-- Clear category associations
          DELETE FROM game_categories;
+         DELETE FROM recommendations;
          
          -- Reset scraped data but keep stubs for search
          UPDATE games SET
@@ -215,6 +260,7 @@ pub fn clear_all_game_data(conn: &Connection) -> Result<(), ScrapingError> {
         "
     -- Remove all stub from sitemaps
     DELETE FROM games;
+    DELETE FROM game_revisions;
     ",
     )?;
     clear_game_cache(conn)?;
@@ -298,6 +344,8 @@ pub fn set_category_games(
         let secondary_json = serialize_secondary_images(&game.secondary_images);
         let slug = extract_slug(&game.href);
 
+        record_revision(&tx, url_hash, game, now)?;
+
         tx.execute(
             r#"
             INSERT INTO games (url_hash, href, slug, title, img, details, features, description, gameplay_features, included_dlcs,
@@ -348,3 +396,58 @@ pub fn set_category_games(
     tx.commit()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_tables;
+
+    fn stub(conn: &Connection, url_hash: i64) {
+        let href = format!("https://fitgirl-repacks.site/game-{url_hash}/");
+        insert_sitemap_stub(conn, url_hash, &href, "game", "Game", None).unwrap();
+    }
+
+    #[test]
+    fn next_unscraped_stub_walks_in_hash_order() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        for url_hash in [30, -10, 20] {
+            stub(&conn, url_hash);
+        }
+        // scraped games are not stubs anymore
+        let scraped = Game {
+            href: "https://fitgirl-repacks.site/game-20/".into(),
+            title: "Game".into(),
+            ..Default::default()
+        };
+        upsert_game(&conn, 20, &scraped).unwrap();
+
+        let next = |after| next_unscraped_stub(&conn, after).unwrap().map(|(h, _)| h);
+        assert_eq!(next(i64::MIN), Some(-10));
+        assert_eq!(next(-10), Some(30));
+        assert_eq!(next(30), None);
+
+        let (_, href) = next_unscraped_stub(&conn, i64::MIN).unwrap().unwrap();
+        assert_eq!(href, "https://fitgirl-repacks.site/game--10/");
+    }
+
+    #[test]
+    fn scraped_counts_cover_the_whole_catalog() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        assert_eq!(get_scraped_counts(&conn).unwrap(), (0, 0));
+
+        for url_hash in 1..=3 {
+            stub(&conn, url_hash);
+        }
+        assert_eq!(get_scraped_counts(&conn).unwrap(), (0, 3));
+
+        let scraped = Game {
+            href: "https://fitgirl-repacks.site/game-2/".into(),
+            title: "Game".into(),
+            ..Default::default()
+        };
+        upsert_game(&conn, 2, &scraped).unwrap();
+        assert_eq!(get_scraped_counts(&conn).unwrap(), (1, 3));
+    }
+}
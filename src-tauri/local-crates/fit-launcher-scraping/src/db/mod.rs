@@ -2,24 +2,35 @@
 //!
 //! Uses SQLite with FTS5 for full-text search. Located at `{app_data_dir}/sitemaps/search.db`.
 
+mod colors;
 mod games;
+mod recommendations;
+mod revisions;
 mod search;
 
+use fit_launcher_config::client::site::normalize_href;
 use rusqlite::{Connection, OptionalExtension, params};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
 use tracing::info;
 
 use crate::errors::ScrapingError;
 
+pub use colors::{ImagePalette, Rgb, get_image_palette, set_image_palette};
 pub use games::{
     batch_insert_sitemap_stubs, cleanup_expired_games, clear_all_game_data, clear_game_cache,
-    extract_slug, get_game_by_hash, get_game_count, get_games_by_category,
-    get_pastebin_by_magnet_hash, insert_sitemap_stub, is_game_cache_valid, set_category_games,
-    upsert_game,
+    extract_slug, get_game_by_hash, get_game_count, get_game_scraped_at, get_games_by_category,
+    get_pastebin_by_magnet_hash, get_scraped_counts, insert_sitemap_stub, is_game_cache_valid,
+    next_unscraped_stub, set_category_games, upsert_game,
 };
+pub(crate) use recommendations::{
+    Candidate, category_positions, recommendation_candidates, save_recommendations,
+};
+pub use recommendations::{Recommendation, RecommendationReason, get_recommendations};
+pub use revisions::{FieldChange, GameRevision, get_game_revisions};
 pub use search::{
-    SearchIndexEntry, get_all_games_for_search, initialize_fts, insert_fts_entries, query_fts,
+    SearchDocument, SearchIndexEntry, get_all_games_for_search, initialize_fts, insert_fts_entries,
+    query_fts, refresh_fts_entry,
 };
 
 pub fn get_db_path(app: &AppHandle) -> PathBuf {
@@ -43,14 +54,27 @@ pub fn open_connection(app: &AppHandle) -> Result<Connection, ScrapingError> {
     Ok(conn)
 }
 
+/// Write a consistent copy of the database to `dest`, safe while it is in use.
+pub fn backup_database(app: &AppHandle, dest: &Path) -> Result<(), ScrapingError> {
+    let conn = open_connection(app)?;
+    if dest.exists() {
+        std::fs::remove_file(dest)?;
+    }
+
+    conn.execute("VACUUM INTO ?1", params![dest.to_string_lossy()])?;
+    Ok(())
+}
+
 pub fn open_connection_at(db_path: &PathBuf) -> Result<Connection, ScrapingError> {
     Connection::open(db_path).map_err(|e| ScrapingError::IOError(e.to_string()))
 }
 
 /// Deterministic URL hash for primary key. Fixed seeds ensure same URL = same hash.
+///
+/// The URL is normalized first, so a page has the same key on every mirror.
 pub fn hash_url(url: &str) -> i64 {
     let hasher = ahash::RandomState::with_seeds(0x1A, 0x6B, 0x4D, 0xF6);
-    hasher.hash_one(url) as _
+    hasher.hash_one(normalize_href(url).as_str()) as _
 }
 
 pub fn get_metadata(conn: &Connection, key: &str) -> Result<Option<String>, ScrapingError> {
@@ -68,6 +92,34 @@ pub fn set_metadata(conn: &Connection, key: &str, value: &str) -> Result<(), Scr
     Ok(())
 }
 
+/// Record that `what` (a category, `sitemaps`...) was just synced with the site
+pub fn mark_synced(conn: &Connection, what: &str) -> Result<(), ScrapingError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    set_metadata(conn, &format!("synced_at:{what}"), &now.to_string())
+}
+
+/// Unix timestamp of the last sync of `what`, see [`mark_synced`]
+pub fn synced_at(conn: &Connection, what: &str) -> Result<Option<i64>, ScrapingError> {
+    Ok(get_metadata(conn, &format!("synced_at:{what}"))?.and_then(|v| v.parse().ok()))
+}
+
+/// Every `(key, value)` whose key starts with `prefix`
+pub fn get_metadata_with_prefix(
+    conn: &Connection,
+    prefix: &str,
+) -> Result<Vec<(String, String)>, ScrapingError> {
+    let mut stmt = conn.prepare("SELECT key, value FROM metadata WHERE substr(key, 1, ?2) = ?1")?;
+    let rows = stmt
+        .query_map(params![prefix, prefix.len() as i64], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
 fn initialize_tables(conn: &Connection) -> Result<(), ScrapingError> {
     use std::sync::OnceLock;
 
@@ -213,6 +265,34 @@ fn create_tables(conn: &Connection) -> Result<(), ScrapingError> {
 
         CREATE INDEX IF NOT EXISTS idx_games_is_scraped
         ON games(is_scraped);
+
+        CREATE TABLE IF NOT EXISTS game_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url_hash INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            details TEXT,
+            features TEXT,
+            included_dlcs TEXT,
+            magnetlink TEXT,
+            recorded_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_game_revisions_url_hash
+        ON game_revisions(url_hash, recorded_at);
+
+        CREATE TABLE IF NOT EXISTS recommendations (
+            url_hash INTEGER PRIMARY KEY,
+            position INTEGER NOT NULL,
+            score REAL NOT NULL,
+            reasons TEXT NOT NULL,
+            computed_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS image_colors (
+            image_url TEXT PRIMARY KEY,
+            palette TEXT NOT NULL,
+            computed_at INTEGER NOT NULL
+        );
         "#,
     )?;
 
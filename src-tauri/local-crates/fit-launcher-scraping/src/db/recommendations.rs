@@ -0,0 +1,141 @@
+//! Stored discovery recommendations, see [`crate::recommend`].
+
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use super::games::get_game_by_hash;
+use crate::errors::ScrapingError;
+use crate::structs::Game;
+
+/// Why a game was recommended, the frontend words it
+#[derive(Debug, Clone, Serialize, Deserialize, Type, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RecommendationReason {
+    /// Shares genres or companies with a game of the library
+    SimilarTo {
+        title: String,
+        href: String,
+        /// `played`, `installed`, `downloaded` or `collected`
+        relation: String,
+        /// Name of the collection when `relation` is `collected`
+        collection: Option<String>,
+        /// The genres and companies both games have
+        shared: Vec<String>,
+    },
+    /// Rank in the popular repacks, 1 is the top
+    Popular { rank: u32 },
+    /// On the first pages of the site
+    Recent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Recommendation {
+    pub game: Game,
+    /// 0 to 1
+    pub score: f64,
+    pub reasons: Vec<RecommendationReason>,
+}
+
+/// A scraped game the recommendations can pick from
+#[derive(Debug, Clone)]
+pub(crate) struct Candidate {
+    pub url_hash: i64,
+    pub title: String,
+    pub tag: String,
+    pub details: String,
+}
+
+/// Scraped games with a cover the discovery page can show
+pub(crate) fn recommendation_candidates(
+    conn: &Connection,
+) -> Result<Vec<Candidate>, ScrapingError> {
+    let mut stmt = conn.prepare(
+        "SELECT url_hash, title, tag, details FROM games
+         WHERE is_scraped = 1 AND img LIKE '%imageban%'",
+    )?;
+    let candidates = stmt
+        .query_map([], |row| {
+            Ok(Candidate {
+                url_hash: row.get(0)?,
+                title: row.get(1)?,
+                tag: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                details: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(candidates)
+}
+
+/// `url_hash` to position (0 first) of every game of `category`
+pub(crate) fn category_positions(
+    conn: &Connection,
+    category: &str,
+) -> Result<Vec<(i64, u32)>, ScrapingError> {
+    let mut stmt = conn.prepare(
+        "SELECT url_hash, position FROM game_categories WHERE category = ?1 ORDER BY position",
+    )?;
+    let positions = stmt
+        .query_map(params![category], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(positions)
+}
+
+/// Replace the stored recommendations, best first, and order the discovery
+/// category the same way
+pub(crate) fn save_recommendations(
+    conn: &Connection,
+    ranked: &[(i64, f64, Vec<RecommendationReason>)],
+    computed_at: i64,
+) -> Result<(), ScrapingError> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM recommendations", [])?;
+    tx.execute(
+        "DELETE FROM game_categories WHERE category = 'discovery'",
+        [],
+    )?;
+
+    for (position, (url_hash, score, reasons)) in ranked.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO recommendations (url_hash, position, score, reasons, computed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                url_hash,
+                position as i64,
+                score,
+                serde_json::to_string(reasons)?,
+                computed_at
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO game_categories (url_hash, category, position) VALUES (?1, 'discovery', ?2)",
+            params![url_hash, position as i64],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Stored recommendations, best first
+pub fn get_recommendations(conn: &Connection) -> Result<Vec<Recommendation>, ScrapingError> {
+    let mut stmt =
+        conn.prepare("SELECT url_hash, score, reasons FROM recommendations ORDER BY position")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<Vec<(i64, f64, String)>, _>>()?;
+
+    let mut recommendations = Vec::with_capacity(rows.len());
+    for (url_hash, score, reasons) in rows {
+        // the game may have been cleared from the cache since
+        let Some(game) = get_game_by_hash(conn, url_hash)? else {
+            continue;
+        };
+        recommendations.push(Recommendation {
+            game,
+            score,
+            reasons: serde_json::from_str(&reasons).unwrap_or_default(),
+        });
+    }
+    Ok(recommendations)
+}
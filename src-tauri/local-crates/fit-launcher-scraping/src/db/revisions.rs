@@ -0,0 +1,314 @@
+//! Repack revision history.
+//!
+//! Re-scraping overwrites the `games` row, so before that happens the fields
+//! that change with a new repack (version in the title, details with the
+//! sizes, repack features, DLCs, magnet) are compared with the last known
+//! state. On a change both states end up in `game_revisions`, games that
+//! never changed have no history.
+
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::errors::ScrapingError;
+use crate::structs::Game;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Snapshot {
+    title: String,
+    details: String,
+    features: String,
+    included_dlcs: String,
+    magnetlink: String,
+}
+
+impl Snapshot {
+    fn of(game: &Game) -> Self {
+        Snapshot {
+            title: game.title.clone(),
+            details: game.details.clone(),
+            features: game.features.clone(),
+            included_dlcs: game.included_dlcs.clone(),
+            magnetlink: game.magnetlink.clone(),
+        }
+    }
+
+    fn fields(&self) -> [(&'static str, &str); 5] {
+        [
+            ("title", &self.title),
+            ("details", &self.details),
+            ("features", &self.features),
+            ("included_dlcs", &self.included_dlcs),
+            ("magnetlink", &self.magnetlink),
+        ]
+    }
+
+    /// Trackers come and go in the magnet, only the info hash tells a new torrent
+    fn same_repack(&self, other: &Snapshot) -> bool {
+        self.title == other.title
+            && self.details == other.details
+            && self.features == other.features
+            && self.included_dlcs == other.included_dlcs
+            && info_hash(&self.magnetlink) == info_hash(&other.magnetlink)
+    }
+
+    fn from_row(row: &rusqlite::Row<'_>, offset: usize) -> rusqlite::Result<Self> {
+        Ok(Snapshot {
+            title: row.get(offset)?,
+            details: row
+                .get::<_, Option<String>>(offset + 1)?
+                .unwrap_or_default(),
+            features: row
+                .get::<_, Option<String>>(offset + 2)?
+                .unwrap_or_default(),
+            included_dlcs: row
+                .get::<_, Option<String>>(offset + 3)?
+                .unwrap_or_default(),
+            magnetlink: row
+                .get::<_, Option<String>>(offset + 4)?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+fn info_hash(magnet: &str) -> String {
+    magnet
+        .split(['?', '&'])
+        .find_map(|param| param.strip_prefix("xt=urn:btih:"))
+        .unwrap_or(magnet)
+        .to_ascii_lowercase()
+}
+
+/// One field that differs from the previous revision
+#[derive(Debug, Clone, Serialize, Deserialize, Type, PartialEq, Eq)]
+pub struct FieldChange {
+    /// `title`, `details`, `features`, `included_dlcs` or `magnetlink`
+    pub field: String,
+    pub before: String,
+    pub after: String,
+    /// Lines only in `after`, e.g. a new DLC
+    pub added: Vec<String>,
+    /// Lines only in `before`
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct GameRevision {
+    pub id: i64,
+    /// Unix timestamp of the scrape that saw this state
+    pub recorded_at: i64,
+    pub title: String,
+    pub details: String,
+    pub features: String,
+    pub included_dlcs: String,
+    pub magnetlink: String,
+    /// Changes from the previous revision, empty for the oldest one
+    pub changes: Vec<FieldChange>,
+}
+
+/// Record `game` as a new revision if it differs from the last known state.
+///
+/// Called before the `games` row is overwritten.
+pub(crate) fn record_revision(
+    conn: &Connection,
+    url_hash: i64,
+    game: &Game,
+    now: i64,
+) -> Result<(), ScrapingError> {
+    let new = Snapshot::of(game);
+    // a page that did not parse is no new repack
+    if new.details.is_empty() && new.magnetlink.is_empty() {
+        return Ok(());
+    }
+
+    let last_revision = conn
+        .query_row(
+            "SELECT title, details, features, included_dlcs, magnetlink FROM game_revisions
+             WHERE url_hash = ?1 ORDER BY recorded_at DESC, id DESC LIMIT 1",
+            params![url_hash],
+            |row| Snapshot::from_row(row, 0),
+        )
+        .optional()?;
+
+    let previous = match last_revision {
+        Some(previous) => previous,
+        None => {
+            let current = conn
+                .query_row(
+                    "SELECT title, details, features, included_dlcs, magnetlink, updated_at
+                     FROM games WHERE url_hash = ?1 AND is_scraped = 1",
+                    params![url_hash],
+                    |row| Ok((Snapshot::from_row(row, 0)?, row.get::<_, i64>(5)?)),
+                )
+                .optional()?;
+            let Some((current, scraped_at)) = current else {
+                return Ok(());
+            };
+            if current.same_repack(&new) {
+                return Ok(());
+            }
+            // first change, the state it replaces starts the history
+            insert_revision(conn, url_hash, &current, scraped_at)?;
+            current
+        }
+    };
+
+    if !previous.same_repack(&new) {
+        insert_revision(conn, url_hash, &new, now)?;
+    }
+    Ok(())
+}
+
+fn insert_revision(
+    conn: &Connection,
+    url_hash: i64,
+    snapshot: &Snapshot,
+    recorded_at: i64,
+) -> Result<(), ScrapingError> {
+    conn.execute(
+        "INSERT INTO game_revisions (url_hash, title, details, features, included_dlcs, magnetlink, recorded_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            url_hash,
+            &snapshot.title,
+            &snapshot.details,
+            &snapshot.features,
+            &snapshot.included_dlcs,
+            &snapshot.magnetlink,
+            recorded_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Revisions of a game, newest first, each with its changes from the one before
+pub fn get_game_revisions(
+    conn: &Connection,
+    url_hash: i64,
+) -> Result<Vec<GameRevision>, ScrapingError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, recorded_at, title, details, features, included_dlcs, magnetlink
+         FROM game_revisions WHERE url_hash = ?1 ORDER BY recorded_at, id",
+    )?;
+    let rows = stmt
+        .query_map(params![url_hash], |row| {
+            Ok((row.get(0)?, row.get(1)?, Snapshot::from_row(row, 2)?))
+        })?
+        .collect::<Result<Vec<(i64, i64, Snapshot)>, _>>()?;
+
+    let mut revisions = Vec::with_capacity(rows.len());
+    let mut previous: Option<Snapshot> = None;
+    for (id, recorded_at, snapshot) in rows {
+        let changes = previous
+            .as_ref()
+            .map(|previous| diff(previous, &snapshot))
+            .unwrap_or_default();
+        revisions.push(GameRevision {
+            id,
+            recorded_at,
+            title: snapshot.title.clone(),
+            details: snapshot.details.clone(),
+            features: snapshot.features.clone(),
+            included_dlcs: snapshot.included_dlcs.clone(),
+            magnetlink: snapshot.magnetlink.clone(),
+            changes,
+        });
+        previous = Some(snapshot);
+    }
+
+    revisions.reverse();
+    Ok(revisions)
+}
+
+fn diff(before: &Snapshot, after: &Snapshot) -> Vec<FieldChange> {
+    before
+        .fields()
+        .into_iter()
+        .zip(after.fields())
+        .filter(|((_, before), (_, after))| before != after)
+        .map(|((field, before), (_, after))| FieldChange {
+            field: field.to_string(),
+            before: before.to_string(),
+            after: after.to_string(),
+            added: lines_missing_from(after, before),
+            removed: lines_missing_from(before, after),
+        })
+        .collect()
+}
+
+/// Lines of `text` that `other` does not have, in order
+fn lines_missing_from(text: &str, other: &str) -> Vec<String> {
+    let other: Vec<&str> = other.lines().map(str::trim).collect();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !other.contains(line))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_tables, upsert_game};
+
+    fn game(title: &str, dlcs: &str, magnet: &str) -> Game {
+        Game {
+            title: title.into(),
+            href: "https://fitgirl-repacks.site/some-game/".into(),
+            details: "Repack Size: 10 GB".into(),
+            included_dlcs: dlcs.into(),
+            magnetlink: magnet.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn history_starts_at_the_first_change() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        let v1 = game(
+            "Some Game – v1.0",
+            "DLC A",
+            "magnet:?xt=urn:btih:AAA&tr=one",
+        );
+        upsert_game(&conn, 1, &v1).unwrap();
+        assert!(get_game_revisions(&conn, 1).unwrap().is_empty());
+
+        // same torrent, other trackers
+        let same = game(
+            "Some Game – v1.0",
+            "DLC A",
+            "magnet:?xt=urn:btih:aaa&tr=two",
+        );
+        upsert_game(&conn, 1, &same).unwrap();
+        assert!(get_game_revisions(&conn, 1).unwrap().is_empty());
+
+        let v2 = game(
+            "Some Game – v1.1",
+            "DLC A\nDLC B",
+            "magnet:?xt=urn:btih:BBB",
+        );
+        upsert_game(&conn, 1, &v2).unwrap();
+        upsert_game(&conn, 1, &v2).unwrap();
+
+        let revisions = get_game_revisions(&conn, 1).unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].title, "Some Game – v1.1");
+        assert!(revisions[1].changes.is_empty());
+
+        let dlcs = revisions[0]
+            .changes
+            .iter()
+            .find(|c| c.field == "included_dlcs")
+            .unwrap();
+        assert_eq!(dlcs.added, ["DLC B"]);
+        assert!(dlcs.removed.is_empty());
+        let fields: Vec<&str> = revisions[0]
+            .changes
+            .iter()
+            .map(|c| c.field.as_str())
+            .collect();
+        assert_eq!(fields, ["title", "included_dlcs", "magnetlink"]);
+    }
+}
@@ -1,10 +1,11 @@
 //! Full-text search implementation using SQLite FTS5.
 
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, OptionalExtension, params};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
 use crate::errors::ScrapingError;
+use crate::recommend::details_line;
 
 #[derive(Debug, Serialize, Deserialize, Type, Clone)]
 pub struct SearchIndexEntry {
@@ -13,6 +14,49 @@ pub struct SearchIndexEntry {
     pub href: String,
 }
 
+/// A game as it goes into the index, genres and sizes are empty for sitemap stubs
+/// until the game page was scraped.
+#[derive(Debug, Clone)]
+pub struct SearchDocument {
+    pub entry: SearchIndexEntry,
+    pub genres: String,
+    /// Original and repack size, e.g. `25.1 GB 12.3 GB`
+    pub size: String,
+}
+
+impl SearchDocument {
+    /// From a `slug, title, href, tag, details` row of `games`
+    fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+        let tag: Option<String> = row.get(3)?;
+        let details: Option<String> = row.get(4)?;
+        let details = details.unwrap_or_default();
+
+        let genres = match tag.filter(|t| !t.trim().is_empty()) {
+            Some(tag) => tag,
+            None => details_line(&details, &["Genres/Tags", "Genres", "Tags"])
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        };
+        let size = ["Original Size", "Repack Size"]
+            .iter()
+            .filter_map(|label| details_line(&details, &[label]))
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Ok(Self {
+            entry: SearchIndexEntry {
+                slug: row.get(0)?,
+                title: row.get(1)?,
+                href: row.get(2)?,
+            },
+            genres,
+            size,
+        })
+    }
+}
+
 /// We use 'porter unicode61' to handle stemming ("games" matches "game") and unicode characters.
 pub fn initialize_fts(conn: &Connection) -> Result<(), ScrapingError> {
     conn.execute_batch(
@@ -22,6 +66,8 @@ pub fn initialize_fts(conn: &Connection) -> Result<(), ScrapingError> {
             slug,
             title,
             href,
+            genres,
+            size,
             tokenize = 'porter unicode61'
         );
         "#,
@@ -31,16 +77,23 @@ pub fn initialize_fts(conn: &Connection) -> Result<(), ScrapingError> {
 
 pub fn insert_fts_entries(
     conn: &Connection,
-    entries: &[SearchIndexEntry],
+    documents: &[SearchDocument],
 ) -> Result<(), ScrapingError> {
     let tx = conn.unchecked_transaction()?;
 
     {
-        let mut stmt =
-            tx.prepare("INSERT INTO games_fts (slug, title, href) VALUES (?1, ?2, ?3)")?;
+        let mut stmt = tx.prepare(
+            "INSERT INTO games_fts (slug, title, href, genres, size) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
 
-        for entry in entries {
-            stmt.execute(params![&entry.slug, &entry.title, &entry.href])?;
+        for doc in documents {
+            stmt.execute(params![
+                &doc.entry.slug,
+                &doc.entry.title,
+                &doc.entry.href,
+                &doc.genres,
+                &doc.size
+            ])?;
         }
     }
 
@@ -48,6 +101,26 @@ pub fn insert_fts_entries(
     Ok(())
 }
 
+/// Re-index a single game, after it was (re)scraped.
+pub fn refresh_fts_entry(conn: &Connection, url_hash: i64) -> Result<(), ScrapingError> {
+    let doc = conn
+        .query_row(
+            "SELECT slug, title, href, tag, details FROM games WHERE url_hash = ?1",
+            params![url_hash],
+            SearchDocument::from_row,
+        )
+        .optional()?;
+    let Some(doc) = doc else {
+        return Ok(());
+    };
+
+    conn.execute(
+        "DELETE FROM games_fts WHERE href = ?1",
+        params![&doc.entry.href],
+    )?;
+    insert_fts_entries(conn, &[doc])
+}
+
 /// Query the FTS index using BM25 ranking.
 pub fn query_fts(
     conn: &Connection,
@@ -58,13 +131,13 @@ pub fn query_fts(
         return Ok(Vec::new());
     }
 
-    let escaped = escape_fts5_query(query);
-    let fts_query = format!("{}*", escaped);
+    let fts_query = fts5_match(query);
 
+    // title hits rank above genre and size hits
     let mut stmt = conn.prepare(
         "SELECT slug, title, href FROM games_fts 
          WHERE games_fts MATCH ?1 
-         ORDER BY rank 
+         ORDER BY bm25(games_fts, 1.0, 10.0, 0.0, 2.0, 2.0) 
          LIMIT ?2",
     )?;
 
@@ -90,27 +163,35 @@ fn escape_fts5_query(query: &str) -> String {
         .join(" ")
 }
 
-/// Get all games as search index entries (for building FTS from games table).
-pub fn get_all_games_for_search(conn: &Connection) -> Result<Vec<SearchIndexEntry>, ScrapingError> {
-    let mut stmt = conn.prepare("SELECT slug, title, href FROM games ORDER BY title")?;
+/// Every term quoted so sizes like `12.3` are not read as query syntax,
+/// the last one matched as a prefix.
+fn fts5_match(query: &str) -> String {
+    let terms = escape_fts5_query(query)
+        .split(' ')
+        .map(|term| format!("\"{term}\""))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{terms}*")
+}
 
-    let entries = stmt
-        .query_map([], |row| {
-            Ok(SearchIndexEntry {
-                slug: row.get(0)?,
-                title: row.get(1)?,
-                href: row.get(2)?,
-            })
-        })?
+/// Get all games as search documents (for building FTS from games table).
+pub fn get_all_games_for_search(conn: &Connection) -> Result<Vec<SearchDocument>, ScrapingError> {
+    let mut stmt =
+        conn.prepare("SELECT slug, title, href, tag, details FROM games ORDER BY title")?;
+
+    let documents = stmt
+        .query_map([], SearchDocument::from_row)?
         .filter_map(|r| r.ok())
         .collect();
 
-    Ok(entries)
+    Ok(documents)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::{create_tables, hash_url, insert_sitemap_stub, upsert_game};
+    use crate::structs::Game;
 
     #[test]
     fn test_escape_fts5_query() {
@@ -120,4 +201,71 @@ mod tests {
             "dragon \"\"quotes\"\" ball"
         );
     }
+
+    #[test]
+    fn fts5_match_quotes_every_term() {
+        assert_eq!(fts5_match("dragon ball"), "\"dragon\" \"ball\"*");
+        assert_eq!(fts5_match("12.3 GB"), "\"12.3\" \"GB\"*");
+    }
+
+    #[test]
+    fn scraped_games_are_found_by_genre_and_size() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        let stub = "https://fitgirl-repacks.site/some-stub/";
+        insert_sitemap_stub(&conn, hash_url(stub), stub, "some-stub", "Some Stub", None).unwrap();
+
+        let href = "https://fitgirl-repacks.site/hollow-knight/";
+        let game = Game {
+            title: "Hollow Knight".into(),
+            href: href.into(),
+            details:
+                "Genres/Tags: Action, Metroidvania\nOriginal Size: 9.1 GB\nRepack Size: 1.2 GB"
+                    .into(),
+            ..Default::default()
+        };
+        upsert_game(&conn, hash_url(href), &game).unwrap();
+
+        initialize_fts(&conn).unwrap();
+        insert_fts_entries(&conn, &get_all_games_for_search(&conn).unwrap()).unwrap();
+
+        let titles = |query: &str| {
+            query_fts(&conn, query, 10)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles("metroid"), vec!["Hollow Knight"]);
+        assert_eq!(titles("1.2 GB"), vec!["Hollow Knight"]);
+        assert_eq!(titles("some"), vec!["Some Stub"]);
+        assert!(titles("roguelike").is_empty());
+    }
+
+    #[test]
+    fn refreshed_entry_picks_up_crawled_details() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        let href = "https://fitgirl-repacks.site/celeste/";
+        let url_hash = hash_url(href);
+        insert_sitemap_stub(&conn, url_hash, href, "celeste", "Celeste", None).unwrap();
+        initialize_fts(&conn).unwrap();
+        insert_fts_entries(&conn, &get_all_games_for_search(&conn).unwrap()).unwrap();
+        assert!(query_fts(&conn, "platformer", 10).unwrap().is_empty());
+
+        let game = Game {
+            title: "Celeste".into(),
+            href: href.into(),
+            tag: "Platformer, Indie".into(),
+            ..Default::default()
+        };
+        upsert_game(&conn, url_hash, &game).unwrap();
+        refresh_fts_entry(&conn, url_hash).unwrap();
+
+        let found = query_fts(&conn, "platformer", 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(query_fts(&conn, "celeste", 10).unwrap().len(), 1);
+    }
 }
@@ -1,12 +1,14 @@
 //! Discovery games rotation logic.
 //!
 //! Maintains a rotating pool of ~100 discovery games, refreshing periodically.
+//! Their order comes from [`crate::recommend`].
 
 use std::collections::HashSet;
 
 use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
+use fit_launcher_config::client::scheduler::{self, RetryBudget};
+use fit_launcher_config::client::site::CANONICAL_ORIGIN;
 use futures::{StreamExt, stream};
-use rand::{prelude::*, rng};
 use scraper::{Html, Selector};
 use serde_with::chrono::{self, DateTime, Utc};
 use tauri::{AppHandle, Emitter, Manager};
@@ -14,6 +16,7 @@ use tracing::info;
 
 use crate::db::{self, hash_url};
 use crate::errors::ScrapingError;
+use crate::health::ParseStats;
 use crate::parser::parse_game_from_article;
 use crate::scraping::fetch_page;
 use crate::structs::Game;
@@ -28,15 +31,15 @@ macro_rules! sel {
     };
 }
 
+/// A probe, there is a smaller image to fall back to so it is never retried
 async fn head_ok(url: &str) -> bool {
-    CUSTOM_DNS_CLIENT
-        .read()
-        .await
-        .head(url)
-        .send()
+    let client = CUSTOM_DNS_CLIENT.read().await.clone();
+    let Ok(request) = client.head(url).build() else {
+        return false;
+    };
+    scheduler::send(&client, request, &mut RetryBudget::new(1))
         .await
-        .map(|r| r.status().is_success())
-        .unwrap_or(false)
+        .is_ok_and(|r| r.status().is_success())
 }
 
 pub async fn try_high_res_img(src: &str) -> String {
@@ -54,8 +57,12 @@ pub async fn try_high_res_img(src: &str) -> String {
     src.into()
 }
 
-fn parse_discovery_article(article: scraper::element_ref::ElementRef) -> Option<Game> {
+fn parse_discovery_article(
+    article: scraper::element_ref::ElementRef,
+    stats: &ParseStats,
+) -> Option<Game> {
     let game = parse_game_from_article(article);
+    stats.record(&game);
 
     // Only include games with imageban images
     if !game.img.contains("imageban") {
@@ -65,21 +72,25 @@ fn parse_discovery_article(article: scraper::element_ref::ElementRef) -> Option<
     Some(game)
 }
 
-async fn fetch_discovery_page(n: u32, app: &AppHandle) -> Result<Vec<Game>, ScrapingError> {
-    let url = format!("https://fitgirl-repacks.site/category/lossless-repack/page/{n}");
+async fn fetch_discovery_page(
+    n: u32,
+    app: &AppHandle,
+    stats: &ParseStats,
+) -> Result<Vec<Game>, ScrapingError> {
+    let url = format!("{CANONICAL_ORIGIN}/category/lossless-repack/page/{n}");
     let body = fetch_page(&url, app).await?;
 
     let doc = Html::parse_document(&body);
     let mut games = Vec::new();
     for art in doc.select(sel!("article")) {
-        if let Some(g) = parse_discovery_article(art) {
+        if let Some(g) = parse_discovery_article(art, stats) {
             games.push(g);
         }
     }
     Ok(games)
 }
 
-fn read_meta_ts(conn: &rusqlite::Connection) -> Option<DateTime<Utc>> {
+pub(crate) fn read_meta_ts(conn: &rusqlite::Connection) -> Option<DateTime<Utc>> {
     db::get_metadata(conn, "discovery_last_refresh")
         .ok()
         .flatten()
@@ -104,20 +115,19 @@ pub async fn refresh_discovery_games(app: AppHandle) -> Result<(), ScrapingError
     };
 
     if queue.len() >= TARGET && !too_old {
-        queue.shuffle(&mut rng());
-        db::set_category_games(&conn, "discovery", &queue, hash_url)?;
-        info!("cache fresh (<{REFRESH_DAYS} days) - shuffled only");
+        info!("cache fresh (<{REFRESH_DAYS} days)");
         return Ok(());
     }
 
     let mut have: HashSet<String> = queue.iter().map(|g| g.title.clone()).collect();
 
+    let stats = ParseStats::default();
     let mut page = 1;
     while queue.len() < TARGET && page <= 10 {
         let mut fresh = Vec::<Game>::new();
 
         while fresh.len() < BATCH && page <= 10 {
-            let mut page_games = fetch_discovery_page(page, &app).await?;
+            let mut page_games = fetch_discovery_page(page, &app, &stats).await?;
             for g in page_games.drain(..) {
                 if have.insert(g.title.clone()) {
                     fresh.push(g);
@@ -146,13 +156,13 @@ pub async fn refresh_discovery_games(app: AppHandle) -> Result<(), ScrapingError
         if queue.len() > TARGET {
             queue.truncate(TARGET)
         }
-        queue.shuffle(&mut rng());
 
         db::set_category_games(&conn, "discovery", &queue, hash_url)?;
         info!("wrote batch, queue size now {}", queue.len());
     }
 
     write_meta_ts(&conn);
+    stats.report(&app, "discovery");
     info!("queue ready with {} games", queue.len());
 
     if let Some(main) = app.get_window("main") {
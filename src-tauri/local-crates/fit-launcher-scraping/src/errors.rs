@@ -1,3 +1,4 @@
+use fit_launcher_config::client::scheduler::RequestError;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
@@ -42,6 +43,9 @@ pub enum ScrapingError {
 
     #[error("Semaphore error: {0}")]
     SemaphoreError(String),
+
+    #[error("Offline, only local data is available")]
+    Offline,
 }
 
 impl From<reqwest::Error> for ScrapingError {
@@ -50,6 +54,15 @@ impl From<reqwest::Error> for ScrapingError {
     }
 }
 
+impl From<RequestError> for ScrapingError {
+    fn from(error: RequestError) -> Self {
+        match error.is_timeout() {
+            true => ScrapingError::TimeoutError(error.to_string()),
+            false => ScrapingError::ReqwestError(error.to_string()),
+        }
+    }
+}
+
 impl From<serde_json::Error> for ScrapingError {
     fn from(error: serde_json::Error) -> Self {
         ScrapingError::JsonError(error.to_string())
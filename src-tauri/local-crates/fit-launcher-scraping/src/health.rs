@@ -0,0 +1,228 @@
+//! Layout-change detector for the article parser.
+//!
+//! The parser leans on text markers ("Repack Features", "Game Description")
+//! and classes like `.su-spoiler`. When FitGirl changes the markup, games do
+//! not fail to parse, their fields just come back empty. Every scrape run
+//! counts those empty fields, stores the rates in `metadata` next to a slow
+//! moving baseline, and emits [`PARSER_HEALTH_EVENT`] when a rate jumps.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{AppHandle, Emitter};
+use tracing::{error, info, warn};
+
+use crate::db;
+use crate::errors::ScrapingError;
+use crate::structs::Game;
+
+pub const PARSER_HEALTH_EVENT: &str = "parser-health-warning";
+
+const METADATA_PREFIX: &str = "parser_health:";
+
+/// Fewer games than this say nothing about the layout
+const MIN_SAMPLE: u32 = 5;
+/// How far above its baseline an empty rate has to go to be a spike
+const SPIKE_MARGIN: f64 = 0.3;
+/// Weight of the latest run in the baseline
+const BASELINE_WEIGHT: f64 = 0.2;
+
+/// Fields every article is expected to have, DLCs and gameplay features are
+/// often legitimately missing
+const FIELDS: [(&str, fn(&Game) -> bool); 8] = [
+    ("title", |g| g.title.trim().is_empty()),
+    ("img", |g| g.img.is_empty()),
+    ("details", |g| g.details.is_empty()),
+    ("features", |g| g.features.is_empty()),
+    ("description", |g| g.description.is_empty()),
+    ("magnetlink", |g| g.magnetlink.is_empty()),
+    ("href", |g| g.href.is_empty()),
+    ("tag", |g| g.tag.is_empty()),
+];
+
+/// Empty field counters of one scrape run, shared by its concurrent fetches
+#[derive(Debug, Default)]
+pub struct ParseStats {
+    parsed: AtomicU32,
+    empty: [AtomicU32; FIELDS.len()],
+}
+
+impl ParseStats {
+    pub fn record(&self, game: &Game) {
+        self.parsed.fetch_add(1, Ordering::Relaxed);
+        for ((_, is_empty), count) in FIELDS.iter().zip(&self.empty) {
+            if is_empty(game) {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn parsed(&self) -> u32 {
+        self.parsed.load(Ordering::Relaxed)
+    }
+
+    /// Store the rates of `run` and warn on a spike, then start counting anew.
+    ///
+    /// Never fails the scrape, errors are only logged.
+    pub fn report(&self, app: &AppHandle, run: &str) {
+        let parsed = self.parsed.swap(0, Ordering::Relaxed);
+        let empty: Vec<u32> = self
+            .empty
+            .iter()
+            .map(|count| count.swap(0, Ordering::Relaxed))
+            .collect();
+        if parsed == 0 {
+            return;
+        }
+
+        let health = match db::open_connection(app)
+            .and_then(|conn| record_run(&conn, run, parsed, &empty))
+        {
+            Ok(health) => health,
+            Err(e) => {
+                error!("Failed to record parser health of {run}: {e}");
+                return;
+            }
+        };
+
+        if health.spiked.is_empty() {
+            info!("Parser health of {run}: {parsed} games, no spike");
+        } else {
+            warn!(
+                "Parser health of {run}: empty {} spiked over {parsed} games, the site layout may have changed",
+                health.spiked.join(", ")
+            );
+            let _ = app.emit(PARSER_HEALTH_EVENT, health);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct FieldHealth {
+    pub field: String,
+    /// Share of the games of the run with this field empty, 0 to 1
+    pub empty_rate: f64,
+    /// Moving average of the previous rates
+    pub baseline: f64,
+}
+
+/// Last scrape run of one kind (`newly_added`, `popular`, `crawler`...)
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ParserHealth {
+    pub run: String,
+    pub recorded_at: i64,
+    pub parsed: u32,
+    pub fields: Vec<FieldHealth>,
+    /// Fields whose empty rate jumped above their baseline
+    pub spiked: Vec<String>,
+}
+
+fn record_run(
+    conn: &Connection,
+    run: &str,
+    parsed: u32,
+    empty: &[u32],
+) -> Result<ParserHealth, ScrapingError> {
+    let key = format!("{METADATA_PREFIX}{run}");
+    let previous: Option<ParserHealth> =
+        db::get_metadata(conn, &key)?.and_then(|json| serde_json::from_str(&json).ok());
+
+    let health = evaluate(run, parsed, empty, previous.as_ref(), now_timestamp());
+    db::set_metadata(conn, &key, &serde_json::to_string(&health)?)?;
+    Ok(health)
+}
+
+fn evaluate(
+    run: &str,
+    parsed: u32,
+    empty: &[u32],
+    previous: Option<&ParserHealth>,
+    recorded_at: i64,
+) -> ParserHealth {
+    let mut fields = Vec::with_capacity(FIELDS.len());
+    let mut spiked = Vec::new();
+
+    for ((name, _), &count) in FIELDS.iter().zip(empty) {
+        let empty_rate = f64::from(count) / f64::from(parsed);
+        let baseline = previous
+            .and_then(|p| p.fields.iter().find(|f| f.field == *name))
+            .map(|f| f.baseline);
+
+        let baseline = match baseline {
+            Some(baseline) => {
+                if parsed >= MIN_SAMPLE && empty_rate >= baseline + SPIKE_MARGIN {
+                    spiked.push(name.to_string());
+                }
+                baseline + (empty_rate - baseline) * BASELINE_WEIGHT
+            }
+            // first run, nothing to compare with
+            None => empty_rate,
+        };
+
+        fields.push(FieldHealth {
+            field: name.to_string(),
+            empty_rate,
+            baseline,
+        });
+    }
+
+    ParserHealth {
+        run: run.to_string(),
+        recorded_at,
+        parsed,
+        fields,
+        spiked,
+    }
+}
+
+/// Last recorded health of every scrape run
+pub fn get_parser_health(conn: &Connection) -> Result<Vec<ParserHealth>, ScrapingError> {
+    Ok(db::get_metadata_with_prefix(conn, METADATA_PREFIX)?
+        .into_iter()
+        .filter_map(|(_, json)| serde_json::from_str(&json).ok())
+        .collect())
+}
+
+fn now_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(health: &ParserHealth, field: &str) -> (f64, f64) {
+        let f = health.fields.iter().find(|f| f.field == field).unwrap();
+        (f.empty_rate, f.baseline)
+    }
+
+    #[test]
+    fn first_run_sets_the_baseline() {
+        let health = evaluate("popular", 10, &[0, 0, 0, 2, 1, 0, 0, 0], None, 0);
+        assert!(health.spiked.is_empty());
+        assert_eq!(rate(&health, "features"), (0.2, 0.2));
+    }
+
+    #[test]
+    fn jump_above_baseline_is_a_spike() {
+        let first = evaluate("popular", 10, &[0; 8], None, 0);
+        let second = evaluate("popular", 10, &[0, 0, 8, 0, 0, 0, 0, 0], Some(&first), 1);
+        assert_eq!(second.spiked, ["details"]);
+        let (rate_now, baseline) = rate(&second, "details");
+        assert_eq!(rate_now, 0.8);
+        assert!((baseline - 0.16).abs() < 1e-9);
+    }
+
+    #[test]
+    fn small_runs_never_spike() {
+        let first = evaluate("crawler", 10, &[0; 8], None, 0);
+        let second = evaluate("crawler", 2, &[2; 8], Some(&first), 1);
+        assert!(second.spiked.is_empty());
+    }
+}
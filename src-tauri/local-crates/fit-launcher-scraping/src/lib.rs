@@ -4,10 +4,13 @@
 
 pub mod captcha;
 pub mod commands;
+pub mod crawler;
 pub mod db;
 pub mod discovery;
 pub mod errors;
+pub mod health;
 pub mod parser;
+pub mod recommend;
 pub mod scraping;
 pub mod sitemap;
 pub mod structs;
@@ -16,6 +19,7 @@ pub mod structs;
 pub use commands::*;
 
 // Re-export commonly used types
+pub use crawler::CatalogCrawler;
 pub use db::{SearchIndexEntry, hash_url};
 pub use errors::ScrapingError;
 pub use structs::Game;
@@ -1,5 +1,6 @@
 //! HTML parsing helpers for extracting game data from FitGirl pages.
 
+use fit_launcher_config::client::site::normalize_href;
 use scraper::ElementRef;
 
 use crate::structs::Game;
@@ -50,6 +51,15 @@ fn extract_features(article: ElementRef<'_>) -> String {
             break;
         }
 
+        // the description spoiler has its own lists, its title is not a <p>
+        if elem
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|a| a.value().classes().any(|c| c == "su-spoiler"))
+        {
+            break;
+        }
+
         if in_features && !trimmed.is_empty() && !trimmed.contains("Repack Features") {
             features_lines.push(trimmed.to_string());
         }
@@ -186,7 +196,7 @@ pub fn parse_game_from_article(article: ElementRef<'_>) -> Game {
         .select(&scraper::Selector::parse("span.entry-date > a").unwrap())
         .next()
         .and_then(|e| e.value().attr("href"))
-        .map(str::to_string)
+        .map(normalize_href)
         .unwrap_or_default();
 
     let img = article
@@ -0,0 +1,409 @@
+//! Discovery recommendations.
+//!
+//! Scraped games with a cover are scored by how much their genres and
+//! companies overlap with the user's library, each library game counting more
+//! the more it is used, then blended with popularity and recency. Owned games
+//! are left out. The ranking is stored with the reasons behind each pick and
+//! orders the discovery category.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::{Rng, rng};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::info;
+
+use crate::db::{self, Candidate, RecommendationReason, hash_url};
+use crate::errors::ScrapingError;
+
+/// Size of the discovery feed
+const LIMIT: usize = 100;
+
+const SIMILARITY_WEIGHT: f64 = 0.6;
+const POPULARITY_WEIGHT: f64 = 0.25;
+const RECENCY_WEIGHT: f64 = 0.15;
+/// Random share of the ordering, keeps the feed moving between refreshes
+const JITTER: f64 = 0.05;
+
+/// A shared company says more than a shared broad genre
+const COMPANY_WEIGHT: f64 = 1.5;
+/// `recently_updated` games are old games with a new repack
+const UPDATED_RECENCY: f64 = 0.8;
+/// Popular games further down are not worth mentioning
+const POPULAR_REASON_RANK: u32 = 20;
+const MAX_SHARED: usize = 3;
+
+/// A game of the user's library, merged across downloads and collections
+#[derive(Debug, Clone, Default)]
+pub struct LibraryGame {
+    pub title: String,
+    pub href: String,
+    pub tag: String,
+    pub details: String,
+    pub downloaded: bool,
+    pub installed: bool,
+    /// Minutes
+    pub play_time: u64,
+    pub collections: Vec<String>,
+}
+
+impl LibraryGame {
+    fn weight(&self) -> f64 {
+        let mut weight = if self.downloaded { 1.0 } else { 0.6 };
+        if self.installed {
+            weight += 0.5;
+        }
+        // hours played, with diminishing returns
+        weight + (1.0 + self.play_time as f64 / 60.0).ln()
+    }
+
+    fn reason(&self, shared: Vec<String>) -> RecommendationReason {
+        let (relation, collection) = if self.play_time > 0 {
+            ("played", None)
+        } else if self.installed {
+            ("installed", None)
+        } else if self.downloaded {
+            ("downloaded", None)
+        } else {
+            ("collected", self.collections.first().cloned())
+        };
+        RecommendationReason::SimilarTo {
+            title: self.title.clone(),
+            href: self.href.clone(),
+            relation: relation.to_string(),
+            collection,
+            shared,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Feature {
+    /// Lowercase, prefixed with `genre:` or `company:`
+    key: String,
+    name: String,
+}
+
+impl Feature {
+    fn weight(&self) -> f64 {
+        if self.key.starts_with("company:") {
+            COMPANY_WEIGHT
+        } else {
+            1.0
+        }
+    }
+}
+
+pub(crate) fn details_line<'a>(details: &'a str, labels: &[&str]) -> Option<&'a str> {
+    details.lines().find_map(|line| {
+        let (label, value) = line.split_once(':')?;
+        labels
+            .iter()
+            .any(|l| label.trim().eq_ignore_ascii_case(l))
+            .then_some(value)
+    })
+}
+
+/// Genres from `tag` (or the details when it is missing) and companies from
+/// the details
+fn features(tag: &str, details: &str) -> HashSet<Feature> {
+    let genres = if tag.trim().is_empty() {
+        details_line(details, &["Genres/Tags", "Genres", "Tags"]).unwrap_or_default()
+    } else {
+        tag
+    };
+    let companies = details_line(details, &["Companies", "Company"]).unwrap_or_default();
+
+    [("genre", genres), ("company", companies)]
+        .into_iter()
+        .flat_map(|(kind, list)| {
+            list.split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty() && *name != "N/A")
+                .map(move |name| Feature {
+                    key: format!("{kind}:{}", name.to_lowercase()),
+                    name: name.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Title without the version and DLCs, `Game – v1.2 + 3 DLCs` is `game`
+fn base_title(title: &str) -> String {
+    let end = [" – ", " - ", " + ", ", v"]
+        .iter()
+        .filter_map(|sep| title.find(sep))
+        .min()
+        .unwrap_or(title.len());
+    title[..end].trim().to_lowercase()
+}
+
+/// Popularity and recency, 0 to 1, by `url_hash`
+#[derive(Debug, Default)]
+struct Signals {
+    popular: HashMap<i64, (u32, f64)>,
+    recency: HashMap<i64, f64>,
+}
+
+impl Signals {
+    fn load(conn: &rusqlite::Connection) -> Result<Self, ScrapingError> {
+        let mut signals = Signals::default();
+
+        let popular = db::category_positions(conn, "popular")?;
+        let count = popular.len() as f64;
+        for (url_hash, position) in popular {
+            let value = 1.0 - f64::from(position) / count;
+            signals.popular.insert(url_hash, (position + 1, value));
+        }
+
+        for (category, scale) in [("newly_added", 1.0), ("recently_updated", UPDATED_RECENCY)] {
+            let positions = db::category_positions(conn, category)?;
+            let count = positions.len() as f64;
+            for (url_hash, position) in positions {
+                let value = scale * (1.0 - f64::from(position) / count);
+                let recency = signals.recency.entry(url_hash).or_default();
+                *recency = recency.max(value);
+            }
+        }
+        Ok(signals)
+    }
+}
+
+/// Best first, each with its score (without the jitter) and reasons
+fn rank(
+    candidates: &[Candidate],
+    library: &[LibraryGame],
+    signals: &Signals,
+    mut jitter: impl FnMut() -> f64,
+) -> Vec<(i64, f64, Vec<RecommendationReason>)> {
+    let owned_hashes: HashSet<i64> = library
+        .iter()
+        .filter(|g| !g.href.is_empty())
+        .map(|g| hash_url(&g.href))
+        .collect();
+    let owned_titles: HashSet<String> = library.iter().map(|g| base_title(&g.title)).collect();
+
+    let candidates: Vec<(&Candidate, HashSet<Feature>)> = candidates
+        .iter()
+        .filter(|c| {
+            !owned_hashes.contains(&c.url_hash) && !owned_titles.contains(&base_title(&c.title))
+        })
+        .map(|c| (c, features(&c.tag, &c.details)))
+        .collect();
+
+    // rare genres say more about taste than "Action"
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (_, features) in &candidates {
+        for feature in features {
+            *counts.entry(&feature.key).or_default() += 1;
+        }
+    }
+    let total = candidates.len() as f64;
+    let idf = |key: &str| {
+        let count = counts.get(key).copied().unwrap_or(0) as f64;
+        ((total + 1.0) / (count + 1.0)).ln() + 1.0
+    };
+
+    let library: Vec<(&LibraryGame, f64, HashSet<Feature>)> = library
+        .iter()
+        .map(|g| (g, g.weight(), features(&g.tag, &g.details)))
+        .collect();
+    let mut profile: HashMap<&str, f64> = HashMap::new();
+    for (_, weight, features) in &library {
+        for feature in features {
+            *profile.entry(&feature.key).or_default() += weight;
+        }
+    }
+
+    let similarities: Vec<f64> = candidates
+        .iter()
+        .map(|(_, features)| {
+            if features.is_empty() {
+                return 0.0;
+            }
+            let overlap: f64 = features
+                .iter()
+                .filter_map(|f| {
+                    profile
+                        .get(f.key.as_str())
+                        .map(|w| w * idf(&f.key) * f.weight())
+                })
+                .sum();
+            overlap / (features.len() as f64).sqrt()
+        })
+        .collect();
+    let best = similarities.iter().copied().fold(0.0, f64::max);
+
+    let mut ranked: Vec<(f64, i64, f64, Vec<RecommendationReason>)> = candidates
+        .iter()
+        .zip(similarities)
+        .map(|((candidate, features), similarity)| {
+            let similarity = if best > 0.0 { similarity / best } else { 0.0 };
+            let popular = signals.popular.get(&candidate.url_hash).copied();
+            let recency = signals
+                .recency
+                .get(&candidate.url_hash)
+                .copied()
+                .unwrap_or(0.0);
+            let score = SIMILARITY_WEIGHT * similarity
+                + POPULARITY_WEIGHT * popular.map_or(0.0, |(_, value)| value)
+                + RECENCY_WEIGHT * recency;
+
+            let mut reasons = Vec::new();
+            if similarity > 0.0 {
+                reasons.extend(closest(features, &library, &idf));
+            }
+            if let Some((rank, _)) = popular.filter(|(rank, _)| *rank <= POPULAR_REASON_RANK) {
+                reasons.push(RecommendationReason::Popular { rank });
+            }
+            if recency > 0.0 {
+                reasons.push(RecommendationReason::Recent);
+            }
+
+            (
+                score + JITTER * jitter(),
+                candidate.url_hash,
+                score,
+                reasons,
+            )
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    ranked.truncate(LIMIT);
+    ranked
+        .into_iter()
+        .map(|(_, url_hash, score, reasons)| (url_hash, score, reasons))
+        .collect()
+}
+
+/// The library game that contributes most to the similarity of `features`
+fn closest(
+    features: &HashSet<Feature>,
+    library: &[(&LibraryGame, f64, HashSet<Feature>)],
+    idf: &impl Fn(&str) -> f64,
+) -> Option<RecommendationReason> {
+    library
+        .iter()
+        .filter_map(|(game, weight, game_features)| {
+            let mut shared: Vec<&Feature> = features.intersection(game_features).collect();
+            if shared.is_empty() {
+                return None;
+            }
+            let contribution: f64 = shared
+                .iter()
+                .map(|f| weight * idf(&f.key) * f.weight())
+                .sum();
+            shared
+                .sort_by(|a, b| (idf(&b.key) * b.weight()).total_cmp(&(idf(&a.key) * a.weight())));
+            let shared = shared
+                .into_iter()
+                .take(MAX_SHARED)
+                .map(|f| f.name.clone())
+                .collect();
+            Some((contribution, game, shared))
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, game, shared)| game.reason(shared))
+}
+
+/// Rank the discovery feed for `library` and store the reasons
+pub fn refresh_recommendations(
+    app: &AppHandle,
+    library: &[LibraryGame],
+) -> Result<(), ScrapingError> {
+    let conn = db::open_connection(app)?;
+    let candidates = db::recommendation_candidates(&conn)?;
+    let signals = Signals::load(&conn)?;
+
+    let mut rng = rng();
+    let ranked = rank(&candidates, library, &signals, || rng.random());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    db::save_recommendations(&conn, &ranked, now)?;
+    info!(
+        "Ranked {} discovery games out of {} for {} library games",
+        ranked.len(),
+        candidates.len(),
+        library.len()
+    );
+
+    if let Some(main) = app.get_window("main") {
+        let _ = main.emit("discovery-ready", ());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(url_hash: i64, title: &str, tag: &str, company: &str) -> Candidate {
+        Candidate {
+            url_hash,
+            title: title.into(),
+            tag: tag.into(),
+            details: format!("Genres/Tags: {tag}\nCompanies: {company}"),
+        }
+    }
+
+    #[test]
+    fn features_come_from_tag_and_companies() {
+        let features = features(
+            "",
+            "Genres/Tags: Action, Metroidvania\nCompanies: Team Cherry\nLanguages: ENG",
+        );
+        let mut keys: Vec<&str> = features.iter().map(|f| f.key.as_str()).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            ["company:team cherry", "genre:action", "genre:metroidvania"]
+        );
+        assert_eq!(
+            base_title("Hollow Knight – v1.5.78 + 2 DLCs"),
+            "hollow knight"
+        );
+    }
+
+    #[test]
+    fn played_games_drive_the_ranking() {
+        let candidates = [
+            candidate(1, "Owned Game – v2", "Metroidvania", "Team Cherry"),
+            candidate(2, "Silksong", "Action, Metroidvania", "Team Cherry"),
+            candidate(3, "Farm Life", "Simulation", "Someone"),
+            candidate(4, "Big Shooter", "Shooter", "Big Studio"),
+        ];
+        let library = [LibraryGame {
+            title: "Owned Game – v1".into(),
+            tag: "Action, Metroidvania".into(),
+            details: "Companies: Team Cherry".into(),
+            downloaded: true,
+            play_time: 600,
+            ..Default::default()
+        }];
+        let signals = Signals {
+            popular: HashMap::from([(4, (1, 1.0))]),
+            recency: HashMap::new(),
+        };
+
+        let ranked = rank(&candidates, &library, &signals, || 0.0);
+        let order: Vec<i64> = ranked.iter().map(|(hash, _, _)| *hash).collect();
+        assert_eq!(order, [2, 4, 3]);
+
+        let RecommendationReason::SimilarTo {
+            title,
+            relation,
+            shared,
+            ..
+        } = &ranked[0].2[0]
+        else {
+            panic!("expected a similarity reason, got {:?}", ranked[0].2);
+        };
+        assert_eq!(title, "Owned Game – v1");
+        assert_eq!(relation, "played");
+        assert_eq!(shared[0], "Team Cherry");
+        assert_eq!(ranked[1].2, [RecommendationReason::Popular { rank: 1 }]);
+    }
+}
@@ -1,22 +1,29 @@
 //! Core scraping logic for game data.
 
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
+use fit_launcher_config::client::connectivity;
 use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
+use fit_launcher_config::client::scheduler::RetryBudget;
+use fit_launcher_config::client::site::{self, CANONICAL_ORIGIN};
 use futures::StreamExt;
 use itertools::Itertools;
 use reqwest::Response;
 use scraper::Html;
 use tauri::{AppHandle, Emitter};
-use tokio::time::timeout;
 use tracing::{error, info, warn};
 
 use crate::captcha::handle_ddos_guard_captcha;
 use crate::db::{self, hash_url};
+use crate::discovery::try_high_res_img;
 use crate::errors::ScrapingError;
+use crate::health::ParseStats;
 use crate::parser::{find_preview_image, parse_game_from_article};
 use crate::structs::Game;
 
+/// Attempts at reading a page body
+const BODY_ATTEMPTS: u32 = 5;
+
 fn likely_guarded(resp: &Response) -> bool {
     resp.status().as_u16() == 403
         && resp
@@ -28,24 +35,33 @@ fn likely_guarded(resp: &Response) -> bool {
 }
 
 async fn get_response(client: &reqwest::Client, url: &str) -> Result<Response, ScrapingError> {
-    let fetch = client.get(url).send();
-    timeout(Duration::from_secs(60), fetch)
+    site::get_with_failover(client, url)
         .await
-        .map_err(|_| ScrapingError::TimeoutError(url.into()))?
-        .map_err(|e| ScrapingError::ReqwestError(e.to_string()))
+        .map_err(|e| match e.is_timeout() {
+            true => ScrapingError::TimeoutError(url.into()),
+            false => ScrapingError::ReqwestError(e.to_string()),
+        })
 }
 
 pub async fn fetch_page(url: &str, app: &AppHandle) -> Result<String, ScrapingError> {
+    if connectivity::is_site_unavailable() {
+        return Err(ScrapingError::Offline);
+    }
     let client = CUSTOM_DNS_CLIENT.read().await.clone();
-    let mut tries = 0u32;
+    // the request itself is retried by `get_with_failover`, this is for the body
+    let mut budget = RetryBudget::new(BODY_ATTEMPTS);
+    // a page still guarded after one challenge fails with its status
+    let mut challenged = false;
     loop {
         let resp = get_response(&client, url).await?;
 
-        if likely_guarded(&resp) {
+        if likely_guarded(&resp) && !challenged {
+            challenged = true;
             let cookies = resp.cookies().collect::<Vec<_>>();
             warn!("cookies: {cookies:?}, status: {}", resp.status().as_u16());
 
-            match handle_ddos_guard_captcha(app, url).await {
+            // solved on the mirror that answered, the cookies are per domain
+            match handle_ddos_guard_captcha(app, resp.url().as_str()).await {
                 Ok(_) => continue,
                 Err(e) => error!("captcha handler error: {e}"),
             }
@@ -59,21 +75,62 @@ pub async fn fetch_page(url: &str, app: &AppHandle) -> Result<String, ScrapingEr
             )));
         }
 
+        let host = resp.url().host_str().unwrap_or_default().to_string();
         match resp.text().await {
-            Err(_) if tries < 5 => tries += 1,
-            Err(e) => Err(ScrapingError::ReqwestError(e.to_string()))?,
             Ok(content) => break Ok(content),
+            Err(e) => {
+                if !budget.backoff(&host).await {
+                    return Err(ScrapingError::ReqwestError(e.to_string()));
+                }
+                warn!("retry {url}: {e}");
+            }
         }
     }
 }
 
+/// Fetch and parse a single game page, with high-res secondary images
+pub async fn scrape_game_page(url: &str, app: &AppHandle) -> Result<Game, ScrapingError> {
+    let body = fetch_page(url, app).await?;
+    let link = url.to_owned();
+    let mut game = tokio::task::spawn_blocking(move || {
+        let doc = Html::parse_document(&body);
+        let article = doc
+            .select(&scraper::Selector::parse("article").unwrap())
+            .next()
+            .ok_or(ScrapingError::ArticleNotFound(link))?;
+        let mut game = parse_game_from_article(article);
+        if game.img.is_empty() {
+            game.img = find_preview_image(article).unwrap_or_default();
+        }
+        Ok::<Game, ScrapingError>(game)
+    })
+    .await
+    .map_err(|e| ScrapingError::GeneralError(e.to_string()))??;
+
+    game.secondary_images = futures::stream::iter(std::mem::take(&mut game.secondary_images))
+        .map(|s| async move { try_high_res_img(&s).await })
+        .buffered(5)
+        .collect()
+        .await;
+    if game.href.is_empty() {
+        game.href = url.to_owned();
+    }
+
+    Ok(game)
+}
+
 fn write_games_to_db(app: &AppHandle, games: &[Game], category: &str) -> Result<(), ScrapingError> {
     let conn = db::open_connection(app)?;
-    db::set_category_games(&conn, category, games, hash_url)
+    db::set_category_games(&conn, category, games, hash_url)?;
+    db::mark_synced(&conn, category)
 }
 
-async fn scrape_new_games_page(page: u32, app: AppHandle) -> Result<Vec<Game>, ScrapingError> {
-    let url = format!("https://fitgirl-repacks.site/category/lossless-repack/page/{page}");
+async fn scrape_new_games_page(
+    page: u32,
+    app: AppHandle,
+    stats: &ParseStats,
+) -> Result<Vec<Game>, ScrapingError> {
+    let url = format!("{CANONICAL_ORIGIN}/category/lossless-repack/page/{page}");
     let body = fetch_page(&url, &app).await?;
     let document = Html::parse_document(&body);
     let article_selector = scraper::Selector::parse("article")
@@ -82,6 +139,7 @@ async fn scrape_new_games_page(page: u32, app: AppHandle) -> Result<Vec<Game>, S
     let mut games = Vec::new();
     for article in document.select(&article_selector) {
         let game = parse_game_from_article(article);
+        stats.record(&game);
         if game.title.is_empty() || game.img.is_empty() || game.href.is_empty() {
             continue;
         }
@@ -95,11 +153,13 @@ pub async fn scrape_new_games(app: AppHandle) -> Result<(), ScrapingError> {
 
     const PAGE_COUNT: u32 = 3;
 
+    let stats = ParseStats::default();
     let stream = futures::stream::iter(0..PAGE_COUNT)
         .map(|page| {
             let ah = app.clone();
+            let stats = &stats;
             async move {
-                let res = scrape_new_games_page(page, ah).await;
+                let res = scrape_new_games_page(page, ah, stats).await;
                 (page, res)
             }
         })
@@ -120,6 +180,7 @@ pub async fn scrape_new_games(app: AppHandle) -> Result<(), ScrapingError> {
     }
 
     let scraped_games: Vec<Game> = per_page.into_iter().flatten().collect();
+    stats.report(&app, "newly_added");
 
     if scraped_games.is_empty() {
         warn!("No newly added games found on website");
@@ -136,6 +197,7 @@ pub async fn scrape_new_games(app: AppHandle) -> Result<(), ScrapingError> {
         existing_games.iter().map(|g| g.href.clone()).collect();
 
     if current_urls == existing_urls && existing_games.len() == scraped_games.len() {
+        db::mark_synced(&conn, "newly_added")?;
         info!(
             "Newly added games already in sync ({} games), skipping",
             existing_games.len()
@@ -160,13 +222,13 @@ pub async fn scrape_new_games(app: AppHandle) -> Result<(), ScrapingError> {
 pub async fn scrape_popular_games(app: AppHandle) -> Result<(), ScrapingError> {
     let start = Instant::now();
 
-    let body = fetch_page("https://fitgirl-repacks.site/popular-repacks/", &app).await?;
+    let body = fetch_page(&format!("{CANONICAL_ORIGIN}/popular-repacks/"), &app).await?;
     let doc = Html::parse_document(&body);
 
     let popular_data: Vec<(String, String)> = doc
         .select(&scraper::Selector::parse(".widget-grid-view-image > a").unwrap())
         .filter_map(|a| {
-            let href = a.value().attr("href")?.to_string();
+            let href = site::normalize_href(a.value().attr("href")?);
             let img = a
                 .select(&scraper::Selector::parse("img").unwrap())
                 .next()
@@ -193,6 +255,7 @@ pub async fn scrape_popular_games(app: AppHandle) -> Result<(), ScrapingError> {
         existing_games.iter().map(|g| g.href.clone()).collect();
 
     if current_urls == existing_urls && existing_games.len() == popular_data.len() {
+        db::mark_synced(&conn, "popular")?;
         info!(
             "Popular games already in sync ({} games), skipping",
             existing_games.len()
@@ -227,9 +290,11 @@ pub async fn scrape_popular_games(app: AppHandle) -> Result<(), ScrapingError> {
     if !missing.is_empty() {
         info!("Fetching {} new popular games", fetched_count);
 
+        let stats = ParseStats::default();
         let stream = futures::stream::iter(missing.into_iter())
             .map(|(idx, link, thumb)| {
                 let ah = app.clone();
+                let stats = &stats;
                 async move {
                     let body = fetch_page(&link, &ah).await?;
                     let doc = Html::parse_document(&body);
@@ -239,6 +304,7 @@ pub async fn scrape_popular_games(app: AppHandle) -> Result<(), ScrapingError> {
                         .ok_or(ScrapingError::ArticleNotFound(link.clone()))?;
 
                     let mut game = parse_game_from_article(article);
+                    stats.record(&game);
                     if game.img.is_empty() {
                         game.img = thumb.clone();
                     }
@@ -258,6 +324,7 @@ pub async fn scrape_popular_games(app: AppHandle) -> Result<(), ScrapingError> {
                 Err(e) => error!("Popular game scrape failed: {:?}", e),
             }
         }
+        stats.report(&app, "popular");
 
         final_games.retain(|g| !g.href.is_empty());
     }
@@ -277,7 +344,7 @@ pub async fn scrape_recently_updated(app: AppHandle) -> Result<(), ScrapingError
     let start = Instant::now();
 
     let body = fetch_page(
-        "https://fitgirl-repacks.site/category/updates-digest/",
+        &format!("{CANONICAL_ORIGIN}/category/updates-digest/"),
         &app,
     )
     .await?;
@@ -286,14 +353,10 @@ pub async fn scrape_recently_updated(app: AppHandle) -> Result<(), ScrapingError
     let links: Vec<String> = doc
         .select(&scraper::Selector::parse(".su-spoiler-content > a:first-child").unwrap())
         .filter_map(|e| e.value().attr("href"))
-        .filter(|href| {
-            href.starts_with("https://fitgirl-repacks.site/")
-                && !href.contains('<')
-                && !href.contains('>')
-        })
+        .filter(|href| site::is_site_url(href) && !href.contains('<') && !href.contains('>'))
+        .map(site::normalize_href)
         .unique()
         .take(20)
-        .map(str::to_owned)
         .collect();
 
     if links.is_empty() {
@@ -309,6 +372,7 @@ pub async fn scrape_recently_updated(app: AppHandle) -> Result<(), ScrapingError
         existing.iter().map(|g| g.href.clone()).collect();
 
     if current_urls == existing_urls && existing.len() == links.len() {
+        db::mark_synced(&conn, "recently_updated")?;
         info!(
             "Recently updated games already in sync ({} games), skipping",
             existing.len()
@@ -341,9 +405,11 @@ pub async fn scrape_recently_updated(app: AppHandle) -> Result<(), ScrapingError
             missing.len()
         );
 
+        let stats = ParseStats::default();
         let stream = futures::stream::iter(missing.into_iter())
             .map(|(idx, link)| {
                 let ah = app.clone();
+                let stats = &stats;
                 async move {
                     let body = fetch_page(&link, &ah).await?;
                     let doc = Html::parse_document(&body);
@@ -353,6 +419,7 @@ pub async fn scrape_recently_updated(app: AppHandle) -> Result<(), ScrapingError
                         .ok_or(ScrapingError::ArticleNotFound(link.clone()))?;
 
                     let mut game = parse_game_from_article(article);
+                    stats.record(&game);
                     //this is a quick fix, it's gettin low res images
                     //todo: find a better way to do this
                     game.secondary_images.clear();
@@ -369,6 +436,7 @@ pub async fn scrape_recently_updated(app: AppHandle) -> Result<(), ScrapingError
                 Err(e) => error!("Recent update scrape failed: {:?}", e),
             }
         }
+        stats.report(&app, "recently_updated");
     }
 
     // Drop any still-empty slots (failed fetches)
@@ -2,6 +2,10 @@
 //!
 //! Downloads sitemaps from FitGirl Repacks and stores game URLs directly in the games table.
 
+use fit_launcher_config::client::{
+    connectivity,
+    site::{self, CANONICAL_ORIGIN},
+};
 use regex::Regex;
 use reqwest::Client;
 use scraper::{Html, Selector};
@@ -11,18 +15,25 @@ use tracing::{info, warn};
 use crate::db::{self, SearchIndexEntry};
 use crate::errors::ScrapingError;
 
-const BASE_URL: &str = "https://fitgirl-repacks.site/sitemap_index.xml";
 const MAX_CONCURRENT: usize = 4;
 
 /// Download all sitemaps and store game URLs in the games table.
 pub async fn download_all_sitemaps(app: &AppHandle) -> Result<(), ScrapingError> {
+    if connectivity::is_site_unavailable() {
+        return Err(ScrapingError::Offline);
+    }
     let client = Client::new();
-    let sitemap_index = client.get(BASE_URL).send().await?.text().await?;
-
-    let re = Regex::new(r"https://fitgirl-repacks\.site/post-sitemap(?:\d*)\.xml")?;
+    let index_url = format!("{CANONICAL_ORIGIN}/sitemap_index.xml");
+    let sitemap_index = site::get_with_failover(&client, &index_url)
+        .await?
+        .text()
+        .await?;
+
+    // the index lists URLs on whichever mirror served it
+    let re = Regex::new(r#"https?://[^\s<>"]+/post-sitemap(?:\d*)\.xml"#)?;
     let mut urls: Vec<String> = re
         .find_iter(&sitemap_index)
-        .map(|m| m.as_str().to_string())
+        .map(|m| site::normalize_href(m.as_str()))
         .collect();
 
     urls.sort_by(|a, b| b.cmp(a)); // Reverse order: newest sitemaps first
@@ -95,6 +106,8 @@ pub async fn download_all_sitemaps(app: &AppHandle) -> Result<(), ScrapingError>
 
     futures::future::join_all(tasks).await;
 
+    db::mark_synced(&conn, "sitemaps")?;
+
     // Log final count
     let final_count = db::get_game_count(&conn)?;
     info!(
@@ -110,7 +123,7 @@ async fn download_and_parse_sitemap(
     client: &Client,
     url: &str,
 ) -> Result<Vec<SearchIndexEntry>, ScrapingError> {
-    let content = client.get(url).send().await?.text().await?;
+    let content = site::get_with_failover(client, url).await?.text().await?;
     parse_sitemap_content(&content)
 }
 
@@ -127,7 +140,7 @@ fn parse_sitemap_content(content: &str) -> Result<Vec<SearchIndexEntry>, Scrapin
 
     for url_node in doc.select(&url_selector) {
         if let Some(loc_node) = url_node.select(&loc_selector).next() {
-            let url_text = loc_node.text().collect::<String>().trim().to_string();
+            let url_text = site::normalize_href(&loc_node.text().collect::<String>());
             if !url_text.is_empty()
                 && let Some((slug, title)) = extract_slug_and_title(&url_text)
             {
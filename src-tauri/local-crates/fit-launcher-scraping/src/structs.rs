@@ -32,3 +32,17 @@ pub struct Game {
     #[serde(default)]
     pub pastebin_link: String,
 }
+
+/// Unix timestamps of the last sync of the local data with the site, `None` if never.
+///
+/// Offline, this tells how stale what the read commands return is.
+#[derive(Default, Debug, Serialize, Type, Clone)]
+pub struct DataFreshness {
+    pub newly_added: Option<i64>,
+    pub popular: Option<i64>,
+    pub recently_updated: Option<i64>,
+    pub discovery: Option<i64>,
+    pub sitemaps: Option<i64>,
+    /// The game asked for, if any
+    pub game: Option<i64>,
+}
@@ -0,0 +1,115 @@
+//! Golden tests for `parser::parse_game_from_article`.
+//!
+//! `tests/fixtures/articles/<name>.html` are FitGirl pages and `<name>.json`
+//! the `Game` each one must parse into. The pages still have to be replaced
+//! by real saved ones, see the `README.md` next to them. After an intended
+//! parser change, run with `UPDATE_GOLDEN=1` to rewrite the JSON files, then
+//! review their diff.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use fit_launcher_scraping::parser::parse_game_from_article;
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/articles");
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+fn parse_article(html: &str) -> Value {
+    let doc = Html::parse_document(html);
+    let article = doc
+        .select(&Selector::parse("article").unwrap())
+        .next()
+        .expect("no <article> in the page");
+    serde_json::to_value(parse_game_from_article(article)).unwrap()
+}
+
+/// One line per field that differs
+fn field_diff(expected: &Value, parsed: &Value) -> Vec<String> {
+    let (Some(expected), Some(parsed)) = (expected.as_object(), parsed.as_object()) else {
+        return vec![format!("expected {expected}, parsed {parsed}")];
+    };
+    let mut fields: Vec<&String> = expected.keys().chain(parsed.keys()).collect();
+    fields.sort();
+    fields.dedup();
+    fields
+        .into_iter()
+        .filter(|field| expected.get(*field) != parsed.get(*field))
+        .map(|field| {
+            format!(
+                "  {field}:\n    expected {}\n    parsed   {}",
+                expected.get(field).unwrap_or(&Value::Null),
+                parsed.get(field).unwrap_or(&Value::Null)
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn articles_parse_into_their_golden_games() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let fixtures = fixtures();
+    assert!(!fixtures.is_empty(), "no article fixtures found");
+
+    let mut failures = Vec::new();
+    for html_path in fixtures {
+        let golden_path = html_path.with_extension("json");
+        let parsed = parse_article(&fs::read_to_string(&html_path).unwrap());
+
+        if update {
+            let json = serde_json::to_string_pretty(&parsed).unwrap() + "\n";
+            fs::write(&golden_path, json).unwrap();
+            continue;
+        }
+
+        let Ok(golden) = fs::read_to_string(&golden_path) else {
+            failures.push(format!(
+                "{}: no golden file, run with UPDATE_GOLDEN=1",
+                html_path.display()
+            ));
+            continue;
+        };
+        let diff = field_diff(&serde_json::from_str(&golden).unwrap(), &parsed);
+        if !diff.is_empty() {
+            failures.push(format!("{}:\n{}", html_path.display(), diff.join("\n")));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn unknown_layout_leaves_fields_empty() {
+    // what a markup change looks like to the parser, see `health::ParseStats`
+    let parsed = parse_article(
+        r#"<article>
+            <h1 class="entry-title">Some Game</h1>
+            <div class="entry-content">
+                <section class="game-info">Tags: Action</section>
+                <details><summary>About the game</summary>Text</details>
+            </div>
+        </article>"#,
+    );
+
+    assert_eq!(parsed["title"], "Some Game");
+    for field in [
+        "img",
+        "details",
+        "features",
+        "description",
+        "magnetlink",
+        "href",
+        "tag",
+    ] {
+        assert_eq!(parsed[field], "", "{field} should be empty");
+    }
+}
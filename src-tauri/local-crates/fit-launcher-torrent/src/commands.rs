@@ -13,6 +13,7 @@ use tracing::{error, info};
 use crate::errors::TorrentApiError;
 use crate::functions::TorrentSession;
 use crate::model::FileInfo;
+use crate::selection::{ClassifiedFile, classify_files, select_files};
 
 use super::*;
 
@@ -114,6 +115,23 @@ pub async fn list_torrent_files(
     Ok(files)
 }
 
+#[tauri::command]
+#[specta]
+pub fn classify_torrent_files(files: Vec<FileInfo>) -> Vec<ClassifiedFile> {
+    classify_files(&files)
+}
+
+/// `file_index` of the files to download by default, following the
+/// language and extras preferences of the download settings
+#[tauri::command]
+#[specta]
+pub async fn preselect_torrent_files(
+    state: tauri::State<'_, Arc<TorrentSession>>,
+    files: Vec<FileInfo>,
+) -> Result<Vec<usize>, TorrentApiError> {
+    Ok(select_files(&files, &state.config().await.selection))
+}
+
 #[tauri::command]
 #[specta]
 pub async fn magnet_to_file(
@@ -165,6 +165,10 @@ pub struct Bittorrent {
     /// The time (in minutes) to continue seeding after the download completes.
     /// If `None`, no time limit is enforced (infinite seeding allowed).
     pub seed_time: Option<u32>,
+
+    /// Minutes without progress and without peers before a torrent job is flagged as stalled.
+    /// If `None`, jobs are never flagged.
+    pub stall_timeout: Option<u32>,
 }
 
 impl Default for Bittorrent {
@@ -175,6 +179,7 @@ impl Default for Bittorrent {
             max_peers: 60,
             seed_ratio: None,
             seed_time: None,
+            stall_timeout: Some(10),
         }
     }
 }
@@ -189,6 +194,43 @@ pub struct FitLauncherConfigV2 {
     pub network: Connection,
     pub bittorrent: Bittorrent,
     pub rpc: FitLauncherConfigAria2,
+    pub engine: TorrentEngine,
+    pub selection: FileSelection,
+}
+
+/// Which backend transfers torrent jobs.
+///
+/// Direct links always go through aria2, librqbit only speaks BitTorrent.
+#[derive(
+    Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Type, Debug, Default, strum::EnumIs,
+)]
+pub enum TorrentEngine {
+    /// External aria2c daemon, driven over its websocket RPC
+    #[default]
+    Aria2,
+    /// The librqbit session embedded in the launcher, no aria2c needed
+    Librqbit,
+}
+
+/// Which FitGirl optional and selective files are picked for a new torrent job
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Type, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FileSelection {
+    /// Language packs to keep, as FitGirl spells them (`english`, `french`, ...)
+    pub preferred_languages: Vec<String>,
+    /// Bonus content such as artbooks or videos
+    pub include_bonus: bool,
+    pub include_soundtrack: bool,
+}
+
+impl Default for FileSelection {
+    fn default() -> Self {
+        Self {
+            preferred_languages: vec!["english".to_string()],
+            include_bonus: false,
+            include_soundtrack: false,
+        }
+    }
 }
 
 //
@@ -215,6 +257,8 @@ impl From<LegacyFitLauncherConfig> for FitLauncherConfigV2 {
                 ..Default::default()
             },
             rpc: old.aria2_rpc,
+            engine: TorrentEngine::default(),
+            selection: FileSelection::default(),
         }
     }
 }
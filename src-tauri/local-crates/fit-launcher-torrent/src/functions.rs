@@ -12,7 +12,7 @@ use std::{
 
 use tokio::{
     sync::{
-        Mutex,
+        Mutex, broadcast,
         oneshot::{Receiver, Sender, channel},
     },
     time::sleep,
@@ -31,6 +31,32 @@ type AriaDaemonType = Arc<tokio::sync::Mutex<Option<(Sender<()>, Receiver<()>, u
 pub static ARIA2_DAEMON: LazyLock<AriaDaemonType> =
     LazyLock::new(|| Arc::new(tokio::sync::Mutex::new(None)));
 
+/// An aria2c we spawned exited without being asked to
+#[derive(Debug, Clone)]
+pub struct DaemonExit {
+    pub port: u16,
+    /// `None` when killed by a signal or the status could not be read
+    pub code: Option<i32>,
+}
+
+static ARIA2_EXITS: LazyLock<broadcast::Sender<DaemonExit>> =
+    LazyLock::new(|| broadcast::channel(8).0);
+
+/// Be told whenever a spawned aria2c dies unexpectedly.
+pub fn subscribe_daemon_exits() -> broadcast::Receiver<DaemonExit> {
+    ARIA2_EXITS.subscribe()
+}
+
+#[cfg(windows)]
+async fn wait_daemon(child: &crate::hooks::windows::JobChild) -> Option<i32> {
+    child.wait().await.ok().map(|code| code as i32)
+}
+
+#[cfg(not(windows))]
+async fn wait_daemon(child: &mut tokio::process::Child) -> Option<i32> {
+    child.wait().await.ok().and_then(|status| status.code())
+}
+
 struct StateShared {
     config: FitLauncherConfigV2,
     aria2_client: Option<Client>,
@@ -137,8 +163,16 @@ fn build_aria2_args(
     }
     a.push("--bt-remove-unselected-file=true".into());
     // Session persistence --------------------------------------------------
+    // Restored downloads start paused, the download manager reattaches them to
+    // their jobs and resumes the ones that were running. New ones opt out.
+    if session_path.exists() {
+        a.push(format!("--input-file={}", session_path.display()));
+    }
+    a.push("--pause=true".into());
     a.push("--save-session".into());
     a.push(session_path.display().to_string());
+    // Also save periodically, a crashed daemon never writes it on exit
+    a.push("--save-session-interval=30".into());
     a.push("--log".into());
     a.push(log_path.display().to_string());
     a.push("--log-level=warn".into());
@@ -286,13 +320,33 @@ pub async fn aria2_client_from_config(
         let client_clone = client.clone();
 
         tokio::spawn(async move {
-            match close_rx.await {
-                Ok(()) => {
+            // Ok(requested) when told to close, Err(code) when aria2c exited on its own
+            let end = tokio::select! {
+                closed = close_rx => Ok(closed.is_ok()),
+                code = wait_daemon(&mut child) => Err(code),
+            };
+
+            match end {
+                Ok(true) => {
                     let _ = client_clone.force_shutdown().await;
                     let _ = child.kill().await;
                     let _ = done_tx.send(());
                 }
-                Err(_) => warn!("Shutdown signal dropped"),
+                Ok(false) => warn!("Shutdown signal dropped"),
+                Err(code) => {
+                    warn!("aria2c on port {rpc_port} exited unexpectedly (code {code:?})");
+                    {
+                        let mut guard = ARIA2_DAEMON.lock().await;
+                        if guard.as_ref().is_some_and(|(_, _, port)| *port == rpc_port) {
+                            *guard = None;
+                        }
+                    }
+                    let _ = done_tx.send(());
+                    let _ = ARIA2_EXITS.send(DaemonExit {
+                        port: rpc_port,
+                        code,
+                    });
+                }
             }
         });
 
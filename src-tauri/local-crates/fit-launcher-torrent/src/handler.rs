@@ -1,17 +1,22 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
 
 use directories::BaseDirs;
 use fit_launcher_scraping::db;
 use librqbit::{
-    AddTorrent, AddTorrentOptions, AddTorrentResponse, ListOnlyResponse, Magnet, Session,
-    SessionOptions, api::TorrentIdOrHash,
+    AddTorrent, AddTorrentOptions, AddTorrentResponse, ListOnlyResponse, Magnet,
+    ManagedTorrentHandle, Session, SessionOptions, TorrentStatsState, api::TorrentIdOrHash,
 };
 use librqbit_dht::PersistentDhtConfig;
 use parking_lot::Mutex;
 use tokio::io::AsyncWriteExt;
 use tracing::{error, info, warn};
 
-use crate::{decrypt_torrent_from_paste, dht::dht_config_with_udp, errors::TorrentApiError};
+use crate::{
+    decrypt_torrent_from_paste,
+    dht::dht_config_with_udp,
+    errors::TorrentApiError,
+    model::{TransferState, TransferStats},
+};
 
 pub struct LibrqbitSession {
     session: Arc<Mutex<Option<Arc<Session>>>>,
@@ -46,6 +51,14 @@ impl LibrqbitSession {
         }
     }
 
+    /// A session that never started, every call fails as after [`Self::shutdown`].
+    /// Lets callers that only need the type (e.g. tests) skip DHT and port setup.
+    pub fn detached() -> Self {
+        Self {
+            session: Arc::new(Mutex::new(None)),
+        }
+    }
+
     /// Shuts down the session by dropping it. Drop = port release.
     /// Idempotent - safe to call multiple times.
     pub fn shutdown(&self) {
@@ -108,6 +121,152 @@ impl LibrqbitSession {
             _ => Err(TorrentApiError::UnexpectedTorrentState),
         }
     }
+
+    fn active_session(&self) -> Result<Arc<Session>, TorrentApiError> {
+        self.session
+            .lock()
+            .as_ref()
+            .cloned()
+            .ok_or(TorrentApiError::LibrqbitError)
+    }
+
+    fn handle(&self, info_hash: &str) -> Result<ManagedTorrentHandle, TorrentApiError> {
+        let id = TorrentIdOrHash::parse(info_hash).map_err(|_| TorrentApiError::TorrentNotFound)?;
+        self.active_session()?
+            .get(id)
+            .ok_or(TorrentApiError::TorrentNotFound)
+    }
+
+    /// Start downloading `torrent` into `dir`, restricted to `only_files` when not empty.
+    ///
+    /// Multi-file torrents land in `dir/<torrent name>`, the same layout aria2 uses,
+    /// and existing partial files are resumed. Returns the info hash.
+    pub async fn add_download(
+        &self,
+        torrent: Vec<u8>,
+        dir: PathBuf,
+        only_files: Vec<usize>,
+    ) -> Result<String, TorrentApiError> {
+        let meta = librqbit::torrent_from_bytes::<'_, librqbit_buffers::ByteBuf>(&torrent)
+            .map_err(|_| TorrentApiError::MetadataError)?;
+        let output_folder = match (&meta.info.files, &meta.info.name) {
+            (Some(_), Some(name)) => dir.join(String::from_utf8_lossy(name).as_ref()),
+            _ => dir,
+        };
+
+        let session = self.active_session()?;
+        let response = session
+            .add_torrent(
+                AddTorrent::from_bytes(torrent.clone()),
+                Some(AddTorrentOptions {
+                    only_files: (!only_files.is_empty()).then_some(only_files),
+                    output_folder: Some(output_folder.to_string_lossy().into_owned()),
+                    overwrite: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .map_err(|e| {
+                error!("librqbit failed to add torrent: {e:#}");
+                TorrentApiError::LibrqbitError
+            })?;
+
+        match response {
+            AddTorrentResponse::Added(_, handle) => Ok(handle.info_hash().as_string()),
+            AddTorrentResponse::AlreadyManaged(_, handle) => {
+                // a paused leftover from a previous add, pick it back up
+                if let Err(e) = session.unpause(&handle).await {
+                    warn!("librqbit could not unpause existing torrent: {e:#}");
+                }
+                Ok(handle.info_hash().as_string())
+            }
+            AddTorrentResponse::ListOnly(_) => Err(TorrentApiError::UnexpectedTorrentState),
+        }
+    }
+
+    pub async fn pause_download(&self, info_hash: &str) -> Result<(), TorrentApiError> {
+        let handle = self.handle(info_hash)?;
+        self.active_session()?.pause(&handle).await.map_err(|e| {
+            error!("librqbit failed to pause {info_hash}: {e:#}");
+            TorrentApiError::UnexpectedTorrentState
+        })
+    }
+
+    pub async fn resume_download(&self, info_hash: &str) -> Result<(), TorrentApiError> {
+        let handle = self.handle(info_hash)?;
+        self.active_session()?.unpause(&handle).await.map_err(|e| {
+            error!("librqbit failed to resume {info_hash}: {e:#}");
+            TorrentApiError::UnexpectedTorrentState
+        })
+    }
+
+    /// Stop tracking a transfer. Downloaded files are kept on disk.
+    pub async fn remove_download(&self, info_hash: &str) -> Result<(), TorrentApiError> {
+        let id = TorrentIdOrHash::parse(info_hash).map_err(|_| TorrentApiError::TorrentNotFound)?;
+        self.active_session()?.delete(id, false).await.map_err(|e| {
+            error!("librqbit failed to remove {info_hash}: {e:#}");
+            TorrentApiError::TorrentNotFound
+        })
+    }
+
+    /// Fetch `only_files` from now on, pieces already on disk are kept
+    pub async fn select_download_files(
+        &self,
+        info_hash: &str,
+        only_files: Vec<usize>,
+    ) -> Result<(), TorrentApiError> {
+        let handle = self.handle(info_hash)?;
+        let only_files: HashSet<usize> = only_files.into_iter().collect();
+        self.active_session()?
+            .update_only_files(&handle, &only_files)
+            .await
+            .map_err(|e| {
+                error!("librqbit failed to change files of {info_hash}: {e:#}");
+                TorrentApiError::UnexpectedTorrentState
+            })
+    }
+
+    pub fn download_stats(&self, info_hash: &str) -> Result<TransferStats, TorrentApiError> {
+        let handle = self.handle(info_hash)?;
+        let stats = handle.stats();
+
+        let state = match stats.state {
+            TorrentStatsState::Error => TransferState::Error,
+            _ if stats.finished => TransferState::Finished,
+            TorrentStatsState::Initializing => TransferState::Initializing,
+            TorrentStatsState::Live => TransferState::Live,
+            TorrentStatsState::Paused => TransferState::Paused,
+        };
+        // librqbit reports speeds in MiB/s
+        let to_bytes = |mib_per_sec: f64| (mib_per_sec * 1024.0 * 1024.0) as u64;
+        let (download_speed, upload_speed) = stats
+            .live
+            .as_ref()
+            .map(|live| {
+                (
+                    to_bytes(live.download_speed.mbps),
+                    to_bytes(live.upload_speed.mbps),
+                )
+            })
+            .unwrap_or_default();
+        let peers = stats
+            .live
+            .as_ref()
+            .map(|live| live.snapshot.peer_stats.live as u64)
+            .unwrap_or_default();
+
+        Ok(TransferStats {
+            state,
+            info_hash: handle.info_hash().as_string(),
+            total_bytes: stats.total_bytes,
+            progress_bytes: stats.progress_bytes,
+            download_speed,
+            upload_speed,
+            peers,
+            file_progress: stats.file_progress,
+            error: stats.error,
+        })
+    }
 }
 
 pub async fn get_torrent_idx_from_url(url: &str) -> Result<TorrentIdOrHash, TorrentApiError> {
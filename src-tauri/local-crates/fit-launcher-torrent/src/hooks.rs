@@ -14,8 +14,8 @@ pub(crate) mod windows {
         SetInformationJobObject,
     };
     use windows::Win32::System::Threading::{
-        CREATE_NO_WINDOW, CREATE_SUSPENDED, CreateProcessW, PROCESS_INFORMATION, ResumeThread,
-        STARTUPINFOW, TerminateProcess,
+        CREATE_NO_WINDOW, CREATE_SUSPENDED, CreateProcessW, GetExitCodeProcess, INFINITE,
+        PROCESS_INFORMATION, ResumeThread, STARTUPINFOW, TerminateProcess, WaitForSingleObject,
     };
 
     use windows::core::{PCWSTR, PWSTR};
@@ -92,6 +92,21 @@ pub(crate) mod windows {
             unsafe { TerminateProcess(self.process, 1).ok() };
             Ok(())
         }
+
+        /// Resolves once the process exits, with its exit code
+        pub async fn wait(&self) -> Result<u32> {
+            // raw handles aren't Send, their address is
+            let process = self.process.0 as usize;
+            tokio::task::spawn_blocking(move || unsafe {
+                let process = HANDLE(process as *mut _);
+                WaitForSingleObject(process, INFINITE);
+                let mut code = 0u32;
+                GetExitCodeProcess(process, &mut code).context("GetExitCodeProcess failed")?;
+                Ok(code)
+            })
+            .await
+            .context("wait task panicked")?
+        }
     }
 
     /// Spawn a Child inside of a job object.
@@ -10,3 +10,4 @@ pub use handler::*;
 pub mod dht;
 pub mod hooks;
 pub mod model;
+pub mod selection;
@@ -8,3 +8,31 @@ pub struct FileInfo {
     pub length: u64,
     pub file_index: usize,
 }
+
+/// Lifecycle of a transfer running in the embedded librqbit session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferState {
+    Initializing,
+    Live,
+    Paused,
+    Finished,
+    Error,
+}
+
+/// Progress of a transfer running in the embedded librqbit session
+#[derive(Debug, Clone)]
+pub struct TransferStats {
+    pub state: TransferState,
+    pub info_hash: String,
+    pub total_bytes: u64,
+    pub progress_bytes: u64,
+    /// In bytes/sec
+    pub download_speed: u64,
+    /// In bytes/sec
+    pub upload_speed: u64,
+    /// Peers currently connected
+    pub peers: u64,
+    /// Downloaded bytes of each file of the torrent
+    pub file_progress: Vec<u64>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,173 @@
+//! Picks which files of a FitGirl torrent to download.
+//!
+//! Repacks ship the game as `fg-XX.bin` archives next to `fg-selective-<language>`
+//! packs, of which the installer only needs the ones you play in, and
+//! `fg-optional-*` extras such as bonus content or the soundtrack.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{config::FileSelection, model::FileInfo};
+
+/// Languages FitGirl names optional voice packs after, e.g. `fg-optional-japanese-vo.bin`
+const LANGUAGES: &[&str] = &[
+    "arabic",
+    "brazilian",
+    "bulgarian",
+    "chinese",
+    "czech",
+    "danish",
+    "dutch",
+    "english",
+    "finnish",
+    "french",
+    "german",
+    "greek",
+    "hungarian",
+    "italian",
+    "japanese",
+    "korean",
+    "mexican",
+    "norwegian",
+    "polish",
+    "portuguese",
+    "romanian",
+    "russian",
+    "spanish",
+    "swedish",
+    "thai",
+    "turkish",
+    "ukrainian",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FileKind {
+    /// Game archives, the installer and checksums, always downloaded
+    Core,
+    /// `fg-selective-*` or, when `optional`, an `fg-optional-*` voice pack.
+    /// `language` is spelled as in the file name
+    Language {
+        language: String,
+        optional: bool,
+    },
+    /// Any other `fg-optional-*` file, `name` is the part after the prefix
+    Bonus {
+        name: String,
+    },
+    Soundtrack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ClassifiedFile {
+    pub file_index: usize,
+    pub kind: FileKind,
+}
+
+/// Tell what a torrent file is for from its name
+pub fn classify(file_name: &Path) -> FileKind {
+    let stem = file_name
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    if let Some(language) = stem.strip_prefix("fg-selective-") {
+        return FileKind::Language {
+            language: language.to_string(),
+            optional: false,
+        };
+    }
+
+    let Some(name) = stem.strip_prefix("fg-optional-") else {
+        return FileKind::Core;
+    };
+    let words: Vec<&str> = name.split(['-', '_', '.']).collect();
+
+    if words
+        .iter()
+        .any(|w| matches!(*w, "soundtrack" | "ost" | "music"))
+    {
+        FileKind::Soundtrack
+    } else if let Some(language) = words.iter().find(|w| LANGUAGES.contains(*w)) {
+        FileKind::Language {
+            language: language.to_string(),
+            optional: true,
+        }
+    } else {
+        FileKind::Bonus {
+            name: name.to_string(),
+        }
+    }
+}
+
+pub fn classify_files(files: &[FileInfo]) -> Vec<ClassifiedFile> {
+    files
+        .iter()
+        .map(|file| ClassifiedFile {
+            file_index: file.file_index,
+            kind: classify(&file.file_name),
+        })
+        .collect()
+}
+
+/// Whether a language pack named `pack` covers `language`, `english-us` covers `english`
+fn covers(pack: &str, language: &str) -> bool {
+    pack.split(['-', '_'])
+        .any(|word| word.eq_ignore_ascii_case(language.trim()))
+}
+
+/// The `file_index` of every file `profile` wants.
+///
+/// Optional voice packs are only picked in a preferred language. When no
+/// selective pack matches, the English ones are kept, and failing that all
+/// of them, so the game still installs.
+pub fn select_files(files: &[FileInfo], profile: &FileSelection) -> Vec<usize> {
+    let classified = classify_files(files);
+    let packs: Vec<&str> = classified
+        .iter()
+        .filter_map(|c| match &c.kind {
+            FileKind::Language {
+                language,
+                optional: false,
+            } => Some(language.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let has_pack = |language: &str| packs.iter().any(|pack| covers(pack, language));
+    let wanted: Option<Vec<&str>> = if profile.preferred_languages.iter().any(|l| has_pack(l)) {
+        Some(
+            profile
+                .preferred_languages
+                .iter()
+                .map(String::as_str)
+                .collect(),
+        )
+    } else if has_pack("english") {
+        Some(vec!["english"])
+    } else {
+        None
+    };
+
+    classified
+        .into_iter()
+        .filter(|c| match &c.kind {
+            FileKind::Core => true,
+            FileKind::Language {
+                language,
+                optional: true,
+            } => profile
+                .preferred_languages
+                .iter()
+                .any(|l| covers(language, l)),
+            FileKind::Language { language, .. } => wanted
+                .as_ref()
+                .is_none_or(|wanted| wanted.iter().any(|l| covers(language, l))),
+            FileKind::Bonus { .. } => profile.include_bonus,
+            FileKind::Soundtrack => profile.include_soundtrack,
+        })
+        .map(|c| c.file_index)
+        .collect()
+}
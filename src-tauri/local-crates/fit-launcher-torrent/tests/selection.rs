@@ -0,0 +1,94 @@
+use fit_launcher_torrent::{
+    FileSelection,
+    model::FileInfo,
+    selection::{FileKind, classify, select_files},
+};
+
+fn files(names: &[&str]) -> Vec<FileInfo> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| FileInfo {
+            file_name: name.into(),
+            length: 1,
+            file_index: i,
+        })
+        .collect()
+}
+
+const REPACK: &[&str] = &[
+    "fg-01.bin",
+    "fg-02.bin",
+    "MD5/fitgirl-bins.md5",
+    "setup.exe",
+    "fg-selective-english.bin",
+    "fg-selective-french.bin",
+    "fg-selective-russian.bin",
+    "fg-optional-bonus-artbook.bin",
+    "fg-optional-soundtrack.bin",
+    "fg-optional-japanese-vo.bin",
+];
+
+#[test]
+fn classifies_fitgirl_file_names() {
+    assert_eq!(classify("fg-01.bin".as_ref()), FileKind::Core);
+    assert_eq!(classify("MD5/fitgirl-bins.md5".as_ref()), FileKind::Core);
+    assert_eq!(
+        classify("fg-selective-French.bin".as_ref()),
+        FileKind::Language {
+            language: "french".into(),
+            optional: false
+        }
+    );
+    assert_eq!(
+        classify("fg-optional-bonus-ost.bin".as_ref()),
+        FileKind::Soundtrack
+    );
+    assert_eq!(
+        classify("fg-optional-japanese-vo.bin".as_ref()),
+        FileKind::Language {
+            language: "japanese".into(),
+            optional: true
+        }
+    );
+    assert_eq!(
+        classify("fg-optional-bonus-artbook.bin".as_ref()),
+        FileKind::Bonus {
+            name: "bonus-artbook".into()
+        }
+    );
+}
+
+#[test]
+fn default_profile_keeps_core_and_english() {
+    let selected = select_files(&files(REPACK), &FileSelection::default());
+    assert_eq!(selected, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn profile_picks_languages_and_extras() {
+    let profile = FileSelection {
+        preferred_languages: vec!["russian".into(), "japanese".into()],
+        include_bonus: false,
+        include_soundtrack: true,
+    };
+    let selected = select_files(&files(REPACK), &profile);
+    assert_eq!(selected, vec![0, 1, 2, 3, 6, 8, 9]);
+}
+
+#[test]
+fn unknown_language_falls_back_to_english() {
+    let profile = FileSelection {
+        preferred_languages: vec!["polish".into()],
+        ..Default::default()
+    };
+    let selected = select_files(&files(REPACK), &profile);
+    assert_eq!(selected, vec![0, 1, 2, 3, 4]);
+
+    let no_english = files(&[
+        "fg-01.bin",
+        "fg-selective-german.bin",
+        "fg-selective-spanish.bin",
+    ]);
+    assert_eq!(select_files(&no_english, &profile), vec![0, 1, 2]);
+}
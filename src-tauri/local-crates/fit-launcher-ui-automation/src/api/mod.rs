@@ -9,7 +9,8 @@ use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::api::types::InstallationJob;
+use crate::InstallationError;
+use crate::api::types::{InstallOutcome, InstallationJob};
 
 pub type JobList = Arc<RwLock<HashMap<Uuid, Arc<InstallationJob>>>>;
 
@@ -79,6 +80,20 @@ impl InstallationManager {
         }
     }
 
+    /// Cancel every job created for the given download,
+    /// returns whether any job was found
+    pub async fn cancel_by_download(&self, download_id: Uuid) -> bool {
+        let list = self.jobs.read().await;
+        let mut found = false;
+        for job in list.values() {
+            if job.download_id == Some(download_id) {
+                job.cancel_emitter.cancel();
+                found = true;
+            }
+        }
+        found
+    }
+
     /// Remove a job from the list
     pub async fn remove(&self, id: Uuid) {
         self.jobs.write().await.remove(&id);
@@ -93,18 +108,28 @@ impl InstallationManager {
 
     /// Start a job
     pub async fn start_job(&self, id: Uuid, app_handle: tauri::AppHandle) {
+        let _ = self.run_job(id, app_handle).await;
+    }
+
+    /// Run a job's installation to completion, failing the job on error
+    pub async fn run_job(
+        &self,
+        id: Uuid,
+        app_handle: tauri::AppHandle,
+    ) -> Result<InstallOutcome, InstallationError> {
         let job_opt = { self.jobs.read().await.get(&id).cloned() };
         let job = match job_opt {
             Some(j) => j,
-            None => return,
+            None => return Err(InstallationError::IOError(format!("Unknown job {id}"))),
         };
         info!("Starting auto installation: {}", &job.game.title);
 
         let result = job.auto_installation(app_handle, id).await;
-        if let Err(e) = result {
+        if let Err(e) = &result {
             job.cancel_emitter.cancel();
             self.fail_job(id, e).await;
         }
+        result
     }
 
     pub async fn clean_job(&self, id: Uuid) {
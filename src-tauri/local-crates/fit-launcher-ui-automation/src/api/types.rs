@@ -20,6 +20,18 @@ pub struct InstallationJob {
     pub path: PathBuf,
 }
 
+/// Written to the folder of every completed install, only a folder holding
+/// it is removed as a whole on uninstall
+pub const INSTALL_MARKER: &str = ".fit-launcher-install";
+
+/// What a finished installation produced
+#[derive(Debug, Clone, Default)]
+pub struct InstallOutcome {
+    pub install_path: Option<String>,
+    /// Full path of the main executable, when it could be located
+    pub executable: Option<String>,
+}
+
 impl InstallationJob {
     /// returns relative path from game installation directory,
     /// to the game main executable or the launcher.
@@ -80,7 +92,7 @@ impl InstallationJob {
         &self,
         app_handle: tauri::AppHandle,
         id: Uuid,
-    ) -> Result<(), crate::InstallationError> {
+    ) -> Result<InstallOutcome, crate::InstallationError> {
         #[cfg(target_os = "windows")]
         {
             use std::time::Duration;
@@ -326,7 +338,13 @@ impl InstallationJob {
             let _ = manager.shutdown_if_idle(); // Will kill process if no other jobs in queue
 
             // Post-installation finalization: find main executable
+            let mut executable = None;
             if success && let Some(ref path) = install_path_received {
+                let marker = std::path::Path::new(path).join(INSTALL_MARKER);
+                if let Err(e) = std::fs::write(&marker, &self.game.href) {
+                    warn!("Failed to write {}: {e}", marker.display());
+                }
+
                 info!("Finalizing installation: locating main executable...");
                 // We run this async block logic here as before
                 if let Some(exe_path) = self.find_main_executable().await {
@@ -334,6 +352,7 @@ impl InstallationJob {
                     info!("Main executable found: {}", exe_path);
                     let full_exe_path = format!("{}\\{}", path, exe_path);
                     let _ = app_handle.emit("setup::progress::executable", &full_exe_path);
+                    executable = Some(full_exe_path);
                 } else {
                     warn!("Could not locate main executable");
                 }
@@ -350,7 +369,10 @@ impl InstallationJob {
             );
 
             if success {
-                Ok(())
+                Ok(InstallOutcome {
+                    install_path: install_path_received,
+                    executable,
+                })
             } else if cancel_token.is_cancelled() {
                 Err(crate::InstallationError::IOError(
                     "Installation was cancelled".to_string(),
@@ -374,31 +396,37 @@ impl InstallationJob {
     }
 
     pub async fn clean_parts(&self) -> Result<(), crate::InstallationError> {
-        #[cfg(target_os = "windows")]
-        {
-            use tokio::fs;
+        clean_parts(&self.path).await
+    }
+}
 
-            let mut attempts = 0;
-            let path = self.path.clone();
-            loop {
-                match fs::remove_dir_all(path.clone()).await {
-                    Ok(_) => return Ok(()),
-                    Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied && attempts < 20 => {
-                        // Folder still locked by Windows
-
-                        use std::time::Duration;
-                        attempts += 1;
-                        tokio::time::sleep(Duration::from_millis(100)).await;
-                        continue;
-                    }
-                    Err(e) => return Err(InstallationError::IOError(e.to_string())),
+/// Remove the download folder of an installed game, retrying while Windows still holds a lock on it.
+/// No-op on other platforms.
+pub async fn clean_parts(path: &std::path::Path) -> Result<(), crate::InstallationError> {
+    #[cfg(target_os = "windows")]
+    {
+        use tokio::fs;
+
+        let mut attempts = 0;
+        loop {
+            match fs::remove_dir_all(path).await {
+                Ok(_) => return Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied && attempts < 20 => {
+                    // Folder still locked by Windows
+
+                    use std::time::Duration;
+                    attempts += 1;
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
                 }
+                Err(e) => return Err(InstallationError::IOError(e.to_string())),
             }
         }
+    }
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            Ok(())
-        }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = path;
+        Ok(())
     }
 }
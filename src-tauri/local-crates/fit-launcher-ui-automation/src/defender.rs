@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fs, io};
 
+use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use tracing::error;
@@ -44,7 +45,22 @@ pub(crate) fn now_utc() -> i64 {
         .as_secs() as i64
 }
 
-pub(crate) fn load_exclusions(path: &PathBuf) -> FolderExclusionsFile {
+/// Path of the file recording the folders excluded from Defender by the launcher
+pub fn exclusions_file_path() -> Option<PathBuf> {
+    let base_dirs = BaseDirs::new()?;
+
+    Some(
+        base_dirs
+            .config_dir()
+            .join("com.fitlauncher.carrotrub")
+            .join("fitgirlConfig")
+            .join("settings")
+            .join("data")
+            .join("folder_exclusions.json"),
+    )
+}
+
+pub fn load_exclusions(path: &PathBuf) -> FolderExclusionsFile {
     if !path.exists() {
         return FolderExclusionsFile::default();
     }
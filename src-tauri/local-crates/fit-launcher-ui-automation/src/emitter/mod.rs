@@ -1,2 +1,87 @@
-// Emitter module - currently empty as events are handled via controller IPC
-// Kept for future non-automation related emitters if needed
+// Emitters for non-automation events (installation progress itself is
+// forwarded from the controller IPC in `api::types`).
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Emitter};
+
+pub const EXTRACT_PROGRESS_EVENT: &str = "extract::progress";
+
+/// Minimum delay between two progress events for the same archive.
+const EXTRACT_EMIT_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionProgress {
+    /// Archive name as displayed to the user (first volume for RAR sets)
+    pub archive: String,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub entries_done: u64,
+    pub entries_total: u64,
+}
+
+/// Throttled progress sink used by `extraction::extract_archive`.
+///
+/// The final state is always emitted through [`ExtractionEmitter::finish`].
+pub struct ExtractionEmitter {
+    app: Option<AppHandle>,
+    progress: ExtractionProgress,
+    last_emit: Option<Instant>,
+}
+
+impl ExtractionEmitter {
+    pub fn new(app: Option<AppHandle>, archive: impl Into<String>) -> Self {
+        Self {
+            app,
+            progress: ExtractionProgress {
+                archive: archive.into(),
+                ..Default::default()
+            },
+            last_emit: None,
+        }
+    }
+
+    pub fn set_totals(&mut self, bytes_total: u64, entries_total: u64) {
+        self.progress.bytes_total = bytes_total;
+        self.progress.entries_total = entries_total;
+        self.emit(true);
+    }
+
+    pub fn add_bytes(&mut self, bytes: u64) {
+        self.progress.bytes_done = self.progress.bytes_done.saturating_add(bytes);
+        self.emit(false);
+    }
+
+    pub fn entry_done(&mut self) {
+        self.progress.entries_done += 1;
+        self.emit(false);
+    }
+
+    pub fn finish(&mut self) {
+        self.emit(true);
+    }
+
+    pub fn progress(&self) -> &ExtractionProgress {
+        &self.progress
+    }
+
+    fn emit(&mut self, force: bool) {
+        let Some(app) = &self.app else {
+            return;
+        };
+
+        if !force
+            && self
+                .last_emit
+                .is_some_and(|last| last.elapsed() < EXTRACT_EMIT_INTERVAL)
+        {
+            return;
+        }
+
+        self.last_emit = Some(Instant::now());
+        let _ = app.emit(EXTRACT_PROGRESS_EVENT, &self.progress);
+    }
+}
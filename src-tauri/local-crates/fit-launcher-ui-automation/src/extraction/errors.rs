@@ -2,7 +2,8 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 use specta::Type;
-use unrar::error::UnrarError;
+use unrar::error::{Code, UnrarError};
+use zip::result::ZipError;
 
 use crate::InstallationError;
 
@@ -11,9 +12,15 @@ use crate::InstallationError;
 pub enum ExtractError {
     Io(String),
     Unrar(String),
+    Zip(String),
+    SevenZip(String),
     InstallationError(InstallationError),
     NoParentDirectory,
     NoRarFileFound,
+    UnsupportedFormat(String),
+    PasswordRequired,
+    WrongPassword,
+    Cancelled,
 }
 
 impl fmt::Display for ExtractError {
@@ -22,10 +29,18 @@ impl fmt::Display for ExtractError {
             ExtractError::Io(e) => write!(f, "IO error: {e}",),
             ExtractError::InstallationError(e) => write!(f, "Installation error: {e}"),
             ExtractError::Unrar(e) => write!(f, "Unrar error: {e}"),
+            ExtractError::Zip(e) => write!(f, "Zip error: {e}"),
+            ExtractError::SevenZip(e) => write!(f, "7z error: {e}"),
             ExtractError::NoParentDirectory => write!(f, "Archive path has no parent directory"),
             ExtractError::NoRarFileFound => {
                 write!(f, "No rar file was found in the given directory")
             }
+            ExtractError::UnsupportedFormat(name) => {
+                write!(f, "Unsupported archive format: {name}")
+            }
+            ExtractError::PasswordRequired => write!(f, "Archive is password protected"),
+            ExtractError::WrongPassword => write!(f, "Wrong archive password"),
+            ExtractError::Cancelled => write!(f, "Extraction was cancelled"),
         }
     }
 }
@@ -40,7 +55,34 @@ impl From<std::io::Error> for ExtractError {
 
 impl From<UnrarError> for ExtractError {
     fn from(err: UnrarError) -> Self {
-        ExtractError::Unrar(err.to_string())
+        match err.code {
+            Code::MissingPassword => ExtractError::PasswordRequired,
+            Code::BadPassword => ExtractError::WrongPassword,
+            _ => ExtractError::Unrar(err.to_string()),
+        }
+    }
+}
+
+impl From<ZipError> for ExtractError {
+    fn from(err: ZipError) -> Self {
+        match err {
+            ZipError::InvalidPassword => ExtractError::WrongPassword,
+            ZipError::UnsupportedArchive(msg) if msg.contains("Password required") => {
+                ExtractError::PasswordRequired
+            }
+            ZipError::Io(e) => ExtractError::Io(e.to_string()),
+            e => ExtractError::Zip(e.to_string()),
+        }
+    }
+}
+
+impl From<sevenz_rust::Error> for ExtractError {
+    fn from(err: sevenz_rust::Error) -> Self {
+        match err {
+            sevenz_rust::Error::PasswordRequired => ExtractError::PasswordRequired,
+            sevenz_rust::Error::MaybeBadPassword(_) => ExtractError::WrongPassword,
+            e => ExtractError::SevenZip(e.to_string()),
+        }
     }
 }
 
@@ -1,21 +1,419 @@
-use std::path::Path;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
 
+use sevenz_rust::{Password, SevenZReader};
+use tauri::AppHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
 use unrar::Archive;
+use zip::ZipArchive;
 
+use crate::emitter::ExtractionEmitter;
 use crate::errors::ExtractError;
 
+const COPY_BUFFER_SIZE: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Rar,
+    SevenZip,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Detect the archive format from the file extension
+    pub fn from_path(p: &Path) -> Option<Self> {
+        if split_7z_volume(p).is_some() {
+            return Some(Self::SevenZip);
+        }
+        let ext = p.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "rar" => Some(Self::Rar),
+            "7z" => Some(Self::SevenZip),
+            "zip" => Some(Self::Zip),
+            _ => None,
+        }
+    }
+}
+
+/// Extract a RAR/7z/zip archive next to itself, on a blocking thread.
+///
+/// Progress is emitted as `extract::progress` when `app` is given, and
+/// `cancel` is checked between entries. 7z and zip entries are also copied
+/// in chunks, with progress and cancellation checked between them, while a
+/// RAR entry is unpacked by unrar in one go.
+pub async fn extract_archive_async(
+    app: Option<AppHandle>,
+    file: PathBuf,
+    password: Option<String>,
+    cancel: CancellationToken,
+) -> Result<(), ExtractError> {
+    tokio::task::spawn_blocking(move || {
+        let name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut emitter = ExtractionEmitter::new(app, name);
+        extract_archive(&file, password.as_deref(), &cancel, &mut emitter)
+    })
+    .await
+    .map_err(|e| ExtractError::Io(e.to_string()))?
+}
+
 /// Find first archive of a rar group, and extract files
 ///
+/// Files are first extracted into a hidden staging directory, which
+/// is moved into place on success and removed on failure or cancellation.
+///
 /// DO NOT call this multiple times for the same RAR group
 /// (foo.part*1~N*.rar e.g.), or it will extract files for N times
-pub fn extract_archive(file: &Path) -> Result<(), ExtractError> {
+pub fn extract_archive(
+    file: &Path,
+    password: Option<&str>,
+    cancel: &CancellationToken,
+    emitter: &mut ExtractionEmitter,
+) -> Result<(), ExtractError> {
+    let format = ArchiveFormat::from_path(file)
+        .ok_or_else(|| ExtractError::UnsupportedFormat(file.display().to_string()))?;
     let target_dir = file.parent().ok_or(ExtractError::NoParentDirectory)?;
 
-    let first_archive = first_part(file);
-    let mut archive = first_archive.open_for_processing()?;
+    let staging = staging_dir(file, target_dir);
+    if staging.exists() {
+        // leftover from an interrupted run
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    let result = match format {
+        ArchiveFormat::Rar => extract_rar(file, password, &staging, cancel, emitter),
+        ArchiveFormat::SevenZip => extract_7z(file, password, &staging, cancel, emitter),
+        ArchiveFormat::Zip => extract_zip(file, password, &staging, cancel, emitter),
+    }
+    .and_then(|()| {
+        if cancel.is_cancelled() {
+            return Err(ExtractError::Cancelled);
+        }
+        move_contents(&staging, target_dir).map_err(ExtractError::from)
+    });
+
+    let result = match result {
+        Err(_) if cancel.is_cancelled() => Err(ExtractError::Cancelled),
+        other => other,
+    };
+
+    if let Err(e) = fs::remove_dir_all(&staging)
+        && e.kind() != io::ErrorKind::NotFound
+    {
+        warn!("Failed to remove staging directory {staging:?}: {e}");
+    }
+
+    match &result {
+        Ok(()) => {
+            emitter.finish();
+            info!("Extracted {file:?} into {target_dir:?}");
+        }
+        Err(e) => warn!("Extraction of {file:?} failed: {e}"),
+    }
+
+    result
+}
+
+fn extract_rar(
+    file: &Path,
+    password: Option<&str>,
+    staging: &Path,
+    cancel: &CancellationToken,
+    emitter: &mut ExtractionEmitter,
+) -> Result<(), ExtractError> {
+    let (mut bytes_total, mut entries_total) = (0, 0);
+    for entry in first_part_with_password(file, password).open_for_listing()? {
+        let entry = entry?;
+        if entry.is_file() {
+            bytes_total += entry.unpacked_size;
+        }
+        entries_total += 1;
+    }
+    emitter.set_totals(bytes_total, entries_total);
 
+    let mut archive = first_part_with_password(file, password).open_for_processing()?;
     while let Some(header) = archive.read_header()? {
-        archive = header.extract_with_base(target_dir)?;
+        if cancel.is_cancelled() {
+            return Err(ExtractError::Cancelled);
+        }
+
+        let size = header.entry().unpacked_size;
+        archive = header.extract_with_base(staging)?;
+        emitter.add_bytes(size);
+        emitter.entry_done();
+    }
+
+    Ok(())
+}
+
+fn extract_zip(
+    file: &Path,
+    password: Option<&str>,
+    staging: &Path,
+    cancel: &CancellationToken,
+    emitter: &mut ExtractionEmitter,
+) -> Result<(), ExtractError> {
+    let mut archive = ZipArchive::new(fs::File::open(file)?)?;
+
+    let mut bytes_total = 0;
+    for i in 0..archive.len() {
+        bytes_total += archive.by_index_raw(i)?.size();
+    }
+    emitter.set_totals(bytes_total, archive.len() as u64);
+
+    for i in 0..archive.len() {
+        if cancel.is_cancelled() {
+            return Err(ExtractError::Cancelled);
+        }
+
+        let mut entry = match password {
+            Some(pw) => archive.by_index_decrypt(i, pw.as_bytes())?,
+            None => archive.by_index(i)?,
+        };
+
+        let Some(relative) = entry.enclosed_name() else {
+            warn!("Skipping zip entry with unsafe path: {}", entry.name());
+            emitter.entry_done();
+            continue;
+        };
+
+        let out = staging.join(relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&out)?;
+        } else {
+            if let Some(parent) = out.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut writer = fs::File::create(&out)?;
+            copy_with_progress(&mut entry, &mut writer, cancel, emitter)?;
+        }
+        emitter.entry_done();
+    }
+
+    Ok(())
+}
+
+fn extract_7z(
+    file: &Path,
+    password: Option<&str>,
+    staging: &Path,
+    cancel: &CancellationToken,
+    emitter: &mut ExtractionEmitter,
+) -> Result<(), ExtractError> {
+    let password = password.map(Password::from).unwrap_or_else(Password::empty);
+    let volumes = SplitVolumes::open(file)?;
+    let len = volumes.len;
+    let mut reader = SevenZReader::new(volumes, len, password)?;
+
+    {
+        let files = &reader.archive().files;
+        let bytes_total = files.iter().map(|e| e.size()).sum();
+        emitter.set_totals(bytes_total, files.len() as u64);
+    }
+
+    reader.for_each_entries(|entry, data| {
+        if cancel.is_cancelled() {
+            return Ok(false);
+        }
+
+        let Some(out) = safe_join(staging, entry.name()) else {
+            warn!("Skipping 7z entry with unsafe path: {}", entry.name());
+            emitter.entry_done();
+            return Ok(true);
+        };
+
+        if entry.is_directory() {
+            fs::create_dir_all(&out)?;
+        } else {
+            if let Some(parent) = out.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut writer = fs::File::create(&out)?;
+            if entry.has_stream() {
+                copy_with_progress(data, &mut writer, cancel, emitter)?;
+            }
+        }
+        emitter.entry_done();
+
+        Ok(true)
+    })?;
+
+    if cancel.is_cancelled() {
+        return Err(ExtractError::Cancelled);
+    }
+
+    Ok(())
+}
+
+/// Copy an entry to disk, reporting progress and bailing out on cancellation.
+fn copy_with_progress(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    cancel: &CancellationToken,
+    emitter: &mut ExtractionEmitter,
+) -> io::Result<()> {
+    let mut buf = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        if cancel.is_cancelled() {
+            return Err(io::Error::other("extraction cancelled"));
+        }
+
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        writer.write_all(&buf[..n])?;
+        emitter.add_bytes(n as u64);
+    }
+
+    writer.flush()
+}
+
+/// Join an archive entry name onto `base`, rejecting absolute paths and `..`
+fn safe_join(base: &Path, name: &str) -> Option<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    let relative = Path::new(&normalized);
+
+    let mut out = base.to_path_buf();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    (out != base).then_some(out)
+}
+
+/// `game.7z` and the width of the volume number of a `game.7z.001` volume
+fn split_7z_volume(p: &Path) -> Option<(&str, usize)> {
+    let name = p.file_name()?.to_str()?;
+    let (archive, number) = name.rsplit_once('.')?;
+    let is_number = !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit());
+    (is_number && archive.to_ascii_lowercase().ends_with(".7z")).then_some((archive, number.len()))
+}
+
+/// Name shared by every volume of an archive set: `game` for
+/// `game.part01.rar` and `game.7z` for `game.7z.001`
+pub fn archive_group(name: &str) -> &str {
+    if let Some((archive, _)) = split_7z_volume(Path::new(name)) {
+        return archive;
+    }
+    name.split_once(".part")
+        .map(|(group, _)| group)
+        .unwrap_or(name)
+}
+
+/// The volumes of a split 7z archive read as one file, or just the archive
+/// when it is not split
+struct SplitVolumes {
+    /// Every volume with its length
+    volumes: Vec<(fs::File, u64)>,
+    len: u64,
+    position: u64,
+}
+
+impl SplitVolumes {
+    /// Opens every volume of the set `file` belongs to, from the first one
+    fn open(file: &Path) -> io::Result<Self> {
+        let paths = match split_7z_volume(file) {
+            Some((archive, width)) => {
+                let dir = file.parent().unwrap_or(Path::new(""));
+                let paths: Vec<PathBuf> = (1..)
+                    .map(|i| dir.join(format!("{archive}.{i:0width$}")))
+                    .take_while(|path| path.is_file())
+                    .collect();
+                if paths.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("first volume of {} not found", file.display()),
+                    ));
+                }
+                paths
+            }
+            None => vec![file.to_path_buf()],
+        };
+
+        let mut volumes = Vec::with_capacity(paths.len());
+        for path in paths {
+            let volume = fs::File::open(&path)?;
+            let len = volume.metadata()?.len();
+            volumes.push((volume, len));
+        }
+        let len = volumes.iter().map(|(_, len)| len).sum();
+        Ok(SplitVolumes {
+            volumes,
+            len,
+            position: 0,
+        })
+    }
+}
+
+impl Read for SplitVolumes {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut start = 0;
+        for (volume, len) in &mut self.volumes {
+            if self.position < start + *len {
+                let offset = self.position - start;
+                volume.seek(SeekFrom::Start(offset))?;
+                let max = buf.len().min((*len - offset) as usize);
+                let n = volume.read(&mut buf[..max])?;
+                self.position += n as u64;
+                return Ok(n);
+            }
+            start += *len;
+        }
+        Ok(0)
+    }
+}
+
+impl Seek for SplitVolumes {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before the start"))?;
+        Ok(self.position)
+    }
+}
+
+fn staging_dir(file: &Path, target_dir: &Path) -> PathBuf {
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    target_dir.join(format!(".{name}.extracting"))
+}
+
+/// Move everything from `src` into `dst`, merging directories that already exist.
+fn move_contents(src: &Path, dst: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let from = entry.path();
+        let to = dst.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() && to.is_dir() {
+            move_contents(&from, &to)?;
+            fs::remove_dir(&from)?;
+            continue;
+        }
+
+        if to.is_file() {
+            fs::remove_file(&to)?;
+        }
+        fs::rename(&from, &to)?;
     }
 
     Ok(())
@@ -25,11 +423,22 @@ fn first_part(p: &Path) -> Archive<'_> {
     Archive::new(p).as_first_part()
 }
 
+fn first_part_with_password<'a>(p: &'a Path, password: Option<&'a str>) -> Archive<'a> {
+    match password {
+        Some(pw) => Archive::with_password(p, pw).as_first_part(),
+        None => first_part(p),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::fs;
+    use std::path::{Path, PathBuf};
 
-    use crate::functions::first_part;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::emitter::ExtractionEmitter;
+    use crate::functions::{ArchiveFormat, archive_group, extract_archive, first_part, safe_join};
 
     #[test]
     fn find_first_archive() {
@@ -65,4 +474,77 @@ mod tests {
             assert_eq!(first.filename().display().to_string(), "game.part0001.rar");
         }
     }
+
+    #[test]
+    fn detect_archive_format() {
+        let detect = |name: &str| ArchiveFormat::from_path(Path::new(name));
+
+        assert_eq!(detect("game.part01.rar"), Some(ArchiveFormat::Rar));
+        assert_eq!(
+            detect("fg-optional-bonus.7z"),
+            Some(ArchiveFormat::SevenZip)
+        );
+        assert_eq!(detect("Soundtrack.ZIP"), Some(ArchiveFormat::Zip));
+        assert_eq!(
+            detect("fg-optional-bonus.7z.001"),
+            Some(ArchiveFormat::SevenZip)
+        );
+        assert_eq!(
+            detect("fg-optional-bonus.7z.012"),
+            Some(ArchiveFormat::SevenZip)
+        );
+        assert_eq!(detect("fg-optional-bonus.zip.001"), None);
+        assert_eq!(detect("setup.exe"), None);
+        assert_eq!(detect("fg-01.bin"), None);
+    }
+
+    #[test]
+    fn reject_unsafe_entry_paths() {
+        let base = Path::new("/games/foo");
+
+        assert_eq!(
+            safe_join(base, "data\\maps/level1.pak"),
+            Some(base.join("data/maps/level1.pak"))
+        );
+        assert_eq!(safe_join(base, "../escape.txt"), None);
+        assert_eq!(safe_join(base, "/etc/passwd"), None);
+        assert_eq!(safe_join(base, "."), None);
+    }
+
+    #[test]
+    fn group_archive_volumes() {
+        assert_eq!(archive_group("game.part01.rar"), "game");
+        assert_eq!(archive_group("bonus.7z.002"), "bonus.7z");
+        assert_eq!(archive_group("bonus.7z"), "bonus.7z");
+    }
+
+    #[test]
+    fn extract_split_7z_from_any_volume() {
+        let dir = std::env::temp_dir().join(format!("split-7z-{}", std::process::id()));
+        let source = dir.join("source");
+        fs::create_dir_all(source.join("data")).unwrap();
+        let content: Vec<u8> = (0..200_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        fs::write(source.join("data/level.pak"), &content).unwrap();
+
+        let out = dir.join("out");
+        fs::create_dir_all(&out).unwrap();
+        let archive = dir.join("bonus.7z");
+        sevenz_rust::compress_to_path(&source, &archive).unwrap();
+        let bytes = fs::read(&archive).unwrap();
+        for (i, chunk) in bytes.chunks(bytes.len() / 3 + 1).enumerate() {
+            fs::write(out.join(format!("bonus.7z.{:03}", i + 1)), chunk).unwrap();
+        }
+
+        let mut emitter = ExtractionEmitter::new(None, "bonus.7z.002");
+        extract_archive(
+            &out.join("bonus.7z.002"),
+            None,
+            &CancellationToken::new(),
+            &mut emitter,
+        )
+        .unwrap();
+        let extracted = fs::read(out.join("data/level.pak"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(extracted.unwrap(), content);
+    }
 }
@@ -1,4 +1,3 @@
-use directories::BaseDirs;
 use specta::specta;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -8,8 +7,8 @@ use crate::{
     controller_client::{ControllerCommand, ControllerEvent},
     controller_manager::ControllerManager,
     defender::{
-        ExclusionAction, ExclusionCleanupPolicy, FolderExclusionEntry, load_exclusions, now_utc,
-        save_exclusions,
+        ExclusionAction, ExclusionCleanupPolicy, FolderExclusionEntry, exclusions_file_path,
+        load_exclusions, now_utc, save_exclusions,
     },
 };
 
@@ -61,10 +60,90 @@ pub fn start_executable(path: String) {
     }
 
     #[cfg(target_os = "linux")]
-    // TODO: WINEPREFIX + wine command configuration
-    // by allowing custom commands, `protonrun` e.g. should be supported automatically
-    // Add usage of wine + check beforehand with Flatpak if steamos
-    {}
+    // Linux launches go through per-game launch profiles (Wine, custom runners...),
+    // see `fit_launcher_library::launch::launch_game`
+    {
+        let _ = path;
+    }
+}
+
+/// Start `program` through the shell, which prompts for elevation when its
+/// manifest requires it. Returns the PID of the started process.
+///
+/// The environment is not passed on and the output is not captured, it is
+/// the way to start what `CreateProcess` refuses to.
+#[cfg(target_os = "windows")]
+pub fn shell_execute(
+    program: &std::path::Path,
+    args: &[String],
+    working_dir: &std::path::Path,
+) -> std::io::Result<u32> {
+    use crate::encode_utf16le_with_null;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::GetProcessId;
+    use windows::Win32::UI::Shell::{SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW, ShellExecuteExW};
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+    use windows::core::PCWSTR;
+
+    let program_wide = encode_utf16le_with_null(program);
+    let params = args
+        .iter()
+        .map(|arg| quote_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let params_wide = encode_utf16le_with_null(&params);
+    let working_dir_wide = encode_utf16le_with_null(working_dir);
+
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOCLOSEPROCESS,
+        lpFile: PCWSTR(program_wide.as_ptr()),
+        lpParameters: PCWSTR(params_wide.as_ptr()),
+        lpDirectory: PCWSTR(working_dir_wide.as_ptr()),
+        nShow: SW_SHOWNORMAL.0,
+        ..Default::default()
+    };
+
+    unsafe { ShellExecuteExW(&mut info) }.map_err(std::io::Error::other)?;
+    info!("Executable launched via shell: {}", program.display());
+
+    if info.hProcess.is_invalid() {
+        return Ok(0);
+    }
+    let pid = unsafe { GetProcessId(info.hProcess) };
+    _ = unsafe { CloseHandle(info.hProcess) };
+    Ok(pid)
+}
+
+/// Quote `arg` for a Windows command line, backslashes only need doubling
+/// in front of a quote
+#[cfg(target_os = "windows")]
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+            }
+        }
+        if c != '\\' {
+            quoted.push(c);
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
 }
 
 #[tauri::command]
@@ -117,7 +196,7 @@ pub async fn folder_exclusion(action: ExclusionAction) -> Result<(), String> {
                 if success {
                     info!("Folder exclusion succeeded");
 
-                    let Some(base_dirs) = BaseDirs::new() else {
+                    let Some(exclusions_file_path) = exclusions_file_path() else {
                         error!("Failed to determine base directories");
                         return Err(
                             "Folder added successfully but failed to determine base directories"
@@ -125,14 +204,6 @@ pub async fn folder_exclusion(action: ExclusionAction) -> Result<(), String> {
                         );
                     };
 
-                    let exclusions_file_path = base_dirs
-                        .config_dir()
-                        .join("com.fitlauncher.carrotrub")
-                        .join("fitgirlConfig")
-                        .join("settings")
-                        .join("data")
-                        .join("folder_exclusions.json");
-
                     let mut file = load_exclusions(&exclusions_file_path);
 
                     let excluded_path = match &action {
@@ -227,7 +298,7 @@ pub async fn folder_exclusion_cleanup(policy: ExclusionCleanupPolicy) -> Result<
                 if success {
                     info!("Folder exclusion cleanup succeeded");
 
-                    let Some(base_dirs) = BaseDirs::new() else {
+                    let Some(exclusions_file_path) = exclusions_file_path() else {
                         error!("Failed to determine base directories");
                         return Err(
                             "Folder added successfully but failed to determine base directories"
@@ -235,14 +306,6 @@ pub async fn folder_exclusion_cleanup(policy: ExclusionCleanupPolicy) -> Result<
                         );
                     };
 
-                    let exclusions_file_path = base_dirs
-                        .config_dir()
-                        .join("com.fitlauncher.carrotrub")
-                        .join("fitgirlConfig")
-                        .join("settings")
-                        .join("data")
-                        .join("folder_exclusions.json");
-
                     let mut file = load_exclusions(&exclusions_file_path);
 
                     let excluded_path = match &policy {
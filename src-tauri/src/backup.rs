@@ -0,0 +1,624 @@
+//! Full backup/restore of the launcher state into a single zip archive.
+//!
+//! The archive holds a `manifest.json` describing its content, the config
+//! files, settings, library, download manager state and the scraping database.
+//! Debrid keys are only included when explicitly asked for.
+
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use directories::BaseDirs;
+use fit_launcher_integrations::{DebridProvider, credentials};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::{Type, specta};
+use tauri::AppHandle;
+use tracing::{info, warn};
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+/// Bump when the archive layout changes in a non backward-compatible way
+const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const SECRETS_NAME: &str = "secrets/debrid.json";
+const DATABASE_NAME: &str = "data/search.db";
+
+/// Restored config files are staged here, relative to the app config dir.
+///
+/// The running managers keep rewriting the live files until the next startup.
+const PENDING_CONFIG_DIR: &str = "restore.pending";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: String,
+    /// `std::env::consts::OS` of the machine that made the backup
+    pub source_os: String,
+    /// Download directory on the source machine, used for remapping on import
+    pub source_download_dir: String,
+    pub includes_secrets: bool,
+    /// Archive paths of every file in the backup (manifest excluded)
+    pub entries: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct BackupImportReport {
+    pub restored: Vec<String>,
+    /// Number of paths rewritten from the old download dir to the new one
+    pub remapped_paths: u32,
+    pub secrets_restored: bool,
+    pub warnings: Vec<String>,
+    /// Managers keep their state in memory, a restart applies the import
+    pub restart_required: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupSecret {
+    provider: DebridProvider,
+    api_key: String,
+}
+
+fn app_config_dir() -> Result<PathBuf, String> {
+    Ok(BaseDirs::new()
+        .ok_or("Failed to determine base directories")?
+        .config_dir()
+        .join("com.fitlauncher.carrotrub"))
+}
+
+/// Files and folders (relative to the app config dir) that make up the backup
+const CONFIG_ENTRIES: &[&str] = &[
+    "manager.json",
+    "manager.journal",
+    "manager_torrents",
+    "config.json",
+    "fitgirlConfig/settings",
+    "library",
+];
+
+/// JSON files whose string values may contain paths under the download dir
+fn is_remappable(archive_path: &str) -> bool {
+    archive_path.ends_with(".json")
+        && (archive_path == "config/manager.json"
+            || archive_path == "config/config.json"
+            || archive_path.starts_with("config/library/")
+            || archive_path.ends_with("folder_exclusions.json"))
+}
+
+/// JSON-lines files, remapped line by line
+fn is_remappable_lines(archive_path: &str) -> bool {
+    archive_path == "config/manager.journal"
+}
+
+#[tauri::command]
+#[specta]
+pub async fn export_backup(
+    app: AppHandle,
+    destination: String,
+    include_secrets: bool,
+) -> Result<BackupManifest, String> {
+    let db_snapshot = std::env::temp_dir().join(format!(
+        "fitlauncher-backup-{}.db",
+        chrono::Utc::now().timestamp_millis()
+    ));
+    let has_db = match fit_launcher_scraping::db::backup_database(&app, &db_snapshot) {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Scraping database not included in backup: {e}");
+            false
+        }
+    };
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let db = has_db.then_some(db_snapshot.as_path());
+        let manifest = write_backup(Path::new(&destination), db, include_secrets);
+        let _ = fs::remove_file(&db_snapshot);
+        manifest
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Ok(manifest) = &result {
+        info!("Backup written with {} entries", manifest.entries.len());
+    }
+    result
+}
+
+fn write_backup(
+    destination: &Path,
+    db_snapshot: Option<&Path>,
+    include_secrets: bool,
+) -> Result<BackupManifest, String> {
+    let config_dir = app_config_dir()?;
+    let tmp = destination.with_extension("tmp");
+    let mut zip = ZipWriter::new(File::create(&tmp).map_err(|e| e.to_string())?);
+    let options = SimpleFileOptions::default();
+    let mut entries = Vec::new();
+
+    for entry in CONFIG_ENTRIES {
+        let path = config_dir.join(entry);
+        for file in collect_files(&path) {
+            let relative = file.strip_prefix(&config_dir).map_err(|e| e.to_string())?;
+            let name = format!("config/{}", archive_name(relative));
+            add_file(&mut zip, &name, &file, options)?;
+            entries.push(name);
+        }
+    }
+
+    if let Some(db) = db_snapshot {
+        add_file(&mut zip, DATABASE_NAME, db, options)?;
+        entries.push(DATABASE_NAME.to_string());
+    }
+
+    if include_secrets {
+        let keys: Vec<BackupSecret> = DebridProvider::all()
+            .into_iter()
+            .filter_map(|provider| {
+                credentials::get(provider)
+                    .ok()
+                    .map(|api_key| BackupSecret { provider, api_key })
+            })
+            .collect();
+        zip.start_file(SECRETS_NAME, options)
+            .map_err(|e| e.to_string())?;
+        zip.write_all(&serde_json::to_vec_pretty(&keys).map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?;
+        entries.push(SECRETS_NAME.to_string());
+    }
+
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        source_os: std::env::consts::OS.to_string(),
+        source_download_dir: fit_launcher_torrent::load_config()
+            .general
+            .download_dir
+            .to_string_lossy()
+            .to_string(),
+        includes_secrets: include_secrets,
+        entries,
+    };
+
+    zip.start_file(MANIFEST_NAME, options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+
+    fs::rename(&tmp, destination).map_err(|e| e.to_string())?;
+    Ok(manifest)
+}
+
+/// Restore a backup made by [`export_backup`].
+///
+/// Paths under the source machine's download dir are rewritten to
+/// `download_dir`, or to the current download dir when not given.
+#[tauri::command]
+#[specta]
+pub async fn import_backup(
+    app: AppHandle,
+    archive: String,
+    download_dir: Option<String>,
+    import_secrets: bool,
+) -> Result<BackupImportReport, String> {
+    let new_download_dir = download_dir
+        .map(PathBuf::from)
+        .unwrap_or_else(|| fit_launcher_torrent::load_config().general.download_dir);
+    let db_path = fit_launcher_scraping::db::get_db_path(&app);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        read_backup(
+            Path::new(&archive),
+            &new_download_dir,
+            &db_path,
+            import_secrets,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn read_backup(
+    archive: &Path,
+    new_download_dir: &Path,
+    db_path: &Path,
+    import_secrets: bool,
+) -> Result<BackupImportReport, String> {
+    let mut zip = ZipArchive::new(File::open(archive).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    let manifest: BackupManifest = serde_json::from_slice(&read_entry(&mut zip, MANIFEST_NAME)?)
+        .map_err(|e| format!("Invalid backup manifest: {e}"))?;
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(format!(
+            "Backup format v{} is newer than supported v{BACKUP_FORMAT_VERSION}, update the launcher first",
+            manifest.format_version
+        ));
+    }
+
+    let staging = app_config_dir()?.join(PENDING_CONFIG_DIR);
+    // drop what an earlier, not yet applied, import left behind
+    let _ = fs::remove_dir_all(&staging);
+    let mut report = BackupImportReport {
+        restart_required: true,
+        ..Default::default()
+    };
+
+    let remap = PathRemap {
+        from: manifest.source_download_dir.clone(),
+        to: new_download_dir.to_string_lossy().to_string(),
+    };
+
+    for name in &manifest.entries {
+        let data = match read_entry(&mut zip, name) {
+            Ok(data) => data,
+            Err(e) => {
+                report.warnings.push(e);
+                continue;
+            }
+        };
+
+        if name == SECRETS_NAME {
+            if import_secrets {
+                restore_secrets(&data, &mut report);
+            }
+            continue;
+        }
+
+        // everything is in use, it is swapped in on next startup
+        let target = if name == DATABASE_NAME {
+            pending_db_path(db_path)
+        } else if let Some(relative) = name.strip_prefix("config/")
+            && let Some(target) = safe_join(&staging, relative)
+        {
+            target
+        } else {
+            report
+                .warnings
+                .push(format!("Skipped unknown entry {name}"));
+            continue;
+        };
+
+        let data = if remap.is_noop() {
+            data
+        } else if is_remappable(name) {
+            match serde_json::from_slice::<Value>(&data) {
+                Ok(mut value) => {
+                    report.remapped_paths += remap.apply(&mut value);
+                    serde_json::to_vec_pretty(&value).map_err(|e| e.to_string())?
+                }
+                Err(_) => data,
+            }
+        } else if is_remappable_lines(name) {
+            let mut out = Vec::with_capacity(data.len());
+            for line in data.split(|b| *b == b'\n').filter(|l| !l.is_empty()) {
+                match serde_json::from_slice::<Value>(line) {
+                    Ok(mut value) => {
+                        report.remapped_paths += remap.apply(&mut value);
+                        serde_json::to_writer(&mut out, &value).map_err(|e| e.to_string())?;
+                    }
+                    Err(_) => out.extend_from_slice(line),
+                }
+                out.push(b'\n');
+            }
+            out
+        } else {
+            data
+        };
+
+        if let Err(e) = write_atomic(&target, &data) {
+            report.warnings.push(format!("{name}: {e}"));
+            continue;
+        }
+        report.restored.push(name.clone());
+    }
+
+    info!(
+        "Backup imported: {} files, {} remapped paths",
+        report.restored.len(),
+        report.remapped_paths
+    );
+    Ok(report)
+}
+
+fn pending_db_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("db.pending")
+}
+
+/// Swap in the files restored by [`import_backup`], before anything reads them.
+pub fn apply_pending_restore(app: &AppHandle) {
+    apply_pending_database(app);
+
+    match app_config_dir() {
+        Ok(config_dir) => apply_pending_config(&config_dir),
+        Err(e) => warn!("Restored config files not applied: {e}"),
+    }
+}
+
+fn apply_pending_config(config_dir: &Path) {
+    let staging = config_dir.join(PENDING_CONFIG_DIR);
+    if !staging.is_dir() {
+        return;
+    }
+
+    // a journal from before the import would be replayed on top of the restored jobs
+    if staging.join("manager.json").exists() && !staging.join("manager.journal").exists() {
+        let _ = fs::remove_file(config_dir.join("manager.journal"));
+    }
+
+    let mut restored = 0;
+    for file in collect_files(&staging) {
+        let Ok(relative) = file.strip_prefix(&staging) else {
+            continue;
+        };
+        let target = config_dir.join(relative);
+        let moved = target
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::rename(&file, &target));
+        match moved {
+            Ok(()) => restored += 1,
+            Err(e) => warn!("Failed to restore {}: {e}", target.display()),
+        }
+    }
+
+    let _ = fs::remove_dir_all(&staging);
+    info!("Restored {restored} config files from backup");
+}
+
+fn apply_pending_database(app: &AppHandle) {
+    let db_path = fit_launcher_scraping::db::get_db_path(app);
+    let pending = pending_db_path(&db_path);
+    if !pending.exists() {
+        return;
+    }
+
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = db_path.clone().into_os_string();
+        sidecar.push(suffix);
+        let _ = fs::remove_file(sidecar);
+    }
+
+    match fs::rename(&pending, &db_path) {
+        Ok(()) => info!("Restored scraping database from backup"),
+        Err(e) => warn!("Failed to restore scraping database: {e}"),
+    }
+}
+
+fn restore_secrets(data: &[u8], report: &mut BackupImportReport) {
+    let keys: Vec<BackupSecret> = match serde_json::from_slice(data) {
+        Ok(keys) => keys,
+        Err(e) => {
+            report.warnings.push(format!("Invalid secrets: {e}"));
+            return;
+        }
+    };
+
+    for BackupSecret { provider, api_key } in keys {
+        if let Err(e) = credentials::store(provider, &api_key) {
+            report.warnings.push(format!("{provider}: {e}"));
+        }
+    }
+    report.secrets_restored = true;
+}
+
+/// Rewrites string values starting with the old download dir
+struct PathRemap {
+    from: String,
+    to: String,
+}
+
+impl PathRemap {
+    fn is_noop(&self) -> bool {
+        self.from.is_empty() || self.from == self.to
+    }
+
+    fn apply(&self, value: &mut Value) -> u32 {
+        match value {
+            Value::String(s) => match self.remap(s) {
+                Some(new) => {
+                    *s = new;
+                    1
+                }
+                None => 0,
+            },
+            Value::Array(items) => items.iter_mut().map(|v| self.apply(v)).sum(),
+            Value::Object(map) => map.values_mut().map(|v| self.apply(v)).sum(),
+            _ => 0,
+        }
+    }
+
+    fn remap(&self, s: &str) -> Option<String> {
+        let normalize = |p: &str| p.replace('\\', "/").trim_end_matches('/').to_string();
+        let from = normalize(&self.from);
+        let value = normalize(s);
+
+        let rest = value.strip_prefix(&from)?;
+        if !(rest.is_empty() || rest.starts_with('/')) {
+            return None;
+        }
+
+        // rebuild with the separators of this machine
+        let mut out = PathBuf::from(&self.to);
+        out.extend(rest.split('/').filter(|p| !p.is_empty()));
+        Some(out.to_string_lossy().to_string())
+    }
+}
+
+fn collect_files(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+
+    let Ok(entries) = fs::read_dir(path) else {
+        return vec![];
+    };
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(collect_files(&path));
+        } else if path.extension().is_none_or(|e| e != "tmp") {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn archive_name(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn add_file(
+    zip: &mut ZipWriter<File>,
+    name: &str,
+    path: &Path,
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    let data = fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    zip.write_all(&data).map_err(|e| e.to_string())
+}
+
+fn read_entry(zip: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>, String> {
+    let mut file = zip
+        .by_name(name)
+        .map_err(|e| format!("{name} missing from backup: {e}"))?;
+    let mut data = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut data)
+        .map_err(|e| format!("{name}: {e}"))?;
+    Ok(data)
+}
+
+/// Join an archive path onto `base`, rejecting anything escaping it
+fn safe_join(base: &Path, relative: &str) -> Option<PathBuf> {
+    let mut out = base.to_path_buf();
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            p if p.contains(['\\', ':']) => return None,
+            p => out.push(p),
+        }
+    }
+    Some(out)
+}
+
+fn write_atomic(target: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = target.with_extension("restore");
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn remap(from: &str, to: &str) -> PathRemap {
+        PathRemap {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    #[test]
+    fn remaps_paths_under_the_old_download_dir() {
+        let remap = remap("D:\\Games", "/home/user/Games");
+
+        let expected = Path::new("/home/user/Games")
+            .join("Elden Ring")
+            .join("setup.exe");
+        assert_eq!(
+            remap.remap("D:\\Games\\Elden Ring\\setup.exe"),
+            Some(expected.to_string_lossy().to_string())
+        );
+        assert_eq!(remap.remap("D:/Games/"), Some("/home/user/Games".into()));
+        // only whole path components match
+        assert_eq!(remap.remap("D:\\Games2\\foo"), None);
+        assert_eq!(remap.remap("C:\\Games\\foo"), None);
+    }
+
+    #[test]
+    fn remap_walks_nested_json() {
+        let remap = remap("/old", "/new");
+        let mut value = json!({
+            "job_path": "/old/a",
+            "files": ["/old/a/b.bin", "/elsewhere/c.bin"],
+            "nested": { "target": "/old", "size": 3 },
+        });
+
+        assert_eq!(remap.apply(&mut value), 3);
+        assert_eq!(value["nested"]["target"], "/new");
+        assert_eq!(value["files"][1], "/elsewhere/c.bin");
+    }
+
+    #[test]
+    fn remap_is_noop_without_a_source_dir() {
+        assert!(remap("", "/new").is_noop());
+        assert!(remap("/same", "/same").is_noop());
+        assert!(!remap("/old", "/new").is_noop());
+    }
+
+    #[test]
+    fn safe_join_stays_inside_base() {
+        let base = Path::new("/config");
+
+        assert_eq!(
+            safe_join(base, "library/./games.json"),
+            Some(base.join("library").join("games.json"))
+        );
+        assert_eq!(safe_join(base, "a//b"), Some(base.join("a").join("b")));
+        assert_eq!(safe_join(base, "../secrets"), None);
+        assert_eq!(safe_join(base, "library/../../etc/passwd"), None);
+        assert_eq!(safe_join(base, "C:/Windows"), None);
+        assert_eq!(safe_join(base, "a\\..\\..\\b"), None);
+    }
+
+    #[test]
+    fn staged_config_replaces_live_files_on_startup() {
+        let config_dir = std::env::temp_dir().join(format!("fit-restore-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&config_dir);
+        let staging = config_dir.join(PENDING_CONFIG_DIR);
+        fs::create_dir_all(staging.join("fitgirlConfig/settings/cookies")).unwrap();
+        fs::create_dir_all(&config_dir).unwrap();
+
+        fs::write(config_dir.join("manager.json"), "live").unwrap();
+        fs::write(config_dir.join("manager.journal"), "stale").unwrap();
+        fs::write(staging.join("manager.json"), "restored").unwrap();
+        fs::write(
+            staging.join("fitgirlConfig/settings/cookies/cookies.json"),
+            "[]",
+        )
+        .unwrap();
+
+        apply_pending_config(&config_dir);
+
+        assert_eq!(
+            fs::read_to_string(config_dir.join("manager.json")).unwrap(),
+            "restored"
+        );
+        assert!(!config_dir.join("manager.journal").exists());
+        assert!(
+            config_dir
+                .join("fitgirlConfig/settings/cookies/cookies.json")
+                .exists()
+        );
+        assert!(!staging.exists());
+
+        let _ = fs::remove_dir_all(&config_dir);
+    }
+}
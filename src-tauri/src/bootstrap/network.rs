@@ -1,8 +1,16 @@
+use std::time::Duration;
+
+use fit_launcher_config::client::connectivity;
+use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
+use fit_launcher_config::client::site::{self, CANONICAL_ORIGIN};
 use serde::Serialize;
 use tauri::async_runtime::spawn;
 use tauri::{AppHandle, Emitter, Listener, Manager};
 use tracing::info;
 
+/// How often the site is probed while it is unreachable
+const PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Small payload type used for emit
 #[derive(Serialize, Clone)]
 pub(crate) struct NetworkFailurePayload {
@@ -23,7 +31,12 @@ pub async fn perform_network_request(app_handle: AppHandle) {
             let app_handle_inner = app_handle_clone.clone();
 
             spawn(async move {
-                match reqwest::get("https://fitgirl-repacks.site").await {
+                if connectivity::connectivity().forced_offline {
+                    info!("perform_network_request: offline mode, skipping the network check");
+                    return;
+                }
+                let client = CUSTOM_DNS_CLIENT.read().await.clone();
+                match site::get_with_failover(&client, CANONICAL_ORIGIN).await {
                     Ok(resp) => {
                         let _ = resp.text().await;
                         info!("perform_network_request: network request successful");
@@ -42,3 +55,37 @@ pub async fn perform_network_request(app_handle: AppHandle) {
         });
     }
 }
+
+/// Forwards connectivity changes to the frontend as `connectivity-changed`
+/// and probes the site and the network while the site is unreachable, so the
+/// app goes back online without waiting for the next user action.
+pub async fn watch_connectivity(app_handle: AppHandle) {
+    let mut rx = connectivity::subscribe();
+    loop {
+        let state = *rx.borrow_and_update();
+        let _ = app_handle.emit("connectivity-changed", state);
+
+        if state.reachable || state.forced_offline {
+            if rx.changed().await.is_err() {
+                break;
+            }
+            continue;
+        }
+
+        tokio::select! {
+            changed = rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(PROBE_INTERVAL) => {
+                let client = CUSTOM_DNS_CLIENT.read().await.clone();
+                // the outcome feeds `connectivity` by itself
+                if site::get_with_failover(&client, CANONICAL_ORIGIN).await.is_err() {
+                    // the network may be back while the site is still down
+                    connectivity::probe_network(&client).await;
+                }
+            }
+        }
+    }
+}
@@ -3,17 +3,29 @@ use crate::game_info::*;
 use crate::image_colors::*;
 use crate::utils::*;
 use fit_launcher_cache::CacheManager;
+use fit_launcher_config::client::connectivity;
 use fit_launcher_download_manager::aria2::Aria2WsClient;
+use fit_launcher_download_manager::engine::{Aria2Engine, DownloadEngine, LibrqbitEngine};
 use fit_launcher_download_manager::manager::DownloadManager;
+use fit_launcher_download_manager::outbox::spawn_outbox_replay;
+use fit_launcher_download_manager::supervisor::{Aria2Supervisor, spawn_supervisor};
+use fit_launcher_download_manager::types::DownloadState;
+use fit_launcher_library::recommendations::{
+    refresh_recommendations, spawn_recommendation_refresher,
+};
 use fit_launcher_scraping::{
-    discovery::refresh_discovery_games, rebuild_search_index, scraping::run_all_scrapers,
-    sitemap::download_all_sitemaps,
+    CatalogCrawler, captcha::watch_ddos_guard_cookies, discovery::refresh_discovery_games,
+    rebuild_search_index, scraping::run_all_scrapers, sitemap::download_all_sitemaps,
 };
-use fit_launcher_torrent::{LibrqbitSession, functions::TorrentSession};
+use fit_launcher_torrent::{LibrqbitSession, TorrentEngine, functions::TorrentSession};
 use fit_launcher_ui_automation::api::InstallationManager;
 use lru::LruCache;
-use std::{num::NonZeroUsize, sync::Arc, time::Instant};
-use tauri::{Emitter, Manager, async_runtime::spawn};
+use std::{
+    num::NonZeroUsize,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager, async_runtime::spawn};
 use tokio::sync::Mutex;
 use tracing::{error, info};
 
@@ -92,6 +104,9 @@ pub async fn start_app() -> anyhow::Result<()> {
             move |app| {
                 let app_handle = app.handle().clone();
 
+                // before the torrent config, the download manager and the cookie jar load
+                crate::backup::apply_pending_restore(&app_handle);
+
                 // Initialize state here to ensure it only runs on the primary instance.
                 app.manage(image_cache);
                 app.manage(InstallationManager::new());
@@ -102,6 +117,9 @@ pub async fn start_app() -> anyhow::Result<()> {
                 let torrent_session = Arc::new(TorrentSession::new());
                 app.manage(torrent_session);
 
+                let crawler = CatalogCrawler::new(app_handle.clone());
+                app.manage(crawler.clone());
+
                 shutdown_hook(app_handle.clone());
 
                 for create_fn in [
@@ -117,6 +135,7 @@ pub async fn start_app() -> anyhow::Result<()> {
                 // Network and download subsystems are started asynchronously so UI can render early.
                 spawn({
                     let app = app_handle.clone();
+                    let crawler = crawler.clone();
                     async move {
                         info!("Download subsystem spawn: starting LibrqbitSession");
                         // LibrqbitSession creates a torrent session which may be heavier.
@@ -128,37 +147,101 @@ pub async fn start_app() -> anyhow::Result<()> {
                         let session = app.state::<Arc<TorrentSession>>();
                         let librqbit = app.state::<LibrqbitSession>();
 
+                        let config = session.config().await;
+
                         info!("Download subsystem spawn: calling init_client");
-                        match session.init_client().await {
-                            Ok(_) => {
-                                info!("Download subsystem spawn: init_client succeeded, getting aria2_client");
-                                if let Ok(client) = session.aria2_client().await {
+                        let aria2 = match session.init_client().await {
+                            Ok(_) => match session.aria2_client().await {
+                                Ok(client) => {
                                     let client = Arc::new(Mutex::new(client));
                                     // Clone the Arc<TorrentSession> for Aria2WsClient reconnection capability
                                     let session_arc = Arc::clone(&session);
+                                    let engine: Arc<dyn DownloadEngine> = Arc::new(Aria2Engine::new(
+                                        Aria2WsClient::new(client.clone(), session_arc),
+                                        config.rpc.clone(),
+                                    ));
+                                    Some((engine, client))
+                                }
+                                Err(err) => {
+                                    error!("ARIA2 client unavailable: {:#?}", err);
+                                    None
+                                }
+                            },
+                            Err(err) => {
+                                error!("ARIA2 init failed: {:#?}", err);
+                                None
+                            }
+                        };
+
+                        let torrent_engine: Arc<dyn DownloadEngine> = match (config.engine, &aria2) {
+                            (TorrentEngine::Librqbit, _) => {
+                                Arc::new(LibrqbitEngine::new(librqbit.inner().clone()))
+                            }
+                            (TorrentEngine::Aria2, Some((engine, _))) => engine.clone(),
+                            (TorrentEngine::Aria2, None) => {
+                                error!("Download subsystem spawn: aria2 is required by the configured engine");
+                                return;
+                            }
+                        };
+
+                        info!("Download subsystem spawn: creating DownloadManager");
+                        let manager = DownloadManager::new(
+                            aria2.as_ref().map(|(engine, _)| engine.clone()),
+                            torrent_engine.clone(),
+                            app.clone(),
+                            librqbit.clone(),
+                        );
+
+                        if let Err(e) = manager.load_from_disk().await {
+                            error!("Failed to load persisted jobs: {:?}", e);
+                        }
 
-                                    info!("Download subsystem spawn: creating DownloadManager");
-                                    let manager = DownloadManager::new(
-                                        Arc::new(Mutex::new(Aria2WsClient::new(client.clone(), session_arc))),
-                                        app.clone(),
-                                        session.config().await.rpc,
-                                        librqbit.clone(),
-                                    );
-
-                                    if let Err(e) = manager.load_from_disk().await {
-                                        error!("Failed to load persisted jobs: {:?}", e);
-                                    }
-
-                                    info!("Download subsystem spawn: managing DownloadManager and starting dispatcher");
-                                    app.manage(manager.clone());
-                                    fit_launcher_download_manager::dispatch::spawn_dispatcher(
-                                        manager, client,
-                                    );
-                                    info!("Download subsystem spawn: complete");
+                        info!("Download subsystem spawn: managing DownloadManager and starting dispatcher");
+                        app.manage(manager.clone());
+                        spawn({
+                            let manager = manager.clone();
+                            async move {
+                                let mut interval = tokio::time::interval(Duration::from_secs(5));
+                                loop {
+                                    interval.tick().await;
+                                    let active = manager
+                                        .all_jobs()
+                                        .await
+                                        .iter()
+                                        .any(|job| job.state == DownloadState::Active);
+                                    crawler.set_downloads_active(active);
                                 }
                             }
-                            Err(err) => error!("ARIA2 init failed: {:#?}", err),
+                        });
+                        if let Some((engine, client)) = aria2 {
+                            fit_launcher_download_manager::dispatch::spawn_dispatcher(
+                                manager.clone(),
+                                client.clone(),
+                            );
+                            let supervisor = Aria2Supervisor::new(
+                                manager.clone(),
+                                engine,
+                                client,
+                                Arc::clone(&session),
+                            );
+                            app.manage(supervisor.clone());
+                            spawn_supervisor(supervisor);
                         }
+                        fit_launcher_download_manager::details::spawn_details_monitor(
+                            manager.clone(),
+                            config
+                                .bittorrent
+                                .stall_timeout
+                                .map(|minutes| Duration::from_secs(u64::from(minutes) * 60)),
+                        );
+                        if config.engine.is_librqbit() {
+                            fit_launcher_download_manager::engine::spawn_status_poller(
+                                manager.clone(),
+                                torrent_engine,
+                            );
+                        }
+                        spawn_outbox_replay(manager);
+                        info!("Download subsystem spawn: complete");
                     }
                 });
 
@@ -175,28 +258,10 @@ pub async fn start_app() -> anyhow::Result<()> {
                     local.block_on(&rt, async move {
                         let start = Instant::now();
 
-                        // Phase 1: Download sitemaps (populates game URLs in database)
-                        info!("Phase 1: Syncing sitemap data...");
-                        if let Err(e) = download_all_sitemaps(&app_for_scrapers).await {
-                            error!("Sitemap sync failed: {:#?}", e);
-                        }
+                        sync_site_data(&app_for_scrapers).await;
 
-                        // Phase 2: Build search index (uses sitemap data)
-                        info!("Phase 2: Building search index...");
-                        if let Err(e) = rebuild_search_index(app_for_scrapers.clone()).await {
-                            error!("Search index build failed: {:#?}", e);
-                            if let Some(main) = app_for_scrapers.get_window("main") {
-                                let _ = main.emit("search-index-error", e.to_string());
-                            }
-                        } else if let Some(main) = app_for_scrapers.get_window("main") {
-                            let _ = main.emit("search-index-ready", ());
-                        }
-
-                        // Phase 3: Run scrapers (fills UI categories)
-                        info!("Phase 3: Updating game categories...");
-                        if let Err(e) = run_all_scrapers(app_for_scrapers.clone()).await {
-                            error!("Scrapers failed: {:#?}", e);
-                        }
+                        // The crawler outlives this runtime, it goes on the Tauri one
+                        spawn(crawler.run());
 
                         // Show main window
                         if let Some(splash) = app_for_scrapers.get_window("splashscreen") {
@@ -208,15 +273,24 @@ pub async fn start_app() -> anyhow::Result<()> {
                         }
 
                         info!("Startup complete in {:?}", start.elapsed());
+
+                        // Started offline, catch up once the site answers again
+                        if connectivity::is_site_unavailable() {
+                            info!("Offline, site data will sync once the site is reachable");
+                            connectivity::wait_until_site_available().await;
+                            sync_site_data(&app_for_scrapers).await;
+                        }
                     });
                 });
 
+                spawn_recommendation_refresher(app_handle.clone());
                 spawn({
                     let app = app_handle.clone();
                     async move {
-                        if let Err(err) = refresh_discovery_games(app).await {
+                        if let Err(err) = refresh_discovery_games(app.clone()).await {
                             error!("refresh_discovery_games failed: {:#?}", err);
                         }
+                        let _ = refresh_recommendations(app).await;
                     }
                 });
 
@@ -227,6 +301,9 @@ pub async fn start_app() -> anyhow::Result<()> {
                     }
                 });
 
+                spawn(crate::bootstrap::network::watch_connectivity(app_handle.clone()));
+                spawn(watch_ddos_guard_cookies(app_handle.clone()));
+
                 spawn({
                     let app = app_handle.clone();
                     async move {
@@ -255,6 +332,10 @@ pub async fn start_app() -> anyhow::Result<()> {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .register_asynchronous_uri_scheme_protocol(
+            fit_launcher_cache::CACHE_SCHEME,
+            fit_launcher_cache::handle_cache_request,
+        )
         .invoke_handler(tauri_helper::tauri_collect_commands!());
 
     let app = app.build(tauri::generate_context!())?;
@@ -289,3 +370,38 @@ pub async fn start_app() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Sitemaps, search index and categories, the index is rebuilt from local
+/// data when offline
+async fn sync_site_data(app: &AppHandle) {
+    let offline = connectivity::is_site_unavailable();
+
+    // Phase 1: Download sitemaps (populates game URLs in database)
+    if !offline {
+        info!("Phase 1: Syncing sitemap data...");
+        if let Err(e) = download_all_sitemaps(app).await {
+            error!("Sitemap sync failed: {:#?}", e);
+        }
+    }
+
+    // Phase 2: Build search index (uses sitemap data)
+    info!("Phase 2: Building search index...");
+    if let Err(e) = rebuild_search_index(app.clone()).await {
+        error!("Search index build failed: {:#?}", e);
+        if let Some(main) = app.get_window("main") {
+            let _ = main.emit("search-index-error", e.to_string());
+        }
+    } else if let Some(main) = app.get_window("main") {
+        let _ = main.emit("search-index-ready", ());
+    }
+
+    // Phase 3: Run scrapers (fills UI categories)
+    if !offline {
+        info!("Phase 3: Updating game categories...");
+        if let Err(e) = run_all_scrapers(app.clone()).await {
+            error!("Scrapers failed: {:#?}", e);
+        }
+        // popular and new games feed the ranking
+        let _ = refresh_recommendations(app.clone()).await;
+    }
+}
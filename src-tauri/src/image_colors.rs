@@ -1,78 +1,297 @@
+//! Colour palettes of cover images.
+//!
+//! Images are read through the cache store, which downloads a miss with the
+//! custom DNS client and its cookies. Palettes are memoized in the scraping
+//! database by image URL, so each image is decoded once.
+
+use std::sync::Arc;
+
 use color_thief::{ColorFormat, get_palette};
-use image::{DynamicImage, ImageError, load_from_memory};
-use specta::specta;
+use fit_launcher_cache::{CacheManager, error::CacheError, load_image};
+use fit_launcher_scraping::db::{self, ImagePalette, Rgb};
+use futures::{StreamExt, stream};
+use image::{ImageError, load_from_memory};
+use serde::Serialize;
+use specta::{Type, specta};
+use tauri::{AppHandle, Manager};
 use thiserror::Error;
+use tracing::warn;
+
+/// Images loaded and decoded at once
+const CONCURRENCY: usize = 4;
+/// Every 10th pixel is enough for a palette
+const QUALITY: u8 = 10;
+const PALETTE_SIZE: u8 = 5;
+
+const BLACK: Rgb = Rgb { r: 0, g: 0, b: 0 };
+const WHITE: Rgb = Rgb {
+    r: 255,
+    g: 255,
+    b: 255,
+};
 
 #[derive(Debug, Error)]
 enum DominantColorError {
-    #[error("Failed to download image from URL: {0}")]
-    Network(reqwest::Error),
+    #[error("Failed to load image: {0}")]
+    Load(#[from] CacheError),
     #[error("Failed to decode image data: {0}")]
-    ImageDecoding(ImageError),
+    ImageDecoding(#[from] ImageError),
     #[error("Failed to extract dominant color")]
     ColorExtraction,
-    #[error("Unexpected image format")]
-    Format,
 }
 
-impl From<reqwest::Error> for DominantColorError {
-    fn from(error: reqwest::Error) -> Self {
-        DominantColorError::Network(error)
-    }
+/// Palette of one image, or why there is none
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ImageColors {
+    pub url: String,
+    pub palette: Option<ImagePalette>,
+    pub error: Option<String>,
 }
 
-impl From<ImageError> for DominantColorError {
-    fn from(error: ImageError) -> Self {
-        DominantColorError::ImageDecoding(error)
-    }
+fn extract_palette(bytes: &[u8]) -> Result<ImagePalette, DominantColorError> {
+    // whatever the format, the palette is taken from RGBA pixels
+    let pixels = load_from_memory(bytes)?.to_rgba8();
+    let colors: Vec<Rgb> = get_palette(pixels.as_raw(), ColorFormat::Rgba, QUALITY, PALETTE_SIZE)
+        .map_err(|_| DominantColorError::ColorExtraction)?
+        .into_iter()
+        .map(|c| Rgb {
+            r: c.r,
+            g: c.g,
+            b: c.b,
+        })
+        .collect();
+
+    let dominant = *colors.first().ok_or(DominantColorError::ColorExtraction)?;
+    Ok(ImagePalette {
+        text_color: text_color_on(dominant),
+        colors,
+    })
 }
 
-async fn get_image_from_url(url: &str) -> Result<DynamicImage, DominantColorError> {
-    let response = reqwest::get(url)
-        .await
-        .map_err(DominantColorError::Network)?;
-    let bytes = response
-        .bytes()
+/// WCAG relative luminance
+fn luminance(color: Rgb) -> f64 {
+    let channel = |value: u8| {
+        let value = f64::from(value) / 255.0;
+        if value <= 0.03928 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(color.r) + 0.7152 * channel(color.g) + 0.0722 * channel(color.b)
+}
+
+/// Black or white, whichever has the higher contrast ratio on `background`
+fn text_color_on(background: Rgb) -> Rgb {
+    let luminance = luminance(background);
+    let on_black = (luminance + 0.05) / 0.05;
+    let on_white = 1.05 / (luminance + 0.05);
+    if on_black >= on_white { BLACK } else { WHITE }
+}
+
+async fn compute_palette(
+    manager: Option<&Arc<CacheManager>>,
+    url: &str,
+) -> Result<ImagePalette, DominantColorError> {
+    let bytes = load_image(manager, url).await?.bytes;
+    tokio::task::spawn_blocking(move || extract_palette(&bytes))
         .await
-        .map_err(DominantColorError::Network)?;
+        .map_err(|_| DominantColorError::ColorExtraction)?
+}
 
-    let img = load_from_memory(&bytes).map_err(DominantColorError::ImageDecoding)?;
-    Ok(img)
+fn memoized_palettes(app: &AppHandle, urls: &[String]) -> Vec<Option<ImagePalette>> {
+    match db::open_connection(app) {
+        Ok(conn) => urls
+            .iter()
+            .map(|url| db::get_image_palette(&conn, url).ok().flatten())
+            .collect(),
+        Err(e) => {
+            warn!("Image colors are not memoized: {e}");
+            vec![None; urls.len()]
+        }
+    }
 }
 
-fn get_image_buffer(img: DynamicImage) -> Result<(Vec<u8>, ColorFormat), DominantColorError> {
-    match img {
-        DynamicImage::ImageRgb8(buffer) => Ok((buffer.to_vec(), ColorFormat::Rgb)),
-        DynamicImage::ImageRgba8(buffer) => Ok((buffer.to_vec(), ColorFormat::Rgba)),
-        _ => Err(DominantColorError::Format),
+fn memoize(app: &AppHandle, palettes: &[(&str, &ImagePalette)]) {
+    if palettes.is_empty() {
+        return;
+    }
+    let result = db::open_connection(app).and_then(|conn| {
+        palettes
+            .iter()
+            .try_for_each(|(url, palette)| db::set_image_palette(&conn, url, palette))
+    });
+    if let Err(e) = result {
+        warn!("Failed to memoize image colors: {e}");
     }
 }
 
-async fn fetch_dominant_color(url: &str) -> Result<String, DominantColorError> {
-    let result_image = get_image_from_url(url).await?; // Await here
-    let image_raw = get_image_buffer(result_image)?;
+/// Palette of every image of `list_images`, in order. A failing image only
+/// has its own `error` set.
+#[tauri::command]
+#[specta]
+pub async fn check_dominant_color_vec(
+    app: AppHandle,
+    list_images: Vec<String>,
+) -> Vec<ImageColors> {
+    let memoized = memoized_palettes(&app, &list_images);
+    let manager = app
+        .try_state::<Arc<CacheManager>>()
+        .map(|manager| manager.inner().clone());
 
-    let color_rgb = get_palette(&image_raw.0, image_raw.1, 10, 2)
-        .map_err(|_| DominantColorError::ColorExtraction)?;
+    let results: Vec<(ImageColors, bool)> = stream::iter(list_images.into_iter().zip(memoized))
+        .map(|(url, memoized)| {
+            let manager = manager.clone();
+            async move {
+                let (palette, computed) = match memoized {
+                    Some(palette) => (Ok(palette), false),
+                    None => (compute_palette(manager.as_ref(), &url).await, true),
+                };
+                match palette {
+                    Ok(palette) => (
+                        ImageColors {
+                            url,
+                            palette: Some(palette),
+                            error: None,
+                        },
+                        computed,
+                    ),
+                    Err(e) => {
+                        warn!("No colors for {url}: {e}");
+                        let error = Some(e.to_string());
+                        let colors = ImageColors {
+                            url,
+                            palette: None,
+                            error,
+                        };
+                        (colors, false)
+                    }
+                }
+            }
+        })
+        .buffered(CONCURRENCY)
+        .collect()
+        .await;
 
-    let dominant_color = color_rgb[0];
-    Ok(format!(
-        "({}, {}, {})",
-        dominant_color.r, dominant_color.g, dominant_color.b
-    ))
+    let computed: Vec<(&str, &ImagePalette)> = results
+        .iter()
+        .filter(|(_, computed)| *computed)
+        .filter_map(|(colors, _)| Some((colors.url.as_str(), colors.palette.as_ref()?)))
+        .collect();
+    memoize(&app, &computed);
+
+    results.into_iter().map(|(colors, _)| colors).collect()
 }
 
-#[tauri::command]
-#[specta]
-pub async fn check_dominant_color_vec(list_images: Vec<String>) -> Result<Vec<String>, String> {
-    let mut rgb_color_list: Vec<String> = Vec::new();
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
 
-    for link in list_images {
-        match fetch_dominant_color(&link).await {
-            Ok(rgb_value) => rgb_color_list.push(rgb_value),
-            Err(e) => return Err(format!("Failed to fetch color for {link}: {e}")),
-        }
+    use image::{DynamicImage, GrayImage, ImageFormat, Luma};
+
+    use super::*;
+
+    const RED: Rgb = Rgb { r: 255, g: 0, b: 0 };
+    const BLUE: Rgb = Rgb { r: 0, g: 0, b: 255 };
+    const YELLOW: Rgb = Rgb {
+        r: 255,
+        g: 255,
+        b: 0,
+    };
+
+    fn png(image: DynamicImage) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn paletted_png(palette: &[[u8; 3]], indices: &[u8], width: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, indices.len() as u32 / width);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(palette.concat());
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(indices)
+            .unwrap();
+        bytes
+    }
+
+    /// The palette is quantized, colors come back a few units off
+    fn assert_close(color: Rgb, expected: Rgb) {
+        let close = |a: u8, b: u8| a.abs_diff(b) <= 8;
+        assert!(
+            close(color.r, expected.r) && close(color.g, expected.g) && close(color.b, expected.b),
+            "{color:?} is not close to {expected:?}"
+        );
+    }
+
+    #[test]
+    fn grayscale_png_has_a_gray_palette() {
+        let gray = GrayImage::from_pixel(32, 32, Luma([0x60]));
+        let palette = extract_palette(&png(DynamicImage::ImageLuma8(gray))).unwrap();
+
+        assert_close(
+            palette.colors[0],
+            Rgb {
+                r: 0x60,
+                g: 0x60,
+                b: 0x60,
+            },
+        );
+        assert_eq!(palette.text_color, WHITE);
+    }
+
+    #[test]
+    fn paletted_png_resolves_its_palette() {
+        let dark_red = [0x90, 0x10, 0x10];
+        let bytes = paletted_png(&[dark_red, [0x10, 0x10, 0xF0]], &[0; 32 * 32], 32);
+        let palette = extract_palette(&bytes).unwrap();
+
+        assert_close(
+            palette.colors[0],
+            Rgb {
+                r: dark_red[0],
+                g: dark_red[1],
+                b: dark_red[2],
+            },
+        );
+        assert_eq!(palette.text_color, WHITE);
     }
 
-    Ok(rgb_color_list)
+    #[test]
+    fn garbage_is_a_decoding_error() {
+        assert!(matches!(
+            extract_palette(b"not an image"),
+            Err(DominantColorError::ImageDecoding(_))
+        ));
+    }
+
+    #[test]
+    fn luminance_spans_black_to_white() {
+        assert_eq!(luminance(BLACK), 0.0);
+        assert!((luminance(WHITE) - 1.0).abs() < 1e-9);
+        assert!((luminance(RED) - 0.2126).abs() < 1e-9);
+        assert!((luminance(BLUE) - 0.0722).abs() < 1e-9);
+        assert!(luminance(YELLOW) > luminance(RED));
+    }
+
+    #[test]
+    fn text_color_contrasts_with_the_background() {
+        assert_eq!(text_color_on(WHITE), BLACK);
+        assert_eq!(text_color_on(BLACK), WHITE);
+        assert_eq!(text_color_on(YELLOW), BLACK);
+        assert_eq!(text_color_on(BLUE), WHITE);
+        // mid gray reads better in black
+        assert_eq!(
+            text_color_on(Rgb {
+                r: 128,
+                g: 128,
+                b: 128
+            }),
+            BLACK
+        );
+    }
 }
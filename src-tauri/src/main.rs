@@ -2,6 +2,7 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 pub mod utils;
 
+pub(crate) mod backup;
 pub mod bootstrap;
 pub(crate) mod game_info;
 pub(crate) mod image_colors;
@@ -9,6 +10,7 @@ pub(crate) mod image_colors;
 use crate::bootstrap::{init_logging, start_app};
 use tracing::warn;
 
+pub use crate::backup::*;
 pub use crate::game_info::*;
 pub use crate::image_colors::*;
 pub use crate::utils::*;
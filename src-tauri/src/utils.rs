@@ -20,7 +20,11 @@ use tauri_specta::Event;
 use tokio::sync::Mutex;
 use tracing::{info, warn};
 
-use fit_launcher_config::client::dns::CUSTOM_DNS_CLIENT;
+use fit_launcher_config::client::{
+    dns::CUSTOM_DNS_CLIENT,
+    scheduler::{self, RetryBudget},
+    site,
+};
 use fit_launcher_scraping::db;
 
 // Define a shared boolean flag
@@ -42,7 +46,9 @@ pub struct GameImageReadyPayload {
 
 /// Helper function.
 async fn check_url_status(url: &str) -> anyhow::Result<bool> {
-    let response = CUSTOM_DNS_CLIENT.read().await.head(url).send().await?;
+    let client = CUSTOM_DNS_CLIENT.read().await.clone();
+    let request = client.head(url).build()?;
+    let response = scheduler::send(&client, request, &mut RetryBudget::new(1)).await?;
     Ok(response.status().is_success())
 }
 
@@ -102,11 +108,8 @@ async fn scrape_and_emit_images(app_handle: &AppHandle, game_link: &str) -> Resu
         return Err(anyhow::anyhow!("Cancelled the Event..."));
     }
 
-    let body = CUSTOM_DNS_CLIENT
-        .read()
-        .await
-        .get(game_link)
-        .send()
+    let client = CUSTOM_DNS_CLIENT.read().await.clone();
+    let body = site::get_with_failover(&client, game_link)
         .await?
         .text()
         .await?;
@@ -1,3 +1,4 @@
+import { message } from "@tauri-apps/plugin-dialog";
 import { showError } from "../../helpers/error";
 import { AlertTriangle, Box, ChevronDown, ChevronRight, Download, Info, Languages, MemoryStick, X, Zap, Loader2 } from "lucide-solid";
 import { createSignal, For, onMount, Show, Component } from "solid-js";
@@ -10,7 +11,7 @@ import Checkbox from "../../components/UI/Checkbox/Checkbox";
 import { formatBytes, toTitleCaseExceptions } from "../../helpers/format";
 import LoadingPage from "../../pages/LoadingPage-01/LoadingPage";
 import { DirectLinkWrapper } from "../../types/download";
-import { classifyDdlFiles, classifyTorrentFiles, classifyDebridFiles } from "../../helpers/classify";
+import { classifyDdlFiles, classifyDebridFiles, labelTorrentFiles } from "../../helpers/classify";
 import { DM } from "../../api/manager/api";
 import * as Debrid from "../../api/debrid/api";
 
@@ -204,12 +205,21 @@ export default function createLastStepDownloadPopup(props: DownloadPopupProps) {
             const resultFiles = await DM.getTorrentFileList(props.downloadedGame.magnetlink);
             if (resultFiles.status === "ok") {
                 setListFiles(resultFiles.data);
-                const classified = classifyTorrentFiles(resultFiles.data);
+                // the same classification `preselectTorrentFiles` picks from
+                const classified = labelTorrentFiles(
+                    resultFiles.data,
+                    await DM.classifyTorrentFiles(resultFiles.data)
+                );
                 setCategorizedFiles({ Languages: classified.Languages, Others: classified.Others });
                 setUncategorizedFiles(classified.Uncategorized);
 
-                const all = new Set(resultFiles.data.map((_, i) => i));
-                setSelectedFileIndices(all);
+                const preselected = await DM.preselectTorrentFiles(resultFiles.data);
+                if (preselected.status === "ok") {
+                    setSelectedFileIndices(new Set(preselected.data));
+                } else {
+                    console.warn("preselectTorrentFiles failed", preselected.error);
+                    setSelectedFileIndices(new Set(resultFiles.data.map((_, i) => i)));
+                }
             } else {
                 console.error("getTorrentFileList failed", resultFiles.error);
                 setError("Failed to get torrent file list");
@@ -292,22 +302,26 @@ export default function createLastStepDownloadPopup(props: DownloadPopupProps) {
                 // Convert DownloadedGame to Game for DM API
                 const game = { ...props.downloadedGame, secondary_images: [] as string[] };
 
+                let result;
                 if (props.downloadType === "bittorrent") {
                     const selected = Array.from(selectedFileIndices());
-                    const result = await DM.addTorrent(game.magnetlink, selected, path, game);
-                    if (result.status === "error") {
-                        throw new Error(result.error);
-                    }
+                    result = await DM.addTorrent(game.magnetlink, selected, path, game);
                 } else {
                     const selectedLinks = directLinks().filter(l => ddlSelectedUrls().has(l.url));
                     if (!selectedLinks.length) throw new Error("No files selected");
 
                     console.log("adding DDL aria2 task...");
 
-                    const result = await DM.addDdl(selectedLinks, path, game);
-                    if (result.status === "error") {
-                        throw new Error(result.error);
-                    }
+                    result = await DM.addDdl(selectedLinks, path, game);
+                }
+                if (result.status === "error") {
+                    throw new Error(result.error);
+                }
+                if (result.data.kind === "queued") {
+                    await message(
+                        "You are offline. The download will start on its own once you are back online, you can find it under Downloads.",
+                        { title: "Download queued", kind: "info" }
+                    );
                 }
 
                 props.onFinish?.();
@@ -659,10 +673,7 @@ export default function createLastStepDownloadPopup(props: DownloadPopupProps) {
         }
 
         const renderTorrentUI = () => {
-            const categorized = classifyTorrentFiles(listFiles());
-            // update signals so the UI reflects classification if needed
-            // we keep original categorizedFiles signal for compatibility
-            // (but don't clobber user selections)
+            const categorized = categorizedFiles();
             return (
                 <>
                     <div class="text-center">
@@ -4,6 +4,7 @@ import {
   commands,
   CustomError,
   Game,
+  Recommendation,
   Result,
   ScrapingError,
 } from "../../bindings";
@@ -31,6 +32,10 @@ export class GamesCacheApi {
     return await this.getCached("discovery", commands.getDiscoveryGames);
   }
 
+  async getRecommendations(): Promise<Result<Recommendation[], ScrapingError>> {
+    return await this.getCached("recommendations", commands.getRecommendations);
+  }
+
   async getSingularGameLocal(
     url: string
   ): Promise<Result<Game, ScrapingError>> {
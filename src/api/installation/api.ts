@@ -34,7 +34,8 @@ export class InstallationApi {
     if (installationSettings.auto_install) {
       const result = await commands.dmExtractAndInstall(
         job,
-        installationSettings.auto_clean
+        installationSettings.auto_clean,
+        null
       );
 
       if (result.status === "error") {
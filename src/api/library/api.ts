@@ -5,6 +5,7 @@ import {
   Game,
   GameCollection,
   InstallationInfo,
+  LaunchProfile,
   Result,
 } from "../../bindings";
 
@@ -66,8 +67,22 @@ export class LibraryApi {
     return await commands.removeCollection(collectionName);
   }
 
-  async runExecutable(path: string): Promise<void> {
-    return await commands.startExecutable(path);
+  async launchGame(gameHref: string): Promise<Result<number, string>> {
+    return await commands.launchGame(gameHref);
+  }
+
+  async updateLaunchProfile(
+    gameHref: string,
+    launchProfile: LaunchProfile
+  ): Promise<Result<null, string>> {
+    return await commands.updateDownloadedGameLaunchProfile(
+      gameHref,
+      launchProfile
+    );
+  }
+
+  async getGameLogPath(gameHref: string): Promise<Result<string, string>> {
+    return await commands.getGameLogPath(gameHref);
   }
 
   async getExecutableInfo(
@@ -115,6 +130,14 @@ export class LibraryApi {
       output_folder: "",
     };
 
+    const launchProfile: LaunchProfile = {
+      args: [],
+      env: [],
+      prefix: null,
+      runner: { type: "native" },
+      working_dir: null,
+    };
+
     return {
       description: game.description,
       details: game.details,
@@ -125,6 +148,7 @@ export class LibraryApi {
       img: game.img,
       included_dlcs: game.included_dlcs,
       installation_info: installationInfo,
+      launch_profile: launchProfile,
       magnetlink: game.magnetlink,
       tag: game.tag,
       title: game.title,
@@ -2,13 +2,20 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import {
   Aria2Error,
+  ClassifiedFile,
   commands,
   DirectLink,
+  DownloadStats,
   FileInfo,
   Game,
   GlobalStat,
   Job,
+  JobDetails,
+  JobRecord,
+  JobSubmission,
+  OutboxEntry,
   Result,
+  StatsExport,
   TorrentApiError,
 } from "../../bindings";
 
@@ -207,10 +214,11 @@ export class GlobalDownloadManager {
   async addDdl(
     files: DirectLink[],
     target: string,
-    game: Game
-  ): Promise<Result<string, string>> {
+    game: Game,
+    ignoreSpaceCheck = false
+  ): Promise<Result<JobSubmission, string>> {
     try {
-      return await commands.dmAddDdlJob(files, target, game);
+      return await commands.dmAddDdlJob(files, target, game, ignoreSpaceCheck);
     } catch (e) {
       return {
         error: e instanceof Error ? e.message : (e as any),
@@ -223,10 +231,17 @@ export class GlobalDownloadManager {
     magnet: string,
     filesList: number[],
     target: string,
-    game: Game
-  ): Promise<Result<string, string>> {
+    game: Game,
+    ignoreSpaceCheck = false
+  ): Promise<Result<JobSubmission, string>> {
     try {
-      return await commands.dmAddTorrentJob(magnet, filesList, target, game);
+      return await commands.dmAddTorrentJob(
+        magnet,
+        filesList,
+        target,
+        game,
+        ignoreSpaceCheck
+      );
     } catch (e) {
       return {
         error: e instanceof Error ? e.message : (e as any),
@@ -307,6 +322,75 @@ export class GlobalDownloadManager {
     return await commands.listTorrentFiles(magnet);
   }
 
+  async jobDetails(jobId: string): Promise<Result<JobDetails, string>> {
+    return await commands.dmJobDetails(jobId);
+  }
+
+  /** Details of active torrent jobs, pushed every few seconds */
+  async onDetails(cb: (details: JobDetails) => void): Promise<UnlistenFn> {
+    return await listen<JobDetails>("download::job_details", (event) =>
+      cb(event.payload)
+    );
+  }
+
+  async classifyTorrentFiles(files: FileInfo[]): Promise<ClassifiedFile[]> {
+    return await commands.classifyTorrentFiles(files);
+  }
+
+  async preselectTorrentFiles(
+    files: FileInfo[]
+  ): Promise<Result<number[], TorrentApiError>> {
+    return await commands.preselectTorrentFiles(files);
+  }
+
+  async addTorrentFiles(
+    jobId: string,
+    fileIndices: number[]
+  ): Promise<Result<null, string>> {
+    return await commands.dmAddTorrentFiles(jobId, fileIndices);
+  }
+
+  // downloads asked for while offline
+
+  async outbox(): Promise<Result<OutboxEntry[], string>> {
+    return await commands.dmOutbox();
+  }
+
+  async discardOutboxEntry(id: string): Promise<Result<boolean, string>> {
+    return await commands.dmDiscardOutboxEntry(id);
+  }
+
+  onOutboxUpdated(cb: (entries: OutboxEntry[]) => void): Promise<UnlistenFn> {
+    return listen<OutboxEntry[]>("download::outbox_updated", (event) =>
+      cb(event.payload)
+    );
+  }
+
+  // history, dates are `YYYY-MM-DD` and both ends are included
+
+  async downloadStats(
+    from: string,
+    to: string
+  ): Promise<Result<DownloadStats, string>> {
+    return await commands.dmDownloadStats(from, to);
+  }
+
+  async downloadHistory(
+    from: string,
+    to: string
+  ): Promise<Result<JobRecord[], string>> {
+    return await commands.dmDownloadHistory(from, to);
+  }
+
+  async exportDownloadStats(
+    kind: StatsExport,
+    from: string,
+    to: string,
+    path: string
+  ): Promise<Result<null, string>> {
+    return await commands.dmExportDownloadStats(kind, from, to, path);
+  }
+
   async getDatahosterLinks(
     gameLink: string,
     datahosterName: string
@@ -242,13 +242,15 @@ async changeInstallationSettings(settings: InstallationSettings) : Promise<Resul
     else return { error: e  as any, status: "error" };
 }
 },
-async checkDominantColorVec(listImages: string[]) : Promise<Result<string[], string>> {
-    try {
-    return { data: await TAURI_INVOKE("check_dominant_color_vec", { listImages }), status: "ok" };
-} catch (e) {
-    if(e instanceof Error) throw e;
-    else return { error: e  as any, status: "error" };
-}
+/**
+ * Palette of every image of `list_images`, in order. A failing image only
+ * has its own `error` set.
+ */
+async checkDominantColorVec(listImages: string[]) : Promise<ImageColors[]> {
+    return await TAURI_INVOKE("check_dominant_color_vec", { listImages });
+},
+async classifyTorrentFiles(files: FileInfo[]) : Promise<ClassifiedFile[]> {
+    return await TAURI_INVOKE("classify_torrent_files", { files });
 },
 async clearAllCache() : Promise<Result<null, SettingsConfigurationError>> {
     try {
@@ -424,17 +426,28 @@ async deleteGameFolderRecursively(folderPath: string) : Promise<Result<null, Tor
     else return { error: e  as any, status: "error" };
 }
 },
-async dmAddDdlJob(files: DirectLink[], target: string, game: Game) : Promise<Result<string, string>> {
+async dmAddDdlJob(files: DirectLink[], target: string, game: Game, ignoreSpaceCheck: boolean) : Promise<Result<JobSubmission, string>> {
     try {
-    return { data: await TAURI_INVOKE("dm_add_ddl_job", { files, game, target }), status: "ok" };
+    return { data: await TAURI_INVOKE("dm_add_ddl_job", { files, game, ignoreSpaceCheck, target }), status: "ok" };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { error: e  as any, status: "error" };
 }
 },
-async dmAddTorrentJob(magnet: string, filesList: number[], target: string, game: Game) : Promise<Result<string, string>> {
+async dmAddTorrentJob(magnet: string, filesList: number[], target: string, game: Game, ignoreSpaceCheck: boolean) : Promise<Result<JobSubmission, string>> {
     try {
-    return { data: await TAURI_INVOKE("dm_add_torrent_job", { filesList, game, magnet, target }), status: "ok" };
+    return { data: await TAURI_INVOKE("dm_add_torrent_job", { filesList, game, ignoreSpaceCheck, magnet, target }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+/**
+ * Download more files of a torrent job, e.g. an optional file skipped at first
+ */
+async dmAddTorrentFiles(jobId: string, fileIndices: number[]) : Promise<Result<null, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_add_torrent_files", { jobId, fileIndices }), status: "ok" };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { error: e  as any, status: "error" };
@@ -448,6 +461,18 @@ async dmAllJobs() : Promise<Result<Job[], string>> {
     else return { error: e  as any, status: "error" };
 }
 },
+/**
+ * Cancel a running extraction (and the installation that would follow it)
+ * for the given download job.
+ */
+async dmCancelExtraction(jobId: string) : Promise<Result<boolean, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_cancel_extraction", { jobId }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async dmCleanJob(jobId: string, installationId: string) : Promise<Result<null, InstallationError>> {
     try {
     return { data: await TAURI_INVOKE("dm_clean_job", { installationId, jobId }), status: "ok" };
@@ -456,9 +481,60 @@ async dmCleanJob(jobId: string, installationId: string) : Promise<Result<null, I
     else return { error: e  as any, status: "error" };
 }
 },
-async dmExtractAndInstall(job: Job, autoClean: boolean) : Promise<Result<string, ExtractError>> {
+async dmDaemonHealth() : Promise<Result<DaemonHealth, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_daemon_health"), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+async dmDiscardOutboxEntry(id: string) : Promise<Result<boolean, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_discard_outbox_entry", { id }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+async dmDownloadHistory(from: string, to: string) : Promise<Result<JobRecord[], string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_download_history", { from, to }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+/**
+ * Transfer totals between `from` and `to`, both included, fastest providers first
+ */
+async dmDownloadStats(from: string, to: string) : Promise<Result<DownloadStats, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_download_stats", { from, to }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+async dmExportDownloadStats(kind: StatsExport, from: string, to: string, path: string) : Promise<Result<null, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_export_download_stats", { kind, from, to, path }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+async dmExtractAndInstall(job: Job, autoClean: boolean, password: string | null) : Promise<Result<string, ExtractError>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_extract_and_install", { autoClean, job, password }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+async dmJobDetails(jobId: string) : Promise<Result<JobDetails, string>> {
     try {
-    return { data: await TAURI_INVOKE("dm_extract_and_install", { autoClean, job }), status: "ok" };
+    return { data: await TAURI_INVOKE("dm_job_details", { jobId }), status: "ok" };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { error: e  as any, status: "error" };
@@ -472,6 +548,17 @@ async dmLoadFromDisk() : Promise<Result<null, string>> {
     else return { error: e  as any, status: "error" };
 }
 },
+/**
+ * Downloads waiting for the app to be online again
+ */
+async dmOutbox() : Promise<Result<OutboxEntry[], string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_outbox"), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async dmPause(jobId: string) : Promise<Result<null, string>> {
     try {
     return { data: await TAURI_INVOKE("dm_pause", { jobId }), status: "ok" };
@@ -480,6 +567,28 @@ async dmPause(jobId: string) : Promise<Result<null, string>> {
     else return { error: e  as any, status: "error" };
 }
 },
+/**
+ * Check free space for a DDL job without adding it
+ */
+async dmPreflightDdl(files: DirectLink[], target: string, game: Game) : Promise<Result<PreflightReport, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_preflight_ddl", { files, game, target }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+/**
+ * Check free space for a torrent job without adding it
+ */
+async dmPreflightTorrent(magnet: string, filesList: number[], target: string, game: Game) : Promise<Result<PreflightReport, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_preflight_torrent", { filesList, game, magnet, target }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async dmRemove(jobId: string) : Promise<Result<null, string>> {
     try {
     return { data: await TAURI_INVOKE("dm_remove", { jobId }), status: "ok" };
@@ -488,6 +597,17 @@ async dmRemove(jobId: string) : Promise<Result<null, string>> {
     else return { error: e  as any, status: "error" };
 }
 },
+/**
+ * Bring aria2 back by hand, e.g. after the supervisor gave up on a crash loop
+ */
+async dmRestartDaemon() : Promise<Result<null, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_restart_daemon"), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async dmResume(jobId: string) : Promise<Result<null, string>> {
     try {
     return { data: await TAURI_INVOKE("dm_resume", { jobId }), status: "ok" };
@@ -496,6 +616,17 @@ async dmResume(jobId: string) : Promise<Result<null, string>> {
     else return { error: e  as any, status: "error" };
 }
 },
+/**
+ * Retry the post-download pipeline of a job from the stage that failed
+ */
+async dmRetryPipeline(jobId: string) : Promise<Result<null, string>> {
+    try {
+    return { data: await TAURI_INVOKE("dm_retry_pipeline", { jobId }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async dmRunAutomateSetupInstall(job: Job) : Promise<Result<string, InstallationError>> {
     try {
     return { data: await TAURI_INVOKE("dm_run_automate_setup_install", { job }), status: "ok" };
@@ -515,6 +646,26 @@ async dmSaveNow() : Promise<Result<null, string>> {
 async executableInfoDiscovery(pathToExe: string, pathToFolder: string) : Promise<ExecutableInfo | null> {
     return await TAURI_INVOKE("executable_info_discovery", { pathToExe, pathToFolder });
 },
+async exportBackup(destination: string, includeSecrets: boolean) : Promise<Result<BackupManifest, string>> {
+    try {
+    return { data: await TAURI_INVOKE("export_backup", { destination, includeSecrets }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+/**
+ * Write every downloaded game with a known executable into Steam's `shortcuts.vdf`,
+ * for each Steam user found under `steam_dir` (or the default Steam install).
+ */
+async exportSteamShortcuts(steamDir: string | null) : Promise<Result<SteamExportReport, string>> {
+    try {
+    return { data: await TAURI_INVOKE("export_steam_shortcuts", { steamDir }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async extractFuckingfastDdl(fuckingfastLinks: string[]) : Promise<DirectLink[]> {
     return await TAURI_INVOKE("extract_fuckingfast_ddl", { fuckingfastLinks });
 },
@@ -545,10 +696,27 @@ async folderExclusionCleanup(policy: ExclusionCleanupPolicy) : Promise<Result<nu
 async getCollectionList() : Promise<GameCollection[]> {
     return await TAURI_INVOKE("get_collection_list");
 },
+async getConnectivity() : Promise<Connectivity> {
+    return await TAURI_INVOKE("get_connectivity");
+},
 /**
  * TODO: this isn't workin at all btw
  * 
  */
+async getCrawlerProgress() : Promise<CrawlerProgress> {
+    return await TAURI_INVOKE("get_crawler_progress");
+},
+/**
+ * When the local data was last synced, to show how stale it is offline
+ */
+async getDataFreshness(gameUrl: string | null) : Promise<Result<DataFreshness, ScrapingError>> {
+    try {
+    return { data: await TAURI_INVOKE("get_data_freshness", { gameUrl }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async getDatahosterLinks(gameLink: string, datahosterName: string) : Promise<string[] | null> {
     return await TAURI_INVOKE("get_datahoster_links", { datahosterName, gameLink });
 },
@@ -599,6 +767,25 @@ async getGamehubSettings() : Promise<GamehubSettings> {
 async getGamehubSettingsPath() : Promise<string> {
     return await TAURI_INVOKE("get_gamehub_settings_path");
 },
+async getGameLogPath(gameHref: string) : Promise<Result<string, string>> {
+    try {
+    return { data: await TAURI_INVOKE("get_game_log_path", { gameHref }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+/**
+ * Past repacks of a game, newest first, with what changed in each one
+ */
+async getGameRevisions(url: string) : Promise<Result<GameRevision[], ScrapingError>> {
+    try {
+    return { data: await TAURI_INVOKE("get_game_revisions", { url }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async getGamesImages(gameLink: string) : Promise<Result<string[], CustomError>> {
     try {
     return { data: await TAURI_INVOKE("get_games_images", { gameLink }), status: "ok" };
@@ -632,6 +819,17 @@ async getNewlyAddedGames() : Promise<Result<Game[], ScrapingError>> {
     else return { error: e  as any, status: "error" };
 }
 },
+/**
+ * Empty field rates of the last scrape runs, to spot a site layout change
+ */
+async getParserHealth() : Promise<Result<ParserHealth[], ScrapingError>> {
+    try {
+    return { data: await TAURI_INVOKE("get_parser_health"), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async getPopularGames() : Promise<Result<Game[], ScrapingError>> {
     try {
     return { data: await TAURI_INVOKE("get_popular_games"), status: "ok" };
@@ -648,6 +846,23 @@ async getRecentlyUpdatedGames() : Promise<Result<Game[], ScrapingError>> {
     else return { error: e  as any, status: "error" };
 }
 },
+/**
+ * The discovery feed with why each game is in it, best first
+ */
+async getRecommendations() : Promise<Result<Recommendation[], ScrapingError>> {
+    try {
+    return { data: await TAURI_INVOKE("get_recommendations"), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
+/**
+ * Per host counters of the request scheduler, for diagnostics
+ */
+async getRequestMetrics() : Promise<HostMetrics[]> {
+    return await TAURI_INVOKE("get_request_metrics");
+},
 async getSearchIndexPathCmd() : Promise<string> {
     return await TAURI_INVOKE("get_search_index_path_cmd");
 },
@@ -684,6 +899,20 @@ async getUsedSpace() : Promise<number> {
 async hashUrl(url: string) : Promise<number> {
     return await TAURI_INVOKE("hash_url", { url });
 },
+/**
+ * Restore a backup made by [`export_backup`].
+ * 
+ * Paths under the source machine's download dir are rewritten to
+ * `download_dir`, or to the current download dir when not given.
+ */
+async importBackup(archive: string, downloadDir: string | null, importSecrets: boolean) : Promise<Result<BackupImportReport, string>> {
+    try {
+    return { data: await TAURI_INVOKE("import_backup", { archive, downloadDir, importSecrets }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async importCookies(cookies: Cookies) : Promise<Result<null, string>> {
     try {
     return { data: await TAURI_INVOKE("import_cookies", { cookies }), status: "ok" };
@@ -708,9 +937,7 @@ async importCookies(cookies: Cookies) : Promise<Result<null, string>> {
  * ]
  * ```
  * 
- * Extra fields are allowed.
- * 
- * Also, to make effect, an restart is required.
+ * Extra fields are allowed. A cookie without a `domain` is sent to the site.
  */
 async importCookiesFile(jsonPath: string) : Promise<Result<null, string>> {
     try {
@@ -723,6 +950,17 @@ async importCookiesFile(jsonPath: string) : Promise<Result<null, string>> {
 async isControllerRunning() : Promise<boolean> {
     return await TAURI_INVOKE("is_controller_running");
 },
+/**
+ * Launch a downloaded game through its launch profile, returns the PID.
+ */
+async launchGame(gameHref: string) : Promise<Result<number, string>> {
+    try {
+    return { data: await TAURI_INVOKE("launch_game", { gameHref }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async listTorrentFiles(magnet: string) : Promise<Result<FileInfo[], TorrentApiError>> {
     try {
     return { data: await TAURI_INVOKE("list_torrent_files", { magnet }), status: "ok" };
@@ -753,6 +991,21 @@ async openLogsDirectory() : Promise<Result<null, string>> {
 async panicForce() : Promise<void> {
     await TAURI_INVOKE("panic_force");
 },
+async pauseCrawler() : Promise<void> {
+    await TAURI_INVOKE("pause_crawler");
+},
+/**
+ * `file_index` of the files to download by default, following the
+ * language and extras preferences of the download settings
+ */
+async preselectTorrentFiles(files: FileInfo[]) : Promise<Result<number[], TorrentApiError>> {
+    try {
+    return { data: await TAURI_INVOKE("preselect_torrent_files", { files }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async querySearchIndex(query: string) : Promise<Result<SearchIndexEntry[], ScrapingError>> {
     try {
     return { data: await TAURI_INVOKE("query_search_index", { query }), status: "ok" };
@@ -780,6 +1033,17 @@ async reclaimSpace(space: number) : Promise<Result<null, CacheError>> {
     else return { error: e  as any, status: "error" };
 }
 },
+/**
+ * Re-rank the discovery feed, e.g. after the library changed
+ */
+async refreshRecommendations() : Promise<Result<null, string>> {
+    try {
+    return { data: await TAURI_INVOKE("refresh_recommendations"), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async removeCollection(collectionName: string) : Promise<Result<null, string>> {
     try {
     return { data: await TAURI_INVOKE("remove_collection", { collectionName }), status: "ok" };
@@ -836,6 +1100,9 @@ async resetInstallationSettings() : Promise<Result<null, SettingsConfigurationEr
     else return { error: e  as any, status: "error" };
 }
 },
+async resumeCrawler() : Promise<void> {
+    await TAURI_INVOKE("resume_crawler");
+},
 /**
  * Set capacity, flush cache for shrink,
  * and modify config (in-memory and on disk)
@@ -848,6 +1115,17 @@ async setCapacity(newCapacity: number) : Promise<Result<null, CacheError>> {
     else return { error: e  as any, status: "error" };
 }
 },
+/**
+ * Switch the offline mode and save it in the gamehub settings
+ */
+async setOfflineMode(enabled: boolean) : Promise<Result<null, SettingsConfigurationError>> {
+    try {
+    return { data: await TAURI_INVOKE("set_offline_mode", { enabled }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 /**
  * Start an executable using tauri::command
  * 
@@ -863,6 +1141,17 @@ async stopGetGamesImages() : Promise<void> {
 async transformLegacyDownload(legacyItems: LegacyDownloadedGame[]) : Promise<DownloadedGame[]> {
     return await TAURI_INVOKE("transform_legacy_download", { legacyItems });
 },
+/**
+ * Uninstall a downloaded game, or list what would be removed with `dry_run`.
+ */
+async uninstallGame(gameHref: string, options: UninstallOptions) : Promise<Result<UninstallReport, string>> {
+    try {
+    return { data: await TAURI_INVOKE("uninstall_game", { gameHref, options }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
+},
 async updateDownloadedGameExecutableInfo(gameTitle: string, executableInfo: ExecutableInfo) : Promise<Result<null, string>> {
     try {
     return { data: await TAURI_INVOKE("update_downloaded_game_executable_info", { executableInfo, gameTitle }), status: "ok" };
@@ -870,6 +1159,14 @@ async updateDownloadedGameExecutableInfo(gameTitle: string, executableInfo: Exec
     if(e instanceof Error) throw e;
     else return { error: e  as any, status: "error" };
 }
+},
+async updateDownloadedGameLaunchProfile(gameHref: string, launchProfile: LaunchProfile) : Promise<Result<null, string>> {
+    try {
+    return { data: await TAURI_INVOKE("update_downloaded_game_launch_profile", { gameHref, launchProfile }), status: "ok" };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { error: e  as any, status: "error" };
+}
 }
 }
 
@@ -891,6 +1188,28 @@ export type AriaTaskProgress = { completed: number; download_speed: number; tota
 export type AriaTaskResult = { task: AriaTask | null; error: Aria2Error | null }
 export type Attach = { type: string; data?: AttachType[] }
 export type AttachType = { src: string | null; src_o: string | null; width: number | null; video: string | null; webp: string | null; height: number | null; title: string | null; type: string | null; description: string | null }
+export type BackupImportReport = { restored: string[]; 
+/**
+ * Number of paths rewritten from the old download dir to the new one
+ */
+remapped_paths: number; secrets_restored: boolean; warnings: string[]; 
+/**
+ * Managers keep their state in memory, a restart applies the import
+ */
+restart_required: boolean }
+export type BackupManifest = { format_version: number; app_version: string; created_at: string; 
+/**
+ * `std::env::consts::OS` of the machine that made the backup
+ */
+source_os: string; 
+/**
+ * Download directory on the source machine, used for remapping on import
+ */
+source_download_dir: string; includes_secrets: boolean; 
+/**
+ * Archive paths of every file in the backup (manifest excluded)
+ */
+entries: string[] }
 export type BitTorrentFileMode = "single" | "multi"
 /**
  * https://aria2.github.io/manual/en/html/aria2c.html
@@ -920,7 +1239,12 @@ export type Bittorrent = {
  * The time (in minutes) to continue seeding after the download completes.
  * If `None`, no time limit is enforced (infinite seeding allowed).
  */
-"seed-time": number | null }
+"seed-time": number | null; 
+/**
+ * Minutes without progress and without peers before a torrent job is flagged as stalled.
+ * If `None`, jobs are never flagged.
+ */
+"stall-timeout": number | null }
 export type BittorrentStatus = { announceList: string[][]; comment: string | null; creationDate?: string | null; mode: BitTorrentFileMode | null }
 export type CacheError = { LRU: string } | { Reqwest: string } | { Kanal: string } | { IO: string } | "CacheMissing" | "MimeGuess" | "ZeroCapacity"
 export type CacheSettings = { 
@@ -928,17 +1252,75 @@ export type CacheSettings = {
  * max image cache size, in bytes
  */
 cache_size?: number }
+/**
+ * Background scraping of the games only known from the sitemaps
+ */
+export type CatalogCrawlerSettings = { enabled: boolean; 
+/**
+ * Game pages fetched per minute at most
+ */
+pages_per_minute: number; pause_while_downloading: boolean; pause_on_metered: boolean }
 export type Chat = { site_id: number; title: string; hash: string; identity: JsonValue | null; url: string; count_comment_all: number; count_comment_load: number; closed: boolean; format: number; root_id: number; fixed_comment: JsonValue | null }
+export type CircuitState = "closed" | "open" | "halfOpen"
+export type ClassifiedFile = { file_index: number; kind: FileKind }
 export type Comment = { id: number; text_template: string | null; data_create: string | null; user: User | null; raiting: Rating | null; attaches?: Attach[]; attaches_icons?: JsonValue[]; attaches_text: string | null; sort: JsonValue; edited: boolean | null; fixed: boolean | null; comment_type: number; answer_comment_root_id: number; answer_comment_count: number; answer_comment: AnswerComment | null }
 export type CommentData = { chat: Chat; comments: Comment[] }
 export type Comments = { data: CommentData }
 export type Connection = { "max-connection-per-server": number; split: number; "min-split-size": number; "connect-timeout": Duration; "rw-timeout": Duration }
-export type Cookie = { name: string; value: string; domain: string | null; path: string | null; expires: string | null; max_age: number | null }
+export type Connectivity = { 
+/**
+ * Offline mode chosen in the settings
+ */
+forced_offline: boolean; 
+/**
+ * Outcome of the last request to the site
+ */
+reachable: boolean; 
+/**
+ * Outcome of the last network probe, see [`probe_network`]
+ */
+network: boolean; 
+/**
+ * Unix timestamp of the last time the site answered
+ */
+last_online: number | null }
+export type Cookie = { name: string; value: string; domain: string | null; path: string | null; expires: string | null; 
+/**
+ * Seconds from the moment the cookie is imported, wins over `expires`
+ */
+max_age: number | null; 
+/**
+ * Only sent over https
+ */
+secure: boolean }
 export type Cookies = Cookie[]
 export type CredentialError = { KeyringError: string } | "NotFound"
 export type CredentialInfo = { configured_providers: DebridProvider[] }
 export type CredentialStatus = { provider: DebridProvider; has_credential: boolean }
+export type CrawlerProgress = { state: CrawlerState; scraped: number; total: number; 
+/**
+ * Pages that failed during the current run
+ */
+failed: number; current: string | null }
+export type CrawlerState = { kind: "disabled" } | { kind: "crawling" } | { kind: "paused"; reason: PauseReason } | { kind: "idle" }
 export type CustomError = { message: string }
+export type DaemonHealth = { state: "running" } | { state: "restarting"; attempt: number; retry_in_secs: number; reason: string } | 
+/**
+ * Crash loop, nothing is retried until [`Aria2Supervisor::request_restart`]
+ */
+{ state: "failed"; reason: string }
+/**
+ * Offline, this tells how stale what the read commands return is.
+ */
+export type DataFreshness = { newly_added: number | null; popular: number | null; recently_updated: number | null; discovery: number | null; sitemaps: number | null; 
+/**
+ * The game asked for, if any
+ */
+game: number | null }
+/**
+ * Transfer counters of one local day
+ */
+export type DayCounters = { date: string; downloaded_bytes: number; uploaded_bytes: number; jobs_completed: number; jobs_failed: number }
 export type DdlJob = { files: DirectLink[] }
 export type DebridCacheStatus = { is_cached: boolean; name: string | null; size: number | null; hash: string }
 export type DebridDirectLink = { url: string; filename: string; size: number }
@@ -955,6 +1337,15 @@ export type DirectLink = { url: string; filename: string;
 size: number }
 export type DownloadSource = "Ddl" | "Torrent"
 export type DownloadState = "active" | "paused" | "waiting" | "error" | "complete" | "installing" | "removed"
+export type DownloadStats = { from: string; to: string; downloaded_bytes: number; uploaded_bytes: number; jobs_completed: number; jobs_failed: number; 
+/**
+ * Days with any traffic, oldest first
+ */
+days: DayCounters[]; 
+/**
+ * Fastest first
+ */
+providers: ProviderStats[] }
 export type DownloadedGame = { title: string; img: string; 
 /**
  * Game details: genres/tags, companies, languages, original size, repack size
@@ -975,7 +1366,11 @@ gameplay_features: string;
 /**
  * Included DLCs section
  */
-included_dlcs: string; magnetlink: string; href: string; tag: string; executable_info: ExecutableInfo; installation_info: InstallationInfo }
+included_dlcs: string; magnetlink: string; href: string; tag: string; executable_info: ExecutableInfo; installation_info: InstallationInfo; 
+/**
+ * How the game executable is started (mostly relevant on Linux)
+ */
+launch_profile: LaunchProfile }
 export type Duration = { secs: number; nanos: number }
 /**
  * Possible errors during requesting/decrypting/decoding/deserialization e.g.
@@ -984,17 +1379,73 @@ export type Error = { KeyLengthMismatch: number } | "ZeroIterations" | "IllForme
 export type ExclusionAction = { Add: string } | { Remove: string }
 export type ExclusionCleanupPolicy = { Keep: string } | { RemoveAfterInstall: string }
 export type ExecutableInfo = { executable_path: string; executable_last_opened_date: string | null; executable_play_time: number; executable_installed_date: string | null; executable_disk_size: number }
-export type ExtractError = { Io: string } | { Unrar: string } | { InstallationError: InstallationError } | "NoParentDirectory" | "NoRarFileFound"
+export type ExtractError = { Io: string } | { Unrar: string } | { Zip: string } | { SevenZip: string } | { InstallationError: InstallationError } | "NoParentDirectory" | "NoRarFileFound" | { UnsupportedFormat: string } | "PasswordRequired" | "WrongPassword" | "Cancelled"
+/**
+ * One field that differs from the previous revision
+ */
+export type FieldChange = { 
+/**
+ * `title`, `details`, `features`, `included_dlcs` or `magnetlink`
+ */
+field: string; before: string; after: string; 
+/**
+ * Lines only in `after`, e.g. a new DLC
+ */
+added: string[]; 
+/**
+ * Lines only in `before`
+ */
+removed: string[] }
+export type FieldHealth = { field: string; 
+/**
+ * Share of the games of the run with this field empty, 0 to 1
+ */
+empty_rate: number; 
+/**
+ * Moving average of the previous rates
+ */
+baseline: number }
 export type File = { index: number; path: string; length: number; completedLength: number; selected: boolean; uris: Uri[] }
 export type FileAllocation = "Auto" | "Falloc" | "Prealloc" | "None"
 export type FileInfo = { file_name: string; length: number; file_index: number }
+export type FileKind = 
+/**
+ * Game archives, the installer and checksums, always downloaded
+ */
+{ kind: "core" } | 
+/**
+ * `fg-selective-*` or, when `optional`, an `fg-optional-*` voice pack.
+ * `language` is spelled as in the file name
+ */
+{ kind: "language"; language: string; optional: boolean } | 
+/**
+ * Any other `fg-optional-*` file, `name` is the part after the prefix
+ */
+{ kind: "bonus"; name: string } | { kind: "soundtrack" }
+export type FileProgress = { 
+/**
+ * 0-based, like `TorrentJob::file_indices`
+ */
+index: number; path: string; length: number; completed_length: number; selected: boolean }
+/**
+ * Which FitGirl optional and selective files are picked for a new torrent job
+ */
+export type FileSelection = { 
+/**
+ * Language packs to keep, as FitGirl spells them (`english`, `french`, ...)
+ */
+"preferred-languages": string[]; 
+/**
+ * Bonus content such as artbooks or videos
+ */
+"include-bonus": boolean; "include-soundtrack": boolean }
 export type FileStatus = { gid: string | null; status: DownloadState; total_length: number; completed_length: number; download_speed: number; upload_speed: number; files: File[]; info_hash: string | null }
 /**
  * 2. Aria2 RPC block
  * 
  */
 export type FitLauncherConfigAria2 = { port: number; token: string | null; start_daemon: boolean; file_allocation: FileAllocation }
-export type FitLauncherConfigV2 = { general: General; cache: CacheSettings; limits: TransferLimits; network: Connection; bittorrent: Bittorrent; rpc: FitLauncherConfigAria2 }
+export type FitLauncherConfigV2 = { general: General; cache: CacheSettings; limits: TransferLimits; network: Connection; bittorrent: Bittorrent; rpc: FitLauncherConfigAria2; engine: TorrentEngine; selection: FileSelection }
 export type FitLauncherDnsConfig = { system_conf: boolean; protocol: string; primary: string | null; secondary: string | null }
 /**
  * Game data extracted from FitGirl Repacks pages.
@@ -1031,21 +1482,242 @@ included_dlcs: string; magnetlink: string; href: string; tag: string;
  */
 secondary_images: string[]; pastebin_link?: string }
 export type GameCollection = { name: string; games_list: Game[] }
-export type GamehubSettings = { nsfw_censorship: boolean; auto_get_colors_popular_games: boolean; close_to_tray: boolean; game_page_allow_comments: boolean }
+export type GamehubSettings = { nsfw_censorship: boolean; auto_get_colors_popular_games: boolean; close_to_tray: boolean; game_page_allow_comments: boolean; catalog_crawler?: CatalogCrawlerSettings; site?: SiteSettings; 
+/**
+ * Serve local data only and hold network writes, see [`crate::client::connectivity`]
+ */
+offline_mode?: boolean }
+export type GameRevision = { id: number; 
+/**
+ * Unix timestamp of the scrape that saw this state
+ */
+recorded_at: number; title: string; details: string; features: string; included_dlcs: string; magnetlink: string; 
+/**
+ * Changes from the previous revision, empty for the oldest one
+ */
+changes: FieldChange[] }
 export type General = { download_dir: string; concurrent_downloads?: number; folder_exclusion: boolean; folder_exclusion_cleanup: boolean }
 export type GlobalStat = { downloadSpeed: number; uploadSpeed: number; numActive: number; numWaiting: number; numStopped: number; numStoppedTotal: number }
+export type HostMetrics = { host: string; max_concurrent: number; min_interval_ms: number; 
+/**
+ * Attempts sent, retries included
+ */
+requests: number; succeeded: number; 
+/**
+ * Requests that gave up, with an error or a retryable status
+ */
+failed: number; retries: number; 
+/**
+ * 429, `Retry-After` or a rate limit page
+ */
+throttled: number; 
+/**
+ * Failed fast on an open circuit
+ */
+rejected: number; in_flight: number; average_latency_ms: number; circuit: CircuitState; consecutive_failures: number }
+/**
+ * Palette of one image, or why there is none
+ */
+export type ImageColors = { url: string; palette: ImagePalette | null; error: string | null }
+export type ImagePalette = { 
+/**
+ * Most dominant first
+ */
+colors: Rgb[]; 
+/**
+ * Black or white, whichever reads better on the dominant colour
+ */
+text_color: Rgb }
 export type InstallationError = { IOError: string } | "AdminModeError"
 export type InstallationInfo = { output_folder: string; download_folder: string; file_list: string[] }
 export type InstallationSettings = { auto_clean: boolean; auto_install: boolean; two_gb_limit: boolean; directx_install: boolean; microsoftcpp_install: boolean }
-export type Job = { id: string; metadata: JobMetadata; game: Game; job_path: string; source: DownloadSource; gids: string[]; ddl: DdlJob | null; torrent: TorrentJob | null; state: DownloadState; status: AggregatedStatus | null }
+export type Job = { id: string; metadata: JobMetadata; game: Game; job_path: string; source: DownloadSource; gids: string[]; ddl: DdlJob | null; torrent: TorrentJob | null; state: DownloadState; status: AggregatedStatus | null; 
+/**
+ * Post-download pipeline, `None` until the download completes
+ */
+pipeline: PipelineState | null; 
+/**
+ * Set while the job makes no progress and has no peers, see `details::spawn_details_monitor`
+ */
+stalled_since: string | null }
+export type JobDetails = { job_id: string; transfers: TransferDetails[]; stalled_since: string | null }
 export type JobMetadata = { game_title: string; target_path: string; created_at: string; updated_at: string }
+export type JobOutcome = "inProgress" | "completed" | "failed" | 
+/**
+ * Removed before it completed
+ */
+"removed"
+export type JobRecord = { job_id: string; game_title: string; source: DownloadSource; 
+/**
+ * Host of the direct links, `None` for torrents
+ */
+host: string | null; 
+/**
+ * Engine that moved the bytes
+ */
+engine: string; total_bytes: number; 
+/**
+ * Bytes transferred while we watched, data already on disk is not counted
+ */
+downloaded_bytes: number; 
+/**
+ * Estimated from the upload speed
+ */
+uploaded_bytes: number; 
+/**
+ * Time spent active, pauses excluded
+ */
+active_secs: number; 
+/**
+ * Bytes per second over `active_secs`
+ */
+average_speed: number; peak_speed: number; started_at: string; finished_at: string | null; outcome: JobOutcome }
+/**
+ * What became of a download request
+ */
+export type JobSubmission = 
+/**
+ * Submitted right away, with the id of the new job
+ */
+{ kind: "started"; id: string } | 
+/**
+ * Offline, waiting in the outbox under the id of its entry
+ */
+{ kind: "queued"; id: string }
 export type JsonValue = null | boolean | number | string | JsonValue[] | Partial<{ [key in string]: JsonValue }>
+export type LaunchProfile = { runner: LaunchRunner; 
+/**
+ * Wine prefix, exported as `WINEPREFIX` when set
+ */
+prefix: string | null; env: ([string, string])[]; args: string[]; 
+/**
+ * Defaults to the executable's folder
+ */
+working_dir: string | null }
+export type LaunchRunner = 
+/**
+ * Execute the executable directly
+ */
+{ type: "native" } | 
+/**
+ * Run through Wine, `binary` defaults to `wine` from `PATH`
+ */
+{ type: "wine"; binary: string | null } | 
+/**
+ * Custom command line, e.g. `protonrun {exe} {args}`
+ * 
+ * Placeholders: `{exe}`, `{args}`, `{prefix}`, `{dir}`
+ */
+{ type: "custom"; template: string }
 export type LegacyDownloadedGame = { torrentExternInfo: TorrentExternInfo; torrentIdx: string; torrentOutputFolder: string; torrentDownloadFolder: string; torrentFileList: string[]; checkboxesList: boolean; executableInfo: ExecutableInfo }
+export type OutboxEntry = { id: string; queued_at: string; request: OutboxRequest; 
+/**
+ * Why the last replay failed
+ */
+last_error: string | null }
+export type OutboxRequest = { kind: "addDdlJob"; files: DirectLink[]; target: string; game: Game; ignore_space_check: boolean } | { kind: "addTorrentJob"; magnet: string; files_list: number[]; target: string; game: Game; ignore_space_check: boolean }
+/**
+ * Last scrape run of one kind (`newly_added`, `popular`, `crawler`...)
+ */
+export type ParserHealth = { run: string; recorded_at: number; parsed: number; fields: FieldHealth[]; 
+/**
+ * Fields whose empty rate jumped above their baseline
+ */
+spiked: string[] }
+export type PauseReason = "user" | "downloadsActive" | "metered" | "offline"
+export type PeerDetails = { ip: string; port: number; seeder: boolean; download_speed: number; upload_speed: number; 
+/**
+ * Share of the pieces the peer has, from 0.0 to 1.0
+ */
+progress: number }
+/**
+ * Post-download stages, run in this order once a job completes
+ */
+export type PipelineStage = "Verify" | "Extract" | "Install" | "Clean" | "Register" | "Done"
+export type PipelineState = { 
+/**
+ * Stage to run next, `Done` once everything finished
+ */
+stage: PipelineStage; 
+/**
+ * Failed attempts of the current stage
+ */
+attempts: number; last_error: string | null; next_retry_at: string | null; 
+/**
+ * Set when the current stage ran out of attempts
+ */
+failed: boolean; 
+/**
+ * Install stage was skipped, nothing left to clean or register
+ */
+install_skipped: boolean; install_path: string | null; executable: string | null }
+export type PreflightReport = { verdict: PreflightVerdict; downloadBytes: number; 
+/**
+ * Estimated from "Original Size", or twice the download when unknown
+ */
+installBytes: number; installEstimatedFromOriginalSize: boolean; volumes: VolumeCheck[] }
+export type PreflightVerdict = "ok" | "warn" | "insufficient"
+/**
+ * Jobs of one DDL host or torrent engine
+ */
+export type ProviderStats = { provider: string; source: DownloadSource; jobs: number; downloaded_bytes: number; active_secs: number; average_speed: number; peak_speed: number }
 export type QueueStatus = { queue: string[]; active: string | null }
 export type Rating = { id: number; val: number; user_val: number }
-export type ScrapingError = { type: "articleNotFound"; data: string } | { type: "reqwestError"; data: string } | { type: "selectorError"; data: string } | { type: "jsonError"; data: string } | { type: "generalError"; data: string } | { type: "httpStatusCodeError"; data: string } | { type: "timeoutError"; data: string } | { type: "ioerror"; data: string } | { type: "windowError"; data: string } | { type: "cookieError"; data: string } | { type: "urlParseError"; data: string } | { type: "regexError"; data: string } | { type: "semaphoreError"; data: string }
+export type Recommendation = { game: Game; 
+/**
+ * 0 to 1
+ */
+score: number; reasons: RecommendationReason[] }
+/**
+ * Why a game was recommended, the frontend words it
+ */
+export type RecommendationReason = { kind: "similarTo"; title: string; href: string; 
+/**
+ * `played`, `installed`, `downloaded` or `collected`
+ */
+relation: string; 
+/**
+ * Name of the collection when `relation` is `collected`
+ */
+collection: string | null; 
+/**
+ * The genres and companies both games have
+ */
+shared: string[] } | { kind: "popular"; rank: number } | { kind: "recent" }
+export type Rgb = { r: number; g: number; b: number }
+export type ScrapingError = { type: "articleNotFound"; data: string } | { type: "reqwestError"; data: string } | { type: "selectorError"; data: string } | { type: "jsonError"; data: string } | { type: "generalError"; data: string } | { type: "httpStatusCodeError"; data: string } | { type: "timeoutError"; data: string } | { type: "ioerror"; data: string } | { type: "windowError"; data: string } | { type: "cookieError"; data: string } | { type: "urlParseError"; data: string } | { type: "regexError"; data: string } | { type: "semaphoreError"; data: string } | { type: "offline" }
 export type SearchIndexEntry = { slug: string; title: string; href: string }
 export type SettingsConfigurationError = { message: string }
+/**
+ * Where the FitGirl site is reached, the base URL first and then the mirrors
+ */
+export type SiteSettings = { 
+/**
+ * Origin tried first, like `https://fitgirl-repacks.site`
+ */
+base_url: string; 
+/**
+ * Origins tried in order when the base URL cannot be reached
+ */
+mirrors: string[] }
+export type StageStatus = "Running" | "Succeeded" | "Skipped" | 
+/**
+ * Failed, `next_retry_at` tells when it runs again
+ */
+"Retrying" | 
+/**
+ * Out of attempts, needs `dm_retry_pipeline`
+ */
+"Failed"
+export type StatsExport = 
+/**
+ * One row per job
+ */
+"jobs" | 
+/**
+ * One row per day
+ */
+"days"
 /**
  * Full status of a task.
  * 
@@ -1118,6 +1790,15 @@ verifiedLength?: number | null;
  * This key exists only when this download is in the queue.
  */
 verifyIntegrityPending?: boolean | null }
+export type SteamExportReport = { 
+/**
+ * `shortcuts.vdf` files that were written
+ */
+shortcut_files: string[]; added: number; updated: number; 
+/**
+ * Games skipped because no executable is known
+ */
+skipped: string[]; artwork_copied: number }
 /**
  * Task status returned by `aria2.tellStatus`.
  * 
@@ -1137,8 +1818,44 @@ verifyIntegrityPending?: boolean | null }
  */
 export type TaskStatus = "active" | "waiting" | "paused" | "error" | "complete" | "removed"
 export type TorrentApiError = { ApiConfigError: string } | "ConfigRetrievalError" | "AdminModeError" | { Aria2StartupError: string } | { ConfigurationError: string } | { InitError: string } | { IOError: string } | "LibrqbitError" | "MetadataError" | "TorrentNotFound" | "InvalidMagnet" | "UnexpectedTorrentState" | "ConfigChangeDuringDownload"
+/**
+ * Which backend transfers torrent jobs.
+ * 
+ * Direct links always go through aria2, librqbit only speaks BitTorrent.
+ */
+export type TorrentEngine = 
+/**
+ * External aria2c daemon, driven over its websocket RPC
+ */
+"Aria2" | 
+/**
+ * The librqbit session embedded in the launcher, no aria2c needed
+ */
+"Librqbit"
 export type TorrentExternInfo = { title: string; img: string; desc: string; magnetlink: string; href: string; tag: string }
 export type TorrentJob = { torrent_bytes: number[]; file_indices: number[]; torrent_files: FileInfo[]; info_hash: string; magnet: string }
+export type TransferDetails = { gid: string; 
+/**
+ * Seeders we are connected to
+ */
+num_seeders: number; 
+/**
+ * Peers we are connected to, seeders included
+ */
+connections: number; 
+/**
+ * We have every selected piece and only upload
+ */
+seeder: boolean; 
+/**
+ * Announce URLs, one tier after the other
+ */
+trackers: string[]; num_pieces: number; piece_length: number; 
+/**
+ * Hex bitfield of the pieces we have, highest bit first.
+ * `None` until the download started or when the engine does not expose it
+ */
+bitfield: string | null; pieces_done: number; peers: PeerDetails[]; files: FileProgress[] }
 /**
  * In bytes/sec – `None` means unlimited
  */
@@ -1159,10 +1876,59 @@ export type TransferLimits = {
  * In bytes/sec – `None` means unlimited
  */
 "max-upload": number | null }
+export type UninstallOptions = { 
+/**
+ * Only compute what would be deleted
+ */
+dry_run: boolean; 
+/**
+ * Leave save folders found in the install folder untouched
+ */
+keep_saves: boolean; 
+/**
+ * Also delete the download folder (archives, `.bin` parts, setup)
+ */
+remove_download_leftovers: boolean }
+export type UninstallPlan = { game_title: string; 
+/**
+ * Files to delete, in deletion order
+ */
+files: string[]; 
+/**
+ * Directories to delete once emptied, deepest first
+ */
+directories: string[]; 
+/**
+ * Paths kept because they look like save folders
+ */
+kept: string[]; 
+/**
+ * Download folder removed as a whole
+ */
+download_leftovers: string[]; 
+/**
+ * Defender exclusions recorded for the install/download folders
+ */
+defender_exclusions: string[]; 
+/**
+ * Collections the game will be removed from
+ */
+collections: string[]; total_bytes: number; 
+/**
+ * Install folder left for the user to delete, it has no file list and
+ * no [`INSTALL_MARKER`] so it may hold more than this game
+ */
+manual: string | null }
+export type UninstallReport = { plan: UninstallPlan; dry_run: boolean; 
+/**
+ * Paths (or steps) that could not be removed, with the reason
+ */
+failures: string[] }
 export type Uri = { status: UriStatus; uri: string }
 export type UriStatus = "used" | "waiting"
 export type User = { id: number | null; name: string; nick: string; ava: string; online: boolean; data_last_visit: string; admin: boolean; is_verified: boolean | null }
 export type Version = { enabledFeatures: string[]; version: string }
+export type VolumeCheck = { mountPoint: string; availableBytes: number; requiredBytes: number; verdict: PreflightVerdict }
 
 /** tauri-specta globals **/
 
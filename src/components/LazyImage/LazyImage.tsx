@@ -7,12 +7,9 @@ import {
     JSX
 } from "solid-js";
 import {
-    observeVisibility,
-    priorityFromEntry,
-    queueImage
+    cachedImageSrc,
+    observeVisibility
 } from "../../services/imagesScheduler";
-import { pageAbortController } from "../../App";
-import { commands } from "../../bindings";
 
 interface LazyImageProps {
     src: string;
@@ -29,7 +26,6 @@ export default function LazyImage(props: LazyImageProps) {
     const [currentUrl, setCurrentUrl] = createSignal<string>();
 
     let el!: HTMLDivElement;
-    let abort = new AbortController();
     let unobserve: (() => void) | undefined;
 
     onMount(() => {
@@ -38,46 +34,18 @@ export default function LazyImage(props: LazyImageProps) {
         });
     });
 
-    createEffect(async () => {
+    createEffect(() => {
         const e = entry();
-        if (!e) return;
-        if (loaded() && props.src == currentUrl()) return;
+        // the observer margin already counts images close to the viewport
+        if (!e?.isIntersecting) return;
+        if (props.src == currentUrl()) return;
 
         setCurrentUrl(props.src);
-
-        abort.abort();
-        abort = new AbortController();
-        if (e.isIntersecting) {
-            // Try to use cached version immediately if visible or else it will cause some stutters
-            const result = await commands.cachedDownloadImage(props.src);
-            setCurrentSrc(result.status === "ok" ? result.data : props.src);
-            setLoaded(true);
-            return;
-        }
-        try {
-            const priority = priorityFromEntry(e);
-
-            const result = await queueImage(
-                props.src,
-                priority,
-                mergeAbortSignals(abort.signal, pageAbortController.signal)
-            );
-
-            if (result.status === "ok") {
-                setCurrentSrc(result.data);
-            } else {
-                setCurrentSrc(props.src);
-            }
-        } catch (err) {
-            if (!abort.signal.aborted) {
-                setCurrentSrc(props.src);
-            }
-            console.error("Error setting src: ", err)
-        }
+        setLoaded(false);
+        setCurrentSrc(cachedImageSrc(props.src));
     });
 
     onCleanup(() => {
-        abort.abort();
         unobserve?.();
     });
 
@@ -100,22 +68,14 @@ export default function LazyImage(props: LazyImageProps) {
                     alt={props.alt ?? ""}
                     class={`w-full h-full ${objectFitClass()}`}
                     onLoad={() => setLoaded(true)}
-                    onError={() => setLoaded(true)}
+                    onError={() => {
+                        // the cache could not fetch it, let the webview try the original
+                        if (currentSrc() !== props.src) setCurrentSrc(props.src);
+                        else setLoaded(true);
+                    }}
                     decoding="async"
                 />
             </Show>
         </div>
     );
 }
-
-function mergeAbortSignals(...signals: AbortSignal[]) {
-    const ctrl = new AbortController();
-    const listeners = signals.map(sig => {
-        const fn = () => ctrl.abort();
-        sig.addEventListener("abort", fn);
-        return () => sig.removeEventListener("abort", fn);
-    });
-    if (signals.some(s => s.aborted)) ctrl.abort();
-    onCleanup(() => listeners.forEach(remove => remove()));
-    return ctrl.signal;
-}
@@ -0,0 +1,82 @@
+import { createSignal, onCleanup, onMount, Show } from "solid-js";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import { WifiOff } from "lucide-solid";
+import { commands, Connectivity, DataFreshness } from "../../../../bindings";
+
+/** Most recent sync of the lists shown offline, in unix seconds */
+function lastSync(freshness: DataFreshness): number | null {
+  const synced = [
+    freshness.newly_added,
+    freshness.popular,
+    freshness.recently_updated,
+    freshness.discovery,
+    freshness.sitemaps,
+  ].filter((ts): ts is number => ts !== null);
+  return synced.length ? Math.max(...synced) : null;
+}
+
+/**
+ * Shown while offline or while the site is down, tells which one it is and
+ * how old the local data is.
+ */
+export default function ConnectivityBadge() {
+  const [state, setState] = createSignal<Connectivity | null>(null);
+  const [syncedAt, setSyncedAt] = createSignal<number | null>(null);
+  let unlisten: UnlistenFn | undefined;
+
+  const isOffline = (s: Connectivity) => s.forced_offline || !s.network;
+  const isSiteDown = () => {
+    const s = state();
+    return !!s && (isOffline(s) || !s.reachable);
+  };
+
+  async function refreshFreshness() {
+    const result = await commands.getDataFreshness(null);
+    if (result.status === "ok") setSyncedAt(lastSync(result.data));
+  }
+
+  async function update(next: Connectivity) {
+    setState(next);
+    if (isOffline(next) || !next.reachable) await refreshFreshness();
+  }
+
+  onMount(async () => {
+    unlisten = await listen<Connectivity>("connectivity-changed", (event) =>
+      update(event.payload)
+    );
+    await update(await commands.getConnectivity());
+  });
+
+  onCleanup(() => unlisten?.());
+
+  const tooltip = () => {
+    const at = syncedAt();
+    const data = at
+      ? `Showing data synced ${new Date(at * 1000).toLocaleString()}`
+      : "No data synced yet";
+    const s = state();
+    const downloads =
+      s && isOffline(s)
+        ? "new downloads wait until you are back online"
+        : "downloads still work";
+    return `${data}, ${downloads}.`;
+  };
+
+  const label = () => {
+    const s = state();
+    if (s?.forced_offline) return "Offline mode";
+    return s && !s.network ? "No network" : "Site unreachable";
+  };
+
+  return (
+    <Show when={isSiteDown()}>
+      <div
+        class="flex items-center gap-2 px-3 py-1.5 rounded-full text-xs font-bold bg-amber-500/10 border border-amber-400/30 text-amber-400"
+        title={tooltip()}
+      >
+        <WifiOff size={14} />
+        <span>{label()}</span>
+      </div>
+    </Show>
+  );
+}
@@ -2,12 +2,13 @@ import { A } from "@solidjs/router";
 import { Compass, Download, Home, Library, Maximize2, Minimize2, Minus, Settings, X } from "lucide-solid";
 import { createSignal, onMount, Show } from "solid-js";
 import Searchbar from "./Topbar-Components-01/Searchbar-01/Searchbar";
+import ConnectivityBadge from "./Topbar-Components-01/Connectivity-01/ConnectivityBadge";
 import { listen, Event } from "@tauri-apps/api/event";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { sendNotification, isPermissionGranted, requestPermission } from "@tauri-apps/plugin-notification";
 import createBasicChoicePopup from "../../Pop-Ups/Basic-Choice-PopUp/Basic-Choice-PopUp";
 import { GlobalSettingsApi } from "../../api/settings/api";
-import { commands } from "../../bindings";
+import { commands, type ParserHealth } from "../../bindings";
 import { routeHistory } from "../../stores/routeStore";
 
 export default function Topbar() {
@@ -121,6 +122,10 @@ export default function Topbar() {
       console.error(`Network failure: ${event.payload.message}`);
     });
 
+    listen('parser-health-warning', (event: Event<ParserHealth>) => {
+      console.warn(`Empty ${event.payload.spiked.join(", ")} on ${event.payload.run}, the site layout may have changed`);
+    });
+
     listen('scraping_failed_event', (event: Event<{ message: string }>) => {
       console.error('Scraping failed:', event.payload.message);
     });
@@ -199,6 +204,8 @@ export default function Topbar() {
 
       {/* Left Section - Logo and Window Controls */}
       <div class="flex items-center gap-6" style="-webkit-app-region: no-drag;">
+        <ConnectivityBadge />
+
         {/* Window Controls */}
         <div data-tauri-drag-region class="flex items-center gap-2">
 
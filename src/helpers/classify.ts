@@ -1,4 +1,4 @@
-import { DebridFile, FileInfo } from "../bindings";
+import { ClassifiedFile, DebridFile, FileInfo } from "../bindings";
 import { DirectLinkWrapper } from "../types/download";
 import { toTitleCaseExceptions } from "./format";
 
@@ -48,39 +48,33 @@ const languageMap: Record<string, string> = {
   vietnamese: "Vietnamese",
 };
 
-export function classifyTorrentFiles(files: FileInfo[]) {
+/**
+ * Labels for the optional and language files of a torrent, from the kinds
+ * `classify_torrent_files` gave them. Core files are left uncategorized.
+ */
+export function labelTorrentFiles(files: FileInfo[], classified: ClassifiedFile[]) {
   const Languages: Record<string, string> = {};
   const Others: Record<string, string> = {};
   const Uncategorized: string[] = [];
+  const kinds = new Map(classified.map((c) => [c.file_index, c.kind]));
 
   for (const file of files) {
-    const lower = file.file_name.toLowerCase();
-    const matchedLanguage = Object.keys(languageMap).find((l) =>
-      lower.includes(l)
-    );
-
-    if (matchedLanguage) {
-      let label = `${languageMap[matchedLanguage]} Language`;
-      if (lower.includes("vo")) label += " VO";
-      Languages[file.file_name] = label;
-      continue;
-    }
-
-    if (
-      lower.includes("optional") ||
-      lower.includes("selective") ||
-      /fg-optional-/i.test(lower)
-    ) {
-      const label = file.file_name
-        .replace(/fg-optional-|optional-|selective-/gi, "")
-        .replace(/\..*$/, "")
-        .replace(/[-_.]/g, " ")
-        .trim();
-      Others[file.file_name] = label || file.file_name;
-      continue;
+    const kind = kinds.get(file.file_index);
+    switch (kind?.kind) {
+      case "language": {
+        const language = languageMap[kind.language.toLowerCase()] ?? kind.language;
+        Languages[file.file_name] = `${language} Language${kind.optional ? " VO" : ""}`;
+        break;
+      }
+      case "bonus":
+        Others[file.file_name] = kind.name.replace(/[-_.]/g, " ").trim() || file.file_name;
+        break;
+      case "soundtrack":
+        Others[file.file_name] = "Soundtrack";
+        break;
+      default:
+        Uncategorized.push(file.file_name);
     }
-
-    Uncategorized.push(file.file_name);
   }
 
   return { Languages, Others, Uncategorized };
@@ -1,8 +1,8 @@
 import { createSignal, createMemo, For, Show } from 'solid-js';
 import { useNavigate } from '@solidjs/router';
-import type { Game } from '../../../bindings';
+import type { Game, RecommendationReason } from '../../../bindings';
 import { commands } from '../../../bindings';
-import { Bookmark, BookmarkCheck, Download, Star } from 'lucide-solid';
+import { Bookmark, BookmarkCheck, Download, Sparkles, Star } from 'lucide-solid';
 import { LibraryApi } from '../../../api/library/api';
 import { useToast } from 'solid-notifications';
 import LazyImage from '../../../components/LazyImage/LazyImage';
@@ -13,6 +13,23 @@ const library = new LibraryApi();
 interface DiscoveryRowProps {
     game: Game;
     isFavorite: boolean;
+    reasons?: RecommendationReason[];
+}
+
+function describeReason(reason: RecommendationReason): string {
+    switch (reason.kind) {
+        case "similarTo": {
+            const title = reason.title.replace(/\s*[–+].*$/, '').trim();
+            if (reason.relation === "collected") {
+                return `Because ${title} is in ${reason.collection ?? "a collection"}`;
+            }
+            return `Because you ${reason.relation} ${title}`;
+        }
+        case "popular":
+            return `#${reason.rank} in popular repacks`;
+        case "recent":
+            return "New on FitGirl";
+    }
 }
 
 export default function DiscoveryRow(props: DiscoveryRowProps) {
@@ -143,6 +160,17 @@ export default function DiscoveryRow(props: DiscoveryRowProps) {
                         {/* Bottom */}
                         <div class="flex items-center justify-between pt-4 border-t border-white/10">
                             <div class="flex items-center gap-4">
+                                <Show when={props.reasons?.[0]}>
+                                    {(reason) => (
+                                        <div
+                                            class="flex items-center gap-2 px-4 py-2 bg-secondary/5 rounded-full border border-white/10"
+                                            title={props.reasons!.map(describeReason).join("\n")}
+                                        >
+                                            <Sparkles class="w-4 h-4 text-accent" />
+                                            <span class="text-sm text-text">{describeReason(reason())}</span>
+                                        </div>
+                                    )}
+                                </Show>
                                 {repackSize() && (
                                     <div class="flex items-center gap-2 px-4 py-2 bg-secondary/5 rounded-full border border-white/10 transition-all hover:bg-accent/20 hover:border-accent/30">
                                         <Download class="w-4 h-4 text-accent" />
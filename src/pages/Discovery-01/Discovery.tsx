@@ -1,5 +1,5 @@
 import { createSignal, For, Suspense, createResource, lazy, createMemo, Show, onMount, onCleanup } from 'solid-js';
-import type { Game } from '../../bindings';
+import type { Game, RecommendationReason } from '../../bindings';
 import LoadingPage from '../LoadingPage-01/LoadingPage';
 import { GamesCacheApi } from '../../api/cache/api';
 import { LibraryApi } from '../../api/library/api';
@@ -18,18 +18,24 @@ const DEFAULT_SIZE_RANGE: SizeRange = { min: 0, max: 100 * 1024 * 1024 * 1024 };
 
 // Fetcher for createResource
 async function fetchDiscoveryData() {
-  const [resultGame, downloadLaterList] = await Promise.all([
+  const [resultGame, downloadLaterList, resultRecommendations] = await Promise.all([
     gameCacheInst.getDiscoveryGames(),
-    libraryInst.getGamesToDownload()
+    libraryInst.getGamesToDownload(),
+    gameCacheInst.getRecommendations()
   ]);
 
   const toDownloadLater = new Set(downloadLaterList.map(g => g.title));
+  const reasons = new Map<string, RecommendationReason[]>(
+    resultRecommendations.status === "ok"
+      ? resultRecommendations.data.map(r => [r.game.href, r.reasons] as const)
+      : []
+  );
 
   if (resultGame.status === "ok") {
     const games = await gameCacheInst.removeNSFW(resultGame.data);
-    return { games, toDownloadLater };
+    return { games, toDownloadLater, reasons };
   }
-  return { games: [] as Game[], toDownloadLater };
+  return { games: [] as Game[], toDownloadLater, reasons };
 }
 
 export default function DiscoveryPage() {
@@ -42,6 +48,7 @@ export default function DiscoveryPage() {
     let unlisten: UnlistenFn | undefined;
     listen('discovery-ready', () => {
       gameCacheInst.clearCache('discovery');
+      gameCacheInst.clearCache('recommendations');
       refetch();
     }).then(fn => { unlisten = fn; });
 
@@ -109,6 +116,7 @@ export default function DiscoveryPage() {
               <LazyDiscoveryRow
                 game={game}
                 isFavorite={toDownloadLater().has(game.title)}
+                reasons={data()?.reasons.get(game.href)}
               />
             )}
           </For>
@@ -40,15 +40,14 @@ export const SidebarSection = (props: SidebarSectionProps) => {
         });
     };
 
-    const handleStartGame = (path: string) => {
+    const handleStartGame = (gameHref: string) => {
         createBasicChoicePopup({
             infoTitle: "Launch Game",
             infoMessage: "Do you want to launch the game now?",
             action: async () => {
-                try {
-                    await api.runExecutable(path);
-                } catch (err) {
-                    await showError(err, "Error");
+                const result = await api.launchGame(gameHref);
+                if (result.status === "error") {
+                    await showError(result.error, "Error");
                 }
             },
         });
@@ -126,7 +125,7 @@ export const SidebarSection = (props: SidebarSectionProps) => {
                     <Button
                         icon={<Play class="w-4 h-4" />}
                         label="Play Now"
-                        onClick={() => handleStartGame(props.game()!.executable_info.executable_path)}
+                        onClick={() => handleStartGame(props.game()!.href)}
                         class="w-full"
                         variant="solid"
                     />
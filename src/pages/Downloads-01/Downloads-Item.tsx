@@ -110,6 +110,11 @@ const DownloadItem: Component<{ item: Accessor<Job>; refreshDownloads?: () => Pr
             return actionMap.install_failed;
         }
 
+        const pipeline = props.item().pipeline;
+        if (pipeline && !pipeline.failed && pipeline.stage !== "Done") {
+            return actionMap.installing;
+        }
+
         if (state === "active" && status?.upload_speed && status.upload_speed > 0 && status.completed_length == status.total_length) {
             return { label: "WAITING FOR PAUSE", icon: <Pause class="w-5 h-5 animate-pulse" /> };
         }
@@ -204,6 +209,23 @@ const DownloadItem: Component<{ item: Accessor<Job>; refreshDownloads?: () => Pr
     }
 
     async function installIfReady() {
+        // completed jobs are set up by the backend pipeline, never run it twice
+        const pipeline = props.item().pipeline;
+        if (pipeline?.failed) {
+            await installerService.retryInstall(props.item());
+            return;
+        }
+        if (pipeline && pipeline.stage !== "Done") {
+            notify(
+                "The download is being set up, the installation follows automatically.",
+                { type: "info", role: "alert", duration: 3000 }
+            );
+            return;
+        }
+        if (pipeline && !pipeline.install_skipped) {
+            notify("This game is already installed.", { type: "info", role: "alert", duration: 3000 });
+            return;
+        }
 
         try {
             if (props.item().source === "Torrent") {
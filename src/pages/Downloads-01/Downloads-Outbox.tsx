@@ -0,0 +1,69 @@
+import { createSignal, For, onCleanup, onMount, Show } from "solid-js";
+import { UnlistenFn } from "@tauri-apps/api/event";
+import { Clock, Trash2, WifiOff } from "lucide-solid";
+import { DM } from "../../api/manager/api";
+import { OutboxEntry } from "../../bindings";
+import { showError } from "../../helpers/error";
+
+/**
+ * Downloads asked for while offline, started by the backend once it is back online.
+ */
+export default function DownloadOutbox() {
+    const [entries, setEntries] = createSignal<OutboxEntry[]>([]);
+    let unlisten: UnlistenFn | undefined;
+
+    onMount(async () => {
+        unlisten = await DM.onOutboxUpdated(setEntries);
+        const result = await DM.outbox();
+        if (result.status === "ok") setEntries(result.data);
+    });
+
+    onCleanup(() => unlisten?.());
+
+    async function discard(id: string) {
+        const result = await DM.discardOutboxEntry(id);
+        if (result.status === "error") {
+            await showError(result.error, "Error discarding the download");
+            return;
+        }
+        setEntries((list) => list.filter((e) => e.id !== id));
+    }
+
+    return (
+        <Show when={entries().length > 0}>
+            <div class="mb-8 rounded-xl border border-amber-500/40 bg-popup/80 backdrop-blur-sm">
+                <div class="flex items-center gap-3 px-6 py-3 border-b border-amber-500/20">
+                    <WifiOff class="w-5 h-5 text-amber-400" />
+                    <span class="font-bold">Waiting for a connection</span>
+                    <span class="px-2.5 py-1 rounded-full bg-amber-500/20 text-amber-300 text-xs font-bold">{entries().length}</span>
+                </div>
+                <For each={entries()}>
+                    {(entry) => (
+                        <div class="flex items-center justify-between gap-4 px-6 py-3">
+                            <div class="flex flex-col min-w-0">
+                                <span class="font-medium truncate">{entry.request.game.title}</span>
+                                <span class="flex items-center gap-1 text-xs text-muted">
+                                    <Clock class="w-3 h-3" />
+                                    Queued {new Date(entry.queued_at).toLocaleString()}
+                                    {entry.request.kind === "addTorrentJob" ? " · Torrent" : " · Direct"}
+                                </span>
+                                <Show when={entry.last_error}>
+                                    <span class="text-xs text-red-400 truncate" title={entry.last_error!}>
+                                        Last attempt failed: {entry.last_error}
+                                    </span>
+                                </Show>
+                            </div>
+                            <button
+                                onClick={() => discard(entry.id)}
+                                class="p-2 rounded-lg text-muted hover:bg-red-500/20 hover:text-red-400 transition-colors"
+                                title="Discard"
+                            >
+                                <Trash2 class="w-4 h-4" />
+                            </button>
+                        </div>
+                    )}
+                </For>
+            </div>
+        </Show>
+    );
+}
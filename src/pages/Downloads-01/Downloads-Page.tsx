@@ -4,6 +4,7 @@ import Button from "../../components/UI/Button/Button";
 import { useNavigate } from "@solidjs/router";
 import InstallQueueStatus from "../../components/InstallQueue/QueueStatus";
 import DownloadList from "./Downloads-List";
+import DownloadOutbox from "./Downloads-Outbox";
 import { formatSpeed } from "../../helpers/format";
 import { DM } from "../../api/manager/api";
 import { AggregatedStatus, DownloadSource } from "../../bindings";
@@ -161,6 +162,7 @@ const DownloadPage: Component = () => {
             </div>
 
             <div class="max-w-[1800px] mx-auto">
+                <DownloadOutbox />
                 <DownloadList
                     items={filteredItems}
                     refreshDownloads={async () => {
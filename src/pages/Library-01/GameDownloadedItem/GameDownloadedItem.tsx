@@ -2,7 +2,7 @@ import { createSignal, For, Show } from "solid-js";
 import { message } from "@tauri-apps/plugin-dialog";
 import { showError } from "../../../helpers/error";
 import { Play, Settings, Info, Trash2, BookmarkPlus } from "lucide-solid";
-import { DownloadedGame, Game, ExecutableInfo, commands } from "../../../bindings";
+import { DownloadedGame, Game, ExecutableInfo, LaunchProfile, commands } from "../../../bindings";
 import { LibraryApi } from "../../../api/library/api";
 import createBasicChoicePopup from "../../../Pop-Ups/Basic-Choice-PopUp/Basic-Choice-PopUp";
 import { Accessor } from "solid-js";
@@ -11,6 +11,7 @@ import createPathInputPopup from "../../../Pop-Ups/Basic-PathInput-PopUp/Basic-P
 import createAddToCollectionPopup from "../../../Pop-Ups/Basic-AddToCollection-PopUp/Basic-AddToCollection-PopUp";
 import { formatDate, formatDiskSize, formatPlayTime } from "../../../helpers/format";
 import { useNavigate } from "@solidjs/router";
+import LaunchProfileEditor from "./LaunchProfileEditor";
 
 const api = new LibraryApi();
 
@@ -21,6 +22,7 @@ export type LayoutType = "column" | "row";
 export default function GameDownloadedItem(props: {
   downloadedGamesList: Accessor<DownloadedGame[]>;
   onGameInfoUpdate?: (title: string, info: ExecutableInfo) => void;
+  onLaunchProfileUpdate?: (href: string, profile: LaunchProfile) => void;
   onGameDelete?: (title: string) => void;
   onGameAddToCollection?: (collectionKey: string, game: Game) => void;
   createCollection: () => void | Promise<void>;
@@ -72,15 +74,14 @@ export default function GameDownloadedItem(props: {
     });
   };
 
-  const handleStartGame = (path: string) => {
+  const handleStartGame = (game: DownloadedGame) => {
     createBasicChoicePopup({
       infoTitle: "Launch Game",
       infoMessage: "Do you want to launch the game now?",
       action: async () => {
-        try {
-          await api.runExecutable(path);
-        } catch (err) {
-          await showError(err, "Error");
+        const result = await api.launchGame(game.href);
+        if (result.status === "error") {
+          await showError(result.error, "Error");
         }
       },
     });
@@ -204,7 +205,7 @@ export default function GameDownloadedItem(props: {
                           class="p-1.5 rounded-full bg-accent/20 hover:bg-accent/30 text-accent transition-colors"
                           onClick={(e) => {
                             e.stopPropagation();
-                            handleStartGame(game.executable_info.executable_path);
+                            handleStartGame(game);
                           }}
                           title="Play"
                         >
@@ -343,7 +344,7 @@ export default function GameDownloadedItem(props: {
                       >
                         <Button size="sm" label="Play Now" icon={<Play class="w-4 h-4" />} onClick={(e) => {
                           e.stopPropagation();
-                          handleStartGame(game.executable_info.executable_path);
+                          handleStartGame(game);
                         }} />
                       </Show>
                     </div>
@@ -369,6 +370,7 @@ export default function GameDownloadedItem(props: {
                         </p>
                       </div>
                     </div>
+                    <LaunchProfileEditor game={game} onSaved={props.onLaunchProfileUpdate} />
                   </div>
                 </Show>
               </div>
@@ -0,0 +1,113 @@
+import { createSignal, Show } from "solid-js";
+import { message } from "@tauri-apps/plugin-dialog";
+import { Save } from "lucide-solid";
+import { DownloadedGame, LaunchProfile, LaunchRunner } from "../../../bindings";
+import { LibraryApi } from "../../../api/library/api";
+import { showError } from "../../../helpers/error";
+import Button from "../../../components/UI/Button/Button";
+import Dropdown from "../../../components/UI/Dropdown/Dropdown";
+import TextInput from "../../../components/UI/TextInput/TextInput";
+
+const api = new LibraryApi();
+
+type RunnerKind = LaunchRunner["type"];
+
+const RUNNER_LABELS: Record<RunnerKind, string> = {
+  native: "Native",
+  wine: "Wine",
+  custom: "Custom command",
+};
+
+export default function LaunchProfileEditor(props: {
+  game: DownloadedGame;
+  onSaved?: (href: string, profile: LaunchProfile) => void;
+}) {
+  const profile = props.game.launch_profile;
+
+  const [runner, setRunner] = createSignal<RunnerKind>(profile.runner.type);
+  const [binary, setBinary] = createSignal(
+    profile.runner.type === "wine" ? profile.runner.binary ?? "" : ""
+  );
+  const [template, setTemplate] = createSignal(
+    profile.runner.type === "custom" ? profile.runner.template : ""
+  );
+  const [prefix, setPrefix] = createSignal(profile.prefix ?? "");
+  const [args, setArgs] = createSignal(profile.args.join(" "));
+
+  const buildProfile = (): LaunchProfile => {
+    let launchRunner: LaunchRunner;
+    switch (runner()) {
+      case "wine":
+        launchRunner = { type: "wine", binary: binary().trim() || null };
+        break;
+      case "custom":
+        launchRunner = { type: "custom", template: template().trim() };
+        break;
+      default:
+        launchRunner = { type: "native" };
+    }
+
+    return {
+      ...profile,
+      runner: launchRunner,
+      prefix: prefix().trim() || null,
+      args: args().split(/\s+/).filter((a) => a !== ""),
+    };
+  };
+
+  const save = async () => {
+    const updated = buildProfile();
+    const result = await api.updateLaunchProfile(props.game.href, updated);
+    if (result.status === "ok") {
+      props.onSaved?.(props.game.href, updated);
+      await message("Launch options saved", { title: "FitLauncher", kind: "info" });
+    } else {
+      await showError(result.error, "Error saving launch options");
+    }
+  };
+
+  const showLog = async () => {
+    const result = await api.getGameLogPath(props.game.href);
+    if (result.status === "ok") {
+      await message(result.data, { title: "Game log", kind: "info" });
+    } else {
+      await showError(result.error, "Error");
+    }
+  };
+
+  return (
+    <div class="flex flex-col gap-3 mt-4">
+      <p class="text-xs text-muted">Launch Options</p>
+      <div class="grid grid-cols-2 gap-3">
+        <Dropdown<string>
+          list={Object.values(RUNNER_LABELS)}
+          activeItem={RUNNER_LABELS[runner()]}
+          onListChange={async (label) => {
+            const kind = (Object.keys(RUNNER_LABELS) as RunnerKind[]).find(
+              (k) => RUNNER_LABELS[k] === label
+            );
+            if (kind) setRunner(kind);
+          }}
+        />
+        <Show when={runner() === "wine"}>
+          <TextInput value={binary()} placeholder="wine" onInput={setBinary} />
+        </Show>
+        <Show when={runner() === "custom"}>
+          <TextInput
+            value={template()}
+            placeholder="protonrun {exe} {args}"
+            onInput={setTemplate}
+          />
+        </Show>
+        <Show when={runner() !== "native"}>
+          <TextInput value={prefix()} placeholder="Wine prefix" onInput={setPrefix} />
+        </Show>
+        <TextInput value={args()} placeholder="Arguments" onInput={setArgs} />
+      </div>
+      <div class="flex justify-end gap-2">
+        <Button size="sm" variant="bordered" label="Show Log" onClick={showLog} />
+        <Button size="sm" label="Save" icon={<Save class="w-4 h-4" />} onClick={save} />
+      </div>
+    </div>
+  );
+}
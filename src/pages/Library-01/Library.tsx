@@ -232,6 +232,11 @@ function Library() {
                     prev.map(g => g.title === title ? { ...g, executable_info: info } : g)
                   );
                 }}
+                onLaunchProfileUpdate={(href, profile) => {
+                  setDownloadedGamesList(prev =>
+                    prev.map(g => g.href === href ? { ...g, launch_profile: profile } : g)
+                  );
+                }}
                 onGameDelete={(title) => {
                   setDownloadedGamesList(prev => prev.filter(g => g.title !== title));
                   setCollectionList(prev => {
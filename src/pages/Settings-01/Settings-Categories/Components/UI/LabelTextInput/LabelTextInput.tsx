@@ -6,7 +6,7 @@ export default function LabelTextInputSettings(props: SettingsTextInputLabelProp
     return (
         <li class="flex items-center justify-between py-3 px-4 bg-popup-background hover:bg-secondary-20 rounded-lg border border-secondary-20 transition-colors w-full">
             <TitleLabel text={props.text} typeText={props.typeText} />
-            <TextInput value={props.value} disabled={props.disabled} />
+            <TextInput value={props.value} disabled={props.disabled} placeholder={props.placeholder} onInput={props.onInput} />
         </li>
     )
 }
\ No newline at end of file
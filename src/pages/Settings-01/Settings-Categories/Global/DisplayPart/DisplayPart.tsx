@@ -1,7 +1,8 @@
-import { createSignal, onMount, createEffect, Show } from "solid-js";
+import { createSignal, onMount, onCleanup, createEffect, Show } from "solid-js";
 import { load } from "@tauri-apps/plugin-store";
+import { listen } from "@tauri-apps/api/event";
 import { DefaultTheme, defaultThemes } from "../../../../../types/theme";
-import type { GamehubSettings } from "../../../../../bindings";
+import { commands, type CrawlerProgress, type GamehubSettings } from "../../../../../bindings";
 import { SettingsSectionProps } from "../../../../../types/settings/types";
 import LoadingPage from "../../../../LoadingPage-01/LoadingPage";
 import PageGroup from "../../Components/PageGroup";
@@ -9,6 +10,7 @@ import LabelCheckboxSettings from "../../Components/UI/LabelCheckbox/LabelCheckb
 import LabelDropdownSettings from "../../Components/UI/LabelDropdown/LabelDropdown";
 import LabelButtonSettings from "../../Components/UI/LabelButton/LabelButton";
 import LabelRangeSettings from "../../Components/UI/LabelRange/LabelRange";
+import LabelTextInputSettings from "../../Components/UI/LabelTextInput/LabelTextInput";
 import { ThemeManagerApi } from "../../../../../api/theme/api";
 
 const themeAPI = new ThemeManagerApi();
@@ -17,6 +19,30 @@ export default function DisplayPart(props: SettingsSectionProps<GamehubSettings>
     const [newThemes, setNewThemes] = createSignal<string[]>([]);
     const [currentTheme, setCurrentTheme] = createSignal<string>("Dark Purple");
     const [blurAmount, setBlurAmount] = createSignal<number>(5);
+    const [crawlerProgress, setCrawlerProgress] = createSignal<CrawlerProgress>();
+
+    onMount(async () => {
+        setCrawlerProgress(await commands.getCrawlerProgress());
+        const unlisten = await listen<CrawlerProgress>("crawler-progress", e => setCrawlerProgress(e.payload));
+        onCleanup(unlisten);
+    });
+
+    function crawlerStatus(): string {
+        const progress = crawlerProgress();
+        if (!progress) return "";
+        const counts = `${progress.scraped} of ${progress.total} games scraped`;
+        switch (progress.state.kind) {
+            case "crawling": return `${counts}, crawling`;
+            case "paused": return `${counts}, paused (${{
+                user: "by you",
+                downloadsActive: "downloads are running",
+                metered: "metered connection",
+                offline: "offline",
+            }[progress.state.reason]})`;
+            case "idle": return `${counts}, up to date`;
+            case "disabled": return counts;
+        }
+    }
 
     onMount(async () => {
         try {
@@ -67,6 +93,12 @@ export default function DisplayPart(props: SettingsSectionProps<GamehubSettings>
                     action={() => props.handleSwitchCheckChange?.("display.close_to_tray")}
                     checked={props.settings().close_to_tray}
                 />
+                <LabelCheckboxSettings
+                    text="Offline Mode"
+                    typeText="Only show the games already saved locally, downloads you start wait until you go back online"
+                    action={() => props.handleSwitchCheckChange?.("display.offline_mode")}
+                    checked={props.settings().offline_mode ?? false}
+                />
                 <LabelDropdownSettings
                     text="Change Themes"
                     typeText="Change themes as you want, you can even add your own!"
@@ -114,6 +146,61 @@ export default function DisplayPart(props: SettingsSectionProps<GamehubSettings>
                     disabled={true}
                 />
             </PageGroup>
+            <Show when={props.settings().site}>
+                {site => (
+                    <PageGroup title="Site Address">
+                        <LabelTextInputSettings
+                            text="Base URL"
+                            typeText="Where FitGirl Repacks is reached first"
+                            value={site().base_url}
+                            placeholder="https://fitgirl-repacks.site"
+                            onInput={(value) => props.handleTextCheckChange?.("display.site.base_url", value)}
+                        />
+                        <LabelTextInputSettings
+                            text="Mirrors"
+                            typeText="Comma separated, tried in order when the base URL is blocked or down"
+                            value={site().mirrors.join(", ")}
+                            placeholder="https://mirror.example"
+                            onInput={(value) => props.handleTextCheckChange?.(
+                                "display.site.mirrors",
+                                value.split(",").map(m => m.trim()).filter(m => m.length > 0),
+                            )}
+                        />
+                    </PageGroup>
+                )}
+            </Show>
+            <Show when={props.settings().catalog_crawler}>
+                {crawler => (
+                    <PageGroup title="Catalog Crawler">
+                        <LabelCheckboxSettings
+                            text="Crawl the catalog in the background"
+                            typeText={`Fetches every game page slowly so the whole catalog works offline. ${crawlerStatus()}`}
+                            action={() => props.handleSwitchCheckChange?.("display.catalog_crawler.enabled")}
+                            checked={crawler().enabled}
+                        />
+                        <LabelRangeSettings
+                            text="Pages per minute"
+                            typeText="Keep it low to stay polite with the site"
+                            min={1}
+                            max={30}
+                            value={crawler().pages_per_minute}
+                            onInput={(val) => props.handleTextCheckChange?.("display.catalog_crawler.pages_per_minute", val)}
+                        />
+                        <LabelCheckboxSettings
+                            text="Pause while downloading"
+                            typeText="Leave the bandwidth to active downloads"
+                            action={() => props.handleSwitchCheckChange?.("display.catalog_crawler.pause_while_downloading")}
+                            checked={crawler().pause_while_downloading}
+                        />
+                        <LabelCheckboxSettings
+                            text="Pause on metered connections"
+                            typeText="Stop crawling on mobile data and other capped networks"
+                            action={() => props.handleSwitchCheckChange?.("display.catalog_crawler.pause_on_metered")}
+                            checked={crawler().pause_on_metered}
+                        />
+                    </PageGroup>
+                )}
+            </Show>
         </Show>
     );
 }
@@ -139,7 +139,7 @@ function GlobalSettingsPage(props: { settingsPart: GlobalSettingsPart }): JSX.El
     });
   }
 
-  function handleTextCheckChange(path: string, newValue: string | number | boolean) {
+  function handleTextCheckChange(path: string, newValue: string | number | boolean | string[]) {
     setDirtyPaths(prev => new Set([...prev, path]));
     setGlobalSettings(prev => {
       if (!prev) return prev;
@@ -225,6 +225,7 @@ function GlobalSettingsPage(props: { settingsPart: GlobalSettingsPart }): JSX.El
             <DisplayPart
               settings={() => globalSettings()!.display}
               handleSwitchCheckChange={handleSwitchCheckChange}
+              handleTextCheckChange={handleTextCheckChange}
               isDirty={isDirty}
               savePulse={savePulse}
             />